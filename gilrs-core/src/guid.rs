@@ -0,0 +1,95 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Layout of SDL-compatible gamepad UUID, shared by all backends.
+
+/// Fields encoded in gamepad's UUID, see
+/// [`Gamepad::guid_components()`](crate::Gamepad::guid_components).
+///
+/// UUID uses the same layout as SDL2 GUID: little-endian bustype (4 bytes), vendor ID, product ID
+/// and version, each followed by two zero bytes. Fields that backend doesn't know are zero.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct GuidComponents {
+    /// Bus type, for example `0x03` for USB and `0x05` for Bluetooth (same as Linux `BUS_*`).
+    pub bustype: u32,
+    pub vendor: u16,
+    pub product: u16,
+    pub version: u16,
+}
+
+impl GuidComponents {
+    // XInput and wasm backends don't know vendor and product IDs.
+    #[allow(dead_code)]
+    pub(crate) fn new(bustype: u32, vendor: u16, product: u16, version: u16) -> Self {
+        GuidComponents {
+            bustype,
+            vendor,
+            product,
+            version,
+        }
+    }
+
+    /// Parses fields from `uuid`. Nil UUID gives all fields zero.
+    pub fn from_uuid(uuid: [u8; 16]) -> Self {
+        let u16_at = |i: usize| u16::from_le_bytes([uuid[i], uuid[i + 1]]);
+
+        GuidComponents {
+            bustype: u32::from_le_bytes([uuid[0], uuid[1], uuid[2], uuid[3]]),
+            vendor: u16_at(4),
+            product: u16_at(8),
+            version: u16_at(12),
+        }
+    }
+
+    /// Inverse of [`from_uuid()`](Self::from_uuid), used by backends to create gamepad's UUID.
+    pub fn to_uuid(self) -> [u8; 16] {
+        let mut uuid = [0; 16];
+        uuid[0..4].copy_from_slice(&self.bustype.to_le_bytes());
+        uuid[4..6].copy_from_slice(&self.vendor.to_le_bytes());
+        uuid[8..10].copy_from_slice(&self.product.to_le_bytes());
+        uuid[12..14].copy_from_slice(&self.version.to_le_bytes());
+
+        uuid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GuidComponents;
+    use uuid::Uuid;
+
+    #[test]
+    fn xbox_360() {
+        let uuid = *Uuid::parse_str("030000005e0400008e02000020200000")
+            .unwrap()
+            .as_bytes();
+        let components = GuidComponents::from_uuid(uuid);
+
+        assert_eq!(components, GuidComponents::new(0x3, 0x045e, 0x028e, 0x2020));
+        assert_eq!(components.to_uuid(), uuid);
+    }
+
+    #[test]
+    fn nil() {
+        assert_eq!(
+            GuidComponents::from_uuid([0; 16]),
+            GuidComponents::default()
+        );
+        assert_eq!(GuidComponents::default().to_uuid(), [0; 16]);
+    }
+
+    #[test]
+    fn round_trip() {
+        for components in [
+            GuidComponents::new(0x5, 0x054c, 0x09cc, 0x8111),
+            GuidComponents::new(0xffff_ffff, 0xffff, 0x1, 0xff00),
+        ] {
+            assert_eq!(GuidComponents::from_uuid(components.to_uuid()), components);
+        }
+    }
+}
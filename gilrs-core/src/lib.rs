@@ -5,16 +5,40 @@ use std::fmt;
 use std::fmt::Display;
 use std::fmt::Formatter;
 
+#[cfg(feature = "replay")]
+use std::collections::VecDeque;
 use std::error;
+use std::io;
+use std::ops::Index;
 use std::time::Duration;
 use std::time::SystemTime;
 
+mod guid;
 mod platform;
+#[cfg(any(test, all(target_os = "windows", feature = "wgi")))]
+mod power_cache;
 pub mod utils;
+mod virtual_device;
+
+pub use crate::guid::GuidComponents;
+pub use crate::virtual_device::VirtualReason;
+
+// `Gilrs` and `Gamepad` can be moved to other threads on all platforms except Wasm. Background
+// threads of backends only communicate with `Gilrs` through channels.
+#[cfg(not(target_arch = "wasm32"))]
+const _: () = {
+    const fn assert_send<T: Send>() {}
+    assert_send::<Gilrs>();
+    assert_send::<Gamepad>();
+    assert_send::<FfDevice>();
+};
 
 /// True, if Y axis of sticks commonly points downwards.
 pub const IS_Y_AXIS_REVERSED: bool = platform::IS_Y_AXIS_REVERSED;
 
+/// Name of the backend used on this platform, for example `"linux"` or `"wgi"`.
+pub const BACKEND_NAME: &str = platform::BACKEND_NAME;
+
 /// Allow control of gamepad's force feedback.
 #[derive(Debug)]
 pub struct FfDevice {
@@ -26,6 +50,17 @@ impl FfDevice {
     pub fn set_ff_state(&mut self, strong: u16, weak: u16, min_duration: Duration) {
         self.inner.set_ff_state(strong, weak, min_duration)
     }
+
+    /// Like [`set_ff_state()`](Self::set_ff_state), but returns error if writing to device
+    /// failed instead of logging it. Backends that can't detect failure always return `Ok`.
+    pub fn try_set_ff_state(
+        &mut self,
+        strong: u16,
+        weak: u16,
+        min_duration: Duration,
+    ) -> io::Result<()> {
+        self.inner.try_set_ff_state(strong, weak, min_duration)
+    }
 }
 
 /// Holds information about gamepad event.
@@ -36,7 +71,7 @@ pub struct Event {
     pub id: usize,
     /// Event's data.
     pub event: EventType,
-    /// Time when event was emitted.
+    /// Time when event was emitted. Its meaning depends on [`Gilrs::timestamp_source()`].
     pub time: SystemTime,
 }
 
@@ -49,6 +84,7 @@ impl Event {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 /// Gamepad event.
 #[non_exhaustive]
 pub enum EventType {
@@ -60,13 +96,47 @@ pub enum EventType {
 }
 
 /// Holds information about expected axis range and deadzone.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 pub struct AxisInfo {
     pub min: i32,
     pub max: i32,
     pub deadzone: Option<u32>,
 }
 
+/// Name, UUID and elements of gamepad added by [`Gilrs::add_replay_gamepad()`].
+///
+/// `EvCode`s are platform specific, so device can only be replayed by the backend it was
+/// described on, see [`BACKEND_NAME`].
+///
+/// Available with `replay` feature.
+#[cfg(feature = "replay")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct ReplayDevice {
+    pub name: String,
+    pub uuid: [u8; 16],
+    pub buttons: Vec<EvCode>,
+    pub axes: Vec<(EvCode, AxisInfo)>,
+}
+
+#[cfg(feature = "replay")]
+impl ReplayDevice {
+    /// Describes `gamepad`, so its events can be recorded and replayed later.
+    pub fn from_gamepad(gamepad: &Gamepad) -> Self {
+        ReplayDevice {
+            name: gamepad.name().to_owned(),
+            uuid: gamepad.uuid(),
+            buttons: gamepad.buttons().to_vec(),
+            axes: gamepad
+                .axes()
+                .iter()
+                .filter_map(|&axis| Some((axis, *gamepad.axis_info(axis)?)))
+                .collect(),
+        }
+    }
+}
+
 /// State of device's power supply.
 ///
 /// Battery level is reported as integer between 0 and 100.
@@ -84,6 +154,7 @@ pub struct AxisInfo {
 /// };
 /// ```
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 pub enum PowerInfo {
     /// Failed to determine power status.
     Unknown,
@@ -97,29 +168,252 @@ pub enum PowerInfo {
     Charged,
 }
 
+/// Options of the platform backend, see [`Gilrs::with_options()`].
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct BackendOptions {
+    /// Windows Gaming Input only: read all controllers as `RawGameController`, even if they can be
+    /// read as WGI `Gamepad`. Such controllers get a non-nil UUID, so SDL mappings are used for
+    /// them. Ignored by other backends.
+    pub prefer_raw_readings: bool,
+    /// Linux only: grab every opened gamepad with `EVIOCGRAB`, see
+    /// [`Gamepad::grab()`]. Failures are reported by [`Gamepad::grab_error()`]. Ignored by other
+    /// backends.
+    pub exclusive_device_access: bool,
+    /// Linux only: also accept devices that have joystick buttons, but not the two axes usually
+    /// required from gamepads, like digital-only arcade sticks. Keyboards and mice are still
+    /// ignored. Ignored by other backends.
+    pub accept_buttons_only: bool,
+    /// Which time is used as [`Event::time`]. Only Linux can report [`TimestampSource::Hardware`]
+    /// timestamps; other backends always use arrival time.
+    pub timestamp_source: TimestampSource,
+}
+
+/// Meaning of [`Event::time`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TimestampSource {
+    /// Time when the kernel received input from the device. It's not delayed by polling, so it
+    /// best reflects when the input happened, but it can be earlier than time when `Gilrs` was
+    /// asked for events and is not monotonic across devices.
+    #[default]
+    Hardware,
+    /// Time when the backend read the event. Includes latency of polling and event processing.
+    Arrival,
+}
+
+/// Devices found when [`Gilrs`] was created, see [`Gilrs::enumeration_report()`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct EnumerationReport {
+    /// Every device that could be a gamepad, in order in which they were found.
+    pub devices: Vec<EnumeratedDevice>,
+}
+
+impl EnumerationReport {
+    /// Returns number of devices that were opened as gamepads.
+    pub fn opened(&self) -> usize {
+        self.devices
+            .iter()
+            .filter(|dev| dev.status == DeviceStatus::Opened)
+            .count()
+    }
+
+    /// Returns `true` if some devices were found, but all of them, except ones ignored because
+    /// they aren't gamepads, failed to open because of missing permissions. This usually means
+    /// that application runs in a sandbox without access to input devices or that user doesn't
+    /// have access to them (missing udev rule).
+    pub fn is_permission_denied(&self) -> bool {
+        let mut candidates = self
+            .devices
+            .iter()
+            .filter(|dev| dev.status != DeviceStatus::Ignored)
+            .peekable();
+
+        candidates.peek().is_some() && candidates.all(|dev| dev.status.is_permission_denied())
+    }
+}
+
+/// Device found when [`Gilrs`] was created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct EnumeratedDevice {
+    /// Path to the device, for example `/dev/input/event3`.
+    pub path: String,
+    /// Result of opening the device.
+    pub status: DeviceStatus,
+}
+
+/// Result of opening device, see [`EnumeratedDevice`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DeviceStatus {
+    /// Device was opened as gamepad.
+    Opened,
+    /// Device was opened, but it isn't a gamepad.
+    Ignored,
+    /// Opening the device failed with OS error `errno`.
+    OpenFailed { errno: i32 },
+}
+
+impl DeviceStatus {
+    /// Returns `true` if opening the device failed because of missing permissions.
+    pub fn is_permission_denied(&self) -> bool {
+        match *self {
+            DeviceStatus::OpenFailed { errno } => {
+                std::io::Error::from_raw_os_error(errno).kind()
+                    == std::io::ErrorKind::PermissionDenied
+            }
+            _ => false,
+        }
+    }
+}
+
 /// Struct used to manage gamepads and retrieve events.
 #[derive(Debug)]
 pub struct Gilrs {
     inner: platform::Gilrs,
+    timestamp_source: TimestampSource,
+    // Events added by `insert_replay_event()`.
+    #[cfg(feature = "replay")]
+    replay_events: VecDeque<Event>,
 }
 
 impl Gilrs {
+    #[allow(clippy::result_large_err)]
     pub fn new() -> Result<Self, Error> {
-        let inner = platform::Gilrs::new().map_err(|e| match e {
-            PlatformError::NotImplemented(inner) => Error::NotImplemented(Gilrs { inner }),
+        Self::with_options(&BackendOptions::default())
+    }
+
+    /// Same as [`new()`](Self::new), but with custom options.
+    #[allow(clippy::result_large_err)]
+    pub fn with_options(options: &BackendOptions) -> Result<Self, Error> {
+        #[cfg(any(target_os = "linux", all(target_os = "windows", feature = "wgi")))]
+        let inner = platform::Gilrs::with_options(options);
+        #[cfg(not(any(target_os = "linux", all(target_os = "windows", feature = "wgi"))))]
+        let inner = {
+            let _ = options;
+            platform::Gilrs::new()
+        };
+
+        let timestamp_source = if cfg!(target_os = "linux") {
+            options.timestamp_source
+        } else {
+            TimestampSource::Arrival
+        };
+
+        let inner = inner.map_err(|e| match e {
+            PlatformError::NotImplemented(inner) => Error::NotImplemented(Gilrs {
+                inner,
+                timestamp_source,
+                #[cfg(feature = "replay")]
+                replay_events: VecDeque::new(),
+            }),
             PlatformError::Other(e) => Error::Other(e),
         })?;
 
-        Ok(Gilrs { inner })
+        Ok(Gilrs {
+            inner,
+            timestamp_source,
+            #[cfg(feature = "replay")]
+            replay_events: VecDeque::new(),
+        })
+    }
+
+    /// Returns devices found when `Gilrs` was created and results of opening them. Use it to
+    /// explain why no gamepads were found, for example with
+    /// [`EnumerationReport::is_permission_denied()`].
+    ///
+    /// Only Linux backend reports devices, other backends return empty report.
+    pub fn enumeration_report(&self) -> &EnumerationReport {
+        #[cfg(target_os = "linux")]
+        {
+            self.inner.enumeration_report()
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            static EMPTY: EnumerationReport = EnumerationReport {
+                devices: Vec::new(),
+            };
+            &EMPTY
+        }
+    }
+
+    /// Frees resources held by disconnected gamepads, like open files and event buffers. Name,
+    /// UUID and elements of gamepads are kept, so reconnected gamepad still gets the same id.
+    ///
+    /// Only Linux backend keeps such resources, on other backends this does nothing.
+    pub fn compact(&mut self) {
+        #[cfg(target_os = "linux")]
+        self.inner.compact();
+    }
+
+    /// Returns which time is used as [`Event::time`]. It can differ from
+    /// [`BackendOptions::timestamp_source`] if backend doesn't support requested source.
+    pub fn timestamp_source(&self) -> TimestampSource {
+        self.timestamp_source
+    }
+
+    /// Adds gamepad that is not backed by any device and returns its id. Gamepad starts
+    /// disconnected and only reports events added with
+    /// [`insert_replay_event()`](Self::insert_replay_event), so together they can replay recorded
+    /// events of a real gamepad.
+    ///
+    /// Only supported by Linux backend, other backends return `None`. Available with `replay`
+    /// feature.
+    #[cfg(feature = "replay")]
+    pub fn add_replay_gamepad(&mut self, device: &ReplayDevice) -> Option<usize> {
+        #[cfg(target_os = "linux")]
+        {
+            Some(self.inner.add_replay_gamepad(device))
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = device;
+            None
+        }
+    }
+
+    /// Adds `event` of gamepad added by [`add_replay_gamepad()`](Self::add_replay_gamepad) to
+    /// queue of replayed events, which are returned before events of real gamepads. `Connected`
+    /// and `Disconnected` events change whether the gamepad is connected when they are returned.
+    ///
+    /// Available with `replay` feature.
+    #[cfg(feature = "replay")]
+    pub fn insert_replay_event(&mut self, event: Event) {
+        self.replay_events.push_back(event);
+    }
+
+    #[cfg(feature = "replay")]
+    fn next_replay_event(&mut self) -> Option<Event> {
+        let event = self.replay_events.pop_front()?;
+        #[cfg(target_os = "linux")]
+        match event.event {
+            EventType::Connected => self.inner.set_replay_connected(event.id, true),
+            EventType::Disconnected => self.inner.set_replay_connected(event.id, false),
+            _ => (),
+        }
+
+        Some(event)
     }
 
     /// Returns oldest event or `None` if all events were processed.
     pub fn next_event(&mut self) -> Option<Event> {
+        #[cfg(feature = "replay")]
+        if let Some(event) = self.next_replay_event() {
+            return Some(event);
+        }
+
         self.inner.next_event()
     }
 
     /// Returns oldest event, waiting for new event if necessary.
     pub fn next_event_blocking(&mut self, timeout: Option<Duration>) -> Option<Event> {
+        #[cfg(feature = "replay")]
+        if let Some(event) = self.next_replay_event() {
+            return Some(event);
+        }
+
         self.inner.next_event_blocking(timeout)
     }
 
@@ -132,6 +426,16 @@ impl Gilrs {
         }
     }
 
+    /// Borrows `Gamepad`. If `id` was never observed, returns shared placeholder that is always
+    /// disconnected and has no buttons or axes.
+    pub fn gamepad_or_placeholder(&self, id: usize) -> &Gamepad {
+        unsafe {
+            let gp: &platform::Gamepad = self.inner.gamepad_or_placeholder(id);
+
+            &*(gp as *const _ as *const Gamepad)
+        }
+    }
+
     /// Returns id greater than id of last connected gamepad. The returned value is only hint
     /// and may be much larger than number of observed gamepads. For example, it may return maximum
     /// number of connected gamepads on platforms when this limit is small.
@@ -143,6 +447,14 @@ impl Gilrs {
     }
 }
 
+impl Index<usize> for Gilrs {
+    type Output = Gamepad;
+
+    fn index(&self, id: usize) -> &Gamepad {
+        self.gamepad_or_placeholder(id)
+    }
+}
+
 /// Provides information about gamepad.
 #[derive(Debug)]
 #[repr(transparent)]
@@ -152,15 +464,32 @@ pub struct Gamepad {
 
 impl Gamepad {
     /// Returns name of gamepad.
+    ///
+    /// On Linux, name is converted to UTF-8 lossily, invalid sequences are replaced with
+    /// `U+FFFD`. Original bytes are available from [`raw_name()`](#method.raw_name).
     pub fn name(&self) -> &str {
         self.inner.name()
     }
 
+    /// Returns name of gamepad exactly as it was reported by OS when gamepad was opened, without
+    /// UTF-8 conversion. Other platforms than Linux provide names as UTF-8 strings, so it's the
+    /// same as `name()`.
+    pub fn raw_name(&self) -> &[u8] {
+        self.inner.raw_name()
+    }
+
     /// Returns true if gamepad is connected.
     pub fn is_connected(&self) -> bool {
         self.inner.is_connected()
     }
 
+    /// Returns true if gamepad is connected and backend is listening for its events. This can be
+    /// false for connected gamepad if registering it for events failed; backend retries it on every
+    /// call to `Gilrs::next_event()`.
+    pub fn is_receiving_events(&self) -> bool {
+        self.inner.is_receiving_events()
+    }
+
     /// Returns UUID that represents gamepad model.
     ///
     /// Returned UUID should be the same as SLD2 uses. If platform does not provide any method to
@@ -172,6 +501,12 @@ impl Gamepad {
         *self.inner.uuid().as_bytes()
     }
 
+    /// Returns bustype, vendor ID, product ID and version encoded in [`uuid()`](Self::uuid).
+    /// Fields that backend doesn't provide are zero, for example all of them for nil UUID.
+    pub fn guid_components(&self) -> GuidComponents {
+        GuidComponents::from_uuid(self.uuid())
+    }
+
     /// Returns the vendor ID, as assigned by the USB-IF, when available.
     pub fn vendor_id(&self) -> Option<u16> {
         self.inner.vendor_id()
@@ -182,6 +517,49 @@ impl Gamepad {
         self.inner.product_id()
     }
 
+    /// Returns platform specific path or identifier of the device, when available.
+    ///
+    /// * Linux: path of the evdev device node, for example `/dev/input/event5`.
+    /// * macOS: IOKit location ID formatted as hexadecimal number, for example `0x14100000`.
+    /// * Windows Gaming Input: HID device interface path, if exactly one HID device with the same
+    ///   vendor and product ID is present. `None` when there are multiple such devices.
+    /// * XInput: `xinput/N`, where `N` is the XInput slot.
+    /// * Web and unsupported platforms: always `None`.
+    ///
+    /// The value is resolved when the gamepad is connected, except on Windows Gaming Input where
+    /// it's looked up on the first call, which can block for a noticeable amount of time. It can
+    /// change when the gamepad is reconnected, so it should not be used as a persistent identifier.
+    pub fn device_path(&self) -> Option<&str> {
+        self.inner.device_path()
+    }
+
+    /// Returns platform specific identifier of the physical port the device is plugged into, when
+    /// available. Unlike [`device_path()`](Self::device_path), it stays the same when the device
+    /// is reconnected to the same port or replaced by another device, so it can be used to assign
+    /// players to ports.
+    ///
+    /// * Linux: sysfs path of the USB port, for example
+    ///   `/sys/devices/pci0000:00/0000:00:14.0/usb1/1-2`. `None` for Bluetooth and virtual devices.
+    /// * macOS: IOKit location ID formatted as hexadecimal number, for example `0x14100000`. It
+    ///   encodes the bus and the ports of all hubs on the way to the device.
+    /// * Windows, web and unsupported platforms: always `None`.
+    pub fn connection_port(&self) -> Option<&str> {
+        self.inner.connection_port()
+    }
+
+    /// Returns why gamepad is considered virtual, for example created by Steam Input, or `None`
+    /// if it looks like physical device. Detection is based on heuristics:
+    ///
+    /// * all platforms: known name, vendor and product ID of Steam Input virtual gamepad and IDs
+    ///   of known emulators,
+    /// * Linux: devices without parent device in sysfs, like devices created with uinput.
+    ///
+    /// Emulators that copy IDs of real gamepads can't be recognized. XInput backend can't
+    /// recognize any virtual gamepads.
+    pub fn virtual_reason(&self) -> Option<VirtualReason> {
+        self.inner.virtual_reason()
+    }
+
     /// Returns device's power supply state.
     pub fn power_info(&self) -> PowerInfo {
         self.inner.power_info()
@@ -192,6 +570,26 @@ impl Gamepad {
         self.inner.is_ff_supported()
     }
 
+    /// Takes exclusive access to the device, so other applications (including desktop
+    /// environment) will not receive its events until [`ungrab()`](Self::ungrab) is called or
+    /// gamepad is dropped. Calling it on already grabbed gamepad does nothing.
+    ///
+    /// Only supported on Linux, other backends return `GrabError::NotSupported`.
+    pub fn grab(&self) -> Result<(), GrabError> {
+        self.inner.set_grabbed(true)
+    }
+
+    /// Releases exclusive access taken by [`grab()`](Self::grab).
+    pub fn ungrab(&self) -> Result<(), GrabError> {
+        self.inner.set_grabbed(false)
+    }
+
+    /// Returns error of grab requested with [`BackendOptions::exclusive_device_access`] when the
+    /// device was opened, or `None` if grab succeeded or wasn't requested.
+    pub fn grab_error(&self) -> Option<&GrabError> {
+        self.inner.grab_error()
+    }
+
     /// Creates `FfDevice` corresponding to this gamepad.
     pub fn ff_device(&self) -> Option<FfDevice> {
         self.inner.ff_device().map(|inner| FfDevice { inner })
@@ -306,6 +704,33 @@ impl error::Error for Error {
     }
 }
 
+/// Error returned by [`Gamepad::grab()`] and [`Gamepad::ungrab()`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrabError {
+    /// Current backend doesn't support exclusive access to devices.
+    NotSupported,
+    /// Gamepad is not connected.
+    Disconnected,
+    /// Device is already grabbed by other application.
+    Busy,
+    /// Platform specific error.
+    Other(String),
+}
+
+impl Display for GrabError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GrabError::NotSupported => f.write_str("Grabbing devices is not supported by backend."),
+            GrabError::Disconnected => f.write_str("Gamepad is not connected."),
+            GrabError::Busy => f.write_str("Device is already grabbed by other application."),
+            GrabError::Other(e) => f.write_str(e),
+        }
+    }
+}
+
+impl error::Error for GrabError {}
+
 /// Provides the most common mappings of physical location of gamepad elements to their EvCodes.
 /// Some (or most) gamepads may use different mappings.
 pub mod native_ev_codes {
@@ -345,4 +770,6 @@ pub mod native_ev_codes {
     pub const BTN_DPAD_DOWN: EvCode = EvCode(nec::BTN_DPAD_DOWN);
     pub const BTN_DPAD_LEFT: EvCode = EvCode(nec::BTN_DPAD_LEFT);
     pub const BTN_DPAD_RIGHT: EvCode = EvCode(nec::BTN_DPAD_RIGHT);
+
+    pub const BTN_MISC1: EvCode = EvCode(nec::BTN_MISC1);
 }
@@ -5,6 +5,7 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use std::io::Result as IoResult;
 use std::time::Duration;
 
 #[derive(Debug)]
@@ -14,4 +15,13 @@ pub struct Device;
 impl Device {
     /// Sets magnitude for strong and weak ff motors.
     pub fn set_ff_state(&mut self, strong: u16, weak: u16, min_duration: Duration) {}
+
+    pub fn try_set_ff_state(
+        &mut self,
+        strong: u16,
+        weak: u16,
+        min_duration: Duration,
+    ) -> IoResult<()> {
+        Ok(())
+    }
 }
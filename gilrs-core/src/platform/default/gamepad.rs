@@ -7,18 +7,23 @@
 #![allow(unused_variables)]
 
 use super::FfDevice;
-use crate::{AxisInfo, Event, PlatformError, PowerInfo};
+use crate::virtual_device::{self, DeviceDescriptor, VirtualReason};
+use crate::{AxisInfo, Event, GrabError, PlatformError, PowerInfo};
 use uuid::Uuid;
 
 use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::time::Duration;
 
 #[derive(Debug)]
-pub struct Gilrs {}
+pub struct Gilrs {
+    not_observed: Gamepad,
+}
 
 impl Gilrs {
     pub(crate) fn new() -> Result<Self, PlatformError> {
-        Err(PlatformError::NotImplemented(Gilrs {}))
+        Err(PlatformError::NotImplemented(Gilrs {
+            not_observed: Gamepad { _priv: 0 },
+        }))
     }
 
     pub(crate) fn next_event(&mut self) -> Option<Event> {
@@ -33,6 +38,10 @@ impl Gilrs {
         None
     }
 
+    pub fn gamepad_or_placeholder(&self, id: usize) -> &Gamepad {
+        &self.not_observed
+    }
+
     /// Returns index greater than index of last connected gamepad.
     pub fn last_gamepad_hint(&self) -> usize {
         0
@@ -49,6 +58,10 @@ impl Gamepad {
         ""
     }
 
+    pub fn raw_name(&self) -> &[u8] {
+        self.name().as_bytes()
+    }
+
     pub fn uuid(&self) -> Uuid {
         Uuid::nil()
     }
@@ -61,10 +74,35 @@ impl Gamepad {
         None
     }
 
+    pub fn device_path(&self) -> Option<&str> {
+        None
+    }
+
+    pub fn grab_error(&self) -> Option<&GrabError> {
+        None
+    }
+
+    pub fn connection_port(&self) -> Option<&str> {
+        None
+    }
+
+    pub fn virtual_reason(&self) -> Option<VirtualReason> {
+        virtual_device::detect(&DeviceDescriptor {
+            name: self.name(),
+            vendor_id: self.vendor_id(),
+            product_id: self.product_id(),
+            sysfs_path: None,
+        })
+    }
+
     pub fn power_info(&self) -> PowerInfo {
         PowerInfo::Unknown
     }
 
+    pub fn set_grabbed(&self, _grabbed: bool) -> Result<(), GrabError> {
+        Err(GrabError::NotSupported)
+    }
+
     pub fn is_ff_supported(&self) -> bool {
         false
     }
@@ -89,6 +127,10 @@ impl Gamepad {
     pub fn is_connected(&self) -> bool {
         false
     }
+
+    pub fn is_receiving_events(&self) -> bool {
+        self.is_connected()
+    }
 }
 
 #[cfg(feature = "serde-serialize")]
@@ -146,4 +188,6 @@ pub mod native_ev_codes {
     pub const BTN_DPAD_DOWN: EvCode = EvCode(28);
     pub const BTN_DPAD_LEFT: EvCode = EvCode(29);
     pub const BTN_DPAD_RIGHT: EvCode = EvCode(30);
+
+    pub const BTN_MISC1: EvCode = EvCode(31);
 }
@@ -6,7 +6,7 @@
 // copied, modified, or distributed except according to those terms.
 
 use std::fs::File;
-use std::io::{Error as IoError, ErrorKind, Result as IoResult, Write};
+use std::io::{Error as IoError, Result as IoResult, Write};
 use std::os::unix::io::AsRawFd;
 use std::{mem, slice};
 
@@ -36,7 +36,7 @@ impl Device {
         let res = unsafe { ioctl::eviocsff(file.as_raw_fd(), &mut effect) };
 
         if res.is_err() {
-            Err(IoError::new(ErrorKind::Other, "Failed to create effect"))
+            Err(IoError::other("Failed to create effect"))
         } else {
             Ok(Device {
                 effect: effect.id,
@@ -46,6 +46,17 @@ impl Device {
     }
 
     pub fn set_ff_state(&mut self, strong: u16, weak: u16, min_duration: Duration) {
+        if let Err(e) = self.try_set_ff_state(strong, weak, min_duration) {
+            error!("Failed to set ff state of gamepad {:?}: {}", self.file, e);
+        }
+    }
+
+    pub fn try_set_ff_state(
+        &mut self,
+        strong: u16,
+        weak: u16,
+        min_duration: Duration,
+    ) -> IoResult<()> {
         let duration = min_duration.as_secs() * 1000 + u64::from(min_duration.subsec_millis());
         let duration = if duration > u64::from(u16::MAX) {
             u16::MAX
@@ -70,14 +81,7 @@ impl Device {
             (*rumble).strong_magnitude = strong;
             (*rumble).weak_magnitude = weak;
 
-            if let Err(err) = ioctl::eviocsff(self.file.as_raw_fd(), &effect) {
-                error!(
-                    "Failed to modify effect of gamepad {:?}, error: {}",
-                    self.file, err
-                );
-
-                return;
-            }
+            ioctl::eviocsff(self.file.as_raw_fd(), &effect)?;
         };
 
         let time = libc::timeval {
@@ -94,10 +98,9 @@ impl Device {
         let size = mem::size_of::<input_event>();
         let s = unsafe { slice::from_raw_parts(&ev as *const _ as *const u8, size) };
 
-        match self.file.write(s) {
-            Ok(s) if s == size => (),
-            Ok(_) => unreachable!(),
-            Err(e) => error!("Failed to set ff state: {}", e),
+        match self.file.write(s)? {
+            s if s == size => Ok(()),
+            _ => unreachable!(),
         }
     }
 }
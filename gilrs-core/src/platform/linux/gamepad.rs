@@ -10,8 +10,12 @@ use super::ioctl;
 use super::ioctl::{input_absinfo, input_event};
 use super::udev::*;
 use crate::utils;
-use crate::{AxisInfo, Event, EventType};
-use crate::{PlatformError, PowerInfo};
+use crate::virtual_device::{self, DeviceDescriptor, VirtualReason};
+#[cfg(feature = "replay")]
+use crate::ReplayDevice;
+use crate::{AxisInfo, BackendOptions, Event, EventType, TimestampSource};
+use crate::{DeviceStatus, EnumeratedDevice, EnumerationReport};
+use crate::{GrabError, GuidComponents, PlatformError, PowerInfo};
 
 use libc as c;
 use uuid::Uuid;
@@ -21,32 +25,82 @@ use inotify::{EventMask, Inotify, WatchMask};
 use nix::errno::Errno;
 use nix::sys::epoll::{Epoll, EpollCreateFlags, EpollEvent, EpollFlags, EpollTimeout};
 use nix::sys::eventfd::{EfdFlags, EventFd};
+use nix::sys::ioctl::ioctl_param_type;
+use std::borrow::Cow;
 use std::collections::VecDeque;
 use std::error;
 use std::ffi::OsStr;
 use std::ffi::{CStr, CString};
 use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::fs::File;
+use std::io;
 use std::mem::{self, MaybeUninit};
 use std::ops::Index;
 use std::os::raw::c_char;
 use std::os::unix::ffi::OsStrExt;
-use std::os::unix::io::{BorrowedFd, RawFd};
+use std::os::unix::io::{AsRawFd, BorrowedFd, RawFd};
 use std::path::{Path, PathBuf};
 use std::str;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
-use std::sync::mpsc::{Receiver, Sender};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-const HOTPLUG_DATA: u64 = u64::MAX;
+/// Epoll data values starting from this one are reserved for file descriptors other than
+/// gamepads. Lower values are indices of gamepads.
+const RESERVED_DATA_START: u64 = u64::MAX - 0xff;
+const HOTPLUG_DATA: u64 = RESERVED_DATA_START;
+
+/// Source of epoll event, decoded from its data.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum EpollSource {
+    Hotplug,
+    Gamepad(usize),
+    Unknown(u64),
+}
+
+impl EpollSource {
+    fn from_data(data: u64) -> Self {
+        if data == HOTPLUG_DATA {
+            EpollSource::Hotplug
+        } else if data >= RESERVED_DATA_START {
+            EpollSource::Unknown(data)
+        } else {
+            match usize::try_from(data) {
+                Ok(idx) => EpollSource::Gamepad(idx),
+                Err(_) => EpollSource::Unknown(data),
+            }
+        }
+    }
+
+    /// Returns epoll data of gamepad with index `idx` or `None` if it would fall into reserved
+    /// range.
+    fn gamepad_data(idx: usize) -> Option<u64> {
+        u64::try_from(idx)
+            .ok()
+            .filter(|&data| data < RESERVED_DATA_START)
+    }
+}
 
 #[derive(Debug)]
 pub struct Gilrs {
     gamepads: Vec<Gamepad>,
     epoll: Epoll,
-    hotplug_rx: Receiver<HotplugEvent>,
+    // Connected and Disconnected events that weren't returned yet.
+    hotplug_events: VecDeque<Event>,
     to_check: VecDeque<usize>,
     discovery_backend: DiscoveryBackend,
+    // Grab every opened gamepad with EVIOCGRAB.
+    exclusive_access: bool,
+    // Accept devices without axes, see `BackendOptions::accept_buttons_only`.
+    accept_buttons_only: bool,
+    timestamp_source: TimestampSource,
+    enumeration_report: Box<EnumerationReport>,
+    not_observed: Box<Gamepad>,
+    // Stopped and joined when `Gilrs` is dropped.
+    hotplug: Box<HotplugThread>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -58,8 +112,12 @@ enum DiscoveryBackend {
 const INPUT_DIR_PATH: &str = "/dev/input";
 
 impl Gilrs {
-    pub(crate) fn new() -> Result<Self, PlatformError> {
+    pub(crate) fn with_options(options: &BackendOptions) -> Result<Self, PlatformError> {
+        let exclusive_access = options.exclusive_device_access;
+        let accept_buttons_only = options.accept_buttons_only;
+        let timestamp_source = options.timestamp_source;
         let mut gamepads = Vec::new();
+        let mut enumeration_report = EnumerationReport::default();
         let epoll = Epoll::new(EpollCreateFlags::empty())
             .map_err(|e| errno_to_platform_error(e, "creating epoll fd"))?;
 
@@ -74,10 +132,9 @@ impl Gilrs {
 
         if Path::new("/.flatpak-info").exists() || std::env::var("GILRS_DISABLE_UDEV").is_ok() {
             log::debug!("Looks like we're in an environment without udev. Falling back to inotify");
-            let (hotplug_tx, hotplug_rx) = mpsc::channel();
             let mut inotify = Inotify::init().map_err(|err| PlatformError::Other(Box::new(err)))?;
             let input_dir = Path::new(INPUT_DIR_PATH);
-            inotify
+            let watch = inotify
                 .watches()
                 .add(
                     input_dir,
@@ -99,43 +156,63 @@ impl Gilrs {
                     None => continue,
                 };
                 let devpath = CString::new(gamepad_path.to_str().unwrap()).unwrap();
-                if let Some(gamepad) = Gamepad::open(&devpath, &syspath, DiscoveryBackend::Inotify)
-                {
+                let gamepad = Gamepad::open(
+                    &devpath,
+                    &syspath,
+                    DiscoveryBackend::Inotify,
+                    exclusive_access,
+                    accept_buttons_only,
+                );
+                // Every event node is opened here, but keyboards and mice often can't be opened
+                // without root. It doesn't mean that gamepads can't, so they are not reported.
+                if gamepad.is_ok() || may_be_joystick(&syspath) {
+                    record_open(&mut enumeration_report, &devpath, &gamepad);
+                }
+                if let Ok(mut gamepad) = gamepad {
                     let idx = gamepads.len();
-                    gamepad
-                        .register_fd(&epoll, idx as u64)
-                        .map_err(|e| errno_to_platform_error(e, "registering gamepad in epoll"))?;
+                    if let Err(e) = gamepad.register_fd(&epoll, idx) {
+                        error!("Failed to add gamepad to epoll, will retry later: {}", e);
+                    }
                     gamepads.push(gamepad);
                 }
             }
 
-            std::thread::Builder::new()
-                .name("gilrs".to_owned())
-                .spawn(move || {
-                    let mut buffer = [0u8; 1024];
-                    debug!("Started gilrs inotify thread");
-                    loop {
-                        let events = match inotify.read_events_blocking(&mut buffer) {
-                            Ok(events) => events,
-                            Err(err) => {
-                                error!("Failed to check for changes to joysticks: {err}");
-                                return;
-                            }
-                        };
-                        for event in events {
-                            if !handle_inotify(&hotplug_tx, event, &mut hotplug_event) {
-                                return;
-                            }
+            let hotplug_thread = HotplugThread::spawn(move |hotplug_tx, shutdown| {
+                let mut buffer = [0u8; 1024];
+                debug!("Started gilrs inotify thread");
+                'wait: while wait_readable(inotify.as_raw_fd(), shutdown) {
+                    let events = match inotify.read_events(&mut buffer) {
+                        Ok(events) => events,
+                        Err(err) if err.kind() == io::ErrorKind::WouldBlock => continue,
+                        Err(err) => {
+                            error!("Failed to check for changes to joysticks: {err}");
+                            break;
+                        }
+                    };
+                    for event in events {
+                        if !handle_inotify(&hotplug_tx, event, &mut hotplug_event) {
+                            break 'wait;
                         }
                     }
-                })
-                .expect("failed to spawn thread");
+                }
+
+                if let Err(err) = inotify.watches().remove(watch) {
+                    debug!("Failed to remove inotify watch: {err}");
+                }
+            })?;
+            warn_if_permission_denied(&enumeration_report);
             return Ok(Gilrs {
                 gamepads,
                 epoll,
-                hotplug_rx,
+                hotplug_events: VecDeque::new(),
                 to_check: VecDeque::new(),
                 discovery_backend: DiscoveryBackend::Inotify,
+                exclusive_access,
+                accept_buttons_only,
+                timestamp_source,
+                enumeration_report: Box::new(enumeration_report),
+                not_observed: Box::new(Gamepad::not_observed()),
+                hotplug: Box::new(hotplug_thread),
             });
         }
         let udev = match Udev::new() {
@@ -162,46 +239,57 @@ impl Gilrs {
                     None => continue,
                 };
                 let syspath = Path::new(OsStr::from_bytes(dev.syspath().to_bytes()));
-                if let Some(gamepad) = Gamepad::open(devpath, syspath, DiscoveryBackend::Udev) {
+                let gamepad = Gamepad::open(
+                    devpath,
+                    syspath,
+                    DiscoveryBackend::Udev,
+                    exclusive_access,
+                    accept_buttons_only,
+                );
+                record_open(&mut enumeration_report, devpath, &gamepad);
+                if let Ok(mut gamepad) = gamepad {
                     let idx = gamepads.len();
-                    gamepad
-                        .register_fd(&epoll, idx as u64)
-                        .map_err(|e| errno_to_platform_error(e, "registering gamepad in epoll"))?;
+                    if let Err(e) = gamepad.register_fd(&epoll, idx) {
+                        error!("Failed to add gamepad to epoll, will retry later: {}", e);
+                    }
                     gamepads.push(gamepad);
                 }
             }
         }
 
-        let (hotplug_tx, hotplug_rx) = mpsc::channel();
-        std::thread::Builder::new()
-            .name("gilrs".to_owned())
-            .spawn(move || {
-                let udev = match Udev::new() {
-                    Some(udev) => udev,
-                    None => {
-                        error!("Failed to create udev for hot plug thread!");
-                        return;
-                    }
-                };
+        let hotplug_thread = HotplugThread::spawn(move |hotplug_tx, shutdown| {
+            let udev = match Udev::new() {
+                Some(udev) => udev,
+                None => {
+                    error!("Failed to create udev for hot plug thread!");
+                    return;
+                }
+            };
 
-                let monitor = match Monitor::new(&udev) {
-                    Some(m) => m,
-                    None => {
-                        error!("Failed to create udev monitor for hot plug thread!");
-                        return;
-                    }
-                };
+            let monitor = match Monitor::new(&udev) {
+                Some(m) => m,
+                None => {
+                    error!("Failed to create udev monitor for hot plug thread!");
+                    return;
+                }
+            };
 
-                handle_hotplug(hotplug_tx, monitor, hotplug_event)
-            })
-            .expect("failed to spawn thread");
+            handle_hotplug(hotplug_tx, monitor, hotplug_event, shutdown)
+        })?;
 
+        warn_if_permission_denied(&enumeration_report);
         Ok(Gilrs {
             gamepads,
             epoll,
-            hotplug_rx,
+            hotplug_events: VecDeque::new(),
             to_check: VecDeque::new(),
             discovery_backend: DiscoveryBackend::Udev,
+            exclusive_access,
+            accept_buttons_only,
+            timestamp_source,
+            enumeration_report: Box::new(enumeration_report),
+            not_observed: Box::new(Gamepad::not_observed()),
+            hotplug: Box::new(hotplug_thread),
         })
     }
 
@@ -216,6 +304,12 @@ impl Gilrs {
     fn next_event_impl(&mut self, timeout: Option<Duration>) -> Option<Event> {
         let mut check_hotplug = false;
 
+        self.register_missing_fds();
+
+        if let Some(event) = self.hotplug_events.pop_front() {
+            return Some(event);
+        }
+
         if self.to_check.is_empty() {
             let mut events = [EpollEvent::new(EpollFlags::empty(), 0); 16];
             let timeout = if let Some(timeout) = timeout {
@@ -238,10 +332,12 @@ impl Gilrs {
 
             for event in events {
                 if event.events().contains(EpollFlags::EPOLLIN) {
-                    if event.data() == HOTPLUG_DATA {
-                        check_hotplug = true;
-                    } else {
-                        self.to_check.push_back(event.data() as usize);
+                    match EpollSource::from_data(event.data()) {
+                        EpollSource::Hotplug => check_hotplug = true,
+                        EpollSource::Gamepad(idx) => self.to_check.push_back(idx),
+                        EpollSource::Unknown(data) => {
+                            warn!("Got epoll event with unknown data {}", data)
+                        }
                     }
                 }
             }
@@ -253,110 +349,285 @@ impl Gilrs {
             }
         }
 
-        while let Some(idx) = self.to_check.front().copied() {
-            let gamepad = match self.gamepads.get_mut(idx) {
-                Some(gp) => gp,
-                None => {
-                    warn!("Somehow got invalid index from event");
-                    self.to_check.pop_front();
-                    return None;
-                }
-            };
-
-            if !gamepad.is_connected {
-                self.to_check.pop_front();
-                continue;
-            }
-
-            match gamepad.event() {
-                Some((event, time)) => {
-                    return Some(Event {
-                        id: idx,
-                        event,
-                        time,
-                    });
-                }
-                None => {
-                    self.to_check.pop_front();
-                    continue;
-                }
-            };
-        }
+        let (id, event, time) = next_gamepad_event(&mut self.gamepads, &mut self.to_check)?;
+        let time = match self.timestamp_source {
+            TimestampSource::Arrival => utils::time_now(),
+            _ => time,
+        };
 
-        None
+        Some(Event { id, event, time })
     }
 
     pub fn gamepad(&self, id: usize) -> Option<&Gamepad> {
         self.gamepads.get(id)
     }
 
+    pub fn gamepad_or_placeholder(&self, id: usize) -> &Gamepad {
+        self.gamepads.get(id).unwrap_or(&self.not_observed)
+    }
+
     pub fn last_gamepad_hint(&self) -> usize {
         self.gamepads.len()
     }
 
+    /// Adds disconnected gamepad without device, see `gilrs_core::Gilrs::add_replay_gamepad()`.
+    #[cfg(feature = "replay")]
+    pub(crate) fn add_replay_gamepad(&mut self, device: &ReplayDevice) -> usize {
+        self.gamepads.push(Gamepad::replay(device));
+        self.gamepads.len() - 1
+    }
+
+    /// Marks replay gamepad as connected or disconnected. Does nothing for real gamepads.
+    #[cfg(feature = "replay")]
+    pub(crate) fn set_replay_connected(&mut self, id: usize, connected: bool) {
+        if let Some(gamepad) = self.gamepads.get_mut(id).filter(|gamepad| gamepad.replay) {
+            gamepad.is_connected = connected;
+        }
+    }
+
+    pub(crate) fn compact(&mut self) {
+        self.gamepads
+            .iter_mut()
+            .filter(|gamepad| !gamepad.is_connected)
+            .for_each(Gamepad::compact);
+    }
+
+    /// Retries adding connected gamepads, which previously failed to register, to epoll. Events
+    /// that arrived in the meantime are read immediately.
+    fn register_missing_fds(&mut self) {
+        for (idx, gamepad) in self.gamepads.iter_mut().enumerate() {
+            if !gamepad.is_connected || gamepad.in_epoll || gamepad.replay {
+                continue;
+            }
+
+            match gamepad.register_fd(&self.epoll, idx) {
+                Ok(()) => {
+                    debug!("Gamepad {} added to epoll after retry.", gamepad.devpath);
+                    self.to_check.push_back(idx);
+                }
+                Err(e) => trace!("Still failing to add gamepad to epoll: {}", e),
+            }
+        }
+    }
+
     fn handle_hotplug(&mut self) -> Option<Event> {
-        while let Ok(event) = self.hotplug_rx.try_recv() {
+        // Device can generate several events when it appears (for example CREATE and ATTRIB with
+        // inotify) or can be quickly unplugged and plugged again, so process all pending events
+        // together.
+        let events = coalesce_hotplug_events(self.hotplug.events.try_iter());
+
+        for event in events {
             match event {
                 HotplugEvent::New { devpath, syspath } => {
-                    // We already know this gamepad, ignore it:
-                    let gamepad_path_str = devpath.clone().to_string_lossy().into_owned();
-                    if self
-                        .gamepads
-                        .iter()
-                        .any(|gamepad| gamepad.devpath == gamepad_path_str && gamepad.is_connected)
-                    {
-                        continue;
-                    }
-                    if let Some(gamepad) = Gamepad::open(&devpath, &syspath, self.discovery_backend)
-                    {
-                        return if let Some(id) = self
-                            .gamepads
-                            .iter()
-                            .position(|gp| gp.uuid() == gamepad.uuid && !gp.is_connected)
-                        {
-                            if let Err(e) = gamepad.register_fd(&self.epoll, id as u64) {
-                                error!("Failed to add gamepad to epoll: {}", e);
-                            }
-                            self.gamepads[id] = gamepad;
-                            Some(Event::new(id, EventType::Connected))
-                        } else {
-                            if let Err(e) =
-                                gamepad.register_fd(&self.epoll, self.gamepads.len() as u64)
-                            {
-                                error!("Failed to add gamepad to epoll: {}", e);
-                            }
-                            self.gamepads.push(gamepad);
-                            Some(Event::new(self.gamepads.len() - 1, EventType::Connected))
-                        };
-                    }
+                    let event = self.connect_gamepad(&devpath, &syspath);
+                    self.hotplug_events.extend(event);
                 }
                 HotplugEvent::Removed(devpath) => {
-                    if let Some(id) = self
-                        .gamepads
-                        .iter()
-                        .position(|gp| devpath == gp.devpath && gp.is_connected)
-                    {
-                        let gamepad_fd = unsafe { BorrowedFd::borrow_raw(self.gamepads[id].fd) };
-                        if let Err(e) = self.epoll.delete(gamepad_fd) {
-                            error!("Failed to remove disconnected gamepad from epoll: {}", e);
-                        }
+                    let event = self.disconnect_gamepad(&devpath);
+                    self.hotplug_events.extend(event);
+                }
+                HotplugEvent::Replugged { devpath, syspath } => {
+                    self.replug_gamepad(&devpath, &syspath);
+                }
+            }
+        }
 
-                        self.gamepads[id].disconnect();
-                        return Some(Event::new(id, EventType::Disconnected));
-                    } else {
-                        debug!("Could not find disconnected gamepad {devpath:?}");
-                    }
+        self.hotplug_events.pop_front()
+    }
+
+    fn connect_gamepad(&mut self, devpath: &CStr, syspath: &Path) -> Option<Event> {
+        // We already know this gamepad, ignore it:
+        let gamepad_path_str = devpath.to_string_lossy().into_owned();
+        if self
+            .gamepads
+            .iter()
+            .any(|gamepad| gamepad.devpath == gamepad_path_str && gamepad.is_connected)
+        {
+            return None;
+        }
+
+        Gamepad::open(
+            devpath,
+            syspath,
+            self.discovery_backend,
+            self.exclusive_access,
+            self.accept_buttons_only,
+        )
+        .ok()
+        .map(|gp| self.add_gamepad(gp))
+    }
+
+    pub(crate) fn enumeration_report(&self) -> &EnumerationReport {
+        &self.enumeration_report
+    }
+
+    fn add_gamepad(&mut self, mut gamepad: Gamepad) -> Event {
+        if let Some(id) = self
+            .gamepads
+            .iter()
+            .position(|gp| gp.uuid() == gamepad.uuid && !gp.is_connected && !gp.replay)
+        {
+            if let Err(e) = gamepad.register_fd(&self.epoll, id) {
+                error!("Failed to add gamepad to epoll, will retry later: {}", e);
+            }
+            gamepad.keep_late_buttons(&self.gamepads[id]);
+            self.gamepads[id] = gamepad;
+            Event::new(id, EventType::Connected)
+        } else {
+            if let Err(e) = gamepad.register_fd(&self.epoll, self.gamepads.len()) {
+                error!("Failed to add gamepad to epoll, will retry later: {}", e);
+            }
+            self.gamepads.push(gamepad);
+            Event::new(self.gamepads.len() - 1, EventType::Connected)
+        }
+    }
+
+    fn disconnect_gamepad(&mut self, devpath: &str) -> Option<Event> {
+        if let Some(id) = self
+            .gamepads
+            .iter()
+            .position(|gp| devpath == gp.devpath && gp.is_connected)
+        {
+            self.remove_from_epoll(id);
+            self.gamepads[id].disconnect();
+            Some(Event::new(id, EventType::Disconnected))
+        } else {
+            debug!("Could not find disconnected gamepad {devpath:?}");
+            None
+        }
+    }
+
+    /// Handles device that was removed and added again. If it's the same gamepad, it keeps its id,
+    /// see [`replace_gamepad()`](Self::replace_gamepad).
+    fn replug_gamepad(&mut self, devpath: &CStr, syspath: &Path) {
+        let devpath_str = devpath.to_string_lossy().into_owned();
+        let id = match self
+            .gamepads
+            .iter()
+            .position(|gp| gp.devpath == devpath_str && gp.is_connected)
+        {
+            Some(id) => id,
+            None => {
+                let event = self.connect_gamepad(devpath, syspath);
+                self.hotplug_events.extend(event);
+                return;
+            }
+        };
+
+        match Gamepad::open(
+            devpath,
+            syspath,
+            self.discovery_backend,
+            self.exclusive_access,
+            self.accept_buttons_only,
+        ) {
+            Ok(gamepad) if gamepad.uuid == self.gamepads[id].uuid => {
+                debug!(
+                    "Gamepad {} was reconnected, reusing id {}.",
+                    devpath_str, id
+                );
+                self.replace_gamepad(id, gamepad);
+            }
+            gamepad => {
+                let event = self.disconnect_gamepad(&devpath_str);
+                self.hotplug_events.extend(event);
+                if let Ok(gamepad) = gamepad {
+                    let event = self.add_gamepad(gamepad);
+                    self.hotplug_events.push_back(event);
                 }
             }
         }
+    }
+
+    /// Replaces connected gamepad `id` with newly opened device of the same gamepad. `Disconnected`
+    /// and `Connected` events are still emitted, so users stop using state and force feedback
+    /// device of the old node. If old device was grabbed, new one is grabbed too; failure is
+    /// logged and reported by `grab_error()`.
+    fn replace_gamepad(&mut self, id: usize, mut gamepad: Gamepad) {
+        let grabbed = self.gamepads[id].grabbed.load(Ordering::Relaxed);
+        if self.gamepads[id].in_epoll {
+            self.remove_from_epoll(id);
+        }
+        self.gamepads[id].disconnect();
+        self.hotplug_events
+            .push_back(Event::new(id, EventType::Disconnected));
+
+        if let Err(e) = gamepad.register_fd(&self.epoll, id) {
+            error!("Failed to add gamepad to epoll, will retry later: {}", e);
+        }
+        gamepad.keep_late_buttons(&self.gamepads[id]);
+        if grabbed {
+            if let Err(e) = gamepad.set_grabbed(true) {
+                warn!(
+                    "Failed to grab reconnected gamepad {} again: {}",
+                    gamepad.devpath, e
+                );
+                gamepad.grab_error = Some(e);
+            }
+        }
+        self.gamepads[id] = gamepad;
+        self.hotplug_events
+            .push_back(Event::new(id, EventType::Connected));
+    }
 
-        None
+    fn remove_from_epoll(&mut self, id: usize) {
+        let gamepad_fd = unsafe { BorrowedFd::borrow_raw(self.gamepads[id].fd) };
+        if let Err(e) = self.epoll.delete(gamepad_fd) {
+            error!("Failed to remove disconnected gamepad from epoll: {}", e);
+        }
     }
 }
 
+#[derive(Debug, PartialEq)]
 enum HotplugEvent {
     New { devpath: CString, syspath: PathBuf },
     Removed(String),
+    // Only created by `coalesce_hotplug_events()`.
+    Replugged { devpath: CString, syspath: PathBuf },
+}
+
+impl HotplugEvent {
+    fn devpath(&self) -> Cow<'_, str> {
+        match self {
+            HotplugEvent::New { devpath, .. } | HotplugEvent::Replugged { devpath, .. } => {
+                devpath.to_string_lossy()
+            }
+            HotplugEvent::Removed(devpath) => Cow::Borrowed(devpath),
+        }
+    }
+}
+
+/// Reduces burst of hotplug events to at most one event per device. Repeated `New` events are
+/// merged, `Removed` always wins over earlier `New` and `Removed` followed by `New` becomes
+/// `Replugged`. Devices are kept in order of their first event.
+fn coalesce_hotplug_events(events: impl IntoIterator<Item = HotplugEvent>) -> Vec<HotplugEvent> {
+    let mut coalesced: Vec<HotplugEvent> = Vec::new();
+
+    for event in events {
+        let prev = match coalesced
+            .iter_mut()
+            .find(|prev| prev.devpath() == event.devpath())
+        {
+            Some(prev) => prev,
+            None => {
+                coalesced.push(event);
+                continue;
+            }
+        };
+
+        *prev = match (&*prev, event) {
+            (HotplugEvent::Removed(_), HotplugEvent::New { devpath, syspath })
+            | (HotplugEvent::Replugged { .. }, HotplugEvent::New { devpath, syspath }) => {
+                HotplugEvent::Replugged { devpath, syspath }
+            }
+            (HotplugEvent::New { .. }, HotplugEvent::New { devpath, syspath }) => {
+                HotplugEvent::New { devpath, syspath }
+            }
+            (_, event) => event,
+        };
+    }
+
+    coalesced
 }
 
 fn handle_inotify(
@@ -410,28 +681,275 @@ fn handle_inotify(
     true
 }
 
-fn get_gamepad_path(name: &str) -> Option<(PathBuf, PathBuf)> {
-    let event_id =  name.strip_prefix("event")?;
+/// How long dropping `Gilrs` waits for hot plug thread to stop.
+const HOTPLUG_THREAD_STOP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Thread that watches for connected and removed devices. It is stopped and joined on drop.
+#[derive(Debug)]
+struct HotplugThread {
+    events: Receiver<HotplugEvent>,
+    handle: Option<JoinHandle<()>>,
+    shutdown: Arc<EventFd>,
+    // Never receives anything, disconnects when thread exits.
+    exited: Receiver<()>,
+}
 
-    if event_id.is_empty()
-        || event_id
-            .chars()
-            .any(|character| !character.is_ascii_digit())
+impl HotplugThread {
+    /// Spawns thread running `f`. `f` gets sender of hot plug events and file descriptor that
+    /// becomes readable when thread should stop.
+    fn spawn<F>(f: F) -> Result<Self, PlatformError>
+    where
+        F: FnOnce(Sender<HotplugEvent>, RawFd) + Send + 'static,
     {
-        return None;
+        let shutdown = EventFd::from_value_and_flags(0, EfdFlags::EFD_CLOEXEC)
+            .map_err(|e| errno_to_platform_error(e, "creating eventfd"))?;
+        let shutdown = Arc::new(shutdown);
+        let (exited_tx, exited) = mpsc::channel::<()>();
+        let (events_tx, events) = mpsc::channel();
+
+        let thread_shutdown = shutdown.clone();
+        let handle = std::thread::Builder::new()
+            .name("gilrs".to_owned())
+            .spawn(move || {
+                let _exited = exited_tx;
+                f(events_tx, thread_shutdown.as_raw_fd());
+                debug!("Hot plug thread stopped");
+            })
+            .expect("failed to spawn thread");
+
+        Ok(HotplugThread {
+            events,
+            handle: Some(handle),
+            shutdown,
+            exited,
+        })
+    }
+
+    /// Asks thread to stop and joins it. Returns `true` if thread stopped without panicking,
+    /// `false` if it was detached, panicked or was already stopped.
+    fn stop(&mut self) -> bool {
+        let handle = match self.handle.take() {
+            Some(handle) => handle,
+            None => return false,
+        };
+
+        if let Err(e) = self.shutdown.write(1) {
+            error!("Failed to stop hot plug thread: {}", e);
+            return false;
+        }
+
+        match self.exited.recv_timeout(HOTPLUG_THREAD_STOP_TIMEOUT) {
+            Err(RecvTimeoutError::Timeout) => {
+                warn!("Hot plug thread didn't stop in time, detaching it");
+                false
+            }
+            _ => handle.join().is_ok(),
+        }
+    }
+}
+
+impl Drop for HotplugThread {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Blocks until `fd` is readable and returns `true`, or returns `false` if `shutdown` becomes
+/// readable first or waiting fails.
+fn wait_readable(fd: RawFd, shutdown: RawFd) -> bool {
+    loop {
+        let mut fds = [
+            c::pollfd {
+                fd,
+                events: c::POLLIN,
+                revents: 0,
+            },
+            c::pollfd {
+                fd: shutdown,
+                events: c::POLLIN,
+                revents: 0,
+            },
+        ];
+
+        if unsafe { c::poll(fds.as_mut_ptr(), fds.len() as c::nfds_t, -1) } < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            error!("Failed to wait for hot plug events: {}", err);
+            return false;
+        }
+
+        if fds[1].revents != 0 {
+            return false;
+        }
+
+        if fds[0].revents & c::POLLIN != 0 {
+            return true;
+        }
+
+        if fds[0].revents != 0 {
+            error!("Hot plug file descriptor failed: {:#x}", fds[0].revents);
+            return false;
+        }
+    }
+}
+
+/// Why device wasn't opened as gamepad.
+#[derive(Debug)]
+enum OpenError {
+    /// Device is not a gamepad or can't be used as one.
+    Ignored,
+    /// `open()` failed.
+    Failed(Errno),
+}
+
+/// Adds result of opening device found when `Gilrs` is created to `report`. Legacy joystick
+/// interfaces (`/dev/input/jsN`) are skipped, udev lists one next to every event node of a joystick
+/// and they are never opened.
+fn record_open(report: &mut EnumerationReport, path: &CStr, result: &Result<Gamepad, OpenError>) {
+    if classify_node(Path::new(OsStr::from_bytes(path.to_bytes()))) == NodeKind::Joystick {
+        return;
+    }
+
+    let status = match result {
+        Ok(_) => DeviceStatus::Opened,
+        Err(OpenError::Ignored) => DeviceStatus::Ignored,
+        Err(OpenError::Failed(errno)) => DeviceStatus::OpenFailed {
+            errno: *errno as i32,
+        },
+    };
+
+    report.devices.push(EnumeratedDevice {
+        path: path.to_string_lossy().into_owned(),
+        status,
+    });
+}
+
+fn warn_if_permission_denied(report: &EnumerationReport) {
+    if report.is_permission_denied() {
+        warn!(
+            "Found {} input devices, but none of them could be opened because of missing \
+             permissions. Check if user has read and write access to /dev/input/event* (for \
+             example, by adding udev rule) and, if application is sandboxed, that it has access \
+             to input devices.",
+            report
+                .devices
+                .iter()
+                .filter(|dev| dev.status != DeviceStatus::Ignored)
+                .count()
+        );
+    }
+}
+
+/// Returns `true` for buttons that only joysticks and gamepads have, the same that udev checks
+/// before setting `ID_INPUT_JOYSTICK`. Keyboard keys and mouse buttons are not included.
+fn is_joystick_button(code: u16) -> bool {
+    (BTN_JOYSTICK..BTN_DIGI).contains(&code)
+        || (BTN_DPAD_UP..=BTN_DPAD_RIGHT).contains(&code)
+        || (BTN_TRIGGER_HAPPY..=BTN_TRIGGER_HAPPY40).contains(&code)
+}
+
+/// Returns `false` if input device with `syspath` (`/sys/class/input/eventN`) has no joystick
+/// buttons, so it can't be a gamepad. Unlike opening the device node, reading its capabilities
+/// doesn't need any permissions. If they can't be read, device may be a joystick.
+fn may_be_joystick(syspath: &Path) -> bool {
+    match std::fs::read_to_string(syspath.join("device/capabilities/key")) {
+        Ok(caps) => has_joystick_button(&caps),
+        Err(_) => true,
+    }
+}
+
+/// Returns `true` if `caps`, key capabilities in sysfs format (hexadecimal `unsigned long` words,
+/// the most significant first), contain a joystick button.
+fn has_joystick_button(caps: &str) -> bool {
+    let word_bits = mem::size_of::<c::c_ulong>() * 8;
+    caps.split_whitespace()
+        .rev()
+        .enumerate()
+        .any(|(idx, word)| {
+            let word = u64::from_str_radix(word, 16).unwrap_or(0);
+            (0..word_bits).any(|bit| {
+                word >> bit & 1 != 0
+                    && u16::try_from(idx * word_bits + bit).is_ok_and(is_joystick_button)
+            })
+        })
+}
+
+/// Kind of input device node, decided only from its path.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum NodeKind {
+    /// `/dev/input/eventN`, evdev interface used by gilrs.
+    Event,
+    /// `/dev/input/jsN`, legacy joystick interface of the same device. Always ignored.
+    Joystick,
+    /// Anything else, for example `mouseN` or symlinks in `/dev/input/by-id`.
+    Other,
+}
+
+/// Classifies device node by its file name. Only nodes directly in `/dev/input` are recognized,
+/// so names of symlinks in `/dev/input/by-id` and `/dev/input/by-path` that happen to contain
+/// "js" or "event" are `Other`.
+fn classify_node(path: &Path) -> NodeKind {
+    fn is_numbered(name: &str, prefix: &str) -> bool {
+        name.strip_prefix(prefix)
+            .is_some_and(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()))
+    }
+
+    if path.parent() != Some(Path::new(INPUT_DIR_PATH)) {
+        return NodeKind::Other;
+    }
+
+    match path.file_name().and_then(|name| name.to_str()) {
+        Some(name) if is_numbered(name, "event") => NodeKind::Event,
+        Some(name) if is_numbered(name, "js") => NodeKind::Joystick,
+        _ => NodeKind::Other,
     }
+}
 
+fn get_gamepad_path(name: &str) -> Option<(PathBuf, PathBuf)> {
     let gamepad_path = Path::new(INPUT_DIR_PATH).join(name);
+    if classify_node(&gamepad_path) != NodeKind::Event {
+        return None;
+    }
+
     let syspath = Path::new("/sys/class/input/").join(name);
     Some((gamepad_path, syspath))
 }
 
-fn handle_hotplug(sender: Sender<HotplugEvent>, monitor: Monitor, event: EventFd) {
-    loop {
-        if !monitor.wait_hotplug_available() {
-            continue;
+/// Returns sysfs path of USB port that device with canonical sysfs `path` is plugged into, for
+/// example `/sys/devices/pci0000:00/0000:00:14.0/usb1/1-2`, or `None` if device is not connected
+/// over USB. This is the deepest USB device directory in `path`. USB devices are named
+/// `<bus>-<port>[.<port>...]` after the physical ports on the way to them, so the name stays the
+/// same for every device plugged into the same port. Bluetooth devices have no port.
+fn usb_port_from_devpath(path: &Path) -> Option<String> {
+    fn is_number(s: &str) -> bool {
+        !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+    }
+
+    let mut port = None;
+    let mut current = PathBuf::new();
+    for component in path.components() {
+        // Bluetooth adapter may be a USB device, but its port says nothing about the gamepad.
+        if component.as_os_str() == "bluetooth" {
+            return None;
+        }
+        current.push(component);
+        let is_port = component
+            .as_os_str()
+            .to_str()
+            .and_then(|name| name.split_once('-'))
+            .is_some_and(|(bus, ports)| is_number(bus) && ports.split('.').all(is_number));
+        if is_port {
+            port = Some(current.to_string_lossy().into_owned());
         }
+    }
+
+    port
+}
 
+fn handle_hotplug(sender: Sender<HotplugEvent>, monitor: Monitor, event: EventFd, shutdown: RawFd) {
+    while wait_readable(monitor.fd(), shutdown) {
         let dev = monitor.device();
 
         unsafe {
@@ -541,7 +1059,12 @@ pub struct Gamepad {
     axes_info: AxesInfo,
     ff_supported: bool,
     devpath: String,
+    // Sysfs path of USB port, see `usb_port_from_devpath()`.
+    port: Option<String>,
+    virtual_reason: Option<VirtualReason>,
     name: String,
+    // Name as returned by EVIOCGNAME, `name` is its lossy UTF-8 conversion.
+    raw_name: Vec<u8>,
     uuid: Uuid,
     vendor_id: u16,
     product_id: u16,
@@ -553,26 +1076,97 @@ pub struct Gamepad {
     axes: Vec<EvCode>,
     buttons: Vec<EvCode>,
     is_connected: bool,
+    // True if `fd` is registered in epoll and we will be notified about new events.
+    in_epoll: bool,
+    // True if invalid battery info was already logged. Reset after successful read.
+    power_info_warned: AtomicBool,
+    // True if device is grabbed with EVIOCGRAB.
+    grabbed: AtomicBool,
+    // Error of grab requested with `BackendOptions::exclusive_device_access`.
+    grab_error: Option<GrabError>,
+    // True if gamepad was added by `Gilrs::add_replay_gamepad()` and has no device.
+    replay: bool,
 }
 
 impl Gamepad {
-    fn open(path: &CStr, syspath: &Path, discovery_backend: DiscoveryBackend) -> Option<Gamepad> {
-        if unsafe { !c::strstr(path.as_ptr(), c"js".as_ptr() as *const c_char).is_null() } {
+    /// Creates disconnected gamepad without any elements. Used for ids that were never observed.
+    fn not_observed() -> Gamepad {
+        Gamepad {
+            fd: -1,
+            axes_info: AxesInfo {
+                info: VecMap::new(),
+            },
+            ff_supported: false,
+            devpath: String::new(),
+            port: None,
+            virtual_reason: None,
+            name: String::new(),
+            raw_name: Vec::new(),
+            uuid: Uuid::nil(),
+            vendor_id: 0,
+            product_id: 0,
+            bt_capacity_fd: -1,
+            bt_status_fd: -1,
+            axes_values: VecMap::new(),
+            buttons_values: VecMap::new(),
+            events: Vec::new(),
+            axes: Vec::new(),
+            buttons: Vec::new(),
+            is_connected: false,
+            in_epoll: false,
+            power_info_warned: AtomicBool::new(false),
+            grabbed: AtomicBool::new(false),
+            grab_error: None,
+            replay: false,
+        }
+    }
+
+    /// Creates disconnected gamepad with elements of `device`, used to replay recorded events.
+    #[cfg(feature = "replay")]
+    fn replay(device: &ReplayDevice) -> Gamepad {
+        let mut info = VecMap::new();
+        for (axis, axis_info) in &device.axes {
+            info.insert(axis.0.code as usize, *axis_info);
+        }
+
+        let components = GuidComponents::from_uuid(device.uuid);
+        let mut gamepad = Gamepad::not_observed();
+        gamepad.axes_info = AxesInfo { info };
+        gamepad.set_name(device.name.clone().into_bytes());
+        gamepad.uuid = Uuid::from_bytes(device.uuid);
+        gamepad.vendor_id = components.vendor;
+        gamepad.product_id = components.product;
+        gamepad.axes = device.axes.iter().map(|(axis, _)| axis.0).collect();
+        gamepad.buttons = device.buttons.iter().map(|button| button.0).collect();
+        gamepad.replay = true;
+        gamepad
+    }
+
+    fn open(
+        path: &CStr,
+        syspath: &Path,
+        discovery_backend: DiscoveryBackend,
+        grab: bool,
+        accept_buttons_only: bool,
+    ) -> Result<Gamepad, OpenError> {
+        if classify_node(Path::new(OsStr::from_bytes(path.to_bytes()))) == NodeKind::Joystick {
             trace!("Device {:?} is js interface, ignoring.", path);
-            return None;
+            return Err(OpenError::Ignored);
         }
 
         let fd = unsafe { c::open(path.as_ptr(), c::O_RDWR | c::O_NONBLOCK) };
         if fd < 0 {
+            let errno = Errno::last();
             log!(
                 match discovery_backend {
                     DiscoveryBackend::Inotify => log::Level::Debug,
                     _ => log::Level::Error,
                 },
-                "Failed to open {:?}",
-                path
+                "Failed to open {:?}: {}",
+                path,
+                errno
             );
-            return None;
+            return Err(OpenError::Failed(errno));
         }
 
         let input_id = match Self::get_input_id(fd) {
@@ -582,25 +1176,30 @@ impl Gamepad {
                 unsafe {
                     c::close(fd);
                 }
-                return None;
+                return Err(OpenError::Ignored);
             }
         };
 
-        let name = Self::get_name(fd).unwrap_or_else(|| {
+        let raw_name = Self::get_name(fd).unwrap_or_else(|| {
             error!("Failed to get name of device {:?}", path);
-            "Unknown".into()
+            b"Unknown".to_vec()
         });
 
         let axesi = AxesInfo::new(fd);
         let ff_supported = Self::test_ff(fd);
         let (cap, status) = Self::battery_fd(syspath);
+        // Syspath from inotify is a symlink in /sys/class/input.
+        let sysfs_path = std::fs::canonicalize(syspath).ok();
 
         let mut gamepad = Gamepad {
             fd,
             axes_info: axesi,
             ff_supported,
             devpath: path.to_string_lossy().into_owned(),
-            name,
+            port: sysfs_path.as_deref().and_then(usb_port_from_devpath),
+            virtual_reason: None,
+            name: String::new(),
+            raw_name: Vec::new(),
             uuid: create_uuid(input_id),
             vendor_id: input_id.vendor,
             product_id: input_id.product,
@@ -612,11 +1211,23 @@ impl Gamepad {
             axes: Vec::new(),
             buttons: Vec::new(),
             is_connected: true,
+            in_epoll: false,
+            power_info_warned: AtomicBool::new(false),
+            grabbed: AtomicBool::new(false),
+            grab_error: None,
+            replay: false,
         };
+        gamepad.set_name(raw_name);
+        gamepad.virtual_reason = virtual_device::detect(&DeviceDescriptor {
+            name: &gamepad.name,
+            vendor_id: Some(input_id.vendor),
+            product_id: Some(input_id.product),
+            sysfs_path: sysfs_path.as_deref().and_then(Path::to_str),
+        });
 
         gamepad.collect_axes_and_buttons();
 
-        if !gamepad.is_gamepad() {
+        if !gamepad.is_gamepad(accept_buttons_only) {
             log!(
                 match discovery_backend {
                     DiscoveryBackend::Inotify => log::Level::Debug,
@@ -625,7 +1236,17 @@ impl Gamepad {
                 "{:?} doesn't have at least 1 button and 2 axes, ignoring.",
                 path
             );
-            return None;
+            return Err(OpenError::Ignored);
+        }
+
+        if grab {
+            if let Err(e) = gamepad.set_grabbed(true) {
+                warn!(
+                    "Failed to get exclusive access to gamepad {}: {}",
+                    gamepad.devpath, e
+                );
+                gamepad.grab_error = Some(e);
+            }
         }
 
         info!("Gamepad {} ({}) connected.", gamepad.devpath, gamepad.name);
@@ -639,12 +1260,16 @@ impl Gamepad {
             gamepad.axes_info
         );
 
-        Some(gamepad)
+        Ok(gamepad)
     }
 
-    fn register_fd(&self, epoll: &Epoll, data: u64) -> Result<(), Errno> {
+    fn register_fd(&mut self, epoll: &Epoll, idx: usize) -> Result<(), Errno> {
+        let data = EpollSource::gamepad_data(idx).ok_or(Errno::EOVERFLOW)?;
         let fd = unsafe { BorrowedFd::borrow_raw(self.fd) };
-        epoll.add(fd, EpollEvent::new(EpollFlags::EPOLLIN, data))
+        epoll.add(fd, EpollEvent::new(EpollFlags::EPOLLIN, data))?;
+        self.in_epoll = true;
+
+        Ok(())
     }
 
     fn collect_axes_and_buttons(&mut self) {
@@ -670,7 +1295,14 @@ impl Gamepad {
         self.axes = Self::find_axes(&abs_bits);
     }
 
-    fn get_name(fd: i32) -> Option<String> {
+    /// Sets name read from device. Names that are not valid UTF-8 are converted lossily, original
+    /// bytes are kept for `raw_name()`.
+    fn set_name(&mut self, raw_name: Vec<u8>) {
+        self.name = String::from_utf8_lossy(&raw_name).into_owned();
+        self.raw_name = raw_name;
+    }
+
+    fn get_name(fd: i32) -> Option<Vec<u8>> {
         unsafe {
             let mut namebuff: [MaybeUninit<u8>; 128] = MaybeUninit::uninit().assume_init();
             if ioctl::eviocgname(fd, &mut namebuff).is_err() {
@@ -678,8 +1310,8 @@ impl Gamepad {
             } else {
                 Some(
                     CStr::from_ptr(namebuff.as_ptr() as *const c_char)
-                        .to_string_lossy()
-                        .into_owned(),
+                        .to_bytes()
+                        .to_vec(),
                 )
             }
         }
@@ -716,9 +1348,12 @@ impl Gamepad {
         }
     }
 
-    fn is_gamepad(&self) -> bool {
+    /// Returns `true` if device has at least one button and two axes or, if `accept_buttons_only`
+    /// is set, at least one joystick button, like digital-only arcade sticks.
+    fn is_gamepad(&self, accept_buttons_only: bool) -> bool {
         // TODO: improve it (for example check for buttons in range)
-        !self.buttons.is_empty() && self.axes.len() >= 2
+        (!self.buttons.is_empty() && self.axes.len() >= 2)
+            || (accept_buttons_only && self.buttons.iter().any(|btn| is_joystick_button(btn.code)))
     }
 
     fn find_buttons(key_bits: &[u8], only_gamepad_btns: bool) -> Vec<EvCode> {
@@ -803,6 +1438,7 @@ impl Gamepad {
                     None
                 }
                 EV_KEY => {
+                    self.add_late_button(event.code);
                     self.buttons_values
                         .insert(event.code as usize, event.value == 1);
                     match event.value {
@@ -829,6 +1465,30 @@ impl Gamepad {
         }
     }
 
+    /// Some devices send key events that were not reported by EVIOCGBIT, for example after
+    /// switching mode. Such buttons are appended to `buttons`, so indices of other buttons don't
+    /// change.
+    fn add_late_button(&mut self, code: u16) {
+        let code = EvCode::new(EV_KEY, code);
+        if !self.buttons.contains(&code) {
+            warn!(
+                "Gamepad {} ({}) sent event for button {} that it didn't report, adding it.",
+                self.devpath, self.name, code
+            );
+            self.buttons.push(code);
+        }
+    }
+
+    /// Keeps buttons that were added to `previous` gamepad by `add_late_button()`. Used when the
+    /// same gamepad is reconnected.
+    fn keep_late_buttons(&mut self, previous: &Gamepad) {
+        for &code in &previous.buttons {
+            if !self.buttons.contains(&code) {
+                self.buttons.push(code);
+            }
+        }
+    }
+
     fn next_event(&mut self) -> Option<input_event> {
         if !self.events.is_empty() {
             self.events.pop()
@@ -919,59 +1579,56 @@ impl Gamepad {
         self.fd = -2;
         self.devpath.clear();
         self.is_connected = false;
+        self.in_epoll = false;
+        // Closing fd released the grab.
+        *self.grabbed.get_mut() = false;
+    }
+
+    /// Frees resources of disconnected gamepad that are not needed to recognize it when it is
+    /// connected again. Name, UUID and elements are kept.
+    fn compact(&mut self) {
+        debug_assert!(!self.is_connected);
+
+        for fd in [&mut self.bt_capacity_fd, &mut self.bt_status_fd] {
+            if *fd >= 0 {
+                unsafe {
+                    c::close(*fd);
+                }
+            }
+            *fd = -1;
+        }
+        self.axes_values = VecMap::new();
+        self.buttons_values = VecMap::new();
+        self.events = Vec::new();
     }
 
     pub fn is_connected(&self) -> bool {
         self.is_connected
     }
 
+    pub fn is_receiving_events(&self) -> bool {
+        self.is_connected && (self.in_epoll || self.replay)
+    }
+
     pub fn power_info(&self) -> PowerInfo {
         if self.bt_capacity_fd > -1 && self.bt_status_fd > -1 {
-            unsafe {
-                let mut buff = [0u8; 15];
-                c::lseek(self.bt_capacity_fd, 0, c::SEEK_SET);
-                c::lseek(self.bt_status_fd, 0, c::SEEK_SET);
-
-                let len = c::read(
-                    self.bt_capacity_fd,
-                    buff.as_mut_ptr() as *mut c::c_void,
-                    buff.len(),
-                );
-
-                if len > 0 {
-                    let len = len as usize;
-                    let cap = match str::from_utf8_unchecked(&buff[..(len - 1)]).parse() {
-                        Ok(cap) => cap,
-                        Err(_) => {
-                            error!(
-                                "Failed to parse battery capacity: {}",
-                                str::from_utf8_unchecked(&buff[..(len - 1)])
-                            );
-                            return PowerInfo::Unknown;
-                        }
-                    };
-
-                    let len = c::read(
-                        self.bt_status_fd,
-                        buff.as_mut_ptr() as *mut c::c_void,
-                        buff.len(),
-                    );
-
-                    if len > 0 {
-                        let len = len as usize;
-                        return match str::from_utf8_unchecked(&buff[..(len - 1)]) {
-                            "Charging" => PowerInfo::Charging(cap),
-                            "Discharging" => PowerInfo::Discharging(cap),
-                            "Full" | "Not charging" => PowerInfo::Charged,
-                            s => {
-                                error!("Unknown battery status value: {}", s);
-                                PowerInfo::Unknown
-                            }
-                        };
+            let mut capacity = [0u8; 15];
+            let mut status = [0u8; 15];
+            let capacity = read_sysfs_attr(self.bt_capacity_fd, &mut capacity);
+            let status = read_sysfs_attr(self.bt_status_fd, &mut status);
+
+            match parse_power_info(capacity, status) {
+                Ok(info) => {
+                    self.power_info_warned.store(false, Ordering::Relaxed);
+                    info
+                }
+                Err(e) => {
+                    if !self.power_info_warned.swap(true, Ordering::Relaxed) {
+                        warn!("Failed to read battery info of {}: {}", self.devpath, e);
                     }
+                    PowerInfo::Unknown
                 }
             }
-            PowerInfo::Unknown
         } else if self.fd > -1 {
             PowerInfo::Wired
         } else {
@@ -983,10 +1640,42 @@ impl Gamepad {
         self.ff_supported
     }
 
+    pub fn set_grabbed(&self, grabbed: bool) -> Result<(), GrabError> {
+        self.set_grabbed_with(grabbed, |fd, grab| unsafe { ioctl::eviocgrab(fd, grab) })
+    }
+
+    /// Implementation of `set_grabbed()` with replaceable EVIOCGRAB call.
+    fn set_grabbed_with(
+        &self,
+        grabbed: bool,
+        eviocgrab: impl FnOnce(RawFd, ioctl_param_type) -> Result<c::c_int, Errno>,
+    ) -> Result<(), GrabError> {
+        if !self.is_connected || self.fd < 0 {
+            return Err(GrabError::Disconnected);
+        }
+
+        if self.grabbed.load(Ordering::Relaxed) == grabbed {
+            return Ok(());
+        }
+
+        match eviocgrab(self.fd, grabbed as _) {
+            Ok(_) => {
+                self.grabbed.store(grabbed, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(Errno::EBUSY) => Err(GrabError::Busy),
+            Err(e) => Err(GrabError::Other(format!("EVIOCGRAB failed: {}", e))),
+        }
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    pub fn raw_name(&self) -> &[u8] {
+        &self.raw_name
+    }
+
     pub fn uuid(&self) -> Uuid {
         self.uuid
     }
@@ -999,6 +1688,26 @@ impl Gamepad {
         Some(self.product_id)
     }
 
+    pub fn device_path(&self) -> Option<&str> {
+        if self.devpath.is_empty() {
+            None
+        } else {
+            Some(&self.devpath)
+        }
+    }
+
+    pub fn connection_port(&self) -> Option<&str> {
+        self.port.as_deref()
+    }
+
+    pub fn virtual_reason(&self) -> Option<VirtualReason> {
+        self.virtual_reason
+    }
+
+    pub fn grab_error(&self) -> Option<&GrabError> {
+        self.grab_error.as_ref()
+    }
+
     pub fn ff_device(&self) -> Option<FfDevice> {
         if self.is_ff_supported() {
             FfDevice::new(&self.devpath).ok()
@@ -1024,10 +1733,51 @@ impl Gamepad {
     }
 }
 
+/// Returns next event from gamepads in `to_check`. Gamepads take turns, one event each, so a single
+/// gamepad that sends a lot of events doesn't delay events of other gamepads. Gamepads without
+/// pending events are removed from `to_check`.
+fn next_gamepad_event(
+    gamepads: &mut [Gamepad],
+    to_check: &mut VecDeque<usize>,
+) -> Option<(usize, EventType, SystemTime)> {
+    while let Some(idx) = to_check.front().copied() {
+        let gamepad = match gamepads.get_mut(idx) {
+            Some(gp) => gp,
+            None => {
+                warn!("Somehow got invalid index from event");
+                to_check.pop_front();
+                return None;
+            }
+        };
+
+        if !gamepad.is_connected {
+            to_check.pop_front();
+            continue;
+        }
+
+        match gamepad.event() {
+            Some((event, time)) => {
+                to_check.rotate_left(1);
+                return Some((idx, event, time));
+            }
+            None => {
+                to_check.pop_front();
+                continue;
+            }
+        };
+    }
+
+    None
+}
+
 impl Drop for Gamepad {
     fn drop(&mut self) {
         unsafe {
             if self.fd >= 0 {
+                // Closing fd also releases grab, but let's be explicit.
+                if *self.grabbed.get_mut() {
+                    let _ = ioctl::eviocgrab(self.fd, 0);
+                }
                 c::close(self.fd);
             }
             if self.bt_capacity_fd >= 0 {
@@ -1047,25 +1797,8 @@ impl PartialEq for Gamepad {
 }
 
 fn create_uuid(iid: ioctl::input_id) -> Uuid {
-    let bus = (u32::from(iid.bustype)).to_be();
-    let vendor = iid.vendor.to_be();
-    let product = iid.product.to_be();
-    let version = iid.version.to_be();
-    Uuid::from_fields(
-        bus,
-        vendor,
-        0,
-        &[
-            (product >> 8) as u8,
-            product as u8,
-            0,
-            0,
-            (version >> 8) as u8,
-            version as u8,
-            0,
-            0,
-        ],
-    )
+    let components = GuidComponents::new(iid.bustype.into(), iid.vendor, iid.product, iid.version);
+    Uuid::from_bytes(components.to_uuid())
 }
 
 unsafe fn cstr_new(bytes: &[u8]) -> &CStr {
@@ -1156,6 +1889,9 @@ const EV_FF: u16 = 0x15;
 const SYN_REPORT: u16 = 0x00;
 const SYN_DROPPED: u16 = 0x03;
 
+// Share button of Xbox Series controllers.
+const KEY_RECORD: u16 = 0xa7;
+
 const BTN_MISC: u16 = 0x100;
 const BTN_MOUSE: u16 = 0x110;
 const BTN_JOYSTICK: u16 = 0x120;
@@ -1177,11 +1913,16 @@ const BTN_MODE: u16 = 0x13c;
 const BTN_THUMBL: u16 = 0x13d;
 const BTN_THUMBR: u16 = 0x13e;
 
+const BTN_DIGI: u16 = 0x140;
+
 const BTN_DPAD_UP: u16 = 0x220;
 const BTN_DPAD_DOWN: u16 = 0x221;
 const BTN_DPAD_LEFT: u16 = 0x222;
 const BTN_DPAD_RIGHT: u16 = 0x223;
 
+const BTN_TRIGGER_HAPPY: u16 = 0x2c0;
+const BTN_TRIGGER_HAPPY40: u16 = 0x2e7;
+
 const ABS_X: u16 = 0x00;
 const ABS_Y: u16 = 0x01;
 const ABS_Z: u16 = 0x02;
@@ -1281,6 +2022,11 @@ pub mod native_ev_codes {
         code: super::BTN_DPAD_RIGHT,
     };
 
+    pub const BTN_MISC1: EvCode = EvCode {
+        kind: EV_KEY,
+        code: super::KEY_RECORD,
+    };
+
     pub const AXIS_LSTICKX: EvCode = EvCode {
         kind: EV_ABS,
         code: super::ABS_X,
@@ -1331,12 +2077,75 @@ pub mod native_ev_codes {
     };
 }
 
+/// Reads whole sysfs attribute from the beginning. Returns empty slice on error.
+fn read_sysfs_attr(fd: RawFd, buf: &mut [u8]) -> &[u8] {
+    let len = unsafe {
+        c::lseek(fd, 0, c::SEEK_SET);
+        c::read(fd, buf.as_mut_ptr() as *mut c::c_void, buf.len())
+    };
+
+    if len > 0 {
+        &buf[..len as usize]
+    } else {
+        &[]
+    }
+}
+
+/// Parses contents of `capacity` and `status` files from `/sys/class/power_supply/*/`.
+fn parse_power_info(capacity: &[u8], status: &[u8]) -> Result<PowerInfo, String> {
+    let capacity = str::from_utf8(capacity)
+        .map_err(|_| "capacity is not valid UTF-8".to_owned())?
+        .trim();
+    let capacity: i64 = capacity
+        .parse()
+        .map_err(|_| format!("invalid capacity {:?}", capacity))?;
+    let capacity = capacity.clamp(0, 100) as u8;
+
+    let status = str::from_utf8(status)
+        .map_err(|_| "status is not valid UTF-8".to_owned())?
+        .trim();
+
+    match status {
+        "Charging" => Ok(PowerInfo::Charging(capacity)),
+        "Discharging" => Ok(PowerInfo::Discharging(capacity)),
+        "Full" | "Not charging" => Ok(PowerInfo::Charged),
+        s => Err(format!("unknown status {:?}", s)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::ioctl;
-    use super::create_uuid;
+    use super::super::ioctl::input_event;
+    use super::{
+        classify_node, coalesce_hotplug_events, create_uuid, get_gamepad_path, has_joystick_button,
+        next_gamepad_event, parse_power_info, record_open, usb_port_from_devpath, wait_readable,
+        EpollSource, EvCode, EventType, Gamepad, Gilrs, HotplugEvent, HotplugThread, NodeKind,
+        OpenError, BTN_SOUTH, EV_ABS, EV_KEY, HOTPLUG_DATA, RESERVED_DATA_START,
+    };
+    #[cfg(feature = "replay")]
+    use crate::ReplayDevice;
+    use crate::{AxisInfo, DeviceStatus, EnumerationReport, GrabError, PowerInfo};
+    use libc as c;
+    use nix::errno::Errno;
+    use nix::sys::epoll::{Epoll, EpollCreateFlags};
+    use nix::sys::eventfd::{EfdFlags, EventFd};
+    use std::collections::VecDeque;
+    use std::ffi::CString;
+    use std::mem;
+    use std::os::unix::io::AsRawFd;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex, MutexGuard};
     use uuid::Uuid;
 
+    /// Tests that spawn hot plug threads hold this lock, so they don't change number of threads
+    /// and watches seen by `repeated_gilrs_doesnt_leak`.
+    static HOTPLUG_THREADS: Mutex<()> = Mutex::new(());
+
+    fn lock_hotplug_threads() -> MutexGuard<'static, ()> {
+        HOTPLUG_THREADS.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
     #[test]
     fn sdl_uuid() {
         let x = Uuid::parse_str("030000005e0400008e02000020200000").unwrap();
@@ -1348,4 +2157,644 @@ mod tests {
         });
         assert_eq!(x, y);
     }
+
+    fn new(devpath: &str) -> HotplugEvent {
+        HotplugEvent::New {
+            devpath: CString::new(devpath).unwrap(),
+            syspath: PathBuf::from("/sys/class/input").join(&devpath[11..]),
+        }
+    }
+
+    fn replugged(devpath: &str) -> HotplugEvent {
+        match new(devpath) {
+            HotplugEvent::New { devpath, syspath } => HotplugEvent::Replugged { devpath, syspath },
+            _ => unreachable!(),
+        }
+    }
+
+    fn removed(devpath: &str) -> HotplugEvent {
+        HotplugEvent::Removed(devpath.to_owned())
+    }
+
+    #[test]
+    fn ev_code_kind_is_part_of_key() {
+        let abs_x = EvCode::new(EV_ABS, 0);
+        let key_0 = EvCode::new(EV_KEY, 0);
+
+        assert_ne!(abs_x, key_0);
+        assert_ne!(abs_x.into_u32(), key_0.into_u32());
+
+        let mut gamepad = Gamepad::not_observed();
+        gamepad.axes_info.info.insert(
+            0,
+            AxisInfo {
+                min: -32768,
+                max: 32767,
+                deadzone: None,
+            },
+        );
+
+        assert!(gamepad.axis_info(abs_x).is_some());
+        assert!(gamepad.axis_info(key_0).is_none());
+    }
+
+    #[test]
+    fn hotplug_coalescing() {
+        let a = "/dev/input/event1";
+        let b = "/dev/input/event2";
+
+        // CREATE followed by ATTRIB
+        assert_eq!(coalesce_hotplug_events([new(a), new(a)]), [new(a)]);
+        assert_eq!(coalesce_hotplug_events([new(a), removed(a)]), [removed(a)]);
+        assert_eq!(
+            coalesce_hotplug_events([removed(a), new(a)]),
+            [replugged(a)]
+        );
+        assert_eq!(
+            coalesce_hotplug_events([removed(a), new(a), new(a)]),
+            [replugged(a)]
+        );
+        assert_eq!(
+            coalesce_hotplug_events([removed(a), new(a), removed(a)]),
+            [removed(a)]
+        );
+        assert_eq!(
+            coalesce_hotplug_events([new(a), new(b), removed(a), new(a), new(b)]),
+            [replugged(a), new(b)]
+        );
+        assert!(coalesce_hotplug_events([]).is_empty());
+    }
+
+    #[test]
+    fn buttons_only_devices() {
+        let btn = |code| EvCode::new(EV_KEY, code);
+        let mut gamepad = Gamepad::not_observed();
+
+        // Arcade stick with stick reported as dpad buttons.
+        gamepad.buttons = vec![btn(BTN_SOUTH), btn(0x220), btn(0x223)];
+        assert!(!gamepad.is_gamepad(false));
+        assert!(gamepad.is_gamepad(true));
+
+        // Generic joystick buttons (BTN_TRIGGER, BTN_THUMB).
+        gamepad.buttons = vec![btn(0x120), btn(0x121)];
+        assert!(gamepad.is_gamepad(true));
+
+        // Keyboard (KEY_A, KEY_ENTER) and mouse (BTN_LEFT, BTN_RIGHT).
+        gamepad.buttons = vec![btn(30), btn(28), btn(0x110), btn(0x111)];
+        assert!(!gamepad.is_gamepad(true));
+
+        // Devices with axes don't need joystick buttons.
+        gamepad.axes = vec![EvCode::new(EV_ABS, 0), EvCode::new(EV_ABS, 1)];
+        assert!(gamepad.is_gamepad(false));
+    }
+
+    #[test]
+    fn joystick_capabilities() {
+        let caps = |long: &'static str, short: &'static str| {
+            if mem::size_of::<c::c_ulong>() == 8 {
+                long
+            } else {
+                short
+            }
+        };
+
+        // Keyboard keys and mouse buttons.
+        let keyboard = caps(
+            "ff9f207ac14057ff febeffdfffefffff fffffffffffffffe\n",
+            "ff9f207a c14057ff febeffdf fffeffff ffffffff fffffffe\n",
+        );
+        assert!(!has_joystick_button(keyboard));
+        let mouse = caps("1f0000 0 0 0 0\n", "1f0000 0 0 0 0 0 0 0 0\n");
+        assert!(!has_joystick_button(mouse));
+        assert!(!has_joystick_button(""));
+
+        // Xbox controller: BTN_SOUTH..BTN_THUMBR and BTN_MODE.
+        let xbox = caps("7cdb000000000000 0 0 0 0\n", "7cdb0000 0 0 0 0 0 0 0 0 0\n");
+        assert!(has_joystick_button(xbox));
+        // BTN_DPAD_UP only.
+        let dpad = caps(
+            "100000000 0 0 0 0 0 0 0 0\n",
+            "1 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0\n",
+        );
+        assert!(has_joystick_button(dpad));
+    }
+
+    #[test]
+    fn enumeration_report() {
+        let mut report = EnumerationReport::default();
+        assert!(!report.is_permission_denied());
+
+        let denied = || Err(OpenError::Failed(Errno::EACCES));
+        record_open(&mut report, c"/dev/input/event0", &denied());
+        record_open(&mut report, c"/dev/input/event1", &denied());
+        assert!(report.is_permission_denied());
+        assert_eq!(report.opened(), 0);
+        assert_eq!(report.devices[1].path, "/dev/input/event1");
+        assert_eq!(
+            report.devices[1].status,
+            DeviceStatus::OpenFailed { errno: c::EACCES }
+        );
+
+        // Other errors and devices that could be opened mean that it's not a permission problem.
+        let mut other_error = report.clone();
+        record_open(
+            &mut other_error,
+            c"/dev/input/event2",
+            &Err(OpenError::Failed(Errno::ENODEV)),
+        );
+        assert!(!other_error.is_permission_denied());
+
+        // Devices that aren't gamepads don't matter, udev lists js interface of every joystick.
+        let mut ignored = report.clone();
+        record_open(&mut ignored, c"/dev/input/js0", &Err(OpenError::Ignored));
+        assert_eq!(ignored.devices.len(), 2);
+        record_open(&mut ignored, c"/dev/input/event2", &Err(OpenError::Ignored));
+        assert!(ignored.is_permission_denied());
+        assert_eq!(ignored.devices[2].status, DeviceStatus::Ignored);
+
+        let mut only_ignored = EnumerationReport::default();
+        record_open(
+            &mut only_ignored,
+            c"/dev/input/event3",
+            &Err(OpenError::Ignored),
+        );
+        assert!(!only_ignored.is_permission_denied());
+
+        record_open(
+            &mut report,
+            c"/dev/input/event2",
+            &Ok(Gamepad::not_observed()),
+        );
+        assert!(!report.is_permission_denied());
+        assert_eq!(report.opened(), 1);
+    }
+
+    #[test]
+    fn grab_ioctl() {
+        use std::cell::RefCell;
+
+        let mut gamepad = Gamepad::not_observed();
+        let calls = RefCell::new(Vec::new());
+        let ioctl = |result| {
+            let calls = &calls;
+            move |fd, grab| {
+                calls.borrow_mut().push((fd, grab));
+                result
+            }
+        };
+
+        assert_eq!(
+            gamepad.set_grabbed_with(true, ioctl(Ok(0))),
+            Err(GrabError::Disconnected)
+        );
+        assert!(calls.borrow().is_empty());
+
+        // Not a valid fd, so nothing is closed when gamepad is dropped.
+        gamepad.fd = i32::MAX;
+        gamepad.is_connected = true;
+
+        assert_eq!(
+            gamepad.set_grabbed_with(true, ioctl(Err(Errno::EBUSY))),
+            Err(GrabError::Busy)
+        );
+        assert!(matches!(
+            gamepad.set_grabbed_with(true, ioctl(Err(Errno::EINVAL))),
+            Err(GrabError::Other(_))
+        ));
+        assert_eq!(gamepad.set_grabbed_with(true, ioctl(Ok(0))), Ok(()));
+        // Already grabbed.
+        assert_eq!(gamepad.set_grabbed_with(true, ioctl(Ok(0))), Ok(()));
+        assert_eq!(gamepad.set_grabbed_with(false, ioctl(Ok(0))), Ok(()));
+        assert_eq!(
+            *calls.borrow(),
+            [(i32::MAX, 1), (i32::MAX, 1), (i32::MAX, 1), (i32::MAX, 0)]
+        );
+    }
+
+    #[test]
+    fn node_classification() {
+        let cases = [
+            ("/dev/input/event0", NodeKind::Event),
+            ("/dev/input/event10", NodeKind::Event),
+            ("/dev/input/js0", NodeKind::Joystick),
+            ("/dev/input/js12", NodeKind::Joystick),
+            ("/dev/input/mouse0", NodeKind::Other),
+            ("/dev/input/mice", NodeKind::Other),
+            ("/dev/input/event", NodeKind::Other),
+            ("/dev/input/js", NodeKind::Other),
+            ("/dev/input/event0js", NodeKind::Other),
+            ("/dev/input/jsevent0", NodeKind::Other),
+            ("/dev/input/js0-event", NodeKind::Other),
+            (
+                "/dev/input/by-id/usb-Sony_PLAYSTATION_R_3_Controller-event-joystick",
+                NodeKind::Other,
+            ),
+            (
+                "/dev/input/by-id/usb-Logitech_Gamepad_F310_jsfoo-joystick",
+                NodeKind::Other,
+            ),
+            ("/dev/input/by-id/js0", NodeKind::Other),
+            ("/dev/input/by-path/event0", NodeKind::Other),
+            ("/tmp/js0", NodeKind::Other),
+            ("js0", NodeKind::Other),
+        ];
+
+        for (path, kind) in cases {
+            assert_eq!(classify_node(Path::new(path)), kind, "{}", path);
+        }
+
+        assert_eq!(
+            get_gamepad_path("event3"),
+            Some((
+                PathBuf::from("/dev/input/event3"),
+                PathBuf::from("/sys/class/input/event3")
+            ))
+        );
+        assert_eq!(get_gamepad_path("js0"), None);
+        assert_eq!(get_gamepad_path("mouse0"), None);
+        assert_eq!(get_gamepad_path("by-id"), None);
+        assert_eq!(get_gamepad_path("event1js"), None);
+    }
+
+    #[test]
+    fn usb_port_path() {
+        let port = |path: &str| usb_port_from_devpath(Path::new(path));
+
+        assert_eq!(
+            port("/sys/devices/pci0000:00/0000:00:14.0/usb1/1-2/1-2:1.0/0003:045E:028E.0001/input/input17/event5")
+                .as_deref(),
+            Some("/sys/devices/pci0000:00/0000:00:14.0/usb1/1-2")
+        );
+        // Behind a hub.
+        assert_eq!(
+            port(
+                "/sys/devices/pci0000:00/0000:00:14.0/usb3/3-1/3-1.4/3-1.4:1.0/input/input9/event9"
+            )
+            .as_deref(),
+            Some("/sys/devices/pci0000:00/0000:00:14.0/usb3/3-1/3-1.4")
+        );
+        // Bluetooth.
+        assert_eq!(
+            port("/sys/devices/pci0000:00/0000:00:14.0/usb1/1-10/1-10:1.0/bluetooth/hci0/hci0:256/0005:054C:09CC.0004/input/input20/event20"),
+            None
+        );
+        assert_eq!(port("/sys/devices/virtual/input/input3/event3"), None);
+    }
+
+    #[test]
+    fn power_info_parsing() {
+        let parse = |cap: &[u8], status: &[u8]| parse_power_info(cap, status).ok();
+
+        assert_eq!(parse(b"42\n", b"Charging\n"), Some(PowerInfo::Charging(42)));
+        assert_eq!(
+            parse(b"42", b"Discharging"),
+            Some(PowerInfo::Discharging(42))
+        );
+        assert_eq!(
+            parse(b"100\r\n", b"Charging\r\n"),
+            Some(PowerInfo::Charging(100))
+        );
+        assert_eq!(
+            parse(b"0\n", b"Discharging\n"),
+            Some(PowerInfo::Discharging(0))
+        );
+        assert_eq!(
+            parse(b"250\n", b"Charging\n"),
+            Some(PowerInfo::Charging(100))
+        );
+        assert_eq!(parse(b"-5\n", b"Charging\n"), Some(PowerInfo::Charging(0)));
+        assert_eq!(parse(b"80\n", b"Not charging\n"), Some(PowerInfo::Charged));
+        assert_eq!(parse(b"80\n", b"Full"), Some(PowerInfo::Charged));
+
+        assert_eq!(parse(b"", b"Charging\n"), None);
+        assert_eq!(parse(b"\n", b"Charging\n"), None);
+        assert_eq!(parse(b"4\xff\n", b"Charging\n"), None);
+        assert_eq!(parse(b"42\n", b""), None);
+        assert_eq!(parse(b"42\n", b"Unknown\n"), None);
+    }
+
+    #[test]
+    fn epoll_data() {
+        assert_eq!(EpollSource::from_data(HOTPLUG_DATA), EpollSource::Hotplug);
+        assert_eq!(EpollSource::from_data(0), EpollSource::Gamepad(0));
+        assert_eq!(EpollSource::from_data(42), EpollSource::Gamepad(42));
+        assert_eq!(
+            EpollSource::from_data(u64::MAX),
+            EpollSource::Unknown(u64::MAX)
+        );
+        assert_eq!(
+            EpollSource::from_data(RESERVED_DATA_START + 1),
+            EpollSource::Unknown(RESERVED_DATA_START + 1)
+        );
+
+        assert_eq!(EpollSource::gamepad_data(7), Some(7));
+        // On 32-bit targets every index is below reserved range.
+        if let Ok(first_reserved) = usize::try_from(RESERVED_DATA_START) {
+            let last = first_reserved - 1;
+            assert_eq!(
+                EpollSource::from_data(EpollSource::gamepad_data(last).unwrap()),
+                EpollSource::Gamepad(last)
+            );
+            assert_eq!(EpollSource::gamepad_data(first_reserved), None);
+        }
+    }
+
+    #[test]
+    fn register_fd_outside_reserved_range() {
+        let epoll = Epoll::new(EpollCreateFlags::empty()).unwrap();
+        let mut gamepad = Gamepad::not_observed();
+        // Gamepad closes its fd when dropped.
+        gamepad.fd = unsafe { c::eventfd(0, c::EFD_NONBLOCK) };
+        assert!(gamepad.fd >= 0);
+        gamepad.is_connected = true;
+
+        if let Ok(first_reserved) = usize::try_from(RESERVED_DATA_START) {
+            assert_eq!(
+                gamepad.register_fd(&epoll, first_reserved),
+                Err(Errno::EOVERFLOW)
+            );
+            assert!(!gamepad.is_receiving_events());
+        }
+
+        assert_eq!(gamepad.register_fd(&epoll, 3), Ok(()));
+        assert!(gamepad.is_receiving_events());
+    }
+
+    #[test]
+    fn not_observed_gamepad() {
+        let gamepad = Gamepad::not_observed();
+        assert!(!gamepad.is_connected());
+        assert!(!gamepad.is_receiving_events());
+        assert_eq!(gamepad.uuid(), Uuid::nil());
+        assert_eq!(gamepad.power_info(), PowerInfo::Unknown);
+        assert!(gamepad.buttons().is_empty());
+        assert!(gamepad.axes().is_empty());
+    }
+
+    #[test]
+    fn non_utf8_name() {
+        let mut gamepad = Gamepad::not_observed();
+        // "Manette é" in Latin-1.
+        let raw_name = b"Manette \xe9".to_vec();
+        gamepad.set_name(raw_name.clone());
+
+        assert_eq!(gamepad.name(), "Manette \u{fffd}");
+        assert_eq!(gamepad.raw_name(), raw_name);
+    }
+
+    #[test]
+    fn compact_disconnected_gamepad() {
+        let south = EvCode::new(EV_KEY, BTN_SOUTH);
+        let mut gamepad = Gamepad::not_observed();
+        gamepad.name = "Test gamepad".into();
+        gamepad.uuid = Uuid::from_u128(1);
+        gamepad.buttons = vec![south];
+        gamepad.buttons_values.insert(BTN_SOUTH as usize, true);
+        gamepad.axes_values.insert(0, 100);
+        gamepad.events = vec![input_event::default(); 12];
+        gamepad.bt_capacity_fd = unsafe { c::open(c"/dev/null".as_ptr(), c::O_RDONLY) };
+        assert!(gamepad.bt_capacity_fd >= 0);
+
+        gamepad.disconnect();
+        gamepad.compact();
+
+        assert_eq!(gamepad.bt_capacity_fd, -1);
+        assert_eq!(gamepad.bt_status_fd, -1);
+        assert!(gamepad.buttons_values.is_empty());
+        assert!(gamepad.axes_values.is_empty());
+        assert_eq!(gamepad.events.capacity(), 0);
+        assert_eq!(gamepad.name(), "Test gamepad");
+        assert_eq!(gamepad.uuid(), Uuid::from_u128(1));
+        assert_eq!(gamepad.buttons(), [south]);
+        assert_eq!(gamepad.power_info(), PowerInfo::Unknown);
+    }
+
+    #[test]
+    #[cfg(feature = "replay")]
+    fn replay_gamepad() {
+        let south = EvCode::new(EV_KEY, BTN_SOUTH);
+        let x = EvCode::new(EV_ABS, 0);
+        let info = AxisInfo {
+            min: -32768,
+            max: 32767,
+            deadzone: Some(128),
+        };
+        let uuid = create_uuid(ioctl::input_id {
+            bustype: 0x3,
+            vendor: 0x045e,
+            product: 0x028e,
+            version: 0x0114,
+        });
+        let device = ReplayDevice {
+            name: "Replayed gamepad".into(),
+            uuid: *uuid.as_bytes(),
+            buttons: vec![crate::EvCode(south)],
+            axes: vec![(crate::EvCode(x), info)],
+        };
+
+        let gamepad = Gamepad::replay(&device);
+        assert!(!gamepad.is_connected());
+        assert_eq!(gamepad.name(), "Replayed gamepad");
+        assert_eq!(gamepad.uuid(), uuid);
+        assert_eq!(gamepad.vendor_id(), Some(0x045e));
+        assert_eq!(gamepad.product_id(), Some(0x028e));
+        assert_eq!(gamepad.buttons(), [south]);
+        assert_eq!(gamepad.axes(), [x]);
+        assert_eq!(gamepad.axis_info(x), Some(&info));
+        assert_eq!(gamepad.axis_info(EvCode::new(EV_ABS, 1)), None);
+        assert_eq!(gamepad.power_info(), PowerInfo::Unknown);
+    }
+
+    #[test]
+    fn unadvertised_button() {
+        let south = EvCode::new(EV_KEY, BTN_SOUTH);
+        let late = EvCode::new(EV_KEY, 0x2c0);
+        let key = |code, value| input_event {
+            type_: EV_KEY,
+            code,
+            value,
+            ..Default::default()
+        };
+
+        let mut gamepad = Gamepad::not_observed();
+        gamepad.buttons = vec![south];
+        // `events` is used as a stack.
+        gamepad.events = vec![key(0x2c0, 0), key(BTN_SOUTH, 1), key(0x2c0, 1)];
+
+        assert_eq!(
+            gamepad.event().map(|(ev, _)| ev),
+            Some(EventType::ButtonPressed(crate::EvCode(late)))
+        );
+        assert_eq!(gamepad.buttons(), [south, late]);
+        assert_eq!(
+            gamepad.event().map(|(ev, _)| ev),
+            Some(EventType::ButtonPressed(crate::EvCode(south)))
+        );
+        assert_eq!(
+            gamepad.event().map(|(ev, _)| ev),
+            Some(EventType::ButtonReleased(crate::EvCode(late)))
+        );
+        assert_eq!(gamepad.buttons(), [south, late]);
+
+        // Same gamepad after reconnect reports only advertised buttons.
+        let mut reconnected = Gamepad::not_observed();
+        reconnected.buttons = vec![south];
+        reconnected.keep_late_buttons(&gamepad);
+        assert_eq!(reconnected.buttons(), gamepad.buttons());
+    }
+
+    #[test]
+    fn gamepads_take_turns() {
+        // Gamepad with `n` buffered axis events. Its fd is invalid, so nothing more is read.
+        let busy = |n| {
+            let mut gamepad = Gamepad::not_observed();
+            gamepad.is_connected = true;
+            gamepad.events = (0..n)
+                .map(|value| input_event {
+                    type_: EV_ABS,
+                    value,
+                    ..Default::default()
+                })
+                .collect();
+            gamepad
+        };
+        let mut gamepads = [busy(5), busy(4), busy(1)];
+        let mut to_check = VecDeque::from([0, 1, 2]);
+
+        let ids: Vec<_> = std::iter::from_fn(|| next_gamepad_event(&mut gamepads, &mut to_check))
+            .map(|(id, _, _)| id)
+            .collect();
+        assert_eq!(ids, [0, 1, 2, 0, 1, 0, 1, 0, 1, 0]);
+        assert!(to_check.is_empty());
+
+        // Disconnected gamepads are skipped.
+        let mut gamepads = [busy(2), busy(2)];
+        gamepads[0].is_connected = false;
+        let mut to_check = VecDeque::from([0, 1]);
+        assert_eq!(
+            next_gamepad_event(&mut gamepads, &mut to_check).map(|(id, _, _)| id),
+            Some(1)
+        );
+        assert_eq!(to_check, [1]);
+    }
+
+    #[test]
+    fn hotplug_thread_stops() {
+        let _lock = lock_hotplug_threads();
+        let mut thread = HotplugThread::spawn(|_, shutdown| {
+            let never_readable = EventFd::from_value_and_flags(0, EfdFlags::EFD_CLOEXEC).unwrap();
+            while wait_readable(never_readable.as_raw_fd(), shutdown) {}
+        })
+        .unwrap();
+
+        assert!(thread.stop());
+        // Thread released its reference to shutdown eventfd, so it's closed on drop.
+        assert_eq!(Arc::strong_count(&thread.shutdown), 1);
+        assert!(!thread.stop());
+    }
+
+    #[test]
+    #[cfg(feature = "replay")]
+    fn replaced_gamepad_reconnects() {
+        let _lock = lock_hotplug_threads();
+        // Backend is not available in this environment.
+        let Ok(mut gilrs) = Gilrs::with_options(&Default::default()) else {
+            return;
+        };
+        let device = ReplayDevice {
+            name: "Replayed gamepad".into(),
+            uuid: [7; 16],
+            buttons: vec![crate::EvCode(EvCode::new(EV_KEY, BTN_SOUTH))],
+            axes: vec![],
+        };
+        let id = gilrs.add_replay_gamepad(&device);
+        gilrs.gamepads[id].is_connected = true;
+        *gilrs.gamepads[id].grabbed.get_mut() = true;
+
+        let mut replugged = Gamepad::replay(&device);
+        replugged.is_connected = true;
+        // Any fd that can be added to epoll, but doesn't support EVIOCGRAB.
+        replugged.fd = unsafe { c::eventfd(0, c::EFD_CLOEXEC) };
+        gilrs.replace_gamepad(id, replugged);
+
+        assert_eq!(
+            gilrs
+                .hotplug_events
+                .drain(..)
+                .map(|ev| (ev.id, ev.event))
+                .collect::<Vec<_>>(),
+            [(id, EventType::Disconnected), (id, EventType::Connected)]
+        );
+        assert!(gilrs.gamepads[id].is_connected());
+        assert!(gilrs.gamepads[id].in_epoll);
+        assert!(matches!(
+            gilrs.gamepads[id].grab_error(),
+            Some(GrabError::Other(_))
+        ));
+    }
+
+    #[test]
+    fn gilrs_stops_hotplug_thread() {
+        let _lock = lock_hotplug_threads();
+        // Backend is not available in this environment.
+        let Ok(mut gilrs) = Gilrs::with_options(&Default::default()) else {
+            return;
+        };
+
+        assert!(gilrs.hotplug.stop());
+        assert_eq!(Arc::strong_count(&gilrs.hotplug.shutdown), 1);
+    }
+
+    #[test]
+    fn repeated_gilrs_doesnt_leak() {
+        let _lock = lock_hotplug_threads();
+        let hotplug_threads = || {
+            std::fs::read_dir("/proc/self/task")
+                .unwrap()
+                .flatten()
+                .filter(|task| {
+                    std::fs::read(task.path().join("comm")).is_ok_and(|name| name == b"gilrs\n")
+                })
+                .count()
+        };
+        // Udev monitor is a netlink socket, inotify watches are listed in fdinfo of inotify
+        // instance. No other test opens sockets or inotify instances.
+        let watches = || {
+            let mut count = 0;
+            for fd in std::fs::read_dir("/proc/self/fd").unwrap().flatten() {
+                let Ok(target) = std::fs::read_link(fd.path()) else {
+                    continue;
+                };
+                let target = target.to_string_lossy();
+                if target.starts_with("socket:") {
+                    count += 1;
+                } else if target == "anon_inode:inotify" {
+                    let info = std::fs::read_to_string(
+                        Path::new("/proc/self/fdinfo").join(fd.file_name()),
+                    )
+                    .unwrap_or_default();
+                    count += 1 + info
+                        .lines()
+                        .filter(|l| l.starts_with("inotify wd:"))
+                        .count();
+                }
+            }
+            count
+        };
+
+        // Backend is not available in this environment.
+        let Ok(gilrs) = Gilrs::with_options(&Default::default()) else {
+            return;
+        };
+        drop(gilrs);
+
+        let threads = hotplug_threads();
+        let fds = watches();
+        for _ in 0..10 {
+            drop(Gilrs::with_options(&Default::default()).unwrap());
+        }
+
+        assert_eq!(hotplug_threads(), threads);
+        assert_eq!(watches(), fds);
+    }
 }
@@ -14,3 +14,4 @@ pub use self::ff::Device as FfDevice;
 pub use self::gamepad::{native_ev_codes, EvCode, Gamepad, Gilrs};
 
 pub const IS_Y_AXIS_REVERSED: bool = true;
+pub const BACKEND_NAME: &str = "linux";
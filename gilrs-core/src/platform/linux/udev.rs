@@ -5,10 +5,10 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use libc as c;
 use libudev_sys as ud;
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
+use std::os::unix::io::RawFd;
 use std::ptr;
 
 #[derive(Debug)]
@@ -229,15 +229,8 @@ impl Monitor {
         }
     }
 
-    pub fn wait_hotplug_available(&self) -> bool {
-        unsafe {
-            let mut fds = c::pollfd {
-                fd: ud::udev_monitor_get_fd(self.0),
-                events: c::POLLIN,
-                revents: 0,
-            };
-            (c::poll(&mut fds, 1, -1) == 1) && (fds.revents & c::POLLIN != 0)
-        }
+    pub fn fd(&self) -> RawFd {
+        unsafe { ud::udev_monitor_get_fd(self.0) }
     }
 
     pub fn device(&self) -> Device {
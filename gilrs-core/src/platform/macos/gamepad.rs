@@ -7,7 +7,8 @@
 
 use super::io_kit::*;
 use super::FfDevice;
-use crate::{AxisInfo, Event, EventType, PlatformError, PowerInfo};
+use crate::virtual_device::{self, DeviceDescriptor, VirtualReason};
+use crate::{AxisInfo, Event, EventType, GrabError, GuidComponents, PlatformError, PowerInfo};
 use uuid::Uuid;
 
 use core_foundation::runloop::{kCFRunLoopDefaultMode, CFRunLoop};
@@ -31,6 +32,7 @@ pub struct Gilrs {
     gamepads: Vec<Gamepad>,
     device_infos: Arc<Mutex<Vec<DeviceInfo>>>,
     rx: Receiver<(Event, Option<IOHIDDevice>)>,
+    not_observed: Box<Gamepad>,
 }
 
 impl Gilrs {
@@ -45,6 +47,7 @@ impl Gilrs {
             gamepads,
             device_infos,
             rx,
+            not_observed: Box::new(Gamepad::not_observed()),
         })
     }
 
@@ -145,6 +148,10 @@ impl Gilrs {
         self.gamepads.get(id)
     }
 
+    pub fn gamepad_or_placeholder(&self, id: usize) -> &Gamepad {
+        self.gamepads.get(id).unwrap_or(&self.not_observed)
+    }
+
     /// Returns index greater than index of last connected gamepad.
     pub fn last_gamepad_hint(&self) -> usize {
         self.gamepads.len()
@@ -160,6 +167,7 @@ pub struct Gamepad {
     uuid: Uuid,
     entry_id: u64,
     location_id: u32,
+    device_path: Option<String>,
     page: u32,
     usage: u32,
     axes_info: VecMap<AxisInfo>,
@@ -170,6 +178,26 @@ pub struct Gamepad {
 }
 
 impl Gamepad {
+    /// Creates disconnected gamepad without any elements. Used for ids that were never observed.
+    fn not_observed() -> Gamepad {
+        Gamepad {
+            name: String::new(),
+            vendor: None,
+            product: None,
+            uuid: Uuid::nil(),
+            entry_id: 0,
+            location_id: 0,
+            device_path: None,
+            page: 0,
+            usage: 0,
+            axes_info: VecMap::new(),
+            axes: Vec::new(),
+            hats: Vec::new(),
+            buttons: Vec::new(),
+            is_connected: false,
+        }
+    }
+
     fn open(device: IOHIDDevice) -> Option<Gamepad> {
         let io_service = match device.get_service() {
             Some(io_service) => io_service,
@@ -245,6 +273,7 @@ impl Gamepad {
             uuid,
             entry_id,
             location_id,
+            device_path: Some(format!("{:#010x}", location_id)),
             page,
             usage,
             axes_info: VecMap::with_capacity(8),
@@ -260,10 +289,10 @@ impl Gamepad {
 
     fn create_uuid(device: &IOHIDDevice) -> Option<Uuid> {
         // SDL always uses USB bus for UUID
-        let bustype = u32::to_be(0x03);
+        let bustype = 0x03;
 
         let vendor_id = match device.get_vendor_id() {
-            Some(vendor_id) => vendor_id.to_be(),
+            Some(vendor_id) => vendor_id,
             None => {
                 warn!("Failed to get vendor id of device");
                 0
@@ -271,7 +300,7 @@ impl Gamepad {
         };
 
         let product_id = match device.get_product_id() {
-            Some(product_id) => product_id.to_be(),
+            Some(product_id) => product_id,
             None => {
                 warn!("Failed to get product id of device");
                 0
@@ -279,7 +308,7 @@ impl Gamepad {
         };
 
         let version = match device.get_version() {
-            Some(version) => version.to_be(),
+            Some(version) => version,
             None => {
                 warn!("Failed to get version of device");
                 0
@@ -289,21 +318,8 @@ impl Gamepad {
         if vendor_id == 0 && product_id == 0 && version == 0 {
             None
         } else {
-            Some(Uuid::from_fields(
-                bustype,
-                vendor_id,
-                0,
-                &[
-                    (product_id >> 8) as u8,
-                    product_id as u8,
-                    0,
-                    0,
-                    (version >> 8) as u8,
-                    version as u8,
-                    0,
-                    0,
-                ],
-            ))
+            let components = GuidComponents::new(bustype, vendor_id, product_id, version);
+            Some(Uuid::from_bytes(components.to_uuid()))
         }
     }
 
@@ -311,6 +327,10 @@ impl Gamepad {
         &self.name
     }
 
+    pub fn raw_name(&self) -> &[u8] {
+        self.name().as_bytes()
+    }
+
     pub fn vendor_id(&self) -> Option<u16> {
         self.vendor
     }
@@ -319,6 +339,28 @@ impl Gamepad {
         self.product
     }
 
+    pub fn device_path(&self) -> Option<&str> {
+        self.device_path.as_deref()
+    }
+
+    pub fn grab_error(&self) -> Option<&GrabError> {
+        None
+    }
+
+    pub fn connection_port(&self) -> Option<&str> {
+        // Location ID is derived from the bus and hub ports, not from the device itself.
+        self.device_path.as_deref()
+    }
+
+    pub fn virtual_reason(&self) -> Option<VirtualReason> {
+        virtual_device::detect(&DeviceDescriptor {
+            name: self.name(),
+            vendor_id: self.vendor_id(),
+            product_id: self.product_id(),
+            sysfs_path: None,
+        })
+    }
+
     pub fn uuid(&self) -> Uuid {
         self.uuid
     }
@@ -327,6 +369,10 @@ impl Gamepad {
         PowerInfo::Unknown
     }
 
+    pub fn set_grabbed(&self, _grabbed: bool) -> Result<(), GrabError> {
+        Err(GrabError::NotSupported)
+    }
+
     pub fn is_ff_supported(&self) -> bool {
         false
     }
@@ -352,6 +398,10 @@ impl Gamepad {
         self.is_connected
     }
 
+    pub fn is_receiving_events(&self) -> bool {
+        self.is_connected()
+    }
+
     fn collect_axes_and_buttons(&mut self, elements: &Vec<IOHIDElement>) {
         let mut cookies = Vec::new();
 
@@ -605,6 +655,11 @@ pub mod native_ev_codes {
         page: super::PAGE_BUTTON,
         usage: super::USAGE_BTN_DPAD_RIGHT,
     };
+
+    pub const BTN_MISC1: EvCode = EvCode {
+        page: super::PAGE_BUTTON,
+        usage: super::USAGE_BTN_MISC1,
+    };
 }
 
 #[allow(clippy::type_complexity)]
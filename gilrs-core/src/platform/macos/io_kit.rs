@@ -610,3 +610,4 @@ pub const USAGE_BTN_Z: u32 = kHIDUsage_Button_1 + 16;
 pub const USAGE_BTN_LTHUMB: u32 = kHIDUsage_Button_1 + 17;
 #[allow(dead_code)]
 pub const USAGE_BTN_RTHUMB: u32 = kHIDUsage_Button_1 + 18;
+pub const USAGE_BTN_MISC1: u32 = kHIDUsage_Button_1 + 19;
@@ -13,3 +13,4 @@ pub use self::gamepad::{native_ev_codes, EvCode, Gamepad, Gilrs};
 
 // True, if Y axis of sticks points downwards.
 pub const IS_Y_AXIS_REVERSED: bool = true;
+pub const BACKEND_NAME: &str = "macos";
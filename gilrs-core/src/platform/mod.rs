@@ -11,9 +11,11 @@
 //!
 //! * A `FfDevice` (a struct which handles force feedback)
 //! * A `Gilrs` context
-//! * A `Gamepad` struct
+//! * A `Gamepad` struct, including a disconnected placeholder returned by
+//!   `Gilrs::gamepad_or_placeholder()` for ids that were never observed
 //! * A static `str` which specifies the name of the SDL input mapping
 //! * A constant which define whether Y axis of sticks points upwards or downwards
+//! * A constant with the name of the backend
 //! * A module with the platform-specific constants for common gamepad buttons
 //!   called `native_ev_codes`
 
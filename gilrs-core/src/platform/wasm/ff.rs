@@ -5,6 +5,7 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use std::io::Result as IoResult;
 use std::time::Duration;
 
 #[derive(Debug)]
@@ -12,4 +13,13 @@ pub struct Device;
 
 impl Device {
     pub fn set_ff_state(&mut self, _strong: u16, _weak: u16, _min_duration: Duration) {}
+
+    pub fn try_set_ff_state(
+        &mut self,
+        _strong: u16,
+        _weak: u16,
+        _min_duration: Duration,
+    ) -> IoResult<()> {
+        Ok(())
+    }
 }
@@ -16,7 +16,8 @@ use web_sys::{DomException, Gamepad as WebGamepad, GamepadButton, GamepadMapping
 
 use super::FfDevice;
 use crate::platform::native_ev_codes::{BTN_LT2, BTN_RT2};
-use crate::{AxisInfo, Event, EventType, PlatformError, PowerInfo};
+use crate::virtual_device::{self, DeviceDescriptor, VirtualReason};
+use crate::{AxisInfo, Event, EventType, GrabError, PlatformError, PowerInfo};
 #[cfg(feature = "serde-serialize")]
 use serde::{Deserialize, Serialize};
 
@@ -26,6 +27,7 @@ pub struct Gilrs {
     gamepads: Vec<Gamepad>,
     new_web_gamepads: Vec<WebGamepad>,
     next_event_error_logged: bool,
+    not_observed: Box<Gamepad>,
 }
 
 impl Gilrs {
@@ -42,6 +44,7 @@ impl Gilrs {
                 gamepads: Vec::new(),
                 new_web_gamepads: Vec::new(),
                 next_event_error_logged: false,
+                not_observed: Box::new(Gamepad::not_observed()),
             }
         })
     }
@@ -93,9 +96,9 @@ impl Gilrs {
             let maybe_js_gamepad_index = self
                 .new_web_gamepads
                 .iter()
-                .position(|x| gamepad.gamepad.index() == x.index());
+                .position(|x| gamepad.gamepad.as_ref().map(|g| g.index()) == Some(x.index()));
             if let Some(js_gamepad_index) = maybe_js_gamepad_index {
-                gamepad.gamepad = self.new_web_gamepads.swap_remove(js_gamepad_index);
+                let web_gamepad = self.new_web_gamepads.swap_remove(js_gamepad_index);
 
                 if !gamepad.connected {
                     self.event_cache
@@ -103,7 +106,7 @@ impl Gilrs {
                     gamepad.connected = true;
                 }
 
-                let buttons = gamepad.gamepad.buttons();
+                let buttons = web_gamepad.buttons();
                 for btn_index in 0..gamepad
                     .mapping
                     .buttons()
@@ -140,7 +143,7 @@ impl Gilrs {
                     gamepad.mapping.buttons_mut()[btn_index] = (new_pressed, new_value);
                 }
 
-                let axes = gamepad.gamepad.axes();
+                let axes = web_gamepad.axes();
                 for axis_index in 0..gamepad.mapping.axes().len().min(axes.length() as usize) {
                     let old_value = gamepad.mapping.axes()[axis_index];
                     let new_value = axes
@@ -156,6 +159,8 @@ impl Gilrs {
 
                     gamepad.mapping.axes_mut()[axis_index] = new_value;
                 }
+
+                gamepad.gamepad = Some(web_gamepad);
             } else {
                 // Create a disconnect event
                 if gamepad.connected {
@@ -187,6 +192,10 @@ impl Gilrs {
         self.gamepads.get(id)
     }
 
+    pub fn gamepad_or_placeholder(&self, id: usize) -> &Gamepad {
+        self.gamepads.get(id).unwrap_or(&self.not_observed)
+    }
+
     pub fn last_gamepad_hint(&self) -> usize {
         self.gamepads.len()
     }
@@ -238,7 +247,8 @@ impl Mapping {
 #[derive(Debug)]
 pub struct Gamepad {
     uuid: Uuid,
-    gamepad: WebGamepad,
+    // `None` only for the placeholder returned for ids that were never observed.
+    gamepad: Option<WebGamepad>,
     name: String,
     vendor: Option<u16>,
     product: Option<u16>,
@@ -315,7 +325,7 @@ impl Gamepad {
 
         Gamepad {
             uuid: Uuid::nil(),
-            gamepad,
+            gamepad: Some(gamepad),
             name,
             vendor,
             product,
@@ -324,10 +334,30 @@ impl Gamepad {
         }
     }
 
+    /// Creates disconnected gamepad without any elements. Used for ids that were never observed.
+    fn not_observed() -> Gamepad {
+        Gamepad {
+            uuid: Uuid::nil(),
+            gamepad: None,
+            name: String::new(),
+            vendor: None,
+            product: None,
+            mapping: Mapping::NoMapping {
+                buttons: Vec::new(),
+                axes: Vec::new(),
+            },
+            connected: false,
+        }
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    pub fn raw_name(&self) -> &[u8] {
+        self.name().as_bytes()
+    }
+
     pub fn uuid(&self) -> Uuid {
         self.uuid
     }
@@ -340,14 +370,43 @@ impl Gamepad {
         self.product
     }
 
+    pub fn device_path(&self) -> Option<&str> {
+        None
+    }
+
+    pub fn grab_error(&self) -> Option<&GrabError> {
+        None
+    }
+
+    pub fn connection_port(&self) -> Option<&str> {
+        None
+    }
+
+    pub fn virtual_reason(&self) -> Option<VirtualReason> {
+        virtual_device::detect(&DeviceDescriptor {
+            name: self.name(),
+            vendor_id: self.vendor_id(),
+            product_id: self.product_id(),
+            sysfs_path: None,
+        })
+    }
+
     pub fn is_connected(&self) -> bool {
-        self.gamepad.connected()
+        self.gamepad.as_ref().is_some_and(|g| g.connected())
+    }
+
+    pub fn is_receiving_events(&self) -> bool {
+        self.is_connected()
     }
 
     pub fn power_info(&self) -> PowerInfo {
         PowerInfo::Unknown
     }
 
+    pub fn set_grabbed(&self, _grabbed: bool) -> Result<(), GrabError> {
+        Err(GrabError::NotSupported)
+    }
+
     pub fn is_ff_supported(&self) -> bool {
         false
     }
@@ -462,6 +521,8 @@ pub mod native_ev_codes {
     pub const BTN_DPAD_LEFT: EvCode = EvCode(29);
     pub const BTN_DPAD_RIGHT: EvCode = EvCode(30);
 
+    pub const BTN_MISC1: EvCode = EvCode(31);
+
     pub(super) static BUTTONS: [EvCode; 17] = [
         BTN_SOUTH,
         BTN_EAST,
@@ -4,6 +4,7 @@
 // http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
+use std::io::{Error as IoError, Result as IoResult};
 use std::time::Duration;
 use windows::Gaming::Input::Gamepad as WgiGamepad;
 use windows::Gaming::Input::GamepadVibration;
@@ -19,19 +20,32 @@ impl Device {
         Device { id, wgi_gamepad }
     }
 
-    pub fn set_ff_state(&mut self, strong: u16, weak: u16, _min_duration: Duration) {
+    pub fn set_ff_state(&mut self, strong: u16, weak: u16, min_duration: Duration) {
+        if let Err(err) = self.try_set_ff_state(strong, weak, min_duration) {
+            error!(
+                "Failed to change FF state – unknown error. ID = {}, error = {:?}.",
+                self.id, err
+            );
+        }
+    }
+
+    pub fn try_set_ff_state(
+        &mut self,
+        strong: u16,
+        weak: u16,
+        _min_duration: Duration,
+    ) -> IoResult<()> {
         if let Some(wgi_gamepad) = &self.wgi_gamepad {
-            if let Err(err) = wgi_gamepad.SetVibration(GamepadVibration {
-                LeftMotor: (strong as f64) / (u16::MAX as f64),
-                RightMotor: (weak as f64) / (u16::MAX as f64),
-                LeftTrigger: 0.0,
-                RightTrigger: 0.0,
-            }) {
-                error!(
-                    "Failed to change FF state – unknown error. ID = {}, error = {:?}.",
-                    self.id, err
-                );
-            }
+            wgi_gamepad
+                .SetVibration(GamepadVibration {
+                    LeftMotor: (strong as f64) / (u16::MAX as f64),
+                    RightMotor: (weak as f64) / (u16::MAX as f64),
+                    LeftTrigger: 0.0,
+                    RightTrigger: 0.0,
+                })
+                .map_err(IoError::other)?;
         }
+
+        Ok(())
     }
 }
@@ -7,17 +7,31 @@
 
 use super::FfDevice;
 use crate::native_ev_codes as nec;
-use crate::{utils, AxisInfo, Event, EventType, PlatformError, PowerInfo};
+use crate::power_cache::{PowerInfoCache, POWER_INFO_TTL};
+use crate::virtual_device::{self, DeviceDescriptor, VirtualReason};
+use crate::{
+    utils, AxisInfo, BackendOptions, Event, EventType, GrabError, GuidComponents, PlatformError,
+    PowerInfo,
+};
 
 #[cfg(feature = "serde-serialize")]
 use serde::{Deserialize, Serialize};
-use std::fmt::{Display, Formatter, Result as FmtResult};
-use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::cell::OnceCell;
+use std::collections::VecDeque;
+use std::fmt::{self, Display, Formatter, Result as FmtResult};
+#[cfg(not(feature = "wgi-no-thread"))]
+use std::sync::mpsc::TryRecvError;
+use std::sync::mpsc::{self, Receiver, Sender};
+#[cfg(any(test, not(feature = "wgi-no-thread")))]
+use std::sync::mpsc::{SyncSender, TrySendError};
 use std::thread;
+#[cfg(not(feature = "wgi-no-thread"))]
 use std::thread::JoinHandle;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 use uuid::Uuid;
 use windows::core::HSTRING;
+use windows::Devices::Enumeration::DeviceInformation;
+use windows::Devices::HumanInterfaceDevice::HidDevice;
 use windows::Devices::Power::BatteryReport;
 use windows::Foundation::EventHandler;
 use windows::Gaming::Input::{
@@ -29,11 +43,25 @@ use windows::System::Power::BatteryStatus;
 const SDL_HARDWARE_BUS_USB: u32 = 0x03;
 // const SDL_HARDWARE_BUS_BLUETOOTH: u32 = 0x05;
 
+// HID usages of devices that can be exposed as RawGameController.
+const HID_USAGE_PAGE_GENERIC_DESKTOP: u16 = 0x01;
+const HID_USAGES: [u16; 3] = [
+    0x04, // Joystick
+    0x05, // Gamepad
+    0x08, // Multi-axis Controller
+];
+
 // The general consensus is that standard xbox controllers poll at ~125 hz which
 // means 8 ms between updates.
-// Seems like a good target for how often we update the background thread.
+// Seems like a good target for how often we update the background thread. Without the thread
+// (`wgi-no-thread` feature) it's used by `next_event_blocking()`.
 const EVENT_THREAD_SLEEP_TIME: u64 = 8;
 
+// Capacity of channel between the background thread and `Gilrs`. Events that don't fit wait in
+// `Backlog`.
+#[cfg(any(test, not(feature = "wgi-no-thread")))]
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
 const WGI_TO_GILRS_BUTTON_MAP: [(GamepadButtons, crate::EvCode); 14] = [
     (GamepadButtons::DPadUp, nec::BTN_DPAD_UP),
     (GamepadButtons::DPadDown, nec::BTN_DPAD_DOWN),
@@ -54,14 +82,14 @@ const WGI_TO_GILRS_BUTTON_MAP: [(GamepadButtons, crate::EvCode); 14] = [
 /// This is similar to `gilrs_core::Event` but has a raw_game_controller that still needs to be
 /// converted to a gilrs gamepad id.
 #[derive(Debug)]
-struct WgiEvent {
-    raw_game_controller: RawGameController,
+struct WgiEvent<C = RawGameController> {
+    raw_game_controller: C,
     event: EventType,
     pub time: SystemTime,
 }
 
-impl WgiEvent {
-    fn new(raw_game_controller: RawGameController, event: EventType) -> Self {
+impl<C> WgiEvent<C> {
+    fn new(raw_game_controller: C, event: EventType) -> Self {
         let time = utils::time_now();
         WgiEvent {
             raw_game_controller,
@@ -75,12 +103,26 @@ impl WgiEvent {
 pub struct Gilrs {
     gamepads: Vec<Gamepad>,
     rx: Receiver<WgiEvent>,
+    #[cfg(not(feature = "wgi-no-thread"))]
     join_handle: Option<JoinHandle<()>>,
+    #[cfg(not(feature = "wgi-no-thread"))]
     stop_tx: Sender<()>,
+    // Without the background thread, readings are compared in `next_event()` and resulting events
+    // are stored in `queue`. `rx` only receives hotplug events.
+    #[cfg(feature = "wgi-no-thread")]
+    poller: Poller,
+    #[cfg(feature = "wgi-no-thread")]
+    queue: VecDeque<WgiEvent>,
+    // Only kept to remove handlers when `Gilrs` is dropped.
+    #[cfg(feature = "wgi-no-thread")]
+    _hotplug_handlers: HotplugHandlers,
+    not_observed: Box<Gamepad>,
+    prefer_raw_readings: bool,
 }
 
 impl Gilrs {
-    pub(crate) fn new() -> Result<Self, PlatformError> {
+    pub(crate) fn with_options(options: &BackendOptions) -> Result<Self, PlatformError> {
+        let prefer_raw_readings = options.prefer_raw_readings;
         let raw_game_controllers = RawGameController::RawGameControllers()
             .map_err(|e| PlatformError::Other(Box::new(e)))?;
         let count = raw_game_controllers
@@ -94,55 +136,55 @@ impl Gilrs {
                 let controller = raw_game_controllers
                     .GetAt(i)
                     .map_err(|e| PlatformError::Other(Box::new(e)))?;
-                Ok(Gamepad::new(i, controller))
+                Ok(Gamepad::new(i, controller, prefer_raw_readings))
             })
             .collect::<Result<Vec<_>, _>>()?;
 
-        let (tx, rx) = mpsc::channel();
-        let (stop_tx, stop_rx) = mpsc::channel();
-        let join_handle = Some(Self::spawn_thread(tx, stop_rx));
-        Ok(Gilrs {
-            gamepads,
-            rx,
-            join_handle,
-            stop_tx,
-        })
-    }
+        #[cfg(not(feature = "wgi-no-thread"))]
+        {
+            let (tx, rx) = mpsc::sync_channel(EVENT_CHANNEL_CAPACITY);
+            let (stop_tx, stop_rx) = mpsc::channel();
+            let join_handle = Some(Self::spawn_thread(tx, stop_rx, prefer_raw_readings));
+            Ok(Gilrs {
+                gamepads,
+                rx,
+                join_handle,
+                stop_tx,
+                not_observed: Box::new(Gamepad::not_observed()),
+                prefer_raw_readings,
+            })
+        }
 
-    fn spawn_thread(tx: Sender<WgiEvent>, stop_rx: Receiver<()>) -> JoinHandle<()> {
-        let added_tx = tx.clone();
-        let added_handler: EventHandler<RawGameController> =
-            EventHandler::new(move |_, g: &Option<RawGameController>| {
-                if let Some(g) = g {
-                    added_tx
-                        .send(WgiEvent::new(g.clone(), EventType::Connected))
-                        .expect("should be able to send to main thread");
-                }
-                Ok(())
-            });
-        let controller_added_token =
-            RawGameController::RawGameControllerAdded(&added_handler).unwrap();
+        #[cfg(feature = "wgi-no-thread")]
+        {
+            let (tx, rx) = mpsc::channel();
+            Ok(Gilrs {
+                gamepads,
+                rx,
+                poller: Poller::new(prefer_raw_readings),
+                queue: VecDeque::new(),
+                _hotplug_handlers: HotplugHandlers::register(tx),
+                not_observed: Box::new(Gamepad::not_observed()),
+                prefer_raw_readings,
+            })
+        }
+    }
 
-        let removed_tx = tx.clone();
-        let removed_handler: EventHandler<RawGameController> =
-            EventHandler::new(move |_, g: &Option<RawGameController>| {
-                if let Some(g) = g {
-                    removed_tx
-                        .send(WgiEvent::new(g.clone(), EventType::Disconnected))
-                        .expect("should be able to send to main thread");
-                }
-                Ok(())
-            });
-        let controller_removed_token =
-            RawGameController::RawGameControllerRemoved(&removed_handler).unwrap();
+    #[cfg(not(feature = "wgi-no-thread"))]
+    fn spawn_thread(
+        tx: SyncSender<WgiEvent>,
+        stop_rx: Receiver<()>,
+        prefer_raw_readings: bool,
+    ) -> JoinHandle<()> {
+        // Hotplug handlers can't block, so their events go through the thread's backlog.
+        let (hotplug_tx, hotplug_rx) = mpsc::channel();
+        let hotplug_handlers = HotplugHandlers::register(hotplug_tx);
 
         std::thread::Builder::new()
             .name("gilrs".to_owned())
             .spawn(move || {
-                let mut controllers: Vec<RawGameController> = Vec::new();
-                // To avoid allocating every update, store old and new readings for every controller
-                // and swap their memory
-                let mut readings: Vec<(HSTRING, Reading, Reading)> = Vec::new();
+                let mut poller = Poller::new(prefer_raw_readings);
+                let mut backlog = Backlog::new();
                 loop {
                     match stop_rx.try_recv() {
                         Ok(_) => break,
@@ -152,85 +194,45 @@ impl Gilrs {
                         }
                         Err(TryRecvError::Empty) => {}
                     }
-                    controllers.clear();
-                    // Avoiding using RawGameControllers().into_iter() here due to it causing an
-                    // unhandled exception when the app is running through steam.
-                    // https://gitlab.com/gilrs-project/gilrs/-/issues/132
-                    if let Ok(raw_game_controllers) = RawGameController::RawGameControllers() {
-                        let count = raw_game_controllers.Size().unwrap_or_default();
-                        for index in 0..count {
-                            if let Ok(controller) = raw_game_controllers.GetAt(index) {
-                                controllers.push(controller);
-                            }
-                        }
-                    }
-
-                    for controller in controllers.iter() {
-                        let id: HSTRING = controller.NonRoamableId().unwrap();
-                        // Find readings for this controller or insert new ones.
-                        let index = match readings.iter().position(|(other_id, ..)| id == *other_id)
-                        {
-                            None => {
-                                let reading = match WgiGamepad::FromGameController(controller) {
-                                    Ok(wgi_gamepad) => {
-                                        Reading::Gamepad(wgi_gamepad.GetCurrentReading().unwrap())
-                                    }
-                                    _ => Reading::Raw(RawGamepadReading::new(controller).unwrap()),
-                                };
-
-                                readings.push((id, reading.clone(), reading));
-                                readings.len() - 1
-                            }
-                            Some(i) => i,
-                        };
-
-                        let (_, old_reading, new_reading) = &mut readings[index];
-
-                        // Make last update's reading the old reading and get a new one.
-                        std::mem::swap(old_reading, new_reading);
-                        if let Err(e) = new_reading.update(controller) {
-                            if e.code().is_err() {
-                                error!("Reading::update() function failed with {e}");
-                            }
-                        }
-
-                        // Skip if this is the same reading as the last one.
-                        if old_reading.time() == new_reading.time() {
-                            continue;
-                        }
-
-                        Reading::send_events_for_differences(
-                            old_reading,
-                            new_reading,
-                            controller,
-                            &tx,
-                        );
+                    for event in hotplug_rx.try_iter() {
+                        backlog.push(None, event);
                     }
+                    poller.poll(|controller, event| backlog.push(Some(controller), event));
+                    backlog.flush(&tx);
                     thread::sleep(Duration::from_millis(EVENT_THREAD_SLEEP_TIME));
                 }
 
-                if let Err(e) =
-                    RawGameController::RemoveRawGameControllerAdded(controller_added_token)
-                {
-                    error!("Failed to remove RawGameControllerAdded event handler: {e}");
-                }
-
-                if let Err(e) =
-                    RawGameController::RemoveRawGameControllerRemoved(controller_removed_token)
-                {
-                    error!("Failed to remove RawGameControllerRemoved event handler: {e}");
-                }
+                // Handlers are removed on the same thread as before.
+                drop(hotplug_handlers);
             })
             .expect("failed to spawn thread")
     }
 
+    #[cfg(not(feature = "wgi-no-thread"))]
+    fn next_wgi_event(&mut self) -> Option<WgiEvent> {
+        self.rx.try_recv().ok()
+    }
+
+    #[cfg(feature = "wgi-no-thread")]
+    fn next_wgi_event(&mut self) -> Option<WgiEvent> {
+        if let Ok(event) = self.rx.try_recv() {
+            return Some(event);
+        }
+
+        if self.queue.is_empty() {
+            let queue = &mut self.queue;
+            self.poller.poll(|_, event| queue.push_back(event));
+        }
+
+        self.queue.pop_front()
+    }
+
     pub(crate) fn next_event(&mut self) -> Option<Event> {
-        self.rx
-            .try_recv()
-            .ok()
+        self.next_wgi_event()
             .map(|wgi_event: WgiEvent| self.handle_event(wgi_event))
     }
 
+    #[cfg(not(feature = "wgi-no-thread"))]
     pub(crate) fn next_event_blocking(&mut self, timeout: Option<Duration>) -> Option<Event> {
         if let Some(timeout) = timeout {
             self.rx
@@ -245,6 +247,29 @@ impl Gilrs {
         }
     }
 
+    #[cfg(feature = "wgi-no-thread")]
+    pub(crate) fn next_event_blocking(&mut self, timeout: Option<Duration>) -> Option<Event> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let interval = Duration::from_millis(EVENT_THREAD_SLEEP_TIME);
+
+        loop {
+            if let Some(event) = self.next_event() {
+                return Some(event);
+            }
+
+            match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return None;
+                    }
+                    thread::sleep(interval.min(deadline - now));
+                }
+                None => thread::sleep(interval),
+            }
+        }
+    }
+
     fn handle_event(&mut self, wgi_event: WgiEvent) -> Event {
         // Find the index of the gamepad in our vec or insert it
         let id = self
@@ -260,6 +285,7 @@ impl Gilrs {
                 self.gamepads.push(Gamepad::new(
                     self.gamepads.len() as u32,
                     wgi_event.raw_game_controller,
+                    self.prefer_raw_readings,
                 ));
                 self.gamepads.len() - 1
             });
@@ -269,6 +295,14 @@ impl Gilrs {
             EventType::Disconnected => self.gamepads[id].is_connected = false,
             _ => (),
         }
+        // WGI doesn't report changes of battery or connection type, but reconnected gamepad may
+        // switch between wired and wireless.
+        if matches!(
+            wgi_event.event,
+            EventType::Connected | EventType::Disconnected
+        ) {
+            self.gamepads[id].power_info.invalidate();
+        }
         Event {
             id,
             event: wgi_event.event,
@@ -280,11 +314,16 @@ impl Gilrs {
         self.gamepads.get(id)
     }
 
+    pub fn gamepad_or_placeholder(&self, id: usize) -> &Gamepad {
+        self.gamepads.get(id).unwrap_or(&self.not_observed)
+    }
+
     pub fn last_gamepad_hint(&self) -> usize {
         self.gamepads.len()
     }
 }
 
+#[cfg(not(feature = "wgi-no-thread"))]
 impl Drop for Gilrs {
     fn drop(&mut self) {
         if let Err(e) = self.stop_tx.send(()) {
@@ -296,6 +335,228 @@ impl Drop for Gilrs {
     }
 }
 
+/// Handlers of `RawGameControllerAdded` and `RawGameControllerRemoved` events. They are removed
+/// when this struct is dropped.
+struct HotplugHandlers {
+    remove: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl HotplugHandlers {
+    /// Registers handlers that send `Connected` and `Disconnected` events to `tx`.
+    fn register(tx: Sender<WgiEvent>) -> Self {
+        let added_tx = tx.clone();
+        let added_handler: EventHandler<RawGameController> =
+            EventHandler::new(move |_, g: &Option<RawGameController>| {
+                if let Some(g) = g {
+                    added_tx
+                        .send(WgiEvent::new(g.clone(), EventType::Connected))
+                        .expect("should be able to send to main thread");
+                }
+                Ok(())
+            });
+        let controller_added_token =
+            RawGameController::RawGameControllerAdded(&added_handler).unwrap();
+
+        let removed_tx = tx;
+        let removed_handler: EventHandler<RawGameController> =
+            EventHandler::new(move |_, g: &Option<RawGameController>| {
+                if let Some(g) = g {
+                    removed_tx
+                        .send(WgiEvent::new(g.clone(), EventType::Disconnected))
+                        .expect("should be able to send to main thread");
+                }
+                Ok(())
+            });
+        let controller_removed_token =
+            RawGameController::RawGameControllerRemoved(&removed_handler).unwrap();
+
+        let remove = move || {
+            if let Err(e) = RawGameController::RemoveRawGameControllerAdded(controller_added_token)
+            {
+                error!("Failed to remove RawGameControllerAdded event handler: {e}");
+            }
+
+            if let Err(e) =
+                RawGameController::RemoveRawGameControllerRemoved(controller_removed_token)
+            {
+                error!("Failed to remove RawGameControllerRemoved event handler: {e}");
+            }
+        };
+
+        HotplugHandlers {
+            remove: Some(Box::new(remove)),
+        }
+    }
+}
+
+impl Drop for HotplugHandlers {
+    fn drop(&mut self) {
+        if let Some(remove) = self.remove.take() {
+            remove();
+        }
+    }
+}
+
+impl fmt::Debug for HotplugHandlers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HotplugHandlers").finish_non_exhaustive()
+    }
+}
+
+/// Events waiting for free space in the bounded channel between the background thread and `Gilrs`.
+///
+/// When `Gilrs` doesn't read events for a while, only the latest value of every axis is kept:
+/// axis event replaces the queued event of the same controller and axis, taking its place in the
+/// queue, if there is no other kind of event after it. This way, axis values seen together with
+/// button and hotplug events stay the same. Other events are never dropped; button events are
+/// rare enough to keep all of them.
+#[cfg(any(test, not(feature = "wgi-no-thread")))]
+#[derive(Debug)]
+struct Backlog<C> {
+    // Events with index of controller in `Poller::readings`. Hotplug events have no index.
+    events: VecDeque<(Option<usize>, WgiEvent<C>)>,
+}
+
+#[cfg(any(test, not(feature = "wgi-no-thread")))]
+impl<C> Backlog<C> {
+    fn new() -> Self {
+        Backlog {
+            events: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, controller: Option<usize>, event: WgiEvent<C>) {
+        if let (Some(_), EventType::AxisValueChanged(_, code)) = (controller, event.event) {
+            // Only look at axis events queued after the last event of other kind.
+            let queued = self
+                .events
+                .iter_mut()
+                .rev()
+                .take_while(|(_, queued)| matches!(queued.event, EventType::AxisValueChanged(..)))
+                .find(|(other, queued)| {
+                    *other == controller
+                        && matches!(queued.event, EventType::AxisValueChanged(_, c) if c == code)
+                });
+            if let Some((_, queued)) = queued {
+                *queued = event;
+                return;
+            }
+        }
+
+        self.events.push_back((controller, event));
+    }
+
+    /// Sends queued events to `tx` until its channel is full.
+    fn flush(&mut self, tx: &SyncSender<WgiEvent<C>>) {
+        while let Some((controller, event)) = self.events.pop_front() {
+            match tx.try_send(event) {
+                Ok(()) => (),
+                Err(TrySendError::Full(event)) => {
+                    self.events.push_front((controller, event));
+                    break;
+                }
+                // `Gilrs` was dropped, nobody will read events.
+                Err(TrySendError::Disconnected(_)) => {
+                    self.events.clear();
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Reads state of all controllers and compares it with previous reading. Used by the background
+/// thread or, with `wgi-no-thread` feature, directly by `Gilrs::next_event()`.
+struct Poller {
+    controllers: Vec<RawGameController>,
+    // To avoid allocating every update, store old and new readings for every controller
+    // and swap their memory
+    readings: Vec<(HSTRING, Reading, Reading)>,
+    // If true, `Reading::Raw` is used even for controllers that can be read as WGI `Gamepad`.
+    prefer_raw_readings: bool,
+}
+
+impl Poller {
+    fn new(prefer_raw_readings: bool) -> Self {
+        Poller {
+            controllers: Vec::new(),
+            readings: Vec::new(),
+            prefer_raw_readings,
+        }
+    }
+
+    /// Calls `emit` with index of controller in `readings` and event for every change.
+    fn poll(&mut self, mut emit: impl FnMut(usize, WgiEvent)) {
+        self.controllers.clear();
+        // Avoiding using RawGameControllers().into_iter() here due to it causing an
+        // unhandled exception when the app is running through steam.
+        // https://gitlab.com/gilrs-project/gilrs/-/issues/132
+        if let Ok(raw_game_controllers) = RawGameController::RawGameControllers() {
+            let count = raw_game_controllers.Size().unwrap_or_default();
+            for index in 0..count {
+                if let Ok(controller) = raw_game_controllers.GetAt(index) {
+                    self.controllers.push(controller);
+                }
+            }
+        }
+
+        for controller in self.controllers.iter() {
+            let id: HSTRING = controller.NonRoamableId().unwrap();
+            // Find readings for this controller or insert new ones.
+            let index = match self
+                .readings
+                .iter()
+                .position(|(other_id, ..)| id == *other_id)
+            {
+                None => {
+                    let wgi_gamepad = match self.prefer_raw_readings {
+                        true => None,
+                        false => WgiGamepad::FromGameController(controller).ok(),
+                    };
+                    let reading = match wgi_gamepad {
+                        Some(wgi_gamepad) => {
+                            Reading::Gamepad(wgi_gamepad.GetCurrentReading().unwrap())
+                        }
+                        None => Reading::Raw(RawGamepadReading::new(controller).unwrap()),
+                    };
+
+                    self.readings.push((id, reading.clone(), reading));
+                    self.readings.len() - 1
+                }
+                Some(i) => i,
+            };
+
+            let (_, old_reading, new_reading) = &mut self.readings[index];
+
+            // Make last update's reading the old reading and get a new one.
+            std::mem::swap(old_reading, new_reading);
+            if let Err(e) = new_reading.update(controller) {
+                if e.code().is_err() {
+                    error!("Reading::update() function failed with {e}");
+                }
+            }
+
+            // Skip if this is the same reading as the last one.
+            if old_reading.time() == new_reading.time() {
+                continue;
+            }
+
+            Reading::events_for_differences(old_reading, new_reading, |event| {
+                emit(index, WgiEvent::new(controller.clone(), event))
+            });
+        }
+    }
+}
+
+impl fmt::Debug for Poller {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Poller")
+            .field("controllers", &self.controllers.len())
+            .field("prefer_raw_readings", &self.prefer_raw_readings)
+            .finish_non_exhaustive()
+    }
+}
+
 #[derive(Debug, Clone)]
 struct RawGamepadReading {
     axes: Vec<f64>,
@@ -377,12 +638,8 @@ impl Reading {
         Ok(())
     }
 
-    fn send_events_for_differences(
-        old: &Self,
-        new: &Self,
-        controller: &RawGameController,
-        tx: &Sender<WgiEvent>,
-    ) {
+    /// Calls `emit` for every difference between `old` and `new` readings.
+    fn events_for_differences(old: &Self, new: &Self, mut emit: impl FnMut(EventType)) {
         match (old, new) {
             // WGI RawGameController
             (Reading::Raw(old), Reading::Raw(new)) => {
@@ -398,8 +655,7 @@ impl Reading {
                                 index: index as u32,
                             }),
                         );
-                        tx.send(WgiEvent::new(controller.clone(), event_type))
-                            .unwrap()
+                        emit(event_type);
                     }
                 }
                 for index in 0..new.buttons.len() {
@@ -414,8 +670,7 @@ impl Reading {
                                 index: index as u32,
                             })),
                         };
-                        tx.send(WgiEvent::new(controller.clone(), event_type))
-                            .unwrap()
+                        emit(event_type);
                     }
                 }
 
@@ -430,8 +685,7 @@ impl Reading {
                                 index: (index * 2) as u32,
                             }),
                         );
-                        tx.send(WgiEvent::new(controller.clone(), event_type))
-                            .unwrap()
+                        emit(event_type);
                     }
                     if old_y != new_y {
                         let event_type = EventType::AxisValueChanged(
@@ -441,8 +695,7 @@ impl Reading {
                                 index: (index * 2) as u32 + 1,
                             }),
                         );
-                        tx.send(WgiEvent::new(controller.clone(), event_type))
-                            .unwrap()
+                        emit(event_type);
                     }
                 }
             }
@@ -459,28 +712,19 @@ impl Reading {
                 ];
                 for (new, old, code, multiplier) in axes {
                     if new != old {
-                        let _ = tx.send(WgiEvent::new(
-                            controller.clone(),
-                            EventType::AxisValueChanged(
-                                (multiplier * new * i32::MAX as f64) as i32,
-                                code,
-                            ),
+                        emit(EventType::AxisValueChanged(
+                            (multiplier * new * i32::MAX as f64) as i32,
+                            code,
                         ));
                     }
                 }
 
                 for (current_button, ev_code) in WGI_TO_GILRS_BUTTON_MAP {
                     if (new.Buttons & current_button) != (old.Buttons & current_button) {
-                        let _ = match new.Buttons & current_button != GamepadButtons::None {
-                            true => tx.send(WgiEvent::new(
-                                controller.clone(),
-                                EventType::ButtonPressed(ev_code),
-                            )),
-                            false => tx.send(WgiEvent::new(
-                                controller.clone(),
-                                EventType::ButtonReleased(ev_code),
-                            )),
-                        };
+                        match new.Buttons & current_button != GamepadButtons::None {
+                            true => emit(EventType::ButtonPressed(ev_code)),
+                            false => emit(EventType::ButtonReleased(ev_code)),
+                        }
                     }
                 }
             }
@@ -512,7 +756,9 @@ pub struct Gamepad {
     is_connected: bool,
     /// This is the generic controller handle without any mappings
     /// https://learn.microsoft.com/en-us/uwp/api/windows.gaming.input.rawgamecontroller
-    raw_game_controller: RawGameController,
+    ///
+    /// `None` only for the placeholder returned for ids that were never observed.
+    raw_game_controller: Option<RawGameController>,
     /// An ID for this device that will survive disconnects and restarts.
     /// [NonRoamableIds](https://learn.microsoft.com/en-us/uwp/api/windows.gaming.input.rawgamecontroller.nonroamableid)
     ///
@@ -522,18 +768,27 @@ pub struct Gamepad {
     /// If the controller has a [Gamepad](https://learn.microsoft.com/en-us/uwp/api/windows.gaming.input.gamepad?view=winrt-22621)
     /// mapping, this is used to access the mapped values.
     wgi_gamepad: Option<WgiGamepad>,
+    /// HID device interface path. Looking it up takes several blocking WinRT calls, so it's only
+    /// done when it's requested for the first time.
+    device_path: OnceCell<Option<String>>,
     axes: Option<Vec<EvCode>>,
     buttons: Option<Vec<EvCode>>,
+    /// Reading battery report takes several WinRT calls, so it's reused for `POWER_INFO_TTL`.
+    power_info: PowerInfoCache,
 }
 
 impl Gamepad {
-    fn new(id: u32, raw_game_controller: RawGameController) -> Gamepad {
+    fn new(id: u32, raw_game_controller: RawGameController, prefer_raw_readings: bool) -> Gamepad {
         let is_connected = true;
 
         let non_roamable_id = raw_game_controller.NonRoamableId().unwrap();
 
-        // See if we can cast this to a windows definition of a gamepad
-        let wgi_gamepad = WgiGamepad::FromGameController(&raw_game_controller).ok();
+        // See if we can cast this to a windows definition of a gamepad. Must match reading type
+        // selected in `Poller::poll()`.
+        let wgi_gamepad = match prefer_raw_readings {
+            true => None,
+            false => WgiGamepad::FromGameController(&raw_game_controller).ok(),
+        };
         let name = match raw_game_controller.DisplayName() {
             Ok(hstring) => hstring.to_string_lossy(),
             Err(_) => "unknown".to_string(),
@@ -542,8 +797,8 @@ impl Gamepad {
         let uuid = match wgi_gamepad.is_some() {
             true => Uuid::nil(),
             false => {
-                let vendor_id = raw_game_controller.HardwareVendorId().unwrap_or(0).to_be();
-                let product_id = raw_game_controller.HardwareProductId().unwrap_or(0).to_be();
+                let vendor_id = raw_game_controller.HardwareVendorId().unwrap_or(0);
+                let product_id = raw_game_controller.HardwareProductId().unwrap_or(0);
                 let version = 0;
 
                 // SDL uses the SDL_HARDWARE_BUS_BLUETOOTH bustype for IsWireless devices:
@@ -551,23 +806,10 @@ impl Gamepad {
                 // In my testing though, it caused my controllers to not find mappings.
                 // SDL only uses their WGI implementation for UWP apps so I guess it hasn't been
                 // used enough for people to submit mappings with the different bustype.
-                let bustype = SDL_HARDWARE_BUS_USB.to_be();
-
-                Uuid::from_fields(
-                    bustype,
-                    vendor_id,
-                    0,
-                    &[
-                        (product_id >> 8) as u8,
-                        product_id as u8,
-                        0,
-                        0,
-                        (version >> 8) as u8,
-                        version as u8,
-                        0,
-                        0,
-                    ],
-                )
+                let bustype = SDL_HARDWARE_BUS_USB;
+
+                let components = GuidComponents::new(bustype, vendor_id, product_id, version);
+                Uuid::from_bytes(components.to_uuid())
             }
         };
 
@@ -576,11 +818,13 @@ impl Gamepad {
             name,
             uuid,
             is_connected,
-            raw_game_controller,
+            raw_game_controller: Some(raw_game_controller),
             non_roamable_id,
             wgi_gamepad,
+            device_path: OnceCell::new(),
             axes: None,
             buttons: None,
+            power_info: PowerInfoCache::new(POWER_INFO_TTL),
         };
 
         if gamepad.wgi_gamepad.is_none() {
@@ -590,36 +834,94 @@ impl Gamepad {
         gamepad
     }
 
+    /// Creates disconnected gamepad without any elements. Used for ids that were never observed.
+    fn not_observed() -> Gamepad {
+        Gamepad {
+            id: u32::MAX,
+            name: String::new(),
+            uuid: Uuid::nil(),
+            is_connected: false,
+            raw_game_controller: None,
+            non_roamable_id: HSTRING::new(),
+            wgi_gamepad: None,
+            device_path: OnceCell::from(None),
+            axes: Some(Vec::new()),
+            buttons: Some(Vec::new()),
+            power_info: PowerInfoCache::new(POWER_INFO_TTL),
+        }
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    pub fn raw_name(&self) -> &[u8] {
+        self.name().as_bytes()
+    }
+
     pub fn uuid(&self) -> Uuid {
         self.uuid
     }
 
     pub fn vendor_id(&self) -> Option<u16> {
-        self.raw_game_controller.HardwareVendorId().ok()
+        self.raw_game_controller.as_ref()?.HardwareVendorId().ok()
     }
 
     pub fn product_id(&self) -> Option<u16> {
-        self.raw_game_controller.HardwareProductId().ok()
+        self.raw_game_controller.as_ref()?.HardwareProductId().ok()
+    }
+
+    pub fn device_path(&self) -> Option<&str> {
+        self.device_path
+            .get_or_init(|| {
+                let controller = self.raw_game_controller.as_ref()?;
+                resolve_device_path(controller)
+            })
+            .as_deref()
+    }
+
+    pub fn grab_error(&self) -> Option<&GrabError> {
+        None
+    }
+
+    pub fn connection_port(&self) -> Option<&str> {
+        None
+    }
+
+    pub fn virtual_reason(&self) -> Option<VirtualReason> {
+        virtual_device::detect(&DeviceDescriptor {
+            name: self.name(),
+            vendor_id: self.vendor_id(),
+            product_id: self.product_id(),
+            sysfs_path: None,
+        })
     }
 
     pub fn is_connected(&self) -> bool {
         self.is_connected
     }
 
+    pub fn is_receiving_events(&self) -> bool {
+        self.is_connected()
+    }
+
+    /// Returns power info read at most `POWER_INFO_TTL` ago.
     pub fn power_info(&self) -> PowerInfo {
-        self.power_info_err().unwrap_or(PowerInfo::Unknown)
+        self.power_info.get(Instant::now(), || {
+            self.power_info_err().unwrap_or(PowerInfo::Unknown)
+        })
     }
 
     /// Using this function so we can easily map errors to unknown
     fn power_info_err(&self) -> windows::core::Result<PowerInfo> {
-        if !self.raw_game_controller.IsWireless()? {
+        let raw_game_controller = match &self.raw_game_controller {
+            Some(raw_game_controller) => raw_game_controller,
+            None => return Ok(PowerInfo::Unknown),
+        };
+        if !raw_game_controller.IsWireless()? {
             return Ok(PowerInfo::Wired);
         }
-        let report: BatteryReport = self.raw_game_controller.TryGetBatteryReport()?;
+        let report: BatteryReport = raw_game_controller.TryGetBatteryReport()?;
         let status: BatteryStatus = report.Status()?;
 
         let power_info = match status {
@@ -641,12 +943,16 @@ impl Gamepad {
         Ok(power_info)
     }
 
+    pub fn set_grabbed(&self, _grabbed: bool) -> Result<(), GrabError> {
+        Err(GrabError::NotSupported)
+    }
+
     pub fn is_ff_supported(&self) -> bool {
         self.wgi_gamepad.is_some()
             && self
                 .raw_game_controller
-                .ForceFeedbackMotors()
-                .ok()
+                .as_ref()
+                .and_then(|controller| controller.ForceFeedbackMotors().ok())
                 .map(|motors| motors.First())
                 .is_some()
     }
@@ -706,9 +1012,13 @@ impl Gamepad {
     }
 
     fn collect_axes_and_buttons(&mut self) {
-        let axis_count = self.raw_game_controller.AxisCount().unwrap() as u32;
-        let button_count = self.raw_game_controller.ButtonCount().unwrap() as u32;
-        let switch_count = self.raw_game_controller.SwitchCount().unwrap() as u32;
+        let raw_game_controller = match &self.raw_game_controller {
+            Some(raw_game_controller) => raw_game_controller,
+            None => return,
+        };
+        let axis_count = raw_game_controller.AxisCount().unwrap() as u32;
+        let button_count = raw_game_controller.ButtonCount().unwrap() as u32;
+        let switch_count = raw_game_controller.SwitchCount().unwrap() as u32;
         self.buttons = Some(
             (0..button_count)
                 .map(|index| EvCode {
@@ -743,6 +1053,96 @@ impl Gamepad {
     }
 }
 
+/// Finds HID device interface path of `raw_game_controller` by looking up HID devices with the
+/// same vendor and product ID. Best effort, returns `None` on failure. Blocks until WinRT finds
+/// the devices.
+fn resolve_device_path(raw_game_controller: &RawGameController) -> Option<String> {
+    let vendor_id = raw_game_controller.HardwareVendorId().ok()?;
+    let product_id = raw_game_controller.HardwareProductId().ok()?;
+
+    find_device_path(&WinRtHidDevices, vendor_id, product_id)
+}
+
+/// Lookup of HID devices. Implemented with WinRT `DeviceInformation`, tests use mocked results.
+trait HidDevices {
+    /// Returns device interface paths (`DeviceInformation.Id`) of HID devices with generic
+    /// desktop `usage`, `vendor_id` and `product_id`. Paths that couldn't be read are errors.
+    fn find(
+        &self,
+        usage: u16,
+        vendor_id: u16,
+        product_id: u16,
+    ) -> windows::core::Result<Vec<windows::core::Result<String>>>;
+}
+
+struct WinRtHidDevices;
+
+impl HidDevices for WinRtHidDevices {
+    fn find(
+        &self,
+        usage: u16,
+        vendor_id: u16,
+        product_id: u16,
+    ) -> windows::core::Result<Vec<windows::core::Result<String>>> {
+        let selector = HidDevice::GetDeviceSelectorVidPid(
+            HID_USAGE_PAGE_GENERIC_DESKTOP,
+            usage,
+            vendor_id,
+            product_id,
+        )?;
+        let devices = DeviceInformation::FindAllAsyncAqsFilter(&selector)?.get()?;
+
+        // Not using into_iter() for the same reason as with RawGameControllers.
+        let mut paths = Vec::new();
+        for i in 0..devices.Size()? {
+            paths.push(
+                devices
+                    .GetAt(i)
+                    .and_then(|info| info.Id())
+                    .map(|id| id.to_string_lossy()),
+            );
+        }
+
+        Ok(paths)
+    }
+}
+
+/// Looks up HID devices for every usage of gamepads and selects device path from found paths.
+/// Usages for which lookup fails and paths that couldn't be read are skipped.
+fn find_device_path(devices: &impl HidDevices, vendor_id: u16, product_id: u16) -> Option<String> {
+    let mut paths = Vec::new();
+    for usage in HID_USAGES {
+        match devices.find(usage, vendor_id, product_id) {
+            Ok(found) => paths.extend(found.into_iter().filter_map(|path| {
+                path.map_err(|e| debug!("Failed to read HID device path: {}", e))
+                    .ok()
+            })),
+            Err(e) => debug!("Failed to find HID devices with usage {:#x}: {}", usage, e),
+        }
+    }
+
+    select_device_path(paths)
+}
+
+/// Returns device interface path if all `paths` refer to the same device. Multiple devices with
+/// the same vendor and product ID can't be told apart, so `None` is returned in that case.
+fn select_device_path(mut paths: Vec<String>) -> Option<String> {
+    paths.sort_unstable();
+    paths.dedup();
+
+    match paths.len() {
+        1 => paths.pop(),
+        0 => None,
+        n => {
+            debug!(
+                "Found {} HID devices matching gamepad, not using any of them",
+                n
+            );
+            None
+        }
+    }
+}
+
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 enum EvCodeKind {
@@ -770,8 +1170,7 @@ pub struct EvCode {
 }
 
 impl EvCode {
-    pub 
-    fn into_u32(self) -> u32 {
+    pub fn into_u32(self) -> u32 {
         ((self.kind as u32) << 16) | self.index
     }
 }
@@ -918,6 +1317,16 @@ pub mod native_ev_codes {
         index: u32::MAX,
     };
 
+    // RawGameController doesn't tell which button is Share, so, like the DPad buttons above, this
+    // code is never reported by the backend and `Button::Misc1` is only available through SDL
+    // mappings.
+    pub const BTN_MISC1: EvCode = EvCode {
+        kind: EvCodeKind::Button,
+        index: u32::MAX - 4,
+    };
+
+    // Buttons reported by Windows.Gaming.Input.Gamepad. There is no BTN_MODE, because the guide
+    // button is reserved by the system and not part of `GamepadButtons`.
     pub(super) static BUTTONS: [EvCode; 14] = [
         BTN_WEST,
         BTN_SOUTH,
@@ -944,3 +1353,348 @@ pub mod native_ev_codes {
         AXIS_RSTICKY,
     ];
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        find_device_path, select_device_path, Backlog, EvCode, EvCodeKind, EventType, HidDevices,
+        RawGamepadReading, Reading, WgiEvent, EVENT_CHANNEL_CAPACITY, HID_USAGES,
+    };
+    use crate::native_ev_codes as nec;
+    use std::cell::RefCell;
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::mpsc;
+    use windows::core::Result;
+    use windows::Gaming::Input::{GameControllerSwitchPosition, GamepadButtons, GamepadReading};
+
+    fn raw_code(kind: EvCodeKind, index: u32) -> crate::EvCode {
+        crate::EvCode(EvCode { kind, index })
+    }
+
+    /// Events received by `Gilrs` when `readings` of one controller are polled one after another
+    /// by the background thread and `Gilrs` reads all events after every poll.
+    fn thread_mode(readings: &[Reading]) -> Vec<EventType> {
+        let (tx, rx) = mpsc::sync_channel(EVENT_CHANNEL_CAPACITY);
+        let mut backlog = Backlog::new();
+        let mut received = Vec::new();
+        for pair in readings.windows(2) {
+            Reading::events_for_differences(&pair[0], &pair[1], |event| {
+                backlog.push(Some(0), WgiEvent::new((), event))
+            });
+            backlog.flush(&tx);
+            received.extend(rx.try_iter().map(|event| event.event));
+        }
+
+        received
+    }
+
+    /// Same as `thread_mode()`, but readings are polled by `next_event()` (`wgi-no-thread`
+    /// feature).
+    fn no_thread_mode(readings: &[Reading]) -> Vec<EventType> {
+        let mut queue = VecDeque::new();
+        let mut received = Vec::new();
+        for pair in readings.windows(2) {
+            if queue.is_empty() {
+                Reading::events_for_differences(&pair[0], &pair[1], |event| {
+                    queue.push_back(WgiEvent::new((), event))
+                });
+            }
+            received.extend(queue.drain(..).map(|event| event.event));
+        }
+
+        received
+    }
+
+    #[test]
+    fn thread_and_no_thread_modes_emit_same_events() {
+        let raw = |axis, button, switch, time| {
+            Reading::Raw(RawGamepadReading {
+                axes: vec![axis],
+                buttons: vec![false, button],
+                switches: vec![switch],
+                time,
+            })
+        };
+        let gamepad = |buttons, x, time| {
+            Reading::Gamepad(GamepadReading {
+                Timestamp: time,
+                Buttons: buttons,
+                LeftThumbstickX: x,
+                ..Default::default()
+            })
+        };
+
+        let cases = [
+            (
+                vec![
+                    raw(0.5, false, GameControllerSwitchPosition::Center, 0),
+                    raw(1.0, true, GameControllerSwitchPosition::Up, 1),
+                    raw(1.0, true, GameControllerSwitchPosition::Up, 2),
+                    raw(0.5, false, GameControllerSwitchPosition::Center, 3),
+                ],
+                vec![
+                    EventType::AxisValueChanged(32767, raw_code(EvCodeKind::Axis, 0)),
+                    EventType::ButtonPressed(raw_code(EvCodeKind::Button, 1)),
+                    EventType::AxisValueChanged(-1, raw_code(EvCodeKind::Switch, 1)),
+                    EventType::AxisValueChanged(0, raw_code(EvCodeKind::Axis, 0)),
+                    EventType::ButtonReleased(raw_code(EvCodeKind::Button, 1)),
+                    EventType::AxisValueChanged(0, raw_code(EvCodeKind::Switch, 1)),
+                ],
+            ),
+            (
+                vec![
+                    gamepad(GamepadButtons::None, 0.0, 0),
+                    gamepad(GamepadButtons::A, 1.0, 1),
+                    gamepad(GamepadButtons::A, 1.0, 2),
+                    gamepad(GamepadButtons::None, 0.0, 3),
+                ],
+                vec![
+                    EventType::AxisValueChanged(i32::MAX, nec::AXIS_LSTICKX),
+                    EventType::ButtonPressed(nec::BTN_SOUTH),
+                    EventType::AxisValueChanged(0, nec::AXIS_LSTICKX),
+                    EventType::ButtonReleased(nec::BTN_SOUTH),
+                ],
+            ),
+        ];
+
+        for (readings, expected) in cases {
+            assert_eq!(thread_mode(&readings), expected);
+            assert_eq!(no_thread_mode(&readings), expected);
+        }
+
+        // Channel is reused, more events than its capacity go through it.
+        let readings: Vec<_> = (0..=2 * EVENT_CHANNEL_CAPACITY as u64)
+            .map(|time| {
+                let buttons = match time % 2 {
+                    0 => GamepadButtons::None,
+                    _ => GamepadButtons::A,
+                };
+                gamepad(buttons, (time % 3) as f64 / 2.0, time)
+            })
+            .collect();
+        let events = thread_mode(&readings);
+        assert!(events.len() > EVENT_CHANNEL_CAPACITY);
+        assert_eq!(events, no_thread_mode(&readings));
+    }
+
+    #[test]
+    fn backlog_coalesces_axes() {
+        let gamepad = |buttons, x, time| {
+            Reading::Gamepad(GamepadReading {
+                Timestamp: time,
+                Buttons: buttons,
+                LeftThumbstickX: x,
+                ..Default::default()
+            })
+        };
+        let x = |time: u64| (time as f64 / 100.0 * i32::MAX as f64) as i32;
+
+        let (tx, rx) = mpsc::sync_channel(2);
+        let mut backlog = Backlog::new();
+        let mut old = gamepad(GamepadButtons::None, 0.0, 0);
+        // Stick moves and A is pressed and released while nobody reads events.
+        for time in 1..=100 {
+            let buttons = match time {
+                40..=59 => GamepadButtons::A,
+                _ => GamepadButtons::None,
+            };
+            let new = gamepad(buttons, time as f64 / 100.0, time);
+            Reading::events_for_differences(&old, &new, |event| {
+                backlog.push(Some(0), WgiEvent::new((), event))
+            });
+            backlog.flush(&tx);
+            old = new;
+        }
+
+        let mut received = Vec::new();
+        loop {
+            backlog.flush(&tx);
+            match rx.try_recv() {
+                Ok(event) => received.push(event.event),
+                Err(_) => break,
+            }
+        }
+
+        // Stick position at time of press and release is kept.
+        assert_eq!(
+            received,
+            [
+                EventType::AxisValueChanged(x(1), nec::AXIS_LSTICKX),
+                EventType::AxisValueChanged(x(2), nec::AXIS_LSTICKX),
+                EventType::AxisValueChanged(x(40), nec::AXIS_LSTICKX),
+                EventType::ButtonPressed(nec::BTN_SOUTH),
+                EventType::AxisValueChanged(x(60), nec::AXIS_LSTICKX),
+                EventType::ButtonReleased(nec::BTN_SOUTH),
+                EventType::AxisValueChanged(x(100), nec::AXIS_LSTICKX),
+            ]
+        );
+    }
+
+    #[test]
+    fn backlog_keeps_other_events() {
+        let axis = |value, code| WgiEvent::new((), EventType::AxisValueChanged(value, code));
+
+        let (tx, rx) = mpsc::sync_channel(0);
+        let mut backlog = Backlog::new();
+        backlog.push(None, WgiEvent::new((), EventType::Connected));
+        backlog.push(Some(0), axis(1, nec::AXIS_LSTICKX));
+        backlog.push(Some(1), axis(2, nec::AXIS_LSTICKX));
+        backlog.push(Some(0), axis(3, nec::AXIS_LSTICKY));
+        backlog.push(Some(0), axis(4, nec::AXIS_LSTICKX));
+        backlog.push(
+            Some(0),
+            WgiEvent::new((), EventType::ButtonPressed(nec::BTN_SOUTH)),
+        );
+        backlog.push(
+            Some(0),
+            WgiEvent::new((), EventType::ButtonPressed(nec::BTN_SOUTH)),
+        );
+        backlog.push(Some(0), axis(5, nec::AXIS_LSTICKX));
+        backlog.push(Some(0), axis(6, nec::AXIS_LSTICKX));
+        backlog.push(None, WgiEvent::new((), EventType::Disconnected));
+        backlog.push(Some(0), axis(7, nec::AXIS_LSTICKX));
+        backlog.flush(&tx);
+
+        let queued: Vec<_> = backlog
+            .events
+            .iter()
+            .map(|(controller, event)| (*controller, event.event))
+            .collect();
+        assert_eq!(
+            queued,
+            [
+                (None, EventType::Connected),
+                (Some(0), EventType::AxisValueChanged(4, nec::AXIS_LSTICKX)),
+                (Some(1), EventType::AxisValueChanged(2, nec::AXIS_LSTICKX)),
+                (Some(0), EventType::AxisValueChanged(3, nec::AXIS_LSTICKY)),
+                (Some(0), EventType::ButtonPressed(nec::BTN_SOUTH)),
+                (Some(0), EventType::ButtonPressed(nec::BTN_SOUTH)),
+                (Some(0), EventType::AxisValueChanged(6, nec::AXIS_LSTICKX)),
+                (None, EventType::Disconnected),
+                (Some(0), EventType::AxisValueChanged(7, nec::AXIS_LSTICKX)),
+            ]
+        );
+
+        // Events are discarded when `Gilrs` is gone.
+        drop(rx);
+        backlog.flush(&tx);
+        assert!(backlog.events.is_empty());
+    }
+
+    #[test]
+    fn backlog_doesnt_move_axis_before_button() {
+        let mut backlog = Backlog::new();
+        backlog.push(
+            Some(0),
+            WgiEvent::new((), EventType::AxisValueChanged(5, nec::AXIS_LSTICKX)),
+        );
+        backlog.push(
+            Some(0),
+            WgiEvent::new((), EventType::ButtonPressed(nec::BTN_SOUTH)),
+        );
+        backlog.push(
+            Some(0),
+            WgiEvent::new((), EventType::AxisValueChanged(9, nec::AXIS_LSTICKX)),
+        );
+
+        let queued: Vec<_> = backlog
+            .events
+            .iter()
+            .map(|(_, event)| event.event)
+            .collect();
+        assert_eq!(
+            queued,
+            [
+                EventType::AxisValueChanged(5, nec::AXIS_LSTICKX),
+                EventType::ButtonPressed(nec::BTN_SOUTH),
+                EventType::AxisValueChanged(9, nec::AXIS_LSTICKX),
+            ]
+        );
+    }
+
+    #[test]
+    fn device_path_selection() {
+        let guid = "{4d1e55b2-f16f-11cf-88cb-001111000030}";
+        let path = format!(r"\\?\HID#VID_045E&PID_028E#7&1a2b3c4d&0&0000#{}", guid);
+        let other = format!(r"\\?\HID#VID_045E&PID_028E#7&5e6f7a8b&0&0000#{}", guid);
+
+        assert_eq!(select_device_path(vec![]), None);
+        assert_eq!(select_device_path(vec![path.clone()]), Some(path.clone()));
+        // Same device can be returned for multiple usages.
+        assert_eq!(
+            select_device_path(vec![path.clone(), path.clone()]),
+            Some(path.clone())
+        );
+        assert_eq!(select_device_path(vec![path.clone(), other]), None);
+    }
+
+    /// Mocked `DeviceInformation` results, keyed by HID usage.
+    struct MockHidDevices {
+        vendor_id: u16,
+        product_id: u16,
+        results: HashMap<u16, Result<Vec<Result<String>>>>,
+        queried: RefCell<Vec<u16>>,
+    }
+
+    impl HidDevices for MockHidDevices {
+        fn find(&self, usage: u16, vendor_id: u16, product_id: u16) -> Result<Vec<Result<String>>> {
+            assert_eq!((vendor_id, product_id), (self.vendor_id, self.product_id));
+            self.queried.borrow_mut().push(usage);
+            self.results.get(&usage).cloned().unwrap_or(Ok(Vec::new()))
+        }
+    }
+
+    #[test]
+    fn device_path_lookup() {
+        let guid = "{4d1e55b2-f16f-11cf-88cb-001111000030}";
+        let path = format!(r"\\?\HID#VID_045E&PID_028E#7&1a2b3c4d&0&0000#{}", guid);
+        let other = format!(r"\\?\HID#VID_045E&PID_028E#7&5e6f7a8b&0&0000#{}", guid);
+        let error = || windows::core::Error::from(windows::core::HRESULT(0x8000_4005_u32 as i32));
+        let lookup = |results: Vec<(u16, Result<Vec<Result<String>>>)>| {
+            let devices = MockHidDevices {
+                vendor_id: 0x045e,
+                product_id: 0x028e,
+                results: results.into_iter().collect(),
+                queried: RefCell::new(Vec::new()),
+            };
+            let found = find_device_path(&devices, 0x045e, 0x028e);
+            assert_eq!(devices.queried.into_inner(), HID_USAGES);
+            found
+        };
+
+        assert_eq!(lookup(vec![]), None);
+        assert_eq!(
+            lookup(vec![(0x05, Ok(vec![Ok(path.clone())]))]),
+            Some(path.clone())
+        );
+        // Failure of one usage doesn't prevent finding device with other usage.
+        assert_eq!(
+            lookup(vec![
+                (0x04, Err(error())),
+                (0x05, Ok(vec![Ok(path.clone())]))
+            ]),
+            Some(path.clone())
+        );
+        // Same for device which path couldn't be read.
+        assert_eq!(
+            lookup(vec![(0x05, Ok(vec![Err(error()), Ok(path.clone())]))]),
+            Some(path.clone())
+        );
+        assert_eq!(
+            lookup(vec![
+                (0x04, Ok(vec![Ok(path.clone())])),
+                (0x08, Ok(vec![Ok(other)]))
+            ]),
+            None
+        );
+        assert_eq!(
+            lookup(
+                HID_USAGES
+                    .iter()
+                    .map(|&usage| (usage, Err(error())))
+                    .collect()
+            ),
+            None
+        );
+    }
+}
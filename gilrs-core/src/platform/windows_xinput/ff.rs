@@ -6,6 +6,7 @@
 // copied, modified, or distributed except according to those terms.
 
 use rusty_xinput::{self, XInputHandle, XInputUsageError};
+use std::io::{Error as IoError, ErrorKind, Result as IoResult};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -20,10 +21,10 @@ impl Device {
         Device { id, xinput_handle }
     }
 
-    pub fn set_ff_state(&mut self, strong: u16, weak: u16, _min_duration: Duration) {
-        match self.xinput_handle.set_state(self.id, strong, weak) {
+    pub fn set_ff_state(&mut self, strong: u16, weak: u16, min_duration: Duration) {
+        match self.try_set_ff_state(strong, weak, min_duration) {
             Ok(()) => (),
-            Err(XInputUsageError::DeviceNotConnected) => {
+            Err(err) if err.kind() == ErrorKind::NotConnected => {
                 error!(
                     "Failed to change FF state – gamepad with id {} is no longer connected.",
                     self.id
@@ -31,10 +32,23 @@ impl Device {
             }
             Err(err) => {
                 error!(
-                    "Failed to change FF state – unknown error. ID = {}, error = {:?}.",
+                    "Failed to change FF state – unknown error. ID = {}, error = {}.",
                     self.id, err
                 );
             }
         }
     }
+
+    pub fn try_set_ff_state(
+        &mut self,
+        strong: u16,
+        weak: u16,
+        _min_duration: Duration,
+    ) -> IoResult<()> {
+        match self.xinput_handle.set_state(self.id, strong, weak) {
+            Ok(()) => Ok(()),
+            Err(XInputUsageError::DeviceNotConnected) => Err(ErrorKind::NotConnected.into()),
+            Err(err) => Err(IoError::other(format!("{:?}", err))),
+        }
+    }
 }
@@ -6,7 +6,8 @@
 // copied, modified, or distributed except according to those terms.
 
 use super::FfDevice;
-use crate::{AxisInfo, Event, EventType, PlatformError, PowerInfo};
+use crate::virtual_device::{self, DeviceDescriptor, VirtualReason};
+use crate::{AxisInfo, Event, EventType, GrabError, PlatformError, PowerInfo};
 
 use std::error::Error as StdError;
 use std::fmt::{Display, Formatter, Result as FmtResult};
@@ -39,6 +40,7 @@ const MAX_XINPUT_CONTROLLERS: usize = 4;
 pub struct Gilrs {
     gamepads: [Gamepad; MAX_XINPUT_CONTROLLERS],
     rx: Receiver<Event>,
+    not_observed: Box<Gamepad>,
 }
 
 impl Gilrs {
@@ -59,11 +61,17 @@ impl Gilrs {
             connected[id] = gamepads[id].is_connected;
         }
 
+        let not_observed = Box::new(Gamepad::not_observed(xinput_handle.clone()));
+
         let (tx, rx) = mpsc::channel();
         Self::spawn_thread(tx, connected, xinput_handle.clone());
 
         // Coerce gamepads vector to slice
-        Ok(Gilrs { gamepads, rx })
+        Ok(Gilrs {
+            gamepads,
+            rx,
+            not_observed,
+        })
     }
 
     pub(crate) fn next_event(&mut self) -> Option<Event> {
@@ -99,6 +107,10 @@ impl Gilrs {
         self.gamepads.get(id)
     }
 
+    pub fn gamepad_or_placeholder(&self, id: usize) -> &Gamepad {
+        self.gamepads.get(id).unwrap_or(&self.not_observed)
+    }
+
     pub fn last_gamepad_hint(&self) -> usize {
         self.gamepads.len()
     }
@@ -387,6 +399,7 @@ pub struct Gamepad {
     uuid: Uuid,
     id: u32,
     is_connected: bool,
+    device_path: Option<String>,
     xinput_handle: Arc<XInputHandle>,
 }
 
@@ -398,6 +411,19 @@ impl Gamepad {
             uuid: Uuid::nil(),
             id,
             is_connected,
+            device_path: Some(format!("xinput/{}", id)),
+            xinput_handle,
+        }
+    }
+
+    /// Creates disconnected gamepad with id outside of valid XInput range. Used for ids that were
+    /// never observed.
+    fn not_observed(xinput_handle: Arc<XInputHandle>) -> Gamepad {
+        Gamepad {
+            uuid: Uuid::nil(),
+            id: MAX_XINPUT_CONTROLLERS as u32,
+            is_connected: false,
+            device_path: None,
             xinput_handle,
         }
     }
@@ -406,6 +432,10 @@ impl Gamepad {
         "Xbox Controller"
     }
 
+    pub fn raw_name(&self) -> &[u8] {
+        self.name().as_bytes()
+    }
+
     pub fn uuid(&self) -> Uuid {
         self.uuid
     }
@@ -418,10 +448,35 @@ impl Gamepad {
         None
     }
 
+    pub fn device_path(&self) -> Option<&str> {
+        self.device_path.as_deref()
+    }
+
+    pub fn grab_error(&self) -> Option<&GrabError> {
+        None
+    }
+
+    pub fn connection_port(&self) -> Option<&str> {
+        None
+    }
+
+    pub fn virtual_reason(&self) -> Option<VirtualReason> {
+        virtual_device::detect(&DeviceDescriptor {
+            name: self.name(),
+            vendor_id: self.vendor_id(),
+            product_id: self.product_id(),
+            sysfs_path: None,
+        })
+    }
+
     pub fn is_connected(&self) -> bool {
         self.is_connected
     }
 
+    pub fn is_receiving_events(&self) -> bool {
+        self.is_connected()
+    }
+
     pub fn power_info(&self) -> PowerInfo {
         match self.xinput_handle.get_gamepad_battery_information(self.id) {
             Ok(binfo) => match binfo.battery_type {
@@ -454,6 +509,10 @@ impl Gamepad {
         }
     }
 
+    pub fn set_grabbed(&self, _grabbed: bool) -> Result<(), GrabError> {
+        Err(GrabError::NotSupported)
+    }
+
     pub fn is_ff_supported(&self) -> bool {
         true
     }
@@ -561,6 +620,8 @@ pub mod native_ev_codes {
     pub const BTN_DPAD_LEFT: EvCode = EvCode(29);
     pub const BTN_DPAD_RIGHT: EvCode = EvCode(30);
 
+    pub const BTN_MISC1: EvCode = EvCode(31);
+
     pub(super) static BUTTONS: [EvCode; 15] = [
         BTN_SOUTH,
         BTN_EAST,
@@ -11,3 +11,4 @@ pub use self::ff::Device as FfDevice;
 pub use self::gamepad::{native_ev_codes, EvCode, Gamepad, Gilrs};
 
 pub const IS_Y_AXIS_REVERSED: bool = false;
+pub const BACKEND_NAME: &str = "xinput";
@@ -0,0 +1,104 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Cache for backends where reading power info is expensive.
+
+use crate::PowerInfo;
+
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+/// How long power info read from OS is reused.
+pub(crate) const POWER_INFO_TTL: Duration = Duration::from_secs(2);
+
+/// Last power info and time when it was read. It is refreshed lazily, when it's requested after
+/// `ttl` passed.
+#[derive(Debug)]
+pub(crate) struct PowerInfoCache {
+    ttl: Duration,
+    cached: Cell<Option<(Instant, PowerInfo)>>,
+}
+
+impl PowerInfoCache {
+    pub fn new(ttl: Duration) -> Self {
+        PowerInfoCache {
+            ttl,
+            cached: Cell::new(None),
+        }
+    }
+
+    /// Returns cached power info or, if it's older than `ttl` at `now`, result of `query`.
+    pub fn get(&self, now: Instant, query: impl FnOnce() -> PowerInfo) -> PowerInfo {
+        match self.cached.get() {
+            Some((time, info)) if now.saturating_duration_since(time) < self.ttl => info,
+            _ => {
+                let info = query();
+                self.cached.set(Some((now, info)));
+                info
+            }
+        }
+    }
+
+    /// Forces next `get()` to query power info, for example because gamepad was reconnected and
+    /// could switch between wired and wireless connection.
+    pub fn invalidate(&self) {
+        self.cached.set(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PowerInfoCache, POWER_INFO_TTL};
+    use crate::PowerInfo;
+
+    use std::cell::Cell;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn ttl() {
+        assert_eq!(POWER_INFO_TTL, Duration::from_secs(2));
+        let cache = PowerInfoCache::new(POWER_INFO_TTL);
+        let queries = Cell::new(0);
+        let start = Instant::now();
+        let get = |secs: f32, info| {
+            cache.get(start + Duration::from_secs_f32(secs), || {
+                queries.set(queries.get() + 1);
+                info
+            })
+        };
+
+        assert_eq!(
+            get(0.0, PowerInfo::Discharging(50)),
+            PowerInfo::Discharging(50)
+        );
+        assert_eq!(queries.get(), 1);
+        // Cached value is returned until TTL passes.
+        assert_eq!(
+            get(1.0, PowerInfo::Discharging(40)),
+            PowerInfo::Discharging(50)
+        );
+        assert_eq!(
+            get(1.9, PowerInfo::Discharging(40)),
+            PowerInfo::Discharging(50)
+        );
+        assert_eq!(queries.get(), 1);
+        assert_eq!(
+            get(2.0, PowerInfo::Discharging(40)),
+            PowerInfo::Discharging(40)
+        );
+        assert_eq!(queries.get(), 2);
+        // Clock going backwards doesn't refresh.
+        assert_eq!(get(1.0, PowerInfo::Wired), PowerInfo::Discharging(40));
+        assert_eq!(queries.get(), 2);
+
+        cache.invalidate();
+        assert_eq!(get(2.5, PowerInfo::Wired), PowerInfo::Wired);
+        assert_eq!(queries.get(), 3);
+        assert_eq!(get(3.0, PowerInfo::Charged), PowerInfo::Wired);
+        assert_eq!(queries.get(), 3);
+    }
+}
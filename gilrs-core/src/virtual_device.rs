@@ -0,0 +1,171 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Heuristics that recognize virtual gamepads, shared by all backends.
+
+/// Why gamepad is considered virtual, see
+/// [`Gamepad::virtual_reason()`](crate::Gamepad::virtual_reason).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum VirtualReason {
+    /// Gamepad is created by Steam Input, which remaps input of other device.
+    SteamInput,
+    /// Vendor and product ID belong to known gamepad emulator.
+    KnownEmulator { vendor_id: u16, product_id: u16 },
+    /// Device is not backed by hardware, for example it was created with uinput on Linux.
+    NoPhysicalDevice,
+}
+
+/// Information about device used to recognize virtual gamepads.
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct DeviceDescriptor<'a> {
+    pub name: &'a str,
+    pub vendor_id: Option<u16>,
+    pub product_id: Option<u16>,
+    /// Canonical sysfs path of device. Linux only.
+    pub sysfs_path: Option<&'a str>,
+}
+
+// Steam Input presents remapped controllers as Xbox 360 pads with Valve's vendor ID.
+const STEAM_INPUT_IDS: [(u16, u16); 1] = [(0x28de, 0x11ff)];
+const STEAM_INPUT_NAMES: [&str; 1] = ["Steam Virtual Gamepad"];
+// vJoy.
+const EMULATOR_IDS: [(u16, u16); 1] = [(0x1234, 0xbead)];
+
+/// Returns why device described by `desc` is virtual or `None` if it looks like real gamepad.
+///
+/// Emulators that copy IDs of real gamepads (like ViGEm) can't be recognized.
+pub(crate) fn detect(desc: &DeviceDescriptor<'_>) -> Option<VirtualReason> {
+    let ids = desc.vendor_id.zip(desc.product_id);
+
+    if ids.is_some_and(|ids| STEAM_INPUT_IDS.contains(&ids))
+        || STEAM_INPUT_NAMES.contains(&desc.name)
+    {
+        return Some(VirtualReason::SteamInput);
+    }
+
+    if let Some((vendor_id, product_id)) = ids.filter(|ids| EMULATOR_IDS.contains(ids)) {
+        return Some(VirtualReason::KnownEmulator {
+            vendor_id,
+            product_id,
+        });
+    }
+
+    // Devices created with uinput have no parent device. Devices created with uhid (used for
+    // example by BlueZ for Bluetooth LE gamepads) are also in `/sys/devices/virtual`, but they are
+    // real HID devices.
+    if desc
+        .sysfs_path
+        .is_some_and(|path| path.starts_with("/sys/devices/virtual/input/"))
+    {
+        return Some(VirtualReason::NoPhysicalDevice);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect, DeviceDescriptor, VirtualReason};
+
+    fn linux<'a>(name: &'a str, ids: (u16, u16), sysfs_path: &'a str) -> DeviceDescriptor<'a> {
+        DeviceDescriptor {
+            name,
+            vendor_id: Some(ids.0),
+            product_id: Some(ids.1),
+            sysfs_path: Some(sysfs_path),
+        }
+    }
+
+    #[test]
+    fn steam_input() {
+        assert_eq!(
+            detect(&linux(
+                "Microsoft X-Box 360 pad 0",
+                (0x28de, 0x11ff),
+                "/sys/devices/virtual/input/input42/event20"
+            )),
+            Some(VirtualReason::SteamInput)
+        );
+
+        // Windows doesn't report IDs of XInput devices.
+        let desc = DeviceDescriptor {
+            name: "Steam Virtual Gamepad",
+            ..Default::default()
+        };
+        assert_eq!(detect(&desc), Some(VirtualReason::SteamInput));
+
+        // Real Steam Controller.
+        assert_eq!(
+            detect(&linux(
+                "Valve Software Steam Controller",
+                (0x28de, 0x1142),
+                "/sys/devices/pci0000:00/0000:00:14.0/usb1/1-3/1-3:1.2/0003:28DE:1142.0005/input/input25/event22"
+            )),
+            None
+        );
+    }
+
+    #[test]
+    fn emulators() {
+        let desc = DeviceDescriptor {
+            name: "vJoy Device",
+            vendor_id: Some(0x1234),
+            product_id: Some(0xbead),
+            sysfs_path: None,
+        };
+        assert_eq!(
+            detect(&desc),
+            Some(VirtualReason::KnownEmulator {
+                vendor_id: 0x1234,
+                product_id: 0xbead
+            })
+        );
+    }
+
+    #[test]
+    fn uinput() {
+        assert_eq!(
+            detect(&linux(
+                "Xbox Gamepad (userspace driver)",
+                (0x0000, 0x0000),
+                "/sys/devices/virtual/input/input30/event12"
+            )),
+            Some(VirtualReason::NoPhysicalDevice)
+        );
+    }
+
+    #[test]
+    fn real_gamepads() {
+        assert_eq!(
+            detect(&linux(
+                "Microsoft X-Box 360 pad",
+                (0x045e, 0x028e),
+                "/sys/devices/pci0000:00/0000:00:14.0/usb1/1-2/1-2:1.0/input/input17/event5"
+            )),
+            None
+        );
+        assert_eq!(
+            detect(&linux(
+                "Wireless Controller",
+                (0x054c, 0x09cc),
+                "/sys/devices/pci0000:00/0000:00:14.0/usb1/1-10/1-10:1.0/bluetooth/hci0/hci0:256/0005:054C:09CC.0004/input/input20/event20"
+            )),
+            None
+        );
+        // Bluetooth LE gamepad, BlueZ creates it with uhid.
+        assert_eq!(
+            detect(&linux(
+                "Xbox Wireless Controller",
+                (0x045e, 0x0b13),
+                "/sys/devices/virtual/misc/uhid/0005:045E:0B13.0007/input/input28/event18"
+            )),
+            None
+        );
+        assert_eq!(detect(&DeviceDescriptor::default()), None);
+    }
+}
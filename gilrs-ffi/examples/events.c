@@ -0,0 +1,60 @@
+/*
+ * Prints events from all gamepads. Optional argument limits number of polls (10 ms apart), by
+ * default it runs until interrupted.
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <time.h>
+
+#include "gilrs.h"
+
+int main(int argc, char **argv) {
+    long polls = argc > 1 ? strtol(argv[1], NULL, 10) : -1;
+    struct timespec delay = {0, 10 * 1000 * 1000};
+    char name[128];
+    GilrsEvent ev;
+
+    Gilrs *gilrs = gilrs_new();
+    if (!gilrs) {
+        fprintf(stderr, "failed to create gilrs context\n");
+        return 1;
+    }
+
+    for (long i = 0; polls < 0 || i < polls; i++) {
+        while (gilrs_next_event(gilrs, &ev)) {
+            switch (ev.event_type) {
+            case GILRS_EVENT_TYPE_CONNECTED: {
+                uint8_t level = 0;
+                GilrsPowerState power = gilrs_gamepad_power_info(gilrs, ev.id, &level);
+
+                gilrs_gamepad_name(gilrs, ev.id, name, sizeof(name));
+                printf("gamepad %zu connected: %s (power %d, %u%%)\n", (size_t)ev.id, name,
+                       (int)power, (unsigned)level);
+                break;
+            }
+            case GILRS_EVENT_TYPE_DISCONNECTED:
+                printf("gamepad %zu disconnected\n", (size_t)ev.id);
+                break;
+            case GILRS_EVENT_TYPE_BUTTON_PRESSED:
+                printf("gamepad %zu: button %u pressed\n", (size_t)ev.id, (unsigned)ev.button);
+                if (ev.button == GILRS_BUTTON_SOUTH) {
+                    gilrs_gamepad_rumble(gilrs, ev.id, 0.5f, 0.5f, 200);
+                }
+                break;
+            case GILRS_EVENT_TYPE_AXIS_CHANGED:
+                printf("gamepad %zu: axis %u = %.3f\n", (size_t)ev.id, (unsigned)ev.axis,
+                       ev.value);
+                break;
+            default:
+                break;
+            }
+        }
+
+        nanosleep(&delay, NULL);
+    }
+
+    gilrs_free(gilrs);
+
+    return 0;
+}
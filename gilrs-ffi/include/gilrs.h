@@ -0,0 +1,202 @@
+#ifndef GILRS_H
+#define GILRS_H
+
+/* This file is generated by cbindgen from gilrs-ffi/src/lib.rs. Do not edit it manually. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#define GILRS_BUTTON_UNKNOWN 0
+
+#define GILRS_BUTTON_SOUTH 1
+
+#define GILRS_BUTTON_EAST 2
+
+#define GILRS_BUTTON_C 3
+
+#define GILRS_BUTTON_NORTH 4
+
+#define GILRS_BUTTON_WEST 5
+
+#define GILRS_BUTTON_Z 6
+
+#define GILRS_BUTTON_LEFT_TRIGGER 7
+
+#define GILRS_BUTTON_RIGHT_TRIGGER 8
+
+#define GILRS_BUTTON_LEFT_TRIGGER2 9
+
+#define GILRS_BUTTON_RIGHT_TRIGGER2 10
+
+#define GILRS_BUTTON_SELECT 11
+
+#define GILRS_BUTTON_START 12
+
+#define GILRS_BUTTON_MODE 13
+
+#define GILRS_BUTTON_LEFT_THUMB 14
+
+#define GILRS_BUTTON_RIGHT_THUMB 15
+
+#define GILRS_BUTTON_DPAD_UP 16
+
+#define GILRS_BUTTON_DPAD_DOWN 17
+
+#define GILRS_BUTTON_DPAD_LEFT 18
+
+#define GILRS_BUTTON_DPAD_RIGHT 19
+
+#define GILRS_BUTTON_MISC1 20
+
+#define GILRS_AXIS_UNKNOWN 0
+
+#define GILRS_AXIS_LEFT_STICK_X 1
+
+#define GILRS_AXIS_LEFT_STICK_Y 2
+
+#define GILRS_AXIS_LEFT_Z 3
+
+#define GILRS_AXIS_RIGHT_STICK_X 4
+
+#define GILRS_AXIS_RIGHT_STICK_Y 5
+
+#define GILRS_AXIS_RIGHT_Z 6
+
+#define GILRS_AXIS_DPAD_X 7
+
+#define GILRS_AXIS_DPAD_Y 8
+
+// Kind of [`GilrsEvent`].
+typedef enum GilrsEventType {
+  GILRS_EVENT_TYPE_BUTTON_PRESSED,
+  GILRS_EVENT_TYPE_BUTTON_REPEATED,
+  GILRS_EVENT_TYPE_BUTTON_RELEASED,
+  GILRS_EVENT_TYPE_BUTTON_CHANGED,
+  GILRS_EVENT_TYPE_AXIS_CHANGED,
+  GILRS_EVENT_TYPE_CONNECTED,
+  GILRS_EVENT_TYPE_DISCONNECTED,
+  GILRS_EVENT_TYPE_RESCANNED,
+  GILRS_EVENT_TYPE_DROPPED,
+  GILRS_EVENT_TYPE_FORCE_FEEDBACK_EFFECT_COMPLETED,
+  // Event that has no representation in C API yet.
+  GILRS_EVENT_TYPE_OTHER,
+} GilrsEventType;
+
+// Power supply state of gamepad.
+typedef enum GilrsPowerState {
+  GILRS_POWER_STATE_UNKNOWN,
+  GILRS_POWER_STATE_WIRED,
+  GILRS_POWER_STATE_DISCHARGING,
+  GILRS_POWER_STATE_CHARGING,
+  GILRS_POWER_STATE_CHARGED,
+} GilrsPowerState;
+
+// Opaque GilRs context.
+typedef struct Gilrs Gilrs;
+
+// Flattened gamepad event.
+//
+// `button` is one of `GILRS_BUTTON_*` and is only set for button events, `axis` is one of
+// `GILRS_AXIS_*` and is only set for `AxisChanged`. `value` is 1.0 for pressed and repeated
+// buttons and 0.0 for released buttons. `code` is platform specific code of the element.
+typedef struct GilrsEvent {
+  uintptr_t id;
+  enum GilrsEventType event_type;
+  uint16_t button;
+  uint16_t axis;
+  float value;
+  uint32_t code;
+  // Time when event was emitted, in microseconds since Unix epoch.
+  uint64_t timestamp_us;
+} GilrsEvent;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates new GilRs context. Returns null on failure.
+//
+// If current platform is not supported, returns context without any gamepads.
+struct Gilrs *gilrs_new(void);
+
+// Destroys context created by `gilrs_new()`. Does nothing if `gilrs` is null.
+//
+// # Safety
+//
+// `gilrs` must be null or pointer returned by `gilrs_new()` that was not freed yet.
+void gilrs_free(struct Gilrs *gilrs);
+
+// Writes next pending event to `out` and returns true, or returns false if there are no more
+// events.
+//
+// # Safety
+//
+// `gilrs` must be valid context and `out` must be valid for writes.
+bool gilrs_next_event(struct Gilrs *gilrs, struct GilrsEvent *out);
+
+// Returns true if gamepad is connected.
+//
+// # Safety
+//
+// `gilrs` must be valid context.
+bool gilrs_gamepad_is_connected(const struct Gilrs *gilrs, uintptr_t id);
+
+// Copies name of gamepad to `buf` as nul terminated UTF-8 string, truncating it if it doesn't
+// fit in `len` bytes. Returns length of the full name in bytes, excluding nul, or 0 if gamepad is
+// not connected. `buf` can be null to only query length.
+//
+// # Safety
+//
+// `gilrs` must be valid context and `buf` must be null or valid for writes of `len` bytes.
+uintptr_t gilrs_gamepad_name(const struct Gilrs *gilrs, uintptr_t id, char *buf, uintptr_t len);
+
+// Returns true if button (one of `GILRS_BUTTON_*`) is pressed.
+//
+// # Safety
+//
+// `gilrs` must be valid context.
+bool gilrs_gamepad_is_pressed(const struct Gilrs *gilrs, uintptr_t id, uint16_t button);
+
+// Returns value of axis (one of `GILRS_AXIS_*`).
+//
+// # Safety
+//
+// `gilrs` must be valid context.
+float gilrs_gamepad_axis_value(const struct Gilrs *gilrs, uintptr_t id, uint16_t axis);
+
+// Returns power supply state of gamepad. If `level` is not null, battery level in percent is
+// written to it, or 0 if state is not `DISCHARGING` or `CHARGING`.
+//
+// # Safety
+//
+// `gilrs` must be valid context and `level` must be null or valid for writes.
+enum GilrsPowerState gilrs_gamepad_power_info(const struct Gilrs *gilrs,
+                                              uintptr_t id,
+                                              uint8_t *level);
+
+// Plays rumble on gamepad for `duration_ms` milliseconds. `strong` and `weak` are magnitudes of
+// strong and weak motor in range [0.0, 1.0]. Replaces rumble previously started on the same
+// gamepad. Returns false if gamepad is not connected or doesn't support force feedback.
+//
+// # Safety
+//
+// `gilrs` must be valid context.
+bool gilrs_gamepad_rumble(struct Gilrs *gilrs,
+                          uintptr_t id,
+                          float strong,
+                          float weak,
+                          uint32_t duration_ms);
+
+// Stops rumble started by `gilrs_gamepad_rumble()`.
+//
+// # Safety
+//
+// `gilrs` must be valid context.
+void gilrs_gamepad_stop_rumble(struct Gilrs *gilrs, uintptr_t id);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* GILRS_H */
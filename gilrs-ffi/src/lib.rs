@@ -0,0 +1,640 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! C API for GilRs.
+//!
+//! This crate exposes a small, stable subset of GilRs through C ABI: creating context, polling
+//! events, querying gamepad state and simple rumble. Matching header is in `include/gilrs.h` and
+//! should be regenerated with `cbindgen --config cbindgen.toml --output include/gilrs.h` after
+//! changing any exported item.
+//!
+//! Gamepads are identified by the same numbers as [`GamepadId`]. Functions
+//! querying gamepads that are not connected return default values. No function unwinds into C;
+//! panics are caught at the boundary and reported as failure.
+
+use std::collections::HashMap;
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::time::UNIX_EPOCH;
+
+use gilrs::ff::{BaseEffect, BaseEffectType, EffectBuilder, Replay, Ticks};
+use gilrs::{Axis, Button, Event, EventType, GamepadId, PowerInfo};
+
+pub const GILRS_BUTTON_UNKNOWN: u16 = 0;
+pub const GILRS_BUTTON_SOUTH: u16 = 1;
+pub const GILRS_BUTTON_EAST: u16 = 2;
+pub const GILRS_BUTTON_C: u16 = 3;
+pub const GILRS_BUTTON_NORTH: u16 = 4;
+pub const GILRS_BUTTON_WEST: u16 = 5;
+pub const GILRS_BUTTON_Z: u16 = 6;
+pub const GILRS_BUTTON_LEFT_TRIGGER: u16 = 7;
+pub const GILRS_BUTTON_RIGHT_TRIGGER: u16 = 8;
+pub const GILRS_BUTTON_LEFT_TRIGGER2: u16 = 9;
+pub const GILRS_BUTTON_RIGHT_TRIGGER2: u16 = 10;
+pub const GILRS_BUTTON_SELECT: u16 = 11;
+pub const GILRS_BUTTON_START: u16 = 12;
+pub const GILRS_BUTTON_MODE: u16 = 13;
+pub const GILRS_BUTTON_LEFT_THUMB: u16 = 14;
+pub const GILRS_BUTTON_RIGHT_THUMB: u16 = 15;
+pub const GILRS_BUTTON_DPAD_UP: u16 = 16;
+pub const GILRS_BUTTON_DPAD_DOWN: u16 = 17;
+pub const GILRS_BUTTON_DPAD_LEFT: u16 = 18;
+pub const GILRS_BUTTON_DPAD_RIGHT: u16 = 19;
+pub const GILRS_BUTTON_MISC1: u16 = 20;
+
+pub const GILRS_AXIS_UNKNOWN: u16 = 0;
+pub const GILRS_AXIS_LEFT_STICK_X: u16 = 1;
+pub const GILRS_AXIS_LEFT_STICK_Y: u16 = 2;
+pub const GILRS_AXIS_LEFT_Z: u16 = 3;
+pub const GILRS_AXIS_RIGHT_STICK_X: u16 = 4;
+pub const GILRS_AXIS_RIGHT_STICK_Y: u16 = 5;
+pub const GILRS_AXIS_RIGHT_Z: u16 = 6;
+pub const GILRS_AXIS_DPAD_X: u16 = 7;
+pub const GILRS_AXIS_DPAD_Y: u16 = 8;
+
+/// Opaque GilRs context.
+pub struct Gilrs {
+    inner: gilrs::Gilrs,
+    rumble: HashMap<usize, gilrs::ff::Effect>,
+}
+
+/// Kind of [`GilrsEvent`].
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GilrsEventType {
+    ButtonPressed,
+    ButtonRepeated,
+    ButtonReleased,
+    ButtonChanged,
+    AxisChanged,
+    Connected,
+    Disconnected,
+    Rescanned,
+    Dropped,
+    ForceFeedbackEffectCompleted,
+    /// Event that has no representation in C API yet.
+    Other,
+}
+
+/// Flattened gamepad event.
+///
+/// `button` is one of `GILRS_BUTTON_*` and is only set for button events, `axis` is one of
+/// `GILRS_AXIS_*` and is only set for `AxisChanged`. `value` is 1.0 for pressed and repeated
+/// buttons and 0.0 for released buttons. `code` is platform specific code of the element.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GilrsEvent {
+    pub id: usize,
+    pub event_type: GilrsEventType,
+    pub button: u16,
+    pub axis: u16,
+    pub value: f32,
+    pub code: u32,
+    /// Time when event was emitted, in microseconds since Unix epoch.
+    pub timestamp_us: u64,
+}
+
+/// Power supply state of gamepad.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GilrsPowerState {
+    Unknown,
+    Wired,
+    Discharging,
+    Charging,
+    Charged,
+}
+
+impl From<Event> for GilrsEvent {
+    fn from(ev: Event) -> Self {
+        let mut out = GilrsEvent {
+            id: ev.id.into(),
+            event_type: GilrsEventType::Other,
+            button: GILRS_BUTTON_UNKNOWN,
+            axis: GILRS_AXIS_UNKNOWN,
+            value: 0.0,
+            code: 0,
+            timestamp_us: ev
+                .time
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_micros() as u64)
+                .unwrap_or(0),
+        };
+
+        match ev.event {
+            EventType::ButtonPressed(btn, code) => {
+                out.event_type = GilrsEventType::ButtonPressed;
+                out.button = btn as u16;
+                out.value = 1.0;
+                out.code = code.into_u32();
+            }
+            EventType::ButtonRepeated(btn, code) => {
+                out.event_type = GilrsEventType::ButtonRepeated;
+                out.button = btn as u16;
+                out.value = 1.0;
+                out.code = code.into_u32();
+            }
+            EventType::ButtonReleased(btn, code) => {
+                out.event_type = GilrsEventType::ButtonReleased;
+                out.button = btn as u16;
+                out.code = code.into_u32();
+            }
+            EventType::ButtonChanged(btn, value, code) => {
+                out.event_type = GilrsEventType::ButtonChanged;
+                out.button = btn as u16;
+                out.value = value;
+                out.code = code.into_u32();
+            }
+            EventType::AxisChanged(axis, value, code) => {
+                out.event_type = GilrsEventType::AxisChanged;
+                out.axis = axis as u16;
+                out.value = value;
+                out.code = code.into_u32();
+            }
+            EventType::Connected => out.event_type = GilrsEventType::Connected,
+            EventType::Disconnected => out.event_type = GilrsEventType::Disconnected,
+            EventType::Rescanned => out.event_type = GilrsEventType::Rescanned,
+            EventType::Dropped(_) => out.event_type = GilrsEventType::Dropped,
+            EventType::ForceFeedbackEffectCompleted => {
+                out.event_type = GilrsEventType::ForceFeedbackEffectCompleted
+            }
+            _ => (),
+        }
+
+        out
+    }
+}
+
+impl From<PowerInfo> for GilrsPowerState {
+    fn from(info: PowerInfo) -> Self {
+        match info {
+            PowerInfo::Unknown => GilrsPowerState::Unknown,
+            PowerInfo::Wired => GilrsPowerState::Wired,
+            PowerInfo::Discharging(_) => GilrsPowerState::Discharging,
+            PowerInfo::Charging(_) => GilrsPowerState::Charging,
+            PowerInfo::Charged => GilrsPowerState::Charged,
+        }
+    }
+}
+
+fn button_from_u16(btn: u16) -> Option<Button> {
+    Some(match btn {
+        GILRS_BUTTON_SOUTH => Button::South,
+        GILRS_BUTTON_EAST => Button::East,
+        GILRS_BUTTON_C => Button::C,
+        GILRS_BUTTON_NORTH => Button::North,
+        GILRS_BUTTON_WEST => Button::West,
+        GILRS_BUTTON_Z => Button::Z,
+        GILRS_BUTTON_LEFT_TRIGGER => Button::LeftTrigger,
+        GILRS_BUTTON_RIGHT_TRIGGER => Button::RightTrigger,
+        GILRS_BUTTON_LEFT_TRIGGER2 => Button::LeftTrigger2,
+        GILRS_BUTTON_RIGHT_TRIGGER2 => Button::RightTrigger2,
+        GILRS_BUTTON_SELECT => Button::Select,
+        GILRS_BUTTON_START => Button::Start,
+        GILRS_BUTTON_MODE => Button::Mode,
+        GILRS_BUTTON_LEFT_THUMB => Button::LeftThumb,
+        GILRS_BUTTON_RIGHT_THUMB => Button::RightThumb,
+        GILRS_BUTTON_DPAD_UP => Button::DPadUp,
+        GILRS_BUTTON_DPAD_DOWN => Button::DPadDown,
+        GILRS_BUTTON_DPAD_LEFT => Button::DPadLeft,
+        GILRS_BUTTON_DPAD_RIGHT => Button::DPadRight,
+        GILRS_BUTTON_MISC1 => Button::Misc1,
+        _ => return None,
+    })
+}
+
+fn axis_from_u16(axis: u16) -> Option<Axis> {
+    Some(match axis {
+        GILRS_AXIS_LEFT_STICK_X => Axis::LeftStickX,
+        GILRS_AXIS_LEFT_STICK_Y => Axis::LeftStickY,
+        GILRS_AXIS_LEFT_Z => Axis::LeftZ,
+        GILRS_AXIS_RIGHT_STICK_X => Axis::RightStickX,
+        GILRS_AXIS_RIGHT_STICK_Y => Axis::RightStickY,
+        GILRS_AXIS_RIGHT_Z => Axis::RightZ,
+        GILRS_AXIS_DPAD_X => Axis::DPadX,
+        GILRS_AXIS_DPAD_Y => Axis::DPadY,
+        _ => return None,
+    })
+}
+
+/// Returns id of connected gamepad with given number. C callers only have numbers, so this is the
+/// only way to get back `GamepadId`.
+fn connected_id(gilrs: &gilrs::Gilrs, id: usize) -> Option<GamepadId> {
+    gilrs
+        .gamepads()
+        .map(|(gid, _)| gid)
+        .find(|&gid| usize::from(gid) == id)
+}
+
+/// Copies `src` to `buf` as nul terminated string, truncating it if necessary. Returns length of
+/// `src` in bytes.
+///
+/// # Safety
+///
+/// `buf` must be null or valid for writes of `len` bytes.
+unsafe fn copy_to_buffer(src: &str, buf: *mut c_char, len: usize) -> usize {
+    if !buf.is_null() && len > 0 {
+        let n = src.len().min(len - 1);
+        ptr::copy_nonoverlapping(src.as_ptr() as *const c_char, buf, n);
+        *buf.add(n) = 0;
+    }
+
+    src.len()
+}
+
+fn guard<R>(default: R, f: impl FnOnce() -> R) -> R {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(default)
+}
+
+/// Creates new GilRs context. Returns null on failure.
+///
+/// If current platform is not supported, returns context without any gamepads.
+#[no_mangle]
+pub extern "C" fn gilrs_new() -> *mut Gilrs {
+    guard(ptr::null_mut(), || {
+        let inner = match gilrs::Gilrs::new() {
+            Ok(g) | Err(gilrs::Error::NotImplemented(g)) => g,
+            Err(_) => return ptr::null_mut(),
+        };
+
+        Box::into_raw(Box::new(Gilrs {
+            inner,
+            rumble: HashMap::new(),
+        }))
+    })
+}
+
+/// Destroys context created by `gilrs_new()`. Does nothing if `gilrs` is null.
+///
+/// # Safety
+///
+/// `gilrs` must be null or pointer returned by `gilrs_new()` that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn gilrs_free(gilrs: *mut Gilrs) {
+    if !gilrs.is_null() {
+        guard((), || drop(Box::from_raw(gilrs)));
+    }
+}
+
+/// Writes next pending event to `out` and returns true, or returns false if there are no more
+/// events.
+///
+/// # Safety
+///
+/// `gilrs` must be valid context and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn gilrs_next_event(gilrs: *mut Gilrs, out: *mut GilrsEvent) -> bool {
+    if gilrs.is_null() || out.is_null() {
+        return false;
+    }
+
+    guard(false, || {
+        let gilrs = &mut *gilrs;
+        match gilrs.inner.next_event() {
+            Some(ev) => {
+                // Reconnected gamepad gets new force feedback device, so old effect is useless.
+                if ev.event == EventType::Disconnected {
+                    gilrs.rumble.remove(&ev.id.into());
+                }
+                out.write(ev.into());
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+/// Returns true if gamepad is connected.
+///
+/// # Safety
+///
+/// `gilrs` must be valid context.
+#[no_mangle]
+pub unsafe extern "C" fn gilrs_gamepad_is_connected(gilrs: *const Gilrs, id: usize) -> bool {
+    if gilrs.is_null() {
+        return false;
+    }
+
+    guard(false, || connected_id(&(*gilrs).inner, id).is_some())
+}
+
+/// Copies name of gamepad to `buf` as nul terminated UTF-8 string, truncating it if it doesn't
+/// fit in `len` bytes. Returns length of the full name in bytes, excluding nul, or 0 if gamepad is
+/// not connected. `buf` can be null to only query length.
+///
+/// # Safety
+///
+/// `gilrs` must be valid context and `buf` must be null or valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn gilrs_gamepad_name(
+    gilrs: *const Gilrs,
+    id: usize,
+    buf: *mut c_char,
+    len: usize,
+) -> usize {
+    if gilrs.is_null() {
+        return 0;
+    }
+
+    guard(0, || {
+        let gilrs = &(*gilrs).inner;
+        match connected_id(gilrs, id) {
+            Some(id) => copy_to_buffer(gilrs.gamepad(id).name(), buf, len),
+            None => copy_to_buffer("", buf, len),
+        }
+    })
+}
+
+/// Returns true if button (one of `GILRS_BUTTON_*`) is pressed.
+///
+/// # Safety
+///
+/// `gilrs` must be valid context.
+#[no_mangle]
+pub unsafe extern "C" fn gilrs_gamepad_is_pressed(
+    gilrs: *const Gilrs,
+    id: usize,
+    button: u16,
+) -> bool {
+    if gilrs.is_null() {
+        return false;
+    }
+
+    guard(false, || {
+        let gilrs = &(*gilrs).inner;
+        match (connected_id(gilrs, id), button_from_u16(button)) {
+            (Some(id), Some(btn)) => gilrs.gamepad(id).is_pressed(btn),
+            _ => false,
+        }
+    })
+}
+
+/// Returns value of axis (one of `GILRS_AXIS_*`).
+///
+/// # Safety
+///
+/// `gilrs` must be valid context.
+#[no_mangle]
+pub unsafe extern "C" fn gilrs_gamepad_axis_value(
+    gilrs: *const Gilrs,
+    id: usize,
+    axis: u16,
+) -> f32 {
+    if gilrs.is_null() {
+        return 0.0;
+    }
+
+    guard(0.0, || {
+        let gilrs = &(*gilrs).inner;
+        match (connected_id(gilrs, id), axis_from_u16(axis)) {
+            (Some(id), Some(axis)) => gilrs.gamepad(id).value(axis),
+            _ => 0.0,
+        }
+    })
+}
+
+/// Returns power supply state of gamepad. If `level` is not null, battery level in percent is
+/// written to it, or 0 if state is not `DISCHARGING` or `CHARGING`.
+///
+/// # Safety
+///
+/// `gilrs` must be valid context and `level` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn gilrs_gamepad_power_info(
+    gilrs: *const Gilrs,
+    id: usize,
+    level: *mut u8,
+) -> GilrsPowerState {
+    if gilrs.is_null() {
+        return GilrsPowerState::Unknown;
+    }
+
+    let info = guard(PowerInfo::Unknown, || {
+        let gilrs = &(*gilrs).inner;
+        connected_id(gilrs, id).map_or(PowerInfo::Unknown, |id| gilrs.gamepad(id).power_info())
+    });
+
+    if !level.is_null() {
+        *level = match info {
+            PowerInfo::Discharging(lvl) | PowerInfo::Charging(lvl) => lvl,
+            _ => 0,
+        };
+    }
+
+    info.into()
+}
+
+/// Plays rumble on gamepad for `duration_ms` milliseconds. `strong` and `weak` are magnitudes of
+/// strong and weak motor in range [0.0, 1.0]. Replaces rumble previously started on the same
+/// gamepad. Returns false if gamepad is not connected or doesn't support force feedback.
+///
+/// # Safety
+///
+/// `gilrs` must be valid context.
+#[no_mangle]
+pub unsafe extern "C" fn gilrs_gamepad_rumble(
+    gilrs: *mut Gilrs,
+    id: usize,
+    strong: f32,
+    weak: f32,
+    duration_ms: u32,
+) -> bool {
+    if gilrs.is_null() {
+        return false;
+    }
+
+    guard(false, || {
+        let gilrs = &mut *gilrs;
+        let gid = match connected_id(&gilrs.inner, id) {
+            Some(gid) if gilrs.inner.gamepad(gid).is_ff_supported() => gid,
+            _ => return false,
+        };
+
+        gilrs.rumble.remove(&id);
+
+        let magnitude = |v: f32| (v.clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+        let scheduling = Replay {
+            play_for: Ticks::from_ms(duration_ms),
+            ..Default::default()
+        };
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong {
+                    magnitude: magnitude(strong),
+                },
+                scheduling,
+                ..Default::default()
+            })
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Weak {
+                    magnitude: magnitude(weak),
+                },
+                scheduling,
+                ..Default::default()
+            })
+            .gamepads(&[gid])
+            .finish(&mut gilrs.inner);
+
+        match effect {
+            Ok(effect) if effect.play().is_ok() => {
+                gilrs.rumble.insert(id, effect);
+                true
+            }
+            _ => false,
+        }
+    })
+}
+
+/// Stops rumble started by `gilrs_gamepad_rumble()`.
+///
+/// # Safety
+///
+/// `gilrs` must be valid context.
+#[no_mangle]
+pub unsafe extern "C" fn gilrs_gamepad_stop_rumble(gilrs: *mut Gilrs, id: usize) {
+    if !gilrs.is_null() {
+        guard((), || {
+            (*gilrs).rumble.remove(&id);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    fn context() -> (Gilrs, usize) {
+        let mut inner = match gilrs::GilrsBuilder::new()
+            .with_default_filters(false)
+            .build()
+        {
+            Ok(g) | Err(gilrs::Error::NotImplemented(g)) => g,
+            Err(e) => panic!("{}", e),
+        };
+        let id = inner.register_test_gamepad("Test gamepad", [7; 16]);
+        let ctx = Gilrs {
+            inner,
+            rumble: HashMap::new(),
+        };
+
+        (ctx, id.into())
+    }
+
+    #[test]
+    fn element_constants() {
+        for btn in 0..=GILRS_BUTTON_MISC1 + 1 {
+            match button_from_u16(btn) {
+                Some(b) => assert_eq!(b as u16, btn),
+                None => assert!(btn == GILRS_BUTTON_UNKNOWN || btn > GILRS_BUTTON_MISC1),
+            }
+        }
+
+        for axis in 0..=GILRS_AXIS_DPAD_Y + 1 {
+            match axis_from_u16(axis) {
+                Some(a) => assert_eq!(a as u16, axis),
+                None => assert!(axis == GILRS_AXIS_UNKNOWN || axis > GILRS_AXIS_DPAD_Y),
+            }
+        }
+    }
+
+    #[test]
+    fn event_conversion() {
+        let (mut ctx, id) = context();
+        let gid = connected_id(&ctx.inner, id).unwrap();
+        let code = ctx.inner.gamepad(gid).button_code(Button::South).unwrap();
+
+        let mut ev = Event::new(gid, EventType::ButtonPressed(Button::South, code));
+        ev.time = UNIX_EPOCH + Duration::from_micros(1_500);
+        let out = GilrsEvent::from(ev);
+        assert_eq!(out.id, id);
+        assert_eq!(out.event_type, GilrsEventType::ButtonPressed);
+        assert_eq!(out.button, GILRS_BUTTON_SOUTH);
+        assert_eq!(out.value, 1.0);
+        assert_eq!(out.code, code.into_u32());
+        assert_eq!(out.timestamp_us, 1_500);
+
+        ev.time = UNIX_EPOCH - Duration::from_secs(1);
+        assert_eq!(GilrsEvent::from(ev).timestamp_us, 0);
+
+        ctx.inner.insert_event(ev);
+        let mut out = GilrsEvent::from(Event::new(gid, EventType::Dropped(None)));
+        unsafe {
+            assert!(gilrs_next_event(&mut ctx, &mut out));
+            assert_eq!(out.event_type, GilrsEventType::Connected);
+            assert!(gilrs_next_event(&mut ctx, &mut out));
+            assert_eq!(out.event_type, GilrsEventType::ButtonPressed);
+            assert!(!gilrs_next_event(&mut ctx, &mut out));
+            assert!(gilrs_gamepad_is_pressed(&ctx, id, GILRS_BUTTON_SOUTH));
+            assert!(!gilrs_gamepad_is_pressed(&ctx, id, GILRS_BUTTON_UNKNOWN));
+            assert_eq!(
+                gilrs_gamepad_axis_value(&ctx, id, GILRS_AXIS_LEFT_STICK_X),
+                0.0
+            );
+        }
+    }
+
+    #[test]
+    fn rumble_removed_on_disconnect() {
+        let (mut ctx, id) = context();
+        let gid = connected_id(&ctx.inner, id).unwrap();
+        let effect = EffectBuilder::new().finish(&mut ctx.inner).unwrap();
+        ctx.rumble.insert(id, effect);
+
+        ctx.inner
+            .insert_event(Event::new(gid, EventType::Disconnected));
+        let mut out = GilrsEvent::from(Event::new(gid, EventType::Dropped(None)));
+        unsafe { while gilrs_next_event(&mut ctx, &mut out) {} }
+        assert_eq!(out.event_type, GilrsEventType::Disconnected);
+        assert!(ctx.rumble.is_empty());
+    }
+
+    #[test]
+    fn gamepad_name() {
+        let (ctx, id) = context();
+        let mut buf = [0x7f as c_char; 5];
+
+        unsafe {
+            assert!(gilrs_gamepad_is_connected(&ctx, id));
+            assert!(!gilrs_gamepad_is_connected(&ctx, id + 1));
+            assert_eq!(gilrs_gamepad_name(&ctx, id, ptr::null_mut(), 0), 12);
+            assert_eq!(
+                gilrs_gamepad_name(&ctx, id, buf.as_mut_ptr(), buf.len()),
+                12
+            );
+            assert_eq!(buf.map(|c| c as u8), *b"Test\0");
+            assert_eq!(
+                gilrs_gamepad_name(&ctx, id + 1, buf.as_mut_ptr(), buf.len()),
+                0
+            );
+        }
+        assert_eq!(buf[0], 0);
+    }
+
+    #[test]
+    fn null_context() {
+        let mut level = 42;
+
+        unsafe {
+            gilrs_free(ptr::null_mut());
+            assert!(!gilrs_next_event(ptr::null_mut(), ptr::null_mut()));
+            assert!(!gilrs_gamepad_is_pressed(
+                ptr::null(),
+                0,
+                GILRS_BUTTON_SOUTH
+            ));
+            assert!(!gilrs_gamepad_rumble(ptr::null_mut(), 0, 1.0, 1.0, 100));
+            assert_eq!(
+                gilrs_gamepad_power_info(ptr::null(), 0, &mut level),
+                GilrsPowerState::Unknown
+            );
+        }
+        assert_eq!(level, 42);
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Processing of synthetic events with `GilrsBuilder::preallocate_events()`. Before measuring,
+//! checks that `next_event()` doesn't allocate once every element sent its first event.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use gilrs::{Axis, Button, Error, Event, EventType, GamepadId, Gilrs, GilrsBuilder};
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Counts allocations made by current thread. Background threads of backend and force feedback
+/// are not interesting here.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+fn count_allocation() {
+    // Thread local may be already destroyed when thread exits.
+    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const FRAME_EVENTS: usize = 64;
+
+fn setup() -> (Gilrs, GamepadId) {
+    let mut gilrs = match GilrsBuilder::new()
+        .preallocate_events(4 * FRAME_EVENTS)
+        .build()
+    {
+        Ok(gilrs) | Err(Error::NotImplemented(gilrs)) => gilrs,
+        Err(e) => panic!("{}", e),
+    };
+    let id = gilrs.register_test_gamepad("Synthetic gamepad", [7; 16]);
+    while gilrs.next_event().is_some() {}
+
+    (gilrs, id)
+}
+
+/// Queues one frame of button and stick events and processes them with all default filters.
+fn frame(gilrs: &mut Gilrs, id: GamepadId, n: usize) {
+    let south = Button::South.to_standard_code().unwrap();
+    let stick = Axis::LeftStickX.to_standard_code().unwrap();
+    for i in 0..FRAME_EVENTS {
+        let event = if i % 8 == 0 {
+            if (n + i) % 16 == 0 {
+                EventType::ButtonPressed(Button::South, south)
+            } else {
+                EventType::ButtonReleased(Button::South, south)
+            }
+        } else {
+            let val = ((n * FRAME_EVENTS + i) % 200) as f32 / 100.0 - 1.0;
+            EventType::AxisChanged(Axis::LeftStickX, val, stick)
+        };
+        gilrs.insert_event(Event::new(id, event));
+    }
+
+    while let Some(ev) = gilrs.next_event() {
+        black_box(ev);
+    }
+}
+
+fn steady_state(c: &mut Criterion) {
+    let (mut gilrs, id) = setup();
+    // Warm up: first event of every element creates its state.
+    for n in 0..16 {
+        frame(&mut gilrs, id, n);
+    }
+
+    let before = allocations();
+    for n in 0..1000 {
+        frame(&mut gilrs, id, n);
+    }
+    assert_eq!(
+        allocations() - before,
+        0,
+        "next_event() allocated in steady state"
+    );
+
+    let mut n = 0;
+    c.bench_function("next_event steady state", |b| {
+        b.iter(|| {
+            frame(&mut gilrs, id, n);
+            n = n.wrapping_add(1);
+        })
+    });
+}
+
+criterion_group!(benches, steady_state);
+criterion_main!(benches);
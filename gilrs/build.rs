@@ -4,11 +4,18 @@
 //! This reduces the binary size fairly significantly compared to including mappings for every
 //! platform.
 //! Especially Wasm since it doesn't use SDL mappings and binary size is important.
+//!
+//! With `all-platform-mappings` feature mappings for other platforms are kept, so they can be used
+//! with `GilrsBuilder::force_platform()`.
+//!
+//! It also writes mappings_info.rs with number of included mappings, hash of the original file
+//! and build date, returned by `gilrs::bundled_mappings_info()`.
 
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[cfg(windows)]
 const PATH_SEPARATOR: &str = "backslash";
@@ -58,16 +65,23 @@ fn main() {
     });
     let original_reader = BufReader::new(original_file);
 
+    let all_platforms = env::var_os("CARGO_FEATURE_ALL_PLATFORM_MAPPINGS").is_some();
+    let mut hash = FNV_OFFSET_BASIS;
+    let mut entries = 0usize;
     original_reader
         .lines()
         .map(|x| match x {
             Ok(x) => x,
             Err(e) => panic!("Failed to read line from gamecontrollerdb.txt: {e}"),
         })
+        .inspect(|line| hash = fnv1a(fnv1a(hash, line.as_bytes()), b"\n"))
         .filter(|line| {
-            line.trim_end()
-                .trim_end_matches(',')
-                .ends_with(&sdl_platform)
+            let line = line.trim_end().trim_end_matches(',');
+            if all_platforms {
+                !line.is_empty() && !line.starts_with('#')
+            } else {
+                line.ends_with(&sdl_platform)
+            }
         })
         .for_each(|line| {
             let mut line = line;
@@ -75,5 +89,55 @@ fn main() {
             new_file
                 .write_all(line.as_bytes())
                 .expect("Failed to write line to gamecontrollerdb.txt in OUT_DIR");
+            entries += 1;
+        });
+
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    let info = format!(
+        "const BUNDLED_ENTRIES: usize = {};\n\
+         const BUNDLED_HASH: &str = \"{:016x}\";\n\
+         const BUILD_DATE: &str = \"{}\";\n",
+        entries,
+        hash,
+        build_date()
+    );
+    fs::write(Path::new(&out_dir).join("mappings_info.rs"), info)
+        .expect("failed to write mappings_info.rs");
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// 64-bit FNV-1a hash of `bytes`, continuing from `hash`.
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Returns current UTC date as YYYY-MM-DD. `SOURCE_DATE_EPOCH` is used instead of current time if
+/// set, so builds can be reproducible.
+fn build_date() -> String {
+    let secs = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs())
         });
+
+    // Converts days since 1970-01-01 to civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
 }
@@ -0,0 +1,106 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Records raw events of a gamepad to a fixture for `replay` test.
+//!
+//! ```text
+//! cargo run --example capture_fixture --features serde-serialize -- <id> <file.json> [seconds]
+//! ```
+//!
+//! Then run `GILRS_BLESS=1 cargo test --test replay --features serde-serialize,test-utils` to
+//! create its golden file.
+
+#[path = "../tests/replay/fixture.rs"]
+mod fixture;
+
+use fixture::{Fixture, RecordedEvent};
+use gilrs::{Gilrs, GilrsBuilder};
+use gilrs_core::{EventType, ReplayDevice};
+
+use std::env;
+use std::fs::File;
+use std::process;
+use std::time::{Duration, Instant, SystemTime};
+
+fn main() {
+    env_logger::init();
+
+    let args: Vec<_> = env::args().skip(1).collect();
+    let (id, path, seconds) = match &args[..] {
+        [id, path] => (id, path, "10"),
+        [id, path, seconds] => (id, path, seconds.as_str()),
+        _ => {
+            eprintln!("Usage: capture_fixture <gamepad id> <output.json> [seconds]");
+            process::exit(1);
+        }
+    };
+    let id: usize = id.parse().expect("invalid gamepad id");
+    let duration = Duration::from_secs(seconds.parse().expect("invalid number of seconds"));
+
+    // Only used to find SDL mapping that would be applied to the gamepad.
+    let gilrs = GilrsBuilder::new().build().unwrap_or_else(|e| match e {
+        gilrs::Error::NotImplemented(g) => g,
+        e => panic!("{}", e),
+    });
+    let mapping = mapping_line(&gilrs, id);
+
+    let mut raw = gilrs_core::Gilrs::new().expect("failed to create raw backend");
+    // Skip `Connected` events of already connected gamepads.
+    while raw.next_event().is_some() {}
+    let device = match raw.gamepad(id).filter(|gamepad| gamepad.is_connected()) {
+        Some(gamepad) => ReplayDevice::from_gamepad(gamepad),
+        None => {
+            eprintln!("Gamepad {} is not connected", id);
+            process::exit(1);
+        }
+    };
+
+    println!(
+        "Recording {} ({}) for {} s, use every button and axis…",
+        device.name,
+        uuid::Uuid::from_bytes(device.uuid),
+        duration.as_secs()
+    );
+    let start = SystemTime::now();
+    let deadline = Instant::now() + duration;
+    let mut events = vec![RecordedEvent {
+        time: 0,
+        event: EventType::Connected,
+    }];
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        let event = match raw.next_event_blocking(Some(remaining)) {
+            Some(event) if event.id == id => event,
+            _ => continue,
+        };
+
+        let time = event.time.duration_since(start).unwrap_or_default();
+        events.push(RecordedEvent {
+            time: time.as_millis() as u64,
+            event: event.event,
+        });
+        if event.event == EventType::Disconnected {
+            break;
+        }
+    }
+
+    let fixture = Fixture {
+        backend: gilrs_core::BACKEND_NAME.to_owned(),
+        mapping,
+        device,
+        events,
+    };
+    let file = File::create(path).expect("failed to create fixture");
+    serde_json::to_writer_pretty(file, &fixture).expect("failed to write fixture");
+    println!("Wrote {} events to {}", fixture.events.len(), path);
+}
+
+fn mapping_line(gilrs: &Gilrs, id: usize) -> Option<String> {
+    let (_, gamepad) = gilrs.gamepads().find(|(gid, _)| usize::from(*gid) == id)?;
+    let line = gamepad.mapping_info().sdl_line()?.to_owned();
+
+    Some(line)
+}
@@ -0,0 +1,58 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Prints information needed in bug reports: backend, connected gamepads with all their elements
+//! and 10 seconds of events before (raw) and after (cooked) default filters, with latency added by
+//! gilrs.
+
+use gilrs::diagnostics;
+use gilrs::ev::filter::{axis_dpad_to_button, deadzone, Jitter};
+use gilrs::{Filter, GilrsBuilder};
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+const RECORD_FOR: Duration = Duration::from_secs(10);
+
+fn main() {
+    env_logger::init();
+    let mut gilrs = GilrsBuilder::new()
+        .with_default_filters(false)
+        .set_update_state(false)
+        .measure_latency(true)
+        .build()
+        .unwrap();
+
+    print!("{}", gilrs.diagnostics_report());
+
+    println!("events (recording for {}s):", RECORD_FOR.as_secs());
+    let jitter = Jitter::new();
+    let start = Instant::now();
+    while start.elapsed() < RECORD_FOR {
+        while let Some(raw) = gilrs.next_event() {
+            let cooked = Some(raw)
+                .filter_ev(&axis_dpad_to_button, &mut gilrs)
+                .filter_ev(&jitter, &mut gilrs)
+                .filter_ev(&deadzone, &mut gilrs)
+                .unwrap();
+            gilrs.update(&cooked);
+
+            println!(
+                "{}",
+                diagnostics::event_line_with_latency(
+                    start.elapsed(),
+                    &raw,
+                    &cooked,
+                    gilrs.last_event_latency()
+                )
+            );
+        }
+        gilrs.inc();
+
+        thread::sleep(Duration::from_millis(10));
+    }
+}
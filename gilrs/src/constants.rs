@@ -28,6 +28,8 @@ pub const BTN_DPAD_DOWN: u16 = 17;
 pub const BTN_DPAD_LEFT: u16 = 18;
 pub const BTN_DPAD_RIGHT: u16 = 19;
 
+pub const BTN_MISC1: u16 = 20;
+
 pub const AXIS_UNKNOWN: u16 = 0;
 
 pub const AXIS_LSTICKX: u16 = 1;
@@ -0,0 +1,190 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Formatting of reports printed by `diagnostics` example.
+//!
+//! Output of this module is meant to be pasted into bug reports, so its format should only change
+//! together with `FORMAT_VERSION`.
+
+use crate::ev::{AxisOrBtn, Code, Event};
+use crate::gamepad::{AxisInfo, Gamepad, Gilrs};
+use crate::mapping::bundled_mappings_info;
+
+use uuid::Uuid;
+
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Version of the report format.
+pub const FORMAT_VERSION: u32 = 4;
+
+/// Returns report header with versions, bundled mapping database and backend name.
+pub fn header(gilrs: &Gilrs) -> String {
+    let db = bundled_mappings_info();
+    format!(
+        "gilrs diagnostics v{}\ngilrs: {}\nmappings db: {} entries, hash {}, built {}\n\
+         backend: {}\n",
+        FORMAT_VERSION,
+        crate::version(),
+        db.entries,
+        db.hash,
+        db.build_date,
+        gilrs.backend_name()
+    )
+}
+
+/// Returns report describing gamepad and all its elements.
+pub fn gamepad_report(gamepad: &Gamepad<'_>) -> String {
+    let mut report = String::new();
+
+    // Writing to String never fails.
+    let _ = writeln!(report, "gamepad {}:", gamepad.id());
+    let _ = writeln!(report, "  name: {}", gamepad.name());
+    let _ = writeln!(report, "  os name: {}", gamepad.os_name());
+    let _ = writeln!(report, "  map name: {:?}", gamepad.map_name());
+    let _ = writeln!(report, "  uuid: {}", Uuid::from_bytes(gamepad.uuid()));
+    let _ = writeln!(report, "  sdl guid: {}", gamepad.sdl_guid());
+    let _ = writeln!(
+        report,
+        "  vendor/product: {:?}/{:?}",
+        gamepad.vendor_id(),
+        gamepad.product_id()
+    );
+    let _ = writeln!(report, "  connected: {}", gamepad.is_connected());
+    let _ = writeln!(report, "  virtual: {:?}", gamepad.virtual_reason());
+    let _ = writeln!(report, "  power: {:?}", gamepad.power_info());
+    let _ = writeln!(report, "  mapping source: {:?}", gamepad.mapping_source());
+    let _ = writeln!(
+        report,
+        "  sdl mapping: {:?}",
+        gamepad.mapping_info().sdl_line()
+    );
+    let _ = writeln!(
+        report,
+        "  unresolved mapping entries: {:?}",
+        gamepad.unresolved_mapping_entries()
+    );
+    let _ = writeln!(report, "  ff supported: {}", gamepad.is_ff_supported());
+
+    let _ = writeln!(report, "  buttons:");
+    for (code, name) in gamepad.buttons() {
+        let _ = writeln!(report, "    {}", element_line(code, name, None));
+    }
+
+    let _ = writeln!(report, "  axes:");
+    for (code, name) in gamepad.axes() {
+        let info = gamepad.axis_info(code);
+        let _ = writeln!(report, "    {}", element_line(code, name, info));
+    }
+
+    report
+}
+
+/// Returns single line describing button or axis.
+pub fn element_line(code: Code, name: Option<AxisOrBtn>, info: Option<&AxisInfo>) -> String {
+    let name = match name {
+        Some(AxisOrBtn::Btn(btn)) => format!("{:?}", btn),
+        Some(AxisOrBtn::Axis(axis)) => format!("{:?}", axis),
+        None => "unmapped".to_owned(),
+    };
+
+    match info {
+        Some(info) => format!(
+            "{} -> {} [min: {}, max: {}, deadzone: {:?}]",
+            code, name, info.min, info.max, info.deadzone
+        ),
+        None => format!("{} -> {}", code, name),
+    }
+}
+
+/// Returns single line with event before and after filtering. `elapsed` is time since start of
+/// recording.
+pub fn event_line(elapsed: Duration, raw: &Event, cooked: &Event) -> String {
+    format!(
+        "{:>4}.{:03} gamepad {}: raw: {:?} | cooked: {:?}",
+        elapsed.as_secs(),
+        elapsed.subsec_millis(),
+        raw.id,
+        raw.event,
+        cooked.event
+    )
+}
+
+/// Returns [`event_line()`] followed by latency added by gilrs, see
+/// [`Gilrs::last_event_latency()`](crate::Gilrs::last_event_latency).
+pub fn event_line_with_latency(
+    elapsed: Duration,
+    raw: &Event,
+    cooked: &Event,
+    latency: Option<Duration>,
+) -> String {
+    match latency {
+        Some(latency) => format!(
+            "{} | latency: {}us",
+            event_line(elapsed, raw, cooked),
+            latency.as_micros()
+        ),
+        None => event_line(elapsed, raw, cooked),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{element_line, event_line, event_line_with_latency};
+    use crate::ev::{AxisOrBtn, Code};
+    use crate::{AxisInfo, Button, DropReason, Event, EventType, GamepadId};
+
+    use gilrs_core::native_ev_codes as nec;
+
+    use std::time::Duration;
+
+    #[test]
+    fn element_line_format() {
+        let code = Code(nec::AXIS_LSTICKX);
+        let info = AxisInfo {
+            min: -128,
+            max: 127,
+            deadzone: Some(8),
+        };
+
+        assert_eq!(
+            element_line(code, Some(AxisOrBtn::Btn(Button::South)), None),
+            format!("{} -> South", code)
+        );
+        assert_eq!(
+            element_line(code, None, Some(&info)),
+            format!(
+                "{} -> unmapped [min: -128, max: 127, deadzone: Some(8)]",
+                code
+            )
+        );
+    }
+
+    #[test]
+    fn event_line_format() {
+        let raw = Event::new(GamepadId(2), EventType::Connected);
+        let cooked = Event::new(GamepadId(2), EventType::Dropped(Some(DropReason::Jitter)));
+
+        assert_eq!(
+            event_line(Duration::from_millis(1_042), &raw, &cooked),
+            "   1.042 gamepad 2: raw: Connected | cooked: Dropped(Some(Jitter))"
+        );
+        assert_eq!(
+            event_line_with_latency(
+                Duration::from_millis(1_042),
+                &raw,
+                &cooked,
+                Some(Duration::from_micros(1_250))
+            ),
+            "   1.042 gamepad 2: raw: Connected | cooked: Dropped(Some(Jitter)) | latency: 1250us"
+        );
+        assert_eq!(
+            event_line_with_latency(Duration::from_millis(1_042), &raw, &cooked, None),
+            event_line(Duration::from_millis(1_042), &raw, &cooked)
+        );
+    }
+}
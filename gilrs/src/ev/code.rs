@@ -0,0 +1,76 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Default [`Code`]s of buttons and axes on current platform.
+//!
+//! These are codes that gamepads without custom mapping use for given element, so they can be used
+//! in `match` statements or as keys of lookup tables (for example per-element deadzones). Actual
+//! devices may report different codes, especially when they have SDL mapping, so always prefer
+//! [`Gamepad::button_code()`](crate::Gamepad::button_code) and
+//! [`Gamepad::axis_code()`](crate::Gamepad::axis_code) when gamepad is known.
+//!
+//! Values are platform-specific, the same as for all other `Code`s.
+//!
+//! ```
+//! use gilrs::ev::code;
+//! use gilrs::ev::Code;
+//!
+//! fn describe(code: Code) -> &'static str {
+//!     match code {
+//!         code::BTN_SOUTH => "south button",
+//!         code::AXIS_LSTICKX => "left stick X",
+//!         _ => "other",
+//!     }
+//! }
+//!
+//! assert_eq!(describe(code::BTN_SOUTH), "south button");
+//! ```
+
+use super::Code;
+
+use gilrs_core::native_ev_codes as nec;
+
+pub const AXIS_LSTICKX: Code = Code(nec::AXIS_LSTICKX);
+pub const AXIS_LSTICKY: Code = Code(nec::AXIS_LSTICKY);
+pub const AXIS_LEFTZ: Code = Code(nec::AXIS_LEFTZ);
+pub const AXIS_RSTICKX: Code = Code(nec::AXIS_RSTICKX);
+pub const AXIS_RSTICKY: Code = Code(nec::AXIS_RSTICKY);
+pub const AXIS_RIGHTZ: Code = Code(nec::AXIS_RIGHTZ);
+pub const AXIS_DPADX: Code = Code(nec::AXIS_DPADX);
+pub const AXIS_DPADY: Code = Code(nec::AXIS_DPADY);
+/// Analog right trigger, mapped to `Button::RightTrigger`.
+pub const AXIS_RT: Code = Code(nec::AXIS_RT);
+/// Analog left trigger, mapped to `Button::LeftTrigger`.
+pub const AXIS_LT: Code = Code(nec::AXIS_LT);
+/// Analog right trigger, mapped to `Button::RightTrigger2`.
+pub const AXIS_RT2: Code = Code(nec::AXIS_RT2);
+/// Analog left trigger, mapped to `Button::LeftTrigger2`.
+pub const AXIS_LT2: Code = Code(nec::AXIS_LT2);
+
+pub const BTN_SOUTH: Code = Code(nec::BTN_SOUTH);
+pub const BTN_EAST: Code = Code(nec::BTN_EAST);
+pub const BTN_C: Code = Code(nec::BTN_C);
+pub const BTN_NORTH: Code = Code(nec::BTN_NORTH);
+pub const BTN_WEST: Code = Code(nec::BTN_WEST);
+pub const BTN_Z: Code = Code(nec::BTN_Z);
+pub const BTN_LT: Code = Code(nec::BTN_LT);
+pub const BTN_RT: Code = Code(nec::BTN_RT);
+pub const BTN_LT2: Code = Code(nec::BTN_LT2);
+pub const BTN_RT2: Code = Code(nec::BTN_RT2);
+pub const BTN_SELECT: Code = Code(nec::BTN_SELECT);
+pub const BTN_START: Code = Code(nec::BTN_START);
+pub const BTN_MODE: Code = Code(nec::BTN_MODE);
+pub const BTN_LTHUMB: Code = Code(nec::BTN_LTHUMB);
+pub const BTN_RTHUMB: Code = Code(nec::BTN_RTHUMB);
+
+pub const BTN_DPAD_UP: Code = Code(nec::BTN_DPAD_UP);
+pub const BTN_DPAD_DOWN: Code = Code(nec::BTN_DPAD_DOWN);
+pub const BTN_DPAD_LEFT: Code = Code(nec::BTN_DPAD_LEFT);
+pub const BTN_DPAD_RIGHT: Code = Code(nec::BTN_DPAD_RIGHT);
+
+/// Share, Create or Capture button, mapped to `Button::Misc1`.
+pub const BTN_MISC1: Code = Code(nec::BTN_MISC1);
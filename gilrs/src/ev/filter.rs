@@ -45,7 +45,8 @@
 //! If you want to implement your own filters, you will have to implement `FilterFn` trait.
 //! **Do not return `None` if you got `Some(event)`**. If you want to discard an event, uses
 //! `EventType::Dropped`. Returning `None` means that there are no more events to process and
-//! will end `while let` loop.
+//! will end `while let` loop. Events dropped without reason get
+//! [`DropReason::UserFilter`](../enum.DropReason.html).
 //!
 //! ## Example
 //!
@@ -63,7 +64,7 @@
 //!             Some(Event { event: EventType::ButtonPressed(Button::Unknown, ..), id, .. })
 //!             | Some(Event { event: EventType::ButtonReleased(Button::Unknown, ..), id, .. })
 //!             | Some(Event { event: EventType::AxisChanged(Axis::Unknown, ..), id, .. })
-//!             => Some(Event::new(id, EventType::Dropped)),
+//!             => Some(Event::new(id, EventType::Dropped(None))),
 //!             _ => ev,
 //!         }
 //!     }
@@ -73,11 +74,15 @@
 //! `FilterFn` is also implemented for all `Fn(Option<Event>, &Gilrs) -> Option<Event>`, so above
 //! example could be simplified to passing closure to `filter()` function.
 
-use crate::ev::{Axis, AxisOrBtn, Button, Code, Event, EventType};
-use crate::gamepad::{Gamepad, Gilrs};
+use crate::ev::{Axis, AxisOrBtn, Button, Code, DropReason, Event, EventSource, EventType};
+use crate::gamepad::{Gamepad, GamepadId, Gilrs};
 use crate::utils;
 
-use std::time::Duration;
+use fnv::FnvHashMap;
+
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::time::{Duration, SystemTime};
 
 /// Discard axis events that changed less than `threshold`.
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -107,7 +112,7 @@ impl FilterFn for Jitter {
                 ..
             }) => match gilrs.gamepad(id).state().axis_data(axis) {
                 Some(data) if val != 0.0 && (val - data.value()).abs() < self.threshold => {
-                    Some(Event::new(id, EventType::Dropped))
+                    Some(Event::new(id, EventType::Dropped(Some(DropReason::Jitter))))
                 }
                 _ => ev,
             },
@@ -147,6 +152,7 @@ pub fn deadzone(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event> {
             event: EventType::AxisChanged(axis, val, nec),
             id,
             time,
+            source,
         }) => {
             let threshold = match gilrs.gamepad(id).deadzone(nec) {
                 Some(t) => t,
@@ -173,12 +179,13 @@ pub fn deadzone(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event> {
                         id,
                         time,
                         event: EventType::AxisChanged(other_axis, 0., other_code),
+                        source: EventSource::Filter,
                     });
                     gilrs.gamepads_data[id.0].have_sent_nonzero_for_axis[other_axis_idx] = false;
                 }
 
                 Some(if gilrs.gamepad(id).state().value(nec) == val.0 {
-                    Event::new(id, EventType::Dropped)
+                    Event::new(id, EventType::Dropped(Some(DropReason::Deadzone)))
                 } else {
                     if let Some(axis_idx) = deadzone_nonzero_axis_idx(axis) {
                         gilrs.gamepads_data[id.0].have_sent_nonzero_for_axis[axis_idx] =
@@ -188,13 +195,14 @@ pub fn deadzone(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event> {
                         id,
                         time,
                         event: EventType::AxisChanged(axis, val.0, nec),
+                        source,
                     }
                 })
             } else {
                 let val = apply_deadzone(val, 0.0, threshold).0;
 
                 Some(if gilrs.gamepad(id).state().value(nec) == val {
-                    Event::new(id, EventType::Dropped)
+                    Event::new(id, EventType::Dropped(Some(DropReason::Deadzone)))
                 } else {
                     if let Some(axis_idx) = deadzone_nonzero_axis_idx(axis) {
                         gilrs.gamepads_data[id.0].have_sent_nonzero_for_axis[axis_idx] = val != 0.;
@@ -203,6 +211,7 @@ pub fn deadzone(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event> {
                         id,
                         time,
                         event: EventType::AxisChanged(axis, val, nec),
+                        source,
                     }
                 })
             }
@@ -211,6 +220,7 @@ pub fn deadzone(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event> {
             event: EventType::ButtonChanged(btn, val, nec),
             id,
             time,
+            source,
         }) => {
             let gp = &gilrs.gamepad(id);
             let threshold = match gp.deadzone(nec) {
@@ -220,12 +230,13 @@ pub fn deadzone(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event> {
             let val = apply_deadzone(val, 0.0, threshold).0;
 
             Some(if gp.state().value(nec) == val {
-                Event::new(id, EventType::Dropped)
+                Event::new(id, EventType::Dropped(Some(DropReason::Deadzone)))
             } else {
                 Event {
                     id,
                     time,
                     event: EventType::ButtonChanged(btn, val, nec),
+                    source,
                 }
             })
         }
@@ -233,24 +244,39 @@ pub fn deadzone(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event> {
     }
 }
 
+// Indices of `GamepadData::dpad_pressed`.
+const DPAD_UP: usize = 0;
+const DPAD_DOWN: usize = 1;
+const DPAD_LEFT: usize = 2;
+const DPAD_RIGHT: usize = 3;
+
 /// Maps axis dpad events to button dpad events.
 ///
 /// This filter will do nothing if gamepad has dpad buttons (to prevent double events for same
 /// element) and if standard `NativeEvCode` for dpads is used by some other buttons. It will always
-/// try to map if SDL mappings contains mappings for all four hats.
+/// try to map if SDL mappings contains mappings for all four hats or if gamepad reports dpad both
+/// as buttons and hat, because then only one of them is used (see
+/// [`GilrsBuilder::dpad_source()`](crate::GilrsBuilder::dpad_source)).
+///
+/// Direction is pressed when absolute value of axis reaches
+/// [`GilrsBuilder::dpad_axis_threshold()`](crate::GilrsBuilder::dpad_axis_threshold).
+///
+/// Created button events always use `BTN_DPAD_*` native codes, never code of the hat axis. Use
+/// this filter before [`Repeat`] to get repeated events while hat direction is held.
 pub fn axis_dpad_to_button(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event> {
     use gilrs_core::native_ev_codes as necs;
 
     fn can_map(gp: &Gamepad<'_>) -> bool {
-        let hats_mapped = gp.mapping().hats_mapped();
-        if hats_mapped == 0b0000_1111 {
+        let info = gp.mapping_info();
+        let hats_mapped = info.hats_mapped();
+        if hats_mapped == 0b0000_1111 || info.has_dual_dpad() {
             true
         } else if hats_mapped == 0 {
             gp.axis_or_btn_name(Code(necs::BTN_DPAD_RIGHT)).is_none()
                 && gp.axis_or_btn_name(Code(necs::BTN_DPAD_LEFT)).is_none()
                 && gp.axis_or_btn_name(Code(necs::BTN_DPAD_DOWN)).is_none()
                 && gp.axis_or_btn_name(Code(necs::BTN_DPAD_UP)).is_none()
-                && gp.button_code(Button::DPadRight).is_none()
+                && !info.is_button_mapped(Button::DPadRight)
         } else {
             // Not all hats are mapped so let's ignore it for now.
             false
@@ -264,50 +290,67 @@ pub fn axis_dpad_to_button(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event
         return Some(ev);
     }
 
-    let mut out_event = ev.drop();
+    // Cached state can't be used here, because it may not be updated (see
+    // `GilrsBuilder::set_update_state()`).
+    let mut pressed = gilrs.gamepads_data[ev.id.0].dpad_pressed;
+    let threshold = gilrs.dpad_axis_threshold;
+    let mut out_event = ev.drop_with(DropReason::Duplicate);
+    let filtered = ev.with_source(EventSource::Filter);
 
-    match ev.event {
+    let out_event = match ev.event {
         EventType::AxisChanged(Axis::DPadX, val, _) => {
             let mut release_left = false;
             let mut release_right = false;
 
-            if val == 1.0 {
+            if val >= threshold {
                 // The axis value might change from left (-1.0) to right (1.0) immediately without
                 // us getting an additional event for the release at the center position (0.0).
-                release_left = gamepad.state().is_pressed(Code(necs::BTN_DPAD_LEFT));
+                release_left = pressed[DPAD_LEFT];
 
-                gilrs.insert_event(Event {
-                    event: EventType::ButtonChanged(
-                        Button::DPadRight,
-                        1.0,
-                        Code(necs::BTN_DPAD_RIGHT),
-                    ),
-                    ..ev
-                });
-                out_event = Event {
-                    event: EventType::ButtonPressed(Button::DPadRight, Code(necs::BTN_DPAD_RIGHT)),
-                    ..ev
-                };
-            } else if val == -1.0 {
+                if !pressed[DPAD_RIGHT] {
+                    gilrs.insert_event(Event {
+                        event: EventType::ButtonChanged(
+                            Button::DPadRight,
+                            1.0,
+                            Code(necs::BTN_DPAD_RIGHT),
+                        ),
+                        ..filtered
+                    });
+                    out_event = Event {
+                        event: EventType::ButtonPressed(
+                            Button::DPadRight,
+                            Code(necs::BTN_DPAD_RIGHT),
+                        ),
+                        ..filtered
+                    };
+                    pressed[DPAD_RIGHT] = true;
+                }
+            } else if val <= -threshold {
                 // The axis value might change from right (1.0) to left (-1.0) immediately without
                 // us getting an additional event for the release at the center position (0.0).
-                release_right = gamepad.state().is_pressed(Code(necs::BTN_DPAD_RIGHT));
+                release_right = pressed[DPAD_RIGHT];
 
-                gilrs.insert_event(Event {
-                    event: EventType::ButtonChanged(
-                        Button::DPadLeft,
-                        1.0,
-                        Code(necs::BTN_DPAD_LEFT),
-                    ),
-                    ..ev
-                });
-                out_event = Event {
-                    event: EventType::ButtonPressed(Button::DPadLeft, Code(necs::BTN_DPAD_LEFT)),
-                    ..ev
-                };
+                if !pressed[DPAD_LEFT] {
+                    gilrs.insert_event(Event {
+                        event: EventType::ButtonChanged(
+                            Button::DPadLeft,
+                            1.0,
+                            Code(necs::BTN_DPAD_LEFT),
+                        ),
+                        ..filtered
+                    });
+                    out_event = Event {
+                        event: EventType::ButtonPressed(
+                            Button::DPadLeft,
+                            Code(necs::BTN_DPAD_LEFT),
+                        ),
+                        ..filtered
+                    };
+                    pressed[DPAD_LEFT] = true;
+                }
             } else {
-                release_left = gamepad.state().is_pressed(Code(necs::BTN_DPAD_LEFT));
-                release_right = gamepad.state().is_pressed(Code(necs::BTN_DPAD_RIGHT));
+                release_left = pressed[DPAD_LEFT];
+                release_right = pressed[DPAD_RIGHT];
             }
 
             if release_right {
@@ -321,12 +364,13 @@ pub fn axis_dpad_to_button(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event
                         0.0,
                         Code(necs::BTN_DPAD_RIGHT),
                     ),
-                    ..ev
+                    ..filtered
                 });
                 out_event = Event {
                     event: EventType::ButtonReleased(Button::DPadRight, Code(necs::BTN_DPAD_RIGHT)),
-                    ..ev
+                    ..filtered
                 };
+                pressed[DPAD_RIGHT] = false;
             }
 
             if release_left {
@@ -340,53 +384,67 @@ pub fn axis_dpad_to_button(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event
                         0.0,
                         Code(necs::BTN_DPAD_LEFT),
                     ),
-                    ..ev
+                    ..filtered
                 });
                 out_event = Event {
                     event: EventType::ButtonReleased(Button::DPadLeft, Code(necs::BTN_DPAD_LEFT)),
-                    ..ev
+                    ..filtered
                 };
+                pressed[DPAD_LEFT] = false;
             }
 
-            Some(out_event)
+            out_event
         }
         EventType::AxisChanged(Axis::DPadY, val, _) => {
             let mut release_up = false;
             let mut release_down = false;
 
-            if val == 1.0 {
+            if val >= threshold {
                 // The axis value might change from down (-1.0) to up (1.0) immediately without us
                 // getting an additional event for the release at the center position (0.0).
-                release_down = gamepad.state().is_pressed(Code(necs::BTN_DPAD_DOWN));
+                release_down = pressed[DPAD_DOWN];
 
-                gilrs.insert_event(Event {
-                    event: EventType::ButtonChanged(Button::DPadUp, 1.0, Code(necs::BTN_DPAD_UP)),
-                    ..ev
-                });
-                out_event = Event {
-                    event: EventType::ButtonPressed(Button::DPadUp, Code(necs::BTN_DPAD_UP)),
-                    ..ev
-                };
-            } else if val == -1.0 {
+                if !pressed[DPAD_UP] {
+                    gilrs.insert_event(Event {
+                        event: EventType::ButtonChanged(
+                            Button::DPadUp,
+                            1.0,
+                            Code(necs::BTN_DPAD_UP),
+                        ),
+                        ..filtered
+                    });
+                    out_event = Event {
+                        event: EventType::ButtonPressed(Button::DPadUp, Code(necs::BTN_DPAD_UP)),
+                        ..filtered
+                    };
+                    pressed[DPAD_UP] = true;
+                }
+            } else if val <= -threshold {
                 // The axis value might change from up (1.0) to down (-1.0) immediately without us
                 // getting an additional event for the release at the center position (0.0).
-                release_up = gamepad.state().is_pressed(Code(necs::BTN_DPAD_UP));
+                release_up = pressed[DPAD_UP];
 
-                gilrs.insert_event(Event {
-                    event: EventType::ButtonChanged(
-                        Button::DPadDown,
-                        1.0,
-                        Code(necs::BTN_DPAD_DOWN),
-                    ),
-                    ..ev
-                });
-                out_event = Event {
-                    event: EventType::ButtonPressed(Button::DPadDown, Code(necs::BTN_DPAD_DOWN)),
-                    ..ev
-                };
+                if !pressed[DPAD_DOWN] {
+                    gilrs.insert_event(Event {
+                        event: EventType::ButtonChanged(
+                            Button::DPadDown,
+                            1.0,
+                            Code(necs::BTN_DPAD_DOWN),
+                        ),
+                        ..filtered
+                    });
+                    out_event = Event {
+                        event: EventType::ButtonPressed(
+                            Button::DPadDown,
+                            Code(necs::BTN_DPAD_DOWN),
+                        ),
+                        ..filtered
+                    };
+                    pressed[DPAD_DOWN] = true;
+                }
             } else {
-                release_up = gamepad.state().is_pressed(Code(necs::BTN_DPAD_UP));
-                release_down = gamepad.state().is_pressed(Code(necs::BTN_DPAD_DOWN));
+                release_up = pressed[DPAD_UP];
+                release_down = pressed[DPAD_DOWN];
             }
 
             if release_up {
@@ -396,12 +454,13 @@ pub fn axis_dpad_to_button(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event
 
                 gilrs.insert_event(Event {
                     event: EventType::ButtonChanged(Button::DPadUp, 0.0, Code(necs::BTN_DPAD_UP)),
-                    ..ev
+                    ..filtered
                 });
                 out_event = Event {
                     event: EventType::ButtonReleased(Button::DPadUp, Code(necs::BTN_DPAD_UP)),
-                    ..ev
+                    ..filtered
                 };
+                pressed[DPAD_UP] = false;
             }
 
             if release_down {
@@ -415,21 +474,42 @@ pub fn axis_dpad_to_button(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event
                         0.0,
                         Code(necs::BTN_DPAD_DOWN),
                     ),
-                    ..ev
+                    ..filtered
                 });
                 out_event = Event {
                     event: EventType::ButtonReleased(Button::DPadDown, Code(necs::BTN_DPAD_DOWN)),
-                    ..ev
+                    ..filtered
                 };
+                pressed[DPAD_DOWN] = false;
             }
 
-            Some(out_event)
+            out_event
         }
-        _ => Some(ev),
+        _ => return Some(ev),
+    };
+
+    gilrs.gamepads_data[ev.id.0].dpad_pressed = pressed;
+
+    Some(out_event)
+}
+
+/// Returns button that `nec` is mapped to, including dpad buttons created by
+/// [`axis_dpad_to_button`] from hat.
+fn repeated_button(gamepad: &Gamepad<'_>, nec: Code) -> Button {
+    match gamepad.event_element(nec) {
+        Some(AxisOrBtn::Btn(btn)) => btn,
+        _ => Button::Unknown,
     }
 }
 
 /// Repeats pressed keys.
+///
+/// Repeated events are created from cached gamepad state when filter is called with `None`, so
+/// events returned by `Repeat` have to be passed to [`Gilrs::update()`](crate::Gilrs::update),
+/// otherwise the same repeat is returned again. `Repeat` should be the last filter. Dpad buttons
+/// created from hat by [`axis_dpad_to_button`] (one of default filters) are repeated like any
+/// other button, with the same `BTN_DPAD_*` codes as their `ButtonPressed` events. If default
+/// filters are disabled, use `axis_dpad_to_button` before `Repeat`.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct Repeat {
     pub after: Duration,
@@ -466,27 +546,25 @@ impl FilterFn for Repeat {
                             now.duration_since(btn_data.timestamp()),
                         ) {
                             (true, false, Ok(dur)) if dur >= self.after => {
-                                let btn_name = match gamepad.axis_or_btn_name(nec) {
-                                    Some(AxisOrBtn::Btn(b)) => b,
-                                    _ => Button::Unknown,
-                                };
-
                                 return Some(Event {
                                     id,
-                                    event: EventType::ButtonRepeated(btn_name, nec),
+                                    event: EventType::ButtonRepeated(
+                                        repeated_button(&gamepad, nec),
+                                        nec,
+                                    ),
                                     time: btn_data.timestamp() + self.after,
+                                    source: EventSource::Filter,
                                 });
                             }
                             (true, true, Ok(dur)) if dur >= self.every => {
-                                let btn_name = match gamepad.axis_or_btn_name(nec) {
-                                    Some(AxisOrBtn::Btn(b)) => b,
-                                    _ => Button::Unknown,
-                                };
-
                                 return Some(Event {
                                     id,
-                                    event: EventType::ButtonRepeated(btn_name, nec),
+                                    event: EventType::ButtonRepeated(
+                                        repeated_button(&gamepad, nec),
+                                        nec,
+                                    ),
                                     time: btn_data.timestamp() + self.every,
+                                    source: EventSource::Filter,
                                 });
                             }
                             _ => (),
@@ -499,6 +577,561 @@ impl FilterFn for Repeat {
     }
 }
 
+/// Ignores buttons that are reported as pressed for too long.
+///
+/// If button is pressed continuously for longer than `threshold`, filter emits synthetic
+/// `ButtonReleased` event and drops all further events of that button, with
+/// `DropReason::StuckButton`, until it is released by the device. Like `Repeat`, stuck buttons are detected when filter is called with `None`.
+///
+/// Button counts as released also when its `ButtonChanged` value drops to 0.0 and it counts as
+/// pressed again on next `ButtonPressed`, so release dropped by earlier filter doesn't make
+/// button look stuck.
+#[derive(Clone, Debug)]
+pub struct StuckButtonGuard {
+    pub threshold: Duration,
+    pressed: RefCell<FnvHashMap<(GamepadId, Code), PressedButton>>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct PressedButton {
+    btn: Button,
+    since: SystemTime,
+    is_stuck: bool,
+}
+
+impl StuckButtonGuard {
+    /// Creates new `StuckButtonGuard` filter with `threshold` set to 30s.
+    pub fn new() -> Self {
+        StuckButtonGuard {
+            threshold: Duration::from_secs(30),
+            pressed: RefCell::new(FnvHashMap::default()),
+        }
+    }
+
+    /// Returns `true` if button was recognized as stuck and its events are dropped.
+    pub fn is_stuck(&self, id: GamepadId, btn: Code) -> bool {
+        self.pressed
+            .borrow()
+            .get(&(id, btn))
+            .is_some_and(|p| p.is_stuck)
+    }
+}
+
+impl Default for StuckButtonGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FilterFn for StuckButtonGuard {
+    fn filter(&self, ev: Option<Event>, _gilrs: &mut Gilrs) -> Option<Event> {
+        let mut pressed = self.pressed.borrow_mut();
+
+        match ev {
+            Some(Event {
+                event: EventType::ButtonPressed(btn, nec),
+                id,
+                time,
+                ..
+            }) => {
+                let entry = pressed.entry((id, nec)).or_insert(PressedButton {
+                    btn,
+                    since: time,
+                    is_stuck: false,
+                });
+                if entry.is_stuck {
+                    Some(Event::new(
+                        id,
+                        EventType::Dropped(Some(DropReason::StuckButton)),
+                    ))
+                } else {
+                    // Button is pressed again without release, so previous release was lost.
+                    entry.since = time;
+                    ev
+                }
+            }
+            Some(Event {
+                event: EventType::ButtonReleased(_, nec),
+                id,
+                ..
+            }) => match pressed.remove(&(id, nec)) {
+                // Synthetic release was already emitted.
+                Some(PressedButton { is_stuck: true, .. }) => Some(Event::new(
+                    id,
+                    EventType::Dropped(Some(DropReason::StuckButton)),
+                )),
+                _ => ev,
+            },
+            Some(Event {
+                event: EventType::ButtonChanged(_, 0.0, nec),
+                id,
+                ..
+            }) => match pressed.remove(&(id, nec)) {
+                Some(PressedButton { is_stuck: true, .. }) => Some(Event::new(
+                    id,
+                    EventType::Dropped(Some(DropReason::StuckButton)),
+                )),
+                _ => ev,
+            },
+            Some(Event {
+                event: EventType::ButtonRepeated(_, nec) | EventType::ButtonChanged(_, _, nec),
+                id,
+                ..
+            }) if pressed.get(&(id, nec)).is_some_and(|p| p.is_stuck) => Some(Event::new(
+                id,
+                EventType::Dropped(Some(DropReason::StuckButton)),
+            )),
+            Some(Event {
+                event: EventType::Disconnected,
+                id,
+                ..
+            }) => {
+                pressed.retain(|&(gamepad_id, _), _| gamepad_id != id);
+                ev
+            }
+            Some(ev) => Some(ev),
+            None => {
+                let now = utils::time_now();
+                for (&(id, nec), button) in pressed.iter_mut() {
+                    match now.duration_since(button.since) {
+                        Ok(dur) if !button.is_stuck && dur >= self.threshold => {
+                            button.is_stuck = true;
+
+                            return Some(Event {
+                                id,
+                                event: EventType::ButtonReleased(button.btn, nec),
+                                time: button.since + self.threshold,
+                                source: EventSource::Filter,
+                            });
+                        }
+                        _ => (),
+                    }
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Limits rate of `AxisChanged` events.
+///
+/// At most `max_rate` events per second are emitted for every axis of every gamepad. Events that
+/// arrive too early are dropped, but the latest value is remembered and emitted once the interval
+/// has passed and the filter is called with `None`, so the last event of a burst always carries
+/// the final value. Zero crossings (sign changes and exact `0.0`) are never delayed and neither are
+/// events other than `AxisChanged`. Setting `max_rate` to 0 disables the filter.
+///
+/// Coalescing is done on already filtered values, so when combined with `Jitter` or `deadzone`,
+/// they should be applied before this filter. This is also the order used by
+/// [`GilrsBuilder::max_axis_event_rate()`](crate::GilrsBuilder::max_axis_event_rate).
+#[derive(Clone, Debug)]
+pub struct AxisRateLimit {
+    pub max_rate: u32,
+    axes: RefCell<FnvHashMap<(GamepadId, Code), RateLimitedAxis>>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct RateLimitedAxis {
+    last_emitted: SystemTime,
+    last_value: f32,
+    pending: Option<Event>,
+}
+
+impl AxisRateLimit {
+    /// Creates new `AxisRateLimit` filter with `max_rate` set to 60.
+    pub fn new() -> Self {
+        AxisRateLimit {
+            max_rate: 60,
+            axes: RefCell::new(FnvHashMap::default()),
+        }
+    }
+
+    /// Returns `true` if some axis has value that was not emitted yet.
+    pub fn has_pending(&self) -> bool {
+        self.axes
+            .borrow()
+            .values()
+            .any(|axis| axis.pending.is_some())
+    }
+}
+
+impl Default for AxisRateLimit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FilterFn for AxisRateLimit {
+    fn filter(&self, ev: Option<Event>, _gilrs: &mut Gilrs) -> Option<Event> {
+        if self.max_rate == 0 {
+            return ev;
+        }
+
+        let interval = Duration::from_secs(1) / self.max_rate;
+        let mut axes = self.axes.borrow_mut();
+
+        match ev {
+            Some(Event {
+                event: EventType::AxisChanged(_, val, nec),
+                id,
+                time,
+                ..
+            }) => {
+                let axis = match axes.entry((id, nec)) {
+                    Entry::Occupied(axis) => axis.into_mut(),
+                    Entry::Vacant(axis) => {
+                        axis.insert(RateLimitedAxis {
+                            last_emitted: time,
+                            last_value: val,
+                            pending: None,
+                        });
+                        return ev;
+                    }
+                };
+                let interval_passed = time
+                    .duration_since(axis.last_emitted)
+                    .is_ok_and(|dur| dur >= interval);
+                let zero_crossing = val == 0.0 || val * axis.last_value < 0.0;
+
+                if interval_passed || zero_crossing {
+                    axis.last_emitted = time;
+                    axis.last_value = val;
+                    axis.pending = None;
+                    ev
+                } else {
+                    axis.pending = ev;
+                    Some(Event::new(
+                        id,
+                        EventType::Dropped(Some(DropReason::RateLimit)),
+                    ))
+                }
+            }
+            Some(Event {
+                event: EventType::Disconnected,
+                id,
+                ..
+            }) => {
+                axes.retain(|&(gamepad_id, _), _| gamepad_id != id);
+                ev
+            }
+            Some(ev) => Some(ev),
+            None => {
+                let now = utils::time_now();
+                for axis in axes.values_mut() {
+                    match (axis.pending, now.duration_since(axis.last_emitted)) {
+                        (Some(pending), Ok(dur)) if dur >= interval => {
+                            axis.last_emitted += interval;
+                            axis.pending = None;
+                            if let EventType::AxisChanged(_, val, _) = pending.event {
+                                axis.last_value = val;
+                            }
+
+                            return Some(pending);
+                        }
+                        _ => (),
+                    }
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Drops `AxisChanged` events that arrive sooner than `min_interval` after the last emitted event
+/// of the same axis.
+///
+/// Unlike [`AxisRateLimit`], which delays events and later emits the latest value, this filter
+/// only throttles: events that come too early are replaced with
+/// `EventType::Dropped(Some(DropReason::RateLimit))` and their values are lost. It never produces
+/// events when called with `None`. Intervals are tracked separately for every `Code` of every
+/// gamepad and measured using [`Event::time`](../struct.Event.html#structfield.time). Setting
+/// `min_interval` to zero disables the filter.
+///
+/// This is useful for applications that sample input at fixed rate and don't want to process
+/// every update of 1000 Hz devices. The tradeoff is that state seen by application can lag behind
+/// the device by up to `min_interval` and, if axis stops moving right after a dropped event, stays
+/// stale until the next event of that axis. Use [`AxisRateLimit`] if the final value must always
+/// be delivered.
+///
+/// # Example
+///
+/// ```
+/// use gilrs::ev::filter::RateLimit;
+/// use gilrs::{Filter, GilrsBuilder};
+/// use std::time::Duration;
+///
+/// let mut gilrs = GilrsBuilder::new().build().unwrap();
+/// // At most 120 events per second for every axis.
+/// let limit = RateLimit::new(Duration::from_secs(1) / 120);
+///
+/// while let Some(event) = gilrs.next_event().filter_ev(&limit, &mut gilrs) {
+///     gilrs.update(&event);
+///     println!("{:?}", event);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct RateLimit {
+    pub min_interval: Duration,
+    last_emitted: RefCell<FnvHashMap<(GamepadId, Code), SystemTime>>,
+}
+
+impl RateLimit {
+    /// Creates new `RateLimit` filter with given `min_interval`.
+    pub fn new(min_interval: Duration) -> Self {
+        RateLimit {
+            min_interval,
+            last_emitted: RefCell::new(FnvHashMap::default()),
+        }
+    }
+}
+
+impl FilterFn for RateLimit {
+    fn filter(&self, ev: Option<Event>, _gilrs: &mut Gilrs) -> Option<Event> {
+        if self.min_interval.is_zero() {
+            return ev;
+        }
+
+        let mut last_emitted = self.last_emitted.borrow_mut();
+
+        match ev {
+            Some(Event {
+                event: EventType::AxisChanged(_, _, nec),
+                id,
+                time,
+                ..
+            }) => {
+                let too_early = last_emitted.get(&(id, nec)).is_some_and(|last| {
+                    time.duration_since(*last)
+                        .is_ok_and(|dur| dur < self.min_interval)
+                });
+
+                if too_early {
+                    Some(Event::new(
+                        id,
+                        EventType::Dropped(Some(DropReason::RateLimit)),
+                    ))
+                } else {
+                    last_emitted.insert((id, nec), time);
+                    ev
+                }
+            }
+            Some(Event {
+                event: EventType::Disconnected,
+                id,
+                ..
+            }) => {
+                last_emitted.retain(|&(gamepad_id, _), _| gamepad_id != id);
+                ev
+            }
+            _ => ev,
+        }
+    }
+}
+
+/// Smooths axis values with exponential moving average.
+///
+/// Every `AxisChanged` event (and `ButtonChanged` event of analog `LeftTrigger2` and
+/// `RightTrigger2`) carries value that moved from previously emitted value towards the raw value
+/// by `1 - exp(-dt / time_constant)`, where `dt` is time between events taken from their
+/// timestamps, so irregular spacing of events doesn't change the result. `dt` is limited to
+/// [`MAX_STEP`](Self::MAX_STEP), otherwise first movement after axis was idle for a while would
+/// jump straight to the raw value. Sticks use
+/// `sticks_time_constant`, triggers (`LeftZ`, `RightZ` and analog triggers) use
+/// `triggers_time_constant`. D-pad axes and digital buttons are never smoothed. Time constant of
+/// zero disables smoothing.
+///
+/// When raw value stops changing, the filter keeps emitting events with smoothed value when called
+/// with `None`, at most once every [`UPDATE_INTERVAL`](Self::UPDATE_INTERVAL) per axis. After
+/// `4.6 * time_constant` value is within 1% of the raw value and once it is closer than 0.001 it
+/// is set exactly to the raw value, so endpoints like 0.0 and 1.0 are always reached, at the
+/// latest after about `7 * time_constant + UPDATE_INTERVAL`.
+///
+/// Events that go into the filter are raw values, so if you need both, apply this filter yourself
+/// instead of enabling it with
+/// [`GilrsBuilder::axis_smoothing()`](crate::GilrsBuilder::axis_smoothing).
+#[derive(Clone, Debug)]
+pub struct Smoothing {
+    pub sticks_time_constant: Duration,
+    pub triggers_time_constant: Duration,
+    gamepads: RefCell<FnvHashMap<GamepadId, (Duration, Duration)>>,
+    axes: RefCell<FnvHashMap<(GamepadId, Code), SmoothedAxis>>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct SmoothedAxis {
+    element: AxisOrBtn,
+    value: f32,
+    target: f32,
+    time: SystemTime,
+}
+
+impl SmoothedAxis {
+    fn advance(&mut self, time: SystemTime, time_constant: Duration) {
+        let dt = time
+            .duration_since(self.time)
+            .unwrap_or_default()
+            .min(Smoothing::MAX_STEP);
+        let alpha = 1.0 - (-dt.as_secs_f32() / time_constant.as_secs_f32()).exp();
+
+        self.value += (self.target - self.value) * alpha;
+        if (self.target - self.value).abs() < Smoothing::SNAP_THRESHOLD {
+            self.value = self.target;
+        }
+        self.time = time;
+    }
+
+    fn event_type(&self, nec: Code) -> EventType {
+        match self.element {
+            AxisOrBtn::Axis(axis) => EventType::AxisChanged(axis, self.value, nec),
+            AxisOrBtn::Btn(btn) => EventType::ButtonChanged(btn, self.value, nec),
+        }
+    }
+}
+
+impl Smoothing {
+    /// Minimal time between events emitted for one axis when raw value doesn't change.
+    pub const UPDATE_INTERVAL: Duration = Duration::from_millis(10);
+    /// Maximal time between events used to move smoothed value.
+    pub const MAX_STEP: Duration = Duration::from_millis(50);
+    const SNAP_THRESHOLD: f32 = 0.001;
+
+    /// Creates new `Smoothing` filter with both time constants set to `time_constant`.
+    pub fn new(time_constant: Duration) -> Self {
+        Smoothing {
+            sticks_time_constant: time_constant,
+            triggers_time_constant: time_constant,
+            gamepads: RefCell::new(FnvHashMap::default()),
+            axes: RefCell::new(FnvHashMap::default()),
+        }
+    }
+
+    /// Overrides time constants of sticks and triggers for gamepad with `id`.
+    pub fn set_gamepad_time_constants(&self, id: GamepadId, sticks: Duration, triggers: Duration) {
+        self.gamepads.borrow_mut().insert(id, (sticks, triggers));
+    }
+
+    /// Removes time constants set with
+    /// [`set_gamepad_time_constants()`](Self::set_gamepad_time_constants).
+    pub fn reset_gamepad_time_constants(&self, id: GamepadId) {
+        self.gamepads.borrow_mut().remove(&id);
+    }
+
+    /// Returns `true` if some axis didn't reach its raw value yet.
+    pub fn has_pending(&self) -> bool {
+        self.axes
+            .borrow()
+            .values()
+            .any(|axis| axis.value != axis.target)
+    }
+
+    fn time_constant(&self, id: GamepadId, element: AxisOrBtn) -> Option<Duration> {
+        let (sticks, triggers) = self
+            .gamepads
+            .borrow()
+            .get(&id)
+            .copied()
+            .unwrap_or((self.sticks_time_constant, self.triggers_time_constant));
+
+        let time_constant = match element {
+            AxisOrBtn::Axis(Axis::DPadX | Axis::DPadY) => return None,
+            AxisOrBtn::Axis(Axis::LeftZ | Axis::RightZ) => triggers,
+            AxisOrBtn::Axis(_) => sticks,
+            AxisOrBtn::Btn(Button::LeftTrigger2 | Button::RightTrigger2) => triggers,
+            AxisOrBtn::Btn(_) => return None,
+        };
+
+        if time_constant.is_zero() {
+            None
+        } else {
+            Some(time_constant)
+        }
+    }
+}
+
+impl FilterFn for Smoothing {
+    fn filter(&self, ev: Option<Event>, _gilrs: &mut Gilrs) -> Option<Event> {
+        let (id, element, val, nec, time, source) = match ev {
+            Some(Event {
+                event: EventType::AxisChanged(axis, val, nec),
+                id,
+                time,
+                source,
+            }) => (id, AxisOrBtn::Axis(axis), val, nec, time, source),
+            Some(Event {
+                event: EventType::ButtonChanged(btn, val, nec),
+                id,
+                time,
+                source,
+            }) => (id, AxisOrBtn::Btn(btn), val, nec, time, source),
+            Some(Event {
+                event: EventType::Disconnected,
+                id,
+                ..
+            }) => {
+                self.axes
+                    .borrow_mut()
+                    .retain(|&(gamepad_id, _), _| gamepad_id != id);
+                return ev;
+            }
+            Some(ev) => return Some(ev),
+            None => {
+                let now = utils::time_now();
+                for (&(id, nec), axis) in self.axes.borrow_mut().iter_mut() {
+                    let due = now
+                        .duration_since(axis.time)
+                        .is_ok_and(|dur| dur >= Self::UPDATE_INTERVAL);
+                    if axis.value == axis.target || !due {
+                        continue;
+                    }
+                    match self.time_constant(id, axis.element) {
+                        Some(time_constant) => axis.advance(now, time_constant),
+                        None => axis.value = axis.target,
+                    }
+
+                    return Some(Event {
+                        id,
+                        event: axis.event_type(nec),
+                        time: now,
+                        source: EventSource::Filter,
+                    });
+                }
+                return None;
+            }
+        };
+
+        let time_constant = match self.time_constant(id, element) {
+            Some(time_constant) => time_constant,
+            None => {
+                self.axes.borrow_mut().remove(&(id, nec));
+                return ev;
+            }
+        };
+
+        let mut axes = self.axes.borrow_mut();
+        let axis = match axes.entry((id, nec)) {
+            Entry::Occupied(axis) => axis.into_mut(),
+            Entry::Vacant(axis) => {
+                axis.insert(SmoothedAxis {
+                    element,
+                    value: val,
+                    target: val,
+                    time,
+                });
+                return ev;
+            }
+        };
+        axis.target = val;
+        axis.advance(time, time_constant);
+
+        Some(Event {
+            id,
+            event: axis.event_type(nec),
+            time,
+            source,
+        })
+    }
+}
+
 /// Allow filtering events.
 ///
 /// See module level documentation for more info.
@@ -530,7 +1163,7 @@ impl Filter for Option<Event> {
             "Filter changed Some(event) into None. See ev::filter documentation for more info."
         );
 
-        e
+        e.map(|e| with_user_drop_reason(self.as_ref(), e))
     }
 }
 
@@ -542,6 +1175,390 @@ impl Filter for Event {
             "Filter changed Some(event) into None. See ev::filter documentation for more info."
         );
 
-        e
+        e.map(|e| with_user_drop_reason(Some(self), e))
+    }
+}
+
+/// Sets `DropReason::UserFilter` if filter dropped event without giving a reason. If `input` was
+/// already dropped, its reason is kept.
+fn with_user_drop_reason(input: Option<&Event>, output: Event) -> Event {
+    match (output.event, input.map(|ev| ev.event)) {
+        (EventType::Dropped(None), Some(dropped @ EventType::Dropped(_))) => Event {
+            event: dropped,
+            ..output
+        },
+        (EventType::Dropped(None), _) => output.drop_with(DropReason::UserFilter),
+        _ => output,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AxisRateLimit, Filter, RateLimit, Smoothing, StuckButtonGuard};
+    use crate::ev::{Axis, Button, Code, DropReason, Event, EventSource, EventType};
+    use crate::gamepad::{Error, GamepadId, Gilrs, GilrsBuilder};
+
+    use gilrs_core::native_ev_codes as nec;
+
+    use std::thread;
+    use std::time::{Duration, SystemTime};
+
+    /// Returns `Gilrs` without default filters and ID of registered test gamepad.
+    fn test_gamepad() -> (Gilrs, GamepadId) {
+        let mut gilrs = match GilrsBuilder::new().with_default_filters(false).build() {
+            Ok(gilrs) | Err(Error::NotImplemented(gilrs)) => gilrs,
+            Err(e) => panic!("{}", e),
+        };
+        let id = gilrs.register_test_gamepad("Test gamepad", [7; 16]);
+
+        (gilrs, id)
+    }
+
+    fn hw_event(id: GamepadId, event: EventType, time: SystemTime) -> Event {
+        Event {
+            id,
+            event,
+            time,
+            source: EventSource::Hardware,
+        }
+    }
+
+    fn is_dropped(ev: Option<Event>) -> bool {
+        matches!(
+            ev,
+            Some(Event {
+                event: EventType::Dropped(Some(DropReason::StuckButton)),
+                ..
+            })
+        )
+    }
+
+    #[test]
+    fn stuck_button_released_after_threshold() {
+        let (mut gilrs, id) = test_gamepad();
+        let code = Code(nec::BTN_SOUTH);
+        let guard = StuckButtonGuard::new();
+        let pressed_at = SystemTime::now() - guard.threshold - Duration::from_secs(1);
+
+        let ev = hw_event(
+            id,
+            EventType::ButtonPressed(Button::South, code),
+            pressed_at,
+        );
+        assert_eq!(ev.filter_ev(&guard, &mut gilrs), Some(ev));
+        assert!(!guard.is_stuck(id, code));
+
+        let released = None.filter_ev(&guard, &mut gilrs).unwrap();
+        assert_eq!(
+            released.event,
+            EventType::ButtonReleased(Button::South, code)
+        );
+        assert_eq!(released.time, pressed_at + guard.threshold);
+        assert_eq!(released.source, EventSource::Filter);
+        assert!(guard.is_stuck(id, code));
+        assert_eq!(None.filter_ev(&guard, &mut gilrs), None);
+
+        // Events of stuck button are dropped until device releases it.
+        let now = SystemTime::now();
+        for event in [
+            EventType::ButtonPressed(Button::South, code),
+            EventType::ButtonRepeated(Button::South, code),
+            EventType::ButtonChanged(Button::South, 1.0, code),
+            EventType::ButtonReleased(Button::South, code),
+        ] {
+            assert!(is_dropped(
+                hw_event(id, event, now).filter_ev(&guard, &mut gilrs)
+            ));
+        }
+        assert!(!guard.is_stuck(id, code));
+
+        let ev = hw_event(id, EventType::ButtonPressed(Button::South, code), now);
+        assert_eq!(ev.filter_ev(&guard, &mut gilrs), Some(ev));
+        assert_eq!(None.filter_ev(&guard, &mut gilrs), None);
+    }
+
+    #[test]
+    fn stuck_button_released_in_time() {
+        let (mut gilrs, id) = test_gamepad();
+        let code = Code(nec::BTN_EAST);
+        let guard = StuckButtonGuard::new();
+        let pressed_at = SystemTime::now() - guard.threshold - Duration::from_secs(5);
+
+        let ev = hw_event(id, EventType::ButtonPressed(Button::East, code), pressed_at);
+        assert_eq!(ev.filter_ev(&guard, &mut gilrs), Some(ev));
+        let ev = hw_event(
+            id,
+            EventType::ButtonReleased(Button::East, code),
+            pressed_at + Duration::from_secs(1),
+        );
+        assert_eq!(ev.filter_ev(&guard, &mut gilrs), Some(ev));
+        assert_eq!(None.filter_ev(&guard, &mut gilrs), None);
+
+        // Analog button that returned to 0.0 is also released.
+        let ev = hw_event(id, EventType::ButtonPressed(Button::East, code), pressed_at);
+        assert_eq!(ev.filter_ev(&guard, &mut gilrs), Some(ev));
+        let ev = hw_event(
+            id,
+            EventType::ButtonChanged(Button::East, 0.0, code),
+            pressed_at + Duration::from_secs(1),
+        );
+        assert_eq!(ev.filter_ev(&guard, &mut gilrs), Some(ev));
+        assert_eq!(None.filter_ev(&guard, &mut gilrs), None);
+        assert!(!guard.is_stuck(id, code));
+    }
+
+    #[test]
+    fn stuck_button_pressed_again_after_lost_release() {
+        let (mut gilrs, id) = test_gamepad();
+        let code = Code(nec::BTN_WEST);
+        let guard = StuckButtonGuard::new();
+        let pressed_at = SystemTime::now() - guard.threshold - Duration::from_secs(5);
+
+        let ev = hw_event(id, EventType::ButtonPressed(Button::West, code), pressed_at);
+        assert_eq!(ev.filter_ev(&guard, &mut gilrs), Some(ev));
+
+        // Release was dropped by earlier filter, so button is pressed again without release.
+        let ev = hw_event(
+            id,
+            EventType::ButtonPressed(Button::West, code),
+            SystemTime::now(),
+        );
+        assert_eq!(ev.filter_ev(&guard, &mut gilrs), Some(ev));
+        assert_eq!(None.filter_ev(&guard, &mut gilrs), None);
+        assert!(!guard.is_stuck(id, code));
+    }
+
+    #[test]
+    fn stuck_button_reset_on_disconnect() {
+        let (mut gilrs, id) = test_gamepad();
+        let south = Code(nec::BTN_SOUTH);
+        let north = Code(nec::BTN_NORTH);
+        let guard = StuckButtonGuard::new();
+        let pressed_at = SystemTime::now() - guard.threshold - Duration::from_secs(1);
+
+        let ev = hw_event(
+            id,
+            EventType::ButtonPressed(Button::South, south),
+            pressed_at,
+        );
+        ev.filter_ev(&guard, &mut gilrs);
+        assert!(None.filter_ev(&guard, &mut gilrs).is_some());
+        assert!(guard.is_stuck(id, south));
+
+        let ev = hw_event(
+            id,
+            EventType::ButtonPressed(Button::North, north),
+            pressed_at,
+        );
+        ev.filter_ev(&guard, &mut gilrs);
+
+        let ev = hw_event(id, EventType::Disconnected, SystemTime::now());
+        assert_eq!(ev.filter_ev(&guard, &mut gilrs), Some(ev));
+        assert!(!guard.is_stuck(id, south));
+        // North button is no longer tracked, so there is no synthetic release for it.
+        assert_eq!(None.filter_ev(&guard, &mut gilrs), None);
+
+        let ev = hw_event(
+            id,
+            EventType::ButtonPressed(Button::South, south),
+            SystemTime::now(),
+        );
+        assert_eq!(ev.filter_ev(&guard, &mut gilrs), Some(ev));
+    }
+
+    #[test]
+    fn axis_rate_limit() {
+        let (mut gilrs, id) = test_gamepad();
+        let code = Code(nec::AXIS_LSTICKX);
+        let mut limit = AxisRateLimit::new();
+        limit.max_rate = 10;
+        let start = SystemTime::now() - Duration::from_secs(10);
+        let axis_ev = |val, ms| {
+            hw_event(
+                id,
+                EventType::AxisChanged(Axis::LeftStickX, val, code),
+                start + Duration::from_millis(ms),
+            )
+        };
+        let value = |ev: Option<Event>| match ev.map(|ev| ev.event) {
+            Some(EventType::AxisChanged(_, val, _)) => Some(val),
+            Some(EventType::Dropped(_)) => None,
+            ev => panic!("unexpected event {:?}", ev),
+        };
+
+        assert_eq!(
+            value(axis_ev(0.1, 0).filter_ev(&limit, &mut gilrs)),
+            Some(0.1)
+        );
+        // Burst is coalesced.
+        for (i, val) in [0.2, 0.3, 0.4, 0.5].into_iter().enumerate() {
+            let ev = axis_ev(val, i as u64 + 1).filter_ev(&limit, &mut gilrs);
+            assert_eq!(value(ev), None);
+        }
+        // Button events are never delayed.
+        let btn = hw_event(
+            id,
+            EventType::ButtonPressed(Button::South, Code(nec::BTN_SOUTH)),
+            start + Duration::from_millis(6),
+        );
+        assert_eq!(btn.filter_ev(&limit, &mut gilrs), Some(btn));
+        // Zero crossings are never delayed.
+        assert_eq!(
+            value(axis_ev(-0.2, 7).filter_ev(&limit, &mut gilrs)),
+            Some(-0.2)
+        );
+        assert_eq!(
+            value(axis_ev(0.0, 8).filter_ev(&limit, &mut gilrs)),
+            Some(0.0)
+        );
+        assert!(!limit.has_pending());
+
+        for (i, val) in [0.3, 0.6, 0.9, 0.7].into_iter().enumerate() {
+            axis_ev(val, i as u64 + 9).filter_ev(&limit, &mut gilrs);
+        }
+        assert!(limit.has_pending());
+        // Interval has passed, latest value is emitted.
+        assert_eq!(value(None.filter_ev(&limit, &mut gilrs)), Some(0.7));
+        assert_eq!(None.filter_ev(&limit, &mut gilrs), None);
+        assert!(!limit.has_pending());
+    }
+
+    #[test]
+    fn axis_smoothing() {
+        let (mut gilrs, _) = test_gamepad();
+        let smoothing = Smoothing::new(Duration::from_millis(100));
+        let start = SystemTime::now() - Duration::from_secs(10);
+        let axis_ev = |id, axis, val, ms| {
+            hw_event(
+                GamepadId(id),
+                EventType::AxisChanged(axis, val, Code(nec::AXIS_LSTICKX)),
+                start + Duration::from_millis(ms),
+            )
+        };
+        let value = |ev: Option<Event>| match ev.map(|ev| ev.event) {
+            Some(EventType::AxisChanged(_, val, _)) => val,
+            ev => panic!("unexpected event {:?}", ev),
+        };
+        let assert_close = |a: f32, b: f32| assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+
+        // Step input.
+        let step = |gilrs: &mut Gilrs, id, ms| {
+            value(axis_ev(id, Axis::LeftStickX, 1.0, ms).filter_ev(&smoothing, gilrs))
+        };
+        assert_eq!(
+            value(axis_ev(0, Axis::LeftStickX, 0.0, 0).filter_ev(&smoothing, &mut gilrs)),
+            0.0
+        );
+        assert_close(step(&mut gilrs, 0, 50), 1.0 - (-0.5f32).exp());
+        assert_close(step(&mut gilrs, 0, 100), 1.0 - (-1.0f32).exp());
+        assert_close(step(&mut gilrs, 0, 150), 1.0 - (-1.5f32).exp());
+        assert_close(step(&mut gilrs, 0, 200), 1.0 - (-2.0f32).exp());
+
+        // Result depends only on timestamps, not on number of events.
+        assert_eq!(
+            value(axis_ev(1, Axis::LeftStickX, 0.0, 0).filter_ev(&smoothing, &mut gilrs)),
+            0.0
+        );
+        for ms in [40, 80, 120, 160] {
+            step(&mut gilrs, 1, ms);
+        }
+        assert_close(step(&mut gilrs, 1, 200), 1.0 - (-2.0f32).exp());
+
+        // Movement after long idle time is smoothed too.
+        assert_eq!(
+            value(axis_ev(4, Axis::LeftStickX, 0.0, 0).filter_ev(&smoothing, &mut gilrs)),
+            0.0
+        );
+        assert_close(step(&mut gilrs, 4, 5000), 1.0 - (-0.5f32).exp());
+
+        // Value converges to raw value when raw value doesn't change. Every update moves it by at
+        // most `MAX_STEP`, and updates of one axis are `UPDATE_INTERVAL` apart.
+        assert!(smoothing.has_pending());
+        let mut last = [0.0; 5];
+        for _ in 0..100 {
+            thread::sleep(Smoothing::UPDATE_INTERVAL);
+            while let Some(ev) = None.filter_ev(&smoothing, &mut gilrs) {
+                last[usize::from(ev.id)] = value(Some(ev));
+            }
+            if !smoothing.has_pending() {
+                break;
+            }
+        }
+        assert!(!smoothing.has_pending());
+        assert_eq!(last, [1.0, 1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(None.filter_ev(&smoothing, &mut gilrs), None);
+
+        // Noise around 0.5 is attenuated.
+        let mut val = 0.0;
+        for i in 0..200 {
+            let noise = if i % 2 == 0 { 0.1 } else { -0.1 };
+            let ev = axis_ev(2, Axis::RightStickX, 0.5 + noise, 1000 + i * 10);
+            val = value(ev.filter_ev(&smoothing, &mut gilrs));
+        }
+        assert!((val - 0.5).abs() < 0.01, "{}", val);
+
+        // D-pad is never smoothed and per gamepad time constant of zero disables smoothing.
+        let dpad = axis_ev(0, Axis::DPadX, -1.0, 300);
+        assert_eq!(dpad.filter_ev(&smoothing, &mut gilrs), Some(dpad));
+        smoothing.set_gamepad_time_constants(GamepadId(3), Duration::ZERO, Duration::ZERO);
+        axis_ev(3, Axis::LeftStickX, 0.0, 0).filter_ev(&smoothing, &mut gilrs);
+        assert_eq!(step(&mut gilrs, 3, 100), 1.0);
+    }
+
+    #[test]
+    fn rate_limit() {
+        let (mut gilrs, id) = test_gamepad();
+        let lstick_x = Code(nec::AXIS_LSTICKX);
+        let lstick_y = Code(nec::AXIS_LSTICKY);
+        let limit = RateLimit::new(Duration::from_millis(10));
+        let start = SystemTime::now() - Duration::from_secs(10);
+        let axis_ev = |code, val, ms| {
+            hw_event(
+                id,
+                EventType::AxisChanged(Axis::LeftStickX, val, code),
+                start + Duration::from_millis(ms),
+            )
+        };
+        let value = |ev: Option<Event>| match ev.map(|ev| ev.event) {
+            Some(EventType::AxisChanged(_, val, _)) => Some(val),
+            Some(EventType::Dropped(Some(DropReason::RateLimit))) => None,
+            ev => panic!("unexpected event {:?}", ev),
+        };
+
+        let values: Vec<_> = [
+            (0, 0.1),
+            (3, 0.2),
+            (9, 0.3),
+            (10, 0.4),
+            (15, 0.5),
+            (21, 0.0),
+        ]
+        .into_iter()
+        .map(|(ms, val)| value(axis_ev(lstick_x, val, ms).filter_ev(&limit, &mut gilrs)))
+        .collect();
+        assert_eq!(values, [Some(0.1), None, None, Some(0.4), None, Some(0.0)]);
+        // Other axes are tracked separately.
+        assert_eq!(
+            value(axis_ev(lstick_y, 0.7, 22).filter_ev(&limit, &mut gilrs)),
+            Some(0.7)
+        );
+        // Throttled events are never emitted later.
+        assert_eq!(None.filter_ev(&limit, &mut gilrs), None);
+        // Button events are not throttled.
+        let btn = hw_event(
+            id,
+            EventType::ButtonPressed(Button::South, Code(nec::BTN_SOUTH)),
+            start + Duration::from_millis(23),
+        );
+        assert_eq!(btn.filter_ev(&limit, &mut gilrs), Some(btn));
+
+        // Zero interval disables filter.
+        let limit = RateLimit::new(Duration::ZERO);
+        for ms in 0..3 {
+            assert_eq!(
+                value(axis_ev(lstick_x, 0.5, ms).filter_ev(&limit, &mut gilrs)),
+                Some(0.5)
+            );
+        }
     }
 }
@@ -7,15 +7,17 @@
 
 //! Gamepad state and other event related functionality.
 
+pub mod code;
 pub mod filter;
 pub mod state;
 
 use std::{
+    convert::TryFrom,
     fmt::{Display, Formatter, Result as FmtResult},
     time::SystemTime,
 };
 
-use crate::{constants::*, gamepad::GamepadId, utils};
+use crate::{constants::*, gamepad::GamepadId, mapping, utils, MappingError};
 
 #[cfg(feature = "serde-serialize")]
 use serde::{Deserialize, Serialize};
@@ -43,6 +45,14 @@ impl Code {
     pub fn into_u32(&self) -> u32 {
         self.0.into_u32()
     }
+
+    /// Returns platform specific name of the element, for example `KEY(304)` on Linux or
+    /// `Button(1)` with Windows Gaming Input. It is the same as `Display` implementation and is
+    /// mostly useful for showing elements that are not mapped (`Button::Unknown` or
+    /// `Axis::Unknown`) to the user.
+    pub fn native_name(&self) -> String {
+        self.0.to_string()
+    }
 }
 
 /// Holds information about gamepad event.
@@ -54,33 +64,77 @@ pub struct Event {
     pub id: GamepadId,
     /// Event's data.
     pub event: EventType,
-    /// Time when event was emitted.
+    /// Time when event was emitted. Its meaning depends on
+    /// [`Gilrs::timestamp_source()`](crate::Gilrs::timestamp_source).
     pub time: SystemTime,
+    /// Origin of event.
+    pub source: EventSource,
 }
 
 impl Event {
-    /// Creates new event with current time.
+    /// Creates new event with current time and `EventSource::Injected` source.
     pub fn new(id: GamepadId, event: EventType) -> Self {
         Event {
             id,
             event,
             time: utils::time_now(),
+            source: EventSource::Injected,
         }
     }
 
-    /// Returns `Event` with `EventType::Dropped`.
+    /// Returns `Event` with changed source. Custom filters can use it to mark events they create
+    /// with `EventSource::Filter`.
+    pub fn with_source(mut self, source: EventSource) -> Event {
+        self.source = source;
+
+        self
+    }
+
+    /// Returns `Event` with `EventType::Dropped` without reason. If it's returned by a filter
+    /// applied with [`Filter::filter_ev()`](filter/trait.Filter.html), reason is set to
+    /// `DropReason::UserFilter`.
     pub fn drop(mut self) -> Event {
-        self.event = EventType::Dropped;
+        self.event = EventType::Dropped(None);
+
+        self
+    }
+
+    /// Returns `Event` with `EventType::Dropped` and given reason.
+    pub fn drop_with(mut self, reason: DropReason) -> Event {
+        self.event = EventType::Dropped(Some(reason));
 
         self
     }
 
     /// Returns true if event is `Dropped` and should be ignored.
     pub fn is_dropped(&self) -> bool {
-        self.event == EventType::Dropped
+        matches!(self.event, EventType::Dropped(_))
     }
 }
 
+/// Origin of [`Event`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum EventSource {
+    /// Event reported by the platform backend, including events that accompany it (like
+    /// `ButtonChanged` after `ButtonPressed`) and events whose value was only adjusted by filter
+    /// (like [`deadzone`](filter/fn.deadzone.html)). This means that user actually did something
+    /// with the gamepad.
+    Hardware,
+    /// Event created by filter, for example `ButtonRepeated` from
+    /// [`Repeat`](filter/struct.Repeat.html) or dpad buttons from
+    /// [`axis_dpad_to_button`](filter/fn.axis_dpad_to_button.html).
+    Filter,
+    /// Event created with [`Event::new()`] or added with
+    /// [`Gilrs::insert_event()`](crate::Gilrs::insert_event).
+    Injected,
+    /// Event created by gilrs itself, for example `Rescanned`, `ForceFeedbackEffectCompleted` or
+    /// events that restore gamepad state after
+    /// [`Gilrs::set_suppressed()`](crate::Gilrs::set_suppressed).
+    Synthetic,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 #[non_exhaustive]
@@ -101,12 +155,74 @@ pub enum EventType {
     Connected,
     /// Gamepad has been disconnected. Disconnected gamepad will not generate any new events.
     Disconnected,
-    /// There was an `Event`, but it was dropped by one of filters. You should ignore it.
-    Dropped,
+    /// Gamepads were reconnected after system resumed from suspend and individual `Connected` and
+    /// `Disconnected` events were suppressed. Use [`Gilrs::gamepads()`](crate::Gilrs::gamepads)
+    /// to get current list of gamepads. Event's `id` is one of affected gamepads.
+    ///
+    /// Only emitted if enabled by
+    /// [`GilrsBuilder::suppress_suspend_events()`](crate::GilrsBuilder::suppress_suspend_events).
+    Rescanned,
+    /// There was an `Event`, but it was dropped by one of filters. You should ignore it. Reason
+    /// is only useful for debugging and is `None` if it's not known.
+    Dropped(Option<DropReason>),
     /// A force feedback effect has ran for its duration and stopped.
     ForceFeedbackEffectCompleted,
 }
 
+impl EventType {
+    /// Returns `true` for `ButtonPressed`, `ButtonRepeated`, `ButtonReleased` and `ButtonChanged`.
+    pub fn is_button(&self) -> bool {
+        matches!(
+            self,
+            EventType::ButtonPressed(..)
+                | EventType::ButtonRepeated(..)
+                | EventType::ButtonReleased(..)
+                | EventType::ButtonChanged(..)
+        )
+    }
+
+    /// Returns `true` for `AxisChanged`.
+    pub fn is_axis(&self) -> bool {
+        matches!(self, EventType::AxisChanged(..))
+    }
+
+    /// Returns `true` for events that change which gamepads are connected: `Connected`,
+    /// `Disconnected` and `Rescanned`.
+    pub fn is_lifecycle(&self) -> bool {
+        matches!(
+            self,
+            EventType::Connected | EventType::Disconnected | EventType::Rescanned
+        )
+    }
+}
+
+/// Why event was changed to [`EventType::Dropped`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum DropReason {
+    /// Axis changed less than threshold of [`Jitter`](filter/struct.Jitter.html) filter.
+    Jitter,
+    /// Value after applying [`deadzone`](filter/fn.deadzone.html) didn't change.
+    Deadzone,
+    /// Event repeats information that was already reported in another way, for example hat event
+    /// converted to button events or dpad event from source that is not used (see
+    /// [`GilrsBuilder::dpad_source()`](crate::GilrsBuilder::dpad_source)).
+    Duplicate,
+    /// Axis event was delayed by [`AxisRateLimit`](filter/struct.AxisRateLimit.html) filter or
+    /// throttled by [`RateLimit`](filter/struct.RateLimit.html) filter.
+    RateLimit,
+    /// Gamepad is disabled, see
+    /// [`Gilrs::set_gamepad_enabled()`](crate::Gilrs::set_gamepad_enabled).
+    Disabled,
+    /// Button was recognized as stuck by
+    /// [`StuckButtonGuard`](filter/struct.StuckButtonGuard.html) filter.
+    StuckButton,
+    /// Event was dropped by filter applied with
+    /// [`Filter::filter_ev()`](filter/trait.Filter.html) that didn't give any reason.
+    UserFilter,
+}
+
 #[repr(u16)]
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
@@ -129,6 +245,8 @@ pub enum Button {
     // Menu Pad
     Select = BTN_SELECT,
     Start = BTN_START,
+    /// Guide button. Not reported by all backends, see
+    /// [`Gamepad::supports_button()`](crate::Gamepad::supports_button).
     Mode = BTN_MODE,
     // Sticks
     LeftThumb = BTN_LTHUMB,
@@ -138,6 +256,8 @@ pub enum Button {
     DPadDown = BTN_DPAD_DOWN,
     DPadLeft = BTN_DPAD_LEFT,
     DPadRight = BTN_DPAD_RIGHT,
+    /// Share, Create or Capture button of newer controllers (`misc1` in SDL mappings).
+    Misc1 = BTN_MISC1,
 
     #[default]
     Unknown = BTN_UNKNOWN,
@@ -172,7 +292,18 @@ impl Button {
         matches!(self, DPadUp | DPadDown | DPadLeft | DPadRight)
     }
 
+    /// Same as [`to_standard_code()`](Button::to_standard_code).
     pub fn to_nec(self) -> Option<Code> {
+        self.to_standard_code()
+    }
+
+    /// Returns code that gamepads without custom mapping use for this button on current platform,
+    /// for example to create default bindings without connected gamepad. Returns `None` for
+    /// `Unknown`. See also [`code`] module.
+    ///
+    /// Connected gamepad may use a different code, especially if it has SDL mapping, so prefer
+    /// [`Gamepad::button_code()`](crate::Gamepad::button_code) when gamepad is known.
+    pub fn to_standard_code(self) -> Option<Code> {
         use gilrs_core::native_ev_codes as necs;
 
         match self {
@@ -195,10 +326,34 @@ impl Button {
             Button::DPadDown => Some(necs::BTN_DPAD_DOWN),
             Button::DPadLeft => Some(necs::BTN_DPAD_LEFT),
             Button::DPadRight => Some(necs::BTN_DPAD_RIGHT),
+            Button::Misc1 => Some(necs::BTN_MISC1),
             _ => None,
         }
         .map(Code)
     }
+
+    /// Returns `true` if button can be used in mappings understood by SDL2, see
+    /// [`Gilrs::set_mapping_strict()`](crate::Gilrs::set_mapping_strict). `C` and `Z` have SDL
+    /// names only in gilrs and `Unknown` has none.
+    pub fn is_sdl2_compatible(self) -> bool {
+        mapping::sdl_element(AxisOrBtn::Btn(self)).is_some_and(|el| el.sdl2)
+    }
+
+    /// Returns name of button used in SDL mappings, for example `"a"` for `South` or
+    /// `"leftshoulder"` for `LeftTrigger`. Returns `None` for `Unknown`.
+    pub fn to_sdl_key(self) -> Option<&'static str> {
+        mapping::sdl_element(AxisOrBtn::Btn(self)).map(|el| el.name)
+    }
+
+    /// Returns button with given SDL mapping name. Returns `None` if `key` is not a name of SDL
+    /// button that gilrs supports.
+    pub fn from_sdl_key(key: &str) -> Option<Button> {
+        match mapping::sdl_key_to_element(key) {
+            Some(AxisOrBtn::Btn(Button::Unknown)) => None,
+            Some(AxisOrBtn::Btn(btn)) => Some(btn),
+            _ => None,
+        }
+    }
 }
 
 #[repr(u16)]
@@ -210,9 +365,14 @@ impl Button {
 pub enum Axis {
     LeftStickX = AXIS_LSTICKX,
     LeftStickY = AXIS_LSTICKY,
+    /// Third axis of left element. This is usually analog trigger of gamepads without SDL
+    /// mapping, which reports -1.0 when released and 1.0 when fully pressed, or twist of flight
+    /// stick, which reports 0.0 when centered.
     LeftZ = AXIS_LEFTZ,
     RightStickX = AXIS_RSTICKX,
     RightStickY = AXIS_RSTICKY,
+    /// Third axis of right element. Like [`LeftZ`](Axis::LeftZ), it can be analog trigger or
+    /// twist of flight stick.
     RightZ = AXIS_RIGHTZ,
     DPadX = AXIS_DPADX,
     DPadY = AXIS_DPADY,
@@ -249,6 +409,48 @@ impl Axis {
             _ => None,
         }
     }
+
+    /// Returns code that gamepads without custom mapping use for this axis on current platform.
+    /// Returns `None` for `Unknown`. See [`Button::to_standard_code()`] for details.
+    pub fn to_standard_code(self) -> Option<Code> {
+        use gilrs_core::native_ev_codes as necs;
+
+        match self {
+            Axis::LeftStickX => Some(necs::AXIS_LSTICKX),
+            Axis::LeftStickY => Some(necs::AXIS_LSTICKY),
+            Axis::LeftZ => Some(necs::AXIS_LEFTZ),
+            Axis::RightStickX => Some(necs::AXIS_RSTICKX),
+            Axis::RightStickY => Some(necs::AXIS_RSTICKY),
+            Axis::RightZ => Some(necs::AXIS_RIGHTZ),
+            Axis::DPadX => Some(necs::AXIS_DPADX),
+            Axis::DPadY => Some(necs::AXIS_DPADY),
+            Axis::Unknown => None,
+        }
+        .map(Code)
+    }
+
+    /// Returns `true` if axis can be used in mappings understood by SDL2, see
+    /// [`Gilrs::set_mapping_strict()`](crate::Gilrs::set_mapping_strict). `LeftZ` and `RightZ`
+    /// have SDL names only in gilrs and `DPadX`, `DPadY` and `Unknown` have none.
+    pub fn is_sdl2_compatible(self) -> bool {
+        mapping::sdl_element(AxisOrBtn::Axis(self)).is_some_and(|el| el.sdl2)
+    }
+
+    /// Returns name of axis used in SDL mappings, for example `"leftx"` for `LeftStickX`. Returns
+    /// `None` for axes that can't be mapped with SDL mappings (`DPadX`, `DPadY` and `Unknown`).
+    pub fn to_sdl_key(self) -> Option<&'static str> {
+        mapping::sdl_element(AxisOrBtn::Axis(self)).map(|el| el.name)
+    }
+
+    /// Returns axis with given SDL mapping name. Returns `None` if `key` is not a name of SDL axis
+    /// that gilrs supports. Note that SDL's `"lefttrigger"` and `"righttrigger"` are buttons in
+    /// gilrs.
+    pub fn from_sdl_key(key: &str) -> Option<Axis> {
+        match mapping::sdl_key_to_element(key) {
+            Some(AxisOrBtn::Axis(axis)) => Some(axis),
+            _ => None,
+        }
+    }
 }
 
 /// Represents `Axis` or `Button`.
@@ -264,3 +466,317 @@ impl AxisOrBtn {
         matches!(self, AxisOrBtn::Btn(_))
     }
 }
+
+/// [`Button`] that SDL2 understands. Created with `SdlButton::try_from(button)`, which fails with
+/// `MappingError::NotSdl2Compatible` if [`Button::is_sdl2_compatible()`] is `false`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct SdlButton(Button);
+
+impl SdlButton {
+    /// Returns name of button used in SDL2 mappings.
+    pub fn sdl_key(self) -> &'static str {
+        self.0
+            .to_sdl_key()
+            .expect("SDL2 compatible button without SDL name")
+    }
+}
+
+impl TryFrom<Button> for SdlButton {
+    type Error = MappingError;
+
+    fn try_from(btn: Button) -> Result<Self, Self::Error> {
+        if btn.is_sdl2_compatible() {
+            Ok(SdlButton(btn))
+        } else {
+            Err(MappingError::NotSdl2Compatible)
+        }
+    }
+}
+
+impl From<SdlButton> for Button {
+    fn from(btn: SdlButton) -> Self {
+        btn.0
+    }
+}
+
+/// [`Axis`] that SDL2 understands. Created with `SdlAxis::try_from(axis)`, which fails with
+/// `MappingError::NotSdl2Compatible` if [`Axis::is_sdl2_compatible()`] is `false`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct SdlAxis(Axis);
+
+impl SdlAxis {
+    /// Returns name of axis used in SDL2 mappings.
+    pub fn sdl_key(self) -> &'static str {
+        self.0
+            .to_sdl_key()
+            .expect("SDL2 compatible axis without SDL name")
+    }
+}
+
+impl TryFrom<Axis> for SdlAxis {
+    type Error = MappingError;
+
+    fn try_from(axis: Axis) -> Result<Self, Self::Error> {
+        if axis.is_sdl2_compatible() {
+            Ok(SdlAxis(axis))
+        } else {
+            Err(MappingError::NotSdl2Compatible)
+        }
+    }
+}
+
+impl From<SdlAxis> for Axis {
+    fn from(axis: SdlAxis) -> Self {
+        axis.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        code, Axis, AxisOrBtn, Button, Code, DropReason, Event, EventType, SdlAxis, SdlButton,
+    };
+    use crate::mapping::Mapping;
+    use crate::{GamepadId, MappingError};
+
+    use gilrs_core::native_ev_codes as nec;
+
+    use std::collections::HashSet;
+    use std::convert::TryFrom;
+
+    // Element names understood by SDL2.
+    const SDL2_KEYS: [&str; 27] = [
+        "a",
+        "b",
+        "x",
+        "y",
+        "back",
+        "guide",
+        "start",
+        "leftstick",
+        "rightstick",
+        "leftshoulder",
+        "rightshoulder",
+        "dpup",
+        "dpdown",
+        "dpleft",
+        "dpright",
+        "misc1",
+        "paddle1",
+        "paddle2",
+        "paddle3",
+        "paddle4",
+        "touchpad",
+        "leftx",
+        "lefty",
+        "rightx",
+        "righty",
+        "lefttrigger",
+        "righttrigger",
+    ];
+
+    fn all_buttons() -> Vec<Button> {
+        use Button::*;
+
+        // Fails to compile when new variant is added, so it can't be missed in the list below.
+        let check = |btn| match btn {
+            South | East | North | West | C | Z | LeftTrigger | LeftTrigger2 | RightTrigger
+            | RightTrigger2 | Select | Start | Mode | LeftThumb | RightThumb | DPadUp
+            | DPadDown | DPadLeft | DPadRight | Misc1 | Unknown => btn,
+        };
+
+        [
+            South,
+            East,
+            North,
+            West,
+            C,
+            Z,
+            LeftTrigger,
+            LeftTrigger2,
+            RightTrigger,
+            RightTrigger2,
+            Select,
+            Start,
+            Mode,
+            LeftThumb,
+            RightThumb,
+            DPadUp,
+            DPadDown,
+            DPadLeft,
+            DPadRight,
+            Misc1,
+            Unknown,
+        ]
+        .into_iter()
+        .map(check)
+        .collect()
+    }
+
+    fn all_axes() -> Vec<Axis> {
+        use Axis::*;
+
+        // Fails to compile when new variant is added, so it can't be missed in the list below.
+        let check = |axis| match axis {
+            LeftStickX | LeftStickY | LeftZ | RightStickX | RightStickY | RightZ | DPadX
+            | DPadY | Unknown => axis,
+        };
+
+        [
+            LeftStickX,
+            LeftStickY,
+            LeftZ,
+            RightStickX,
+            RightStickY,
+            RightZ,
+            DPadX,
+            DPadY,
+            Unknown,
+        ]
+        .into_iter()
+        .map(check)
+        .collect()
+    }
+
+    #[test]
+    fn event_type_classification() {
+        let btn = Code(nec::BTN_SOUTH);
+        let axis = Code(nec::AXIS_LSTICKX);
+        let events = [
+            EventType::ButtonPressed(Button::South, btn),
+            EventType::ButtonRepeated(Button::South, btn),
+            EventType::ButtonReleased(Button::South, btn),
+            EventType::ButtonChanged(Button::South, 0.5, btn),
+            EventType::AxisChanged(Axis::LeftStickX, 0.5, axis),
+            EventType::Connected,
+            EventType::Disconnected,
+            EventType::Rescanned,
+            EventType::Dropped(None),
+            EventType::ForceFeedbackEffectCompleted,
+        ];
+        let classify =
+            |f: fn(&EventType) -> bool| events.iter().map(|ev| u8::from(f(ev))).collect::<Vec<_>>();
+
+        assert_eq!(
+            classify(EventType::is_button),
+            [1, 1, 1, 1, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(classify(EventType::is_axis), [0, 0, 0, 0, 1, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            classify(EventType::is_lifecycle),
+            [0, 0, 0, 0, 0, 1, 1, 1, 0, 0]
+        );
+    }
+
+    #[test]
+    fn sdl2_compatible_buttons() {
+        for btn in all_buttons() {
+            let key = btn.to_sdl_key();
+            let is_sdl2_key = key.is_some_and(|key| SDL2_KEYS.contains(&key));
+            assert_eq!(btn.is_sdl2_compatible(), is_sdl2_key, "{:?}", btn);
+
+            match SdlButton::try_from(btn) {
+                Ok(sdl) => {
+                    assert!(btn.is_sdl2_compatible());
+                    assert_eq!(Some(sdl.sdl_key()), key);
+                    assert_eq!(Button::from(sdl), btn);
+                }
+                Err(e) => {
+                    assert!(!btn.is_sdl2_compatible());
+                    assert_eq!(e, MappingError::NotSdl2Compatible);
+                }
+            }
+        }
+
+        assert!(Button::Misc1.is_sdl2_compatible());
+        assert!(!Button::C.is_sdl2_compatible());
+        assert!(!Button::Unknown.is_sdl2_compatible());
+    }
+
+    #[test]
+    fn sdl2_compatible_axes() {
+        for axis in all_axes() {
+            let key = axis.to_sdl_key();
+            let is_sdl2_key = key.is_some_and(|key| SDL2_KEYS.contains(&key));
+            assert_eq!(axis.is_sdl2_compatible(), is_sdl2_key, "{:?}", axis);
+
+            match SdlAxis::try_from(axis) {
+                Ok(sdl) => {
+                    assert!(axis.is_sdl2_compatible());
+                    assert_eq!(Some(sdl.sdl_key()), key);
+                    assert_eq!(Axis::from(sdl), axis);
+                }
+                Err(e) => {
+                    assert!(!axis.is_sdl2_compatible());
+                    assert_eq!(e, MappingError::NotSdl2Compatible);
+                }
+            }
+        }
+
+        assert!(!Axis::LeftZ.is_sdl2_compatible());
+        assert!(!Axis::DPadX.is_sdl2_compatible());
+    }
+
+    #[test]
+    fn standard_codes() {
+        let mapping = Mapping::default_all();
+        let mut codes = HashSet::new();
+
+        for btn in all_buttons() {
+            match btn.to_standard_code() {
+                Some(code) => {
+                    assert_eq!(mapping.map(&code.0), Some(AxisOrBtn::Btn(btn)), "{:?}", btn);
+                    assert!(codes.insert(code), "{:?}", btn);
+                }
+                None => assert_eq!(btn, Button::Unknown),
+            }
+            assert_eq!(btn.to_nec(), btn.to_standard_code());
+        }
+
+        for axis in all_axes() {
+            match axis.to_standard_code() {
+                Some(code) => {
+                    assert_eq!(
+                        mapping.map(&code.0),
+                        Some(AxisOrBtn::Axis(axis)),
+                        "{:?}",
+                        axis
+                    );
+                    assert!(codes.insert(code), "{:?}", axis);
+                }
+                None => assert_eq!(axis, Axis::Unknown),
+            }
+        }
+
+        assert_eq!(Button::South.to_standard_code(), Some(code::BTN_SOUTH));
+        assert_eq!(
+            Axis::LeftStickX.to_standard_code(),
+            Some(code::AXIS_LSTICKX)
+        );
+    }
+
+    #[test]
+    fn sdl2_keys_map_to_compatible_elements() {
+        for key in SDL2_KEYS {
+            if let Some(btn) = Button::from_sdl_key(key) {
+                assert!(btn.is_sdl2_compatible(), "{}", key);
+            }
+            if let Some(axis) = Axis::from_sdl_key(key) {
+                assert!(axis.is_sdl2_compatible(), "{}", key);
+            }
+        }
+    }
+
+    #[test]
+    fn drop_with_reason() {
+        let ev = Event::new(GamepadId(0), EventType::Connected);
+
+        assert!(!ev.is_dropped());
+        assert_eq!(ev.drop().event, EventType::Dropped(None));
+        assert!(ev.drop_with(DropReason::Deadzone).is_dropped());
+        assert_eq!(
+            ev.drop_with(DropReason::Deadzone).event,
+            EventType::Dropped(Some(DropReason::Deadzone))
+        );
+    }
+}
@@ -5,7 +5,7 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use crate::ev::Code;
+use crate::ev::{Axis, Button, Code, Event, EventType};
 
 use fnv::FnvHashMap;
 
@@ -13,6 +13,10 @@ use std::collections::hash_map;
 use std::iter::Iterator;
 use std::time::SystemTime;
 
+/// Value of counter saved in state after `Gilrs` counter was reset or wrapped around. It's never
+/// returned by `Gilrs::counter()`.
+pub(crate) const INVALID_COUNTER: u64 = u64::MAX;
+
 /// Cached gamepad state.
 #[derive(Clone, Debug)]
 pub struct GamepadState {
@@ -20,6 +24,9 @@ pub struct GamepadState {
     buttons: FnvHashMap<Code, ButtonData>,
     // Indexed by EvCode (nec)
     axes: FnvHashMap<Code, AxisData>,
+    // Logical names of buttons and axes as reported by last event. Unmapped elements are absent.
+    button_names: FnvHashMap<Code, Button>,
+    axis_names: FnvHashMap<Code, Axis>,
 }
 
 impl GamepadState {
@@ -27,9 +34,20 @@ impl GamepadState {
         GamepadState {
             buttons: FnvHashMap::default(),
             axes: FnvHashMap::default(),
+            button_names: FnvHashMap::default(),
+            axis_names: FnvHashMap::default(),
         }
     }
 
+    /// Reserves space for state of `elements` buttons and axes. Element can be reported both as
+    /// button and axis depending on mapping, so every map gets the same capacity.
+    pub(crate) fn reserve(&mut self, elements: usize) {
+        self.buttons.reserve(elements);
+        self.axes.reserve(elements);
+        self.button_names.reserve(elements);
+        self.axis_names.reserve(elements);
+    }
+
     /// Returns `true` if given button is pressed. Returns `false` if there is no information about
     /// `btn` or it is not pressed.
     pub fn is_pressed(&self, btn: Code) -> bool {
@@ -59,6 +77,47 @@ impl GamepadState {
         AxisDataIter(self.axes.iter())
     }
 
+    /// Returns buttons data together with their logical names in stable order.
+    ///
+    /// Buttons are sorted by `Button` and then by `Code`. Unmapped buttons (`Button::Unknown`) are
+    /// placed last and sorted by `Code`.
+    pub fn buttons_sorted(&self) -> Vec<(Button, Code, &ButtonData)> {
+        let mut buttons: Vec<_> = self
+            .buttons
+            .iter()
+            .map(|(&code, data)| {
+                let btn = self
+                    .button_names
+                    .get(&code)
+                    .copied()
+                    .unwrap_or(Button::Unknown);
+                (btn, code, data)
+            })
+            .collect();
+        buttons
+            .sort_unstable_by_key(|&(btn, code, _)| (btn == Button::Unknown, btn as u16, code.0));
+
+        buttons
+    }
+
+    /// Returns axes data together with their logical names in stable order.
+    ///
+    /// Axes are sorted by `Axis` and then by `Code`. Unmapped axes (`Axis::Unknown`) are placed
+    /// last and sorted by `Code`.
+    pub fn axes_sorted(&self) -> Vec<(Axis, Code, &AxisData)> {
+        let mut axes: Vec<_> = self
+            .axes
+            .iter()
+            .map(|(&code, data)| {
+                let axis = self.axis_names.get(&code).copied().unwrap_or(Axis::Unknown);
+                (axis, code, data)
+            })
+            .collect();
+        axes.sort_unstable_by_key(|&(axis, code, _)| (axis == Axis::Unknown, axis as u16, code.0));
+
+        axes
+    }
+
     /// Returns button state and when it changed.
     pub fn button_data(&self, btn: Code) -> Option<&ButtonData> {
         self.buttons.get(&btn)
@@ -69,13 +128,75 @@ impl GamepadState {
         self.axes.get(&axis)
     }
 
+    /// Updates state according to `event`. Returns `true` if state changed. `Connected`,
+    /// `Disconnected` and `Rescanned` events always count as a change.
+    pub(crate) fn update(&mut self, event: &Event, counter: u64) -> bool {
+        use crate::EventType::*;
+
+        match event.event {
+            ButtonPressed(btn, nec) => self.set_btn_pressed(btn, nec, true, counter, event.time),
+            ButtonReleased(btn, nec) => self.set_btn_pressed(btn, nec, false, counter, event.time),
+            ButtonRepeated(btn, nec) => self.set_btn_repeating(btn, nec, counter, event.time),
+            ButtonChanged(btn, value, nec) => {
+                self.set_btn_value(btn, nec, value, counter, event.time)
+            }
+            AxisChanged(axis, value, nec) => {
+                self.update_axis(axis, nec, AxisData::new(value, counter, event.time))
+            }
+            Disconnected | Connected | Rescanned => true,
+            Dropped(_) | ForceFeedbackEffectCompleted => false,
+        }
+    }
+
+    /// Returns events that would change `before` into this state.
+    pub(crate) fn diff(&self, before: &GamepadState) -> Vec<EventType> {
+        let mut events = Vec::new();
+
+        for (btn, code, data) in self.buttons_sorted() {
+            let old = before.button_data(code);
+            let old_pressed = old.map(|d| d.is_pressed()).unwrap_or(false);
+            let old_value = old.map(|d| d.value()).unwrap_or(0.0);
+
+            if data.is_pressed() != old_pressed {
+                events.push(if data.is_pressed() {
+                    EventType::ButtonPressed(btn, code)
+                } else {
+                    EventType::ButtonReleased(btn, code)
+                });
+                events.push(EventType::ButtonChanged(btn, data.value(), code));
+            } else if data.value() != old_value {
+                events.push(EventType::ButtonChanged(btn, data.value(), code));
+            }
+        }
+
+        for (axis, code, data) in self.axes_sorted() {
+            let old_value = before.axis_data(code).map(|d| d.value()).unwrap_or(0.0);
+
+            if data.value() != old_value {
+                events.push(EventType::AxisChanged(axis, data.value(), code));
+            }
+        }
+
+        events
+    }
+
+    fn set_btn_name(&mut self, btn: Button, nec: Code) {
+        if btn == Button::Unknown {
+            self.button_names.remove(&nec);
+        } else {
+            self.button_names.insert(nec, btn);
+        }
+    }
+
     pub(crate) fn set_btn_pressed(
         &mut self,
+        name: Button,
         btn: Code,
         pressed: bool,
         counter: u64,
         timestamp: SystemTime,
-    ) {
+    ) -> bool {
+        let was_pressed = self.is_pressed(btn);
         let data = self.buttons.entry(btn).or_insert_with(|| {
             ButtonData::new(
                 if pressed { 1.0 } else { 0.0 },
@@ -89,9 +210,19 @@ impl GamepadState {
         data.is_repeating = false;
         data.counter = counter;
         data.last_event_ts = timestamp;
+        self.set_btn_name(name, btn);
+
+        was_pressed != pressed
     }
 
-    pub(crate) fn set_btn_repeating(&mut self, btn: Code, counter: u64, timestamp: SystemTime) {
+    pub(crate) fn set_btn_repeating(
+        &mut self,
+        name: Button,
+        btn: Code,
+        counter: u64,
+        timestamp: SystemTime,
+    ) -> bool {
+        let was_repeating = self.buttons.get(&btn).is_some_and(|data| data.is_repeating);
         let data = self
             .buttons
             .entry(btn)
@@ -99,15 +230,20 @@ impl GamepadState {
         data.is_repeating = true;
         data.counter = counter;
         data.last_event_ts = timestamp;
+        self.set_btn_name(name, btn);
+
+        !was_repeating
     }
 
     pub(crate) fn set_btn_value(
         &mut self,
+        name: Button,
         btn: Code,
         value: f32,
         counter: u64,
         timestamp: SystemTime,
-    ) {
+    ) -> bool {
+        let old_value = self.buttons.get(&btn).map_or(0.0, |data| data.value());
         let data = self
             .buttons
             .entry(btn)
@@ -115,10 +251,32 @@ impl GamepadState {
         data.value = value;
         data.counter = counter;
         data.last_event_ts = timestamp;
+        self.set_btn_name(name, btn);
+
+        old_value != value
+    }
+
+    /// Sets all saved counters to value that is never used by `Gilrs::counter()`.
+    pub(crate) fn invalidate_counters(&mut self) {
+        for data in self.buttons.values_mut() {
+            data.counter = INVALID_COUNTER;
+        }
+        for data in self.axes.values_mut() {
+            data.last_event_c = INVALID_COUNTER;
+        }
     }
 
-    pub(crate) fn update_axis(&mut self, axis: Code, data: AxisData) {
+    pub(crate) fn update_axis(&mut self, name: Axis, axis: Code, data: AxisData) -> bool {
+        let old_value = self.axes.get(&axis).map_or(0.0, |data| data.value());
+        let new_value = data.value();
         self.axes.insert(axis, data);
+        if name == Axis::Unknown {
+            self.axis_names.remove(&axis);
+        } else {
+            self.axis_names.insert(axis, name);
+        }
+
+        old_value != new_value
     }
 }
 
@@ -186,7 +344,8 @@ impl ButtonData {
         self.is_repeating
     }
 
-    /// Returns value of counter when button state last changed.
+    /// Returns value of counter when button state last changed or `u64::MAX` if counter was reset
+    /// since then.
     pub fn counter(&self) -> u64 {
         self.counter
     }
@@ -218,7 +377,8 @@ impl AxisData {
         self.value
     }
 
-    /// Returns value of counter when axis value last changed.
+    /// Returns value of counter when axis value last changed or `u64::MAX` if counter was reset
+    /// since then.
     pub fn counter(&self) -> u64 {
         self.last_event_c
     }
@@ -228,3 +388,196 @@ impl AxisData {
         self.last_event_ts
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{AxisData, ButtonData, GamepadState, INVALID_COUNTER};
+    use crate::ev::{Axis, Button, Code, Event, EventType};
+    use crate::GamepadId;
+
+    use gilrs_core::native_ev_codes as nec;
+
+    use std::time::SystemTime;
+
+    #[test]
+    fn update_button_changed() {
+        let lt2 = Code(nec::AXIS_LT2);
+        let mut state = GamepadState::new();
+        let ev = |event| Event::new(GamepadId(0), event);
+
+        assert!(state.update(
+            &ev(EventType::ButtonChanged(Button::LeftTrigger2, 0.4, lt2)),
+            1
+        ));
+        assert_eq!(state.value(lt2), 0.4);
+        assert!(!state.is_pressed(lt2));
+        assert_eq!(state.button_data(lt2).unwrap().counter(), 1);
+
+        assert!(state.update(&ev(EventType::ButtonPressed(Button::LeftTrigger2, lt2)), 2));
+        assert!(state.update(
+            &ev(EventType::ButtonChanged(Button::LeftTrigger2, 0.9, lt2)),
+            2
+        ));
+        assert!(state.is_pressed(lt2));
+        assert_eq!(state.value(lt2), 0.9);
+        assert!(!state.update(
+            &ev(EventType::ButtonChanged(Button::LeftTrigger2, 0.9, lt2)),
+            3
+        ));
+    }
+
+    #[test]
+    fn diff_pressed_while_suppressed() {
+        let now = SystemTime::now();
+        let south = Code(nec::BTN_SOUTH);
+        let mut state = GamepadState::new();
+        let before = state.clone();
+
+        state.set_btn_pressed(Button::South, south, true, 1, now);
+        state.set_btn_value(Button::South, south, 1.0, 1, now);
+
+        assert_eq!(
+            state.diff(&before),
+            [
+                EventType::ButtonPressed(Button::South, south),
+                EventType::ButtonChanged(Button::South, 1.0, south)
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_pressed_and_released_while_suppressed() {
+        let now = SystemTime::now();
+        let south = Code(nec::BTN_SOUTH);
+        let lstick = Code(nec::AXIS_LSTICKX);
+        let mut state = GamepadState::new();
+        state.update_axis(Axis::LeftStickX, lstick, AxisData::new(0.5, 0, now));
+        let before = state.clone();
+
+        state.set_btn_pressed(Button::South, south, true, 1, now);
+        state.set_btn_value(Button::South, south, 1.0, 1, now);
+        state.update_axis(Axis::LeftStickX, lstick, AxisData::new(-0.5, 1, now));
+        state.set_btn_pressed(Button::South, south, false, 2, now);
+        state.set_btn_value(Button::South, south, 0.0, 2, now);
+        state.update_axis(Axis::LeftStickX, lstick, AxisData::new(0.5, 2, now));
+
+        assert!(state.diff(&before).is_empty());
+    }
+
+    #[test]
+    fn buttons_sorted_order() {
+        let now = SystemTime::now();
+        let mut state = GamepadState::new();
+        let (unmapped_a, unmapped_b) = (Code(nec::BTN_NORTH), Code(nec::BTN_EAST));
+
+        state.set_btn_pressed(Button::DPadUp, Code(nec::BTN_DPAD_UP), true, 0, now);
+        state.set_btn_value(Button::Unknown, unmapped_a, 0.5, 1, now);
+        state.set_btn_pressed(Button::LeftTrigger, Code(nec::BTN_LT), true, 2, now);
+        state.set_btn_repeating(Button::Unknown, unmapped_b, 3, now);
+        state.set_btn_pressed(Button::South, Code(nec::BTN_SOUTH), false, 4, now);
+
+        let (first, second) = if unmapped_a.0 < unmapped_b.0 {
+            (unmapped_a, unmapped_b)
+        } else {
+            (unmapped_b, unmapped_a)
+        };
+        let sorted: Vec<_> = state
+            .buttons_sorted()
+            .into_iter()
+            .map(|(btn, code, _)| (btn, code))
+            .collect();
+
+        assert_eq!(
+            sorted,
+            [
+                (Button::South, Code(nec::BTN_SOUTH)),
+                (Button::LeftTrigger, Code(nec::BTN_LT)),
+                (Button::DPadUp, Code(nec::BTN_DPAD_UP)),
+                (Button::Unknown, first),
+                (Button::Unknown, second),
+            ]
+        );
+    }
+
+    #[test]
+    fn axes_sorted_order() {
+        let now = SystemTime::now();
+        let mut state = GamepadState::new();
+        let (unmapped_a, unmapped_b) = (Code(nec::AXIS_RSTICKY), Code(nec::AXIS_LSTICKY));
+
+        state.update_axis(
+            Axis::DPadX,
+            Code(nec::AXIS_DPADX),
+            AxisData::new(1.0, 0, now),
+        );
+        state.update_axis(Axis::Unknown, unmapped_a, AxisData::new(0.2, 1, now));
+        state.update_axis(
+            Axis::RightStickX,
+            Code(nec::AXIS_RSTICKX),
+            AxisData::new(0.3, 2, now),
+        );
+        state.update_axis(Axis::Unknown, unmapped_b, AxisData::new(0.4, 3, now));
+        state.update_axis(
+            Axis::LeftStickX,
+            Code(nec::AXIS_LSTICKX),
+            AxisData::new(0.5, 4, now),
+        );
+
+        let (first, second) = if unmapped_a.0 < unmapped_b.0 {
+            (unmapped_a, unmapped_b)
+        } else {
+            (unmapped_b, unmapped_a)
+        };
+        let sorted: Vec<_> = state
+            .axes_sorted()
+            .into_iter()
+            .map(|(axis, code, _)| (axis, code))
+            .collect();
+
+        assert_eq!(
+            sorted,
+            [
+                (Axis::LeftStickX, Code(nec::AXIS_LSTICKX)),
+                (Axis::RightStickX, Code(nec::AXIS_RSTICKX)),
+                (Axis::DPadX, Code(nec::AXIS_DPADX)),
+                (Axis::Unknown, first),
+                (Axis::Unknown, second),
+            ]
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    proptest::proptest! {
+        #[test]
+        fn counters_round_trip(
+            value in 0.0f32..=1.0,
+            pressed: bool,
+            counter in 0..INVALID_COUNTER,
+        ) {
+            let now = SystemTime::now();
+            let code = Code(nec::BTN_SOUTH);
+            let axis = Code(nec::AXIS_LSTICKX);
+
+            let data = ButtonData::new(value, pressed, false, counter, now);
+            proptest::prop_assert_eq!(data.value(), value);
+            proptest::prop_assert_eq!(data.is_pressed(), pressed);
+            proptest::prop_assert!(!data.is_repeating());
+            proptest::prop_assert_eq!(data.counter(), counter);
+
+            let mut state = GamepadState::new();
+            state.set_btn_pressed(Button::South, code, pressed, counter, now);
+            state.set_btn_repeating(Button::South, code, counter, now);
+            state.update_axis(Axis::LeftStickX, axis, AxisData::new(value, counter, now));
+            let data = state.button_data(code).unwrap();
+            proptest::prop_assert_eq!(data.is_pressed(), pressed);
+            proptest::prop_assert!(data.is_repeating());
+            proptest::prop_assert_eq!(data.counter(), counter);
+            proptest::prop_assert_eq!(state.axis_data(axis).unwrap().counter(), counter);
+
+            state.invalidate_counters();
+            proptest::prop_assert_eq!(state.button_data(code).unwrap().counter(), INVALID_COUNTER);
+            proptest::prop_assert_eq!(state.axis_data(axis).unwrap().counter(), INVALID_COUNTER);
+            proptest::prop_assert_eq!(state.value(axis), value);
+        }
+    }
+}
@@ -9,6 +9,9 @@ use std::ops::Mul;
 
 use super::time::Ticks;
 
+#[cfg(feature = "serde-serialize")]
+use serde::{Deserialize, Serialize};
+
 /// Kind of [`BaseEffect`](struct.BaseEffect.html).
 ///
 /// Currently base effect support only xinput model of force feedback, which means that  gamepad
@@ -51,7 +54,7 @@ impl Default for BaseEffectType {
 ///
 /// For each base effect you can specify it's type, for how long should it be played and it's
 /// strength during playback.
-#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[derive(Clone, PartialEq, Debug, Default)]
 pub struct BaseEffect {
     /// Type of base effect.
     pub kind: BaseEffectType,
@@ -63,6 +66,14 @@ pub struct BaseEffect {
 }
 
 impl BaseEffect {
+    /// Moves the start of this base effect `offset` ticks later on the timeline of the `Effect` it
+    /// is added to. Offsets accumulate with `scheduling.after`, so multiple base effects can be
+    /// arranged one after another (for example buzz, pause, buzz) within a single `Effect`.
+    pub fn at_offset(mut self, offset: Ticks) -> Self {
+        self.scheduling.after = self.scheduling.after.saturating_add(offset);
+        self
+    }
+
     /// Returns `Weak` or `Strong` after applying envelope.
     pub(super) fn magnitude_at(&self, ticks: Ticks) -> BaseEffectType {
         if let Some(wrapped) = self.scheduling.wrap(ticks) {
@@ -76,32 +87,119 @@ impl BaseEffect {
 }
 
 // TODO: Image with "envelope"
-#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[derive(Clone, PartialEq, Debug, Default)]
+#[non_exhaustive]
 /// Envelope shaped attenuation(time) function.
+///
+/// Create it with [`new()`](#method.new) or `Envelope::default()` and change the curve with
+/// [`with_curve()`](#method.with_curve).
 pub struct Envelope {
     pub attack_length: Ticks,
     pub attack_level: f32,
     pub fade_length: Ticks,
     pub fade_level: f32,
+    /// Shape of attack and fade.
+    pub curve: EnvelopeCurve,
 }
 
 impl Envelope {
+    /// Creates envelope with linear attack and fade.
+    pub fn new(
+        attack_length: Ticks,
+        attack_level: f32,
+        fade_length: Ticks,
+        fade_level: f32,
+    ) -> Self {
+        Envelope {
+            attack_length,
+            attack_level,
+            fade_length,
+            fade_level,
+            curve: EnvelopeCurve::Linear,
+        }
+    }
+
+    /// Sets shape of attack and fade.
+    pub fn with_curve(mut self, curve: EnvelopeCurve) -> Self {
+        self.curve = curve;
+        self
+    }
+
     pub(super) fn at(&self, ticks: Ticks, dur: Ticks) -> f32 {
+        if let EnvelopeCurve::Custom(ref points) = self.curve {
+            return EnvelopeCurve::custom_at(points, ticks);
+        }
+
         debug_assert!(self.fade_length < dur);
         debug_assert!(self.attack_length + self.fade_length < dur);
 
         if ticks < self.attack_length {
-            self.attack_level
-                + ticks.0 as f32 * (1.0 - self.attack_level) / self.attack_length.0 as f32
+            let progress = ticks.0 as f32 / self.attack_length.0 as f32;
+            self.attack_level + self.curve.ease(progress) * (1.0 - self.attack_level)
         } else if ticks + self.fade_length > dur {
-            1.0 + (ticks + self.fade_length - dur).0 as f32 * (self.fade_level - 1.0)
-                / self.fade_length.0 as f32
+            let progress = (ticks + self.fade_length - dur).0 as f32 / self.fade_length.0 as f32;
+            1.0 + self.curve.ease(progress) * (self.fade_level - 1.0)
         } else {
             1.0
         }
     }
 }
 
+/// Shape of [`Envelope`](struct.Envelope.html)'s attack and fade.
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum EnvelopeCurve {
+    /// Level changes linearly.
+    #[default]
+    Linear,
+    /// Level changes with square of time, starting slowly.
+    Quadratic,
+    /// Level changes with cube of time, starting even more slowly.
+    Cubic,
+    /// Level is linearly interpolated between `(time, level)` control points. Before the first
+    /// and after the last point, the level of that point is used. Attack and fade parameters of
+    /// `Envelope` are ignored.
+    ///
+    /// Times must be strictly increasing and levels must be in range `0.0..=1.0`.
+    Custom(Vec<(Ticks, f32)>),
+}
+
+impl EnvelopeCurve {
+    /// Maps `progress` in range `0.0..=1.0` to curve's value in the same range.
+    fn ease(&self, progress: f32) -> f32 {
+        match self {
+            EnvelopeCurve::Linear | EnvelopeCurve::Custom(_) => progress,
+            EnvelopeCurve::Quadratic => progress * progress,
+            EnvelopeCurve::Cubic => progress * progress * progress,
+        }
+    }
+
+    fn custom_at(points: &[(Ticks, f32)], ticks: Ticks) -> f32 {
+        let next = points.iter().position(|&(t, _)| t > ticks);
+        match next {
+            Some(0) => points[0].1,
+            Some(i) => {
+                let (t0, l0) = points[i - 1];
+                let (t1, l1) = points[i];
+                l0 + (ticks - t0).0 as f32 * (l1 - l0) / (t1 - t0).0 as f32
+            }
+            None => points.last().map(|&(_, l)| l).unwrap_or(1.0),
+        }
+    }
+
+    pub(super) fn is_valid(&self) -> bool {
+        match self {
+            EnvelopeCurve::Custom(points) => {
+                !points.is_empty()
+                    && points.windows(2).all(|w| w[0].0 < w[1].0)
+                    && points.iter().all(|&(_, l)| (0.0..=1.0).contains(&l))
+            }
+            _ => true,
+        }
+    }
+}
+
 /// Defines scheduling of the basic force feedback effect.
 ///
 /// ```text
@@ -7,9 +7,10 @@
 
 use std::error::Error;
 use std::ops::{AddAssign, Mul};
+use std::time::Instant;
 use std::{fmt, mem};
 
-use crate::{Event, EventType, GamepadId};
+use crate::{Event, EventSource, EventType, GamepadId};
 
 use super::base_effect::{BaseEffect, BaseEffectType};
 use super::time::{Repeat, Ticks};
@@ -66,7 +67,19 @@ pub enum DistanceModel {
 }
 
 impl DistanceModel {
-    fn attenuation(self, mut distance: f32) -> f32 {
+    /// Returns attenuation for given distance. Result is never NaN and is clamped to
+    /// \[0.0, u16::MAX\], so models that diverge at zero distance (`Inverse` with
+    /// `rolloff_factor` ≥ 1, `Exponential`) don't poison the final magnitude.
+    pub(super) fn attenuation(self, distance: f32) -> f32 {
+        let attenuation = self.raw_attenuation(distance);
+        if attenuation.is_nan() {
+            0.0
+        } else {
+            attenuation.clamp(0.0, u16::MAX as f32)
+        }
+    }
+
+    fn raw_attenuation(self, mut distance: f32) -> f32 {
         // For now we will follow OpenAL[1] specification for distance models. See chapter 3.4 for
         // more details.
         //
@@ -234,12 +247,96 @@ impl fmt::Display for DistanceModelError {
     }
 }
 
+/// Specifies how magnitudes of multiple effects playing on the same gamepad are combined.
+///
+/// Default is `Sum`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FfMixing {
+    /// Magnitudes of all effects are added together and clamped to maximum value.
+    #[default]
+    Sum,
+    /// Strongest magnitude of all effects is used, separately for each motor.
+    Max,
+    /// Only effects with highest [priority](struct.EffectBuilder.html#method.priority) that are
+    /// currently producing any force are played. Magnitudes of effects with the same priority are
+    /// added together.
+    Priority,
+}
+
+impl FfMixing {
+    /// Combines magnitudes of effects given as `(priority, magnitude)` pairs.
+    pub(super) fn mix<I>(self, magnitudes: I) -> Magnitude
+    where
+        I: IntoIterator<Item = (u8, Magnitude)>,
+    {
+        let mut result = Magnitude::zero();
+        let mut result_priority = 0;
+
+        for (priority, magnitude) in magnitudes {
+            match self {
+                FfMixing::Sum => result += magnitude,
+                FfMixing::Max => {
+                    result.strong = result.strong.max(magnitude.strong);
+                    result.weak = result.weak.max(magnitude.weak);
+                }
+                FfMixing::Priority => {
+                    if magnitude.is_zero() || priority < result_priority {
+                        continue;
+                    }
+
+                    if priority > result_priority {
+                        result = Magnitude::zero();
+                        result_priority = priority;
+                    }
+                    result += magnitude;
+                }
+            }
+        }
+
+        result
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub(super) enum EffectState {
-    Playing { since: Ticks },
+    Playing {
+        since: Ticks,
+    },
+    /// Waiting for `Effect::play_at()` deadline.
+    Scheduled {
+        at: Instant,
+    },
     Stopped,
 }
 
+/// Linear movement of the effect source started by `Effect::move_to()`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct Motion {
+    from: [f32; 3],
+    to: [f32; 3],
+    start: Ticks,
+    duration: Ticks,
+}
+
+impl Motion {
+    /// Position at absolute tick `tick` or `None` if target was already reached.
+    fn position_at(&self, tick: Ticks) -> Option<[f32; 3]> {
+        let elapsed = tick.checked_sub(self.start).unwrap_or_default();
+        if elapsed >= self.duration {
+            return None;
+        }
+
+        let t = elapsed.0 as f32 / self.duration.0 as f32;
+        let mut pos = self.from;
+        for (p, to) in pos.iter_mut().zip(self.to) {
+            *p += (to - *p) * t;
+        }
+
+        Some(pos)
+    }
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub(crate) struct EffectSource {
     base_effects: Vec<BaseEffect>,
@@ -247,8 +344,10 @@ pub(crate) struct EffectSource {
     pub(super) devices: VecMap<()>,
     pub(super) repeat: Repeat,
     pub(super) distance_model: DistanceModel,
-    pub(super) position: [f32; 3],
+    position: [f32; 3],
+    motion: Option<Motion>,
     pub(super) gain: f32,
+    pub(super) priority: u8,
     pub(super) state: EffectState,
     pub(super) completion_events: Vec<Event>,
 }
@@ -261,6 +360,7 @@ impl EffectSource {
         dist_model: DistanceModel,
         position: [f32; 3],
         gain: f32,
+        priority: u8,
     ) -> Self {
         EffectSource {
             base_effects,
@@ -268,26 +368,68 @@ impl EffectSource {
             repeat,
             distance_model: dist_model,
             position,
+            motion: None,
             gain,
+            priority,
             state: EffectState::Stopped,
             completion_events: vec![],
         }
     }
 
+    /// Moves source to `position` immediately, cancelling movement started by `move_to()`.
+    pub(super) fn set_position(&mut self, position: [f32; 3]) {
+        self.position = position;
+        self.motion = None;
+    }
+
+    /// Starts moving source from its current position to `position`. Target is reached `over`
+    /// ticks after absolute tick `now`.
+    pub(super) fn move_to(&mut self, position: [f32; 3], now: Ticks, over: Ticks) {
+        self.update_position(now);
+        if over == Ticks(0) {
+            self.set_position(position);
+        } else {
+            self.motion = Some(Motion {
+                from: self.position,
+                to: position,
+                start: now,
+                duration: over,
+            });
+        }
+    }
+
+    #[cfg(test)]
+    pub(super) fn position(&mut self, tick: Ticks) -> [f32; 3] {
+        self.update_position(tick);
+        self.position
+    }
+
+    fn update_position(&mut self, tick: Ticks) {
+        if let Some(motion) = self.motion {
+            match motion.position_at(tick) {
+                Some(pos) => self.position = pos,
+                None => self.set_position(motion.to),
+            }
+        }
+    }
+
     pub(super) fn combine_base_effects(&mut self, ticks: Ticks, actor_pos: [f32; 3]) -> Magnitude {
+        self.update_position(ticks);
+
         let ticks = match self.state {
             EffectState::Playing { since } => {
                 debug_assert!(ticks >= since);
                 ticks - since
             }
-            EffectState::Stopped => return Magnitude::zero(),
+            EffectState::Scheduled { .. } | EffectState::Stopped => return Magnitude::zero(),
         };
 
         match self.repeat {
             Repeat::For(max_dur) if ticks > max_dur => {
                 self.state = EffectState::Stopped;
                 self.devices.keys().for_each(|id| {
-                    let event = Event::new(GamepadId(id), EventType::ForceFeedbackEffectCompleted);
+                    let event = Event::new(GamepadId(id), EventType::ForceFeedbackEffectCompleted)
+                        .with_source(EventSource::Synthetic);
                     self.completion_events.push(event);
                 });
             }
@@ -322,7 +464,7 @@ impl EffectSource {
 }
 
 /// (strong, weak) pair.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub(super) struct Magnitude {
     pub strong: u16,
     pub weak: u16,
@@ -332,6 +474,10 @@ impl Magnitude {
     pub fn zero() -> Self {
         Magnitude { strong: 0, weak: 0 }
     }
+
+    pub fn is_zero(&self) -> bool {
+        self.strong == 0 && self.weak == 0
+    }
 }
 
 impl Mul<f32> for Magnitude {
@@ -57,15 +57,16 @@ mod effect_source;
 pub(crate) mod server;
 mod time;
 
-pub use self::base_effect::{BaseEffect, BaseEffectType, Envelope, Replay};
-pub use self::effect_source::{DistanceModel, DistanceModelError};
+pub use self::base_effect::{BaseEffect, BaseEffectType, Envelope, EnvelopeCurve, Replay};
+pub use self::effect_source::{DistanceModel, DistanceModelError, FfMixing};
 #[allow(unused_imports)]
 pub(crate) use self::time::TICK_DURATION;
 pub use self::time::{Repeat, Ticks};
 
 use std::error::Error as StdError;
 use std::hash::{Hash, Hasher};
-use std::sync::mpsc::{SendError, Sender};
+use std::sync::mpsc::{self, SendError, Sender};
+use std::time::{Duration, Instant};
 use std::{f32, fmt};
 
 use self::effect_source::EffectSource;
@@ -81,6 +82,9 @@ use vec_map::VecMap;
 /// form of reference counting, so it can be cheaply cloned. To create new `Effect` use
 /// [`EffectBuilder`](struct.EffectBuilder.html).
 ///
+/// Every clone owns the effect: it is removed (and stops playing) only after all clones are
+/// dropped. Use [`weak()`](#method.weak) to get handle that doesn't keep the effect alive.
+///
 /// All methods on can return `Error::SendFailed` although it shouldn't normally happen.
 pub struct Effect {
     id: usize,
@@ -118,15 +122,60 @@ impl Drop for Effect {
 }
 
 impl Effect {
+    /// Returns non-owning handle to this effect. See [`WeakEffect`](struct.WeakEffect.html).
+    pub fn weak(&self) -> WeakEffect {
+        WeakEffect {
+            id: self.id,
+            tx: self.tx.clone(),
+        }
+    }
+
     /// Plays effect on all associated gamepads.
     pub fn play(&self) -> Result<(), Error> {
-        self.tx.send(Message::Play { id: self.id })?;
+        self.tx.send(Message::Play {
+            id: self.id,
+            weak: false,
+        })?;
+
+        Ok(())
+    }
+
+    /// Plays effect on all associated gamepads at `at`, for example to synchronize it with sound.
+    ///
+    /// Force feedback is updated every tick (50 ms), but effect's start isn't rounded to ticks.
+    /// Instead, its first tick is processed at `at`, so it starts with accuracy of the system
+    /// sleep (usually around 1 ms). Following ticks keep their usual pace, so the first tick of
+    /// the effect can be up to one tick longer. Apart from that, effect plays exactly like one
+    /// delayed with [`Replay::after`](struct.Replay.html). If `at` already passed, the effect
+    /// starts on the next tick, like with [`play()`](#method.play).
+    ///
+    /// Calling `play()` or [`stop()`](#method.stop) cancels scheduled start.
+    pub fn play_at(&self, at: Instant) -> Result<(), Error> {
+        self.tx.send(Message::PlayAt { id: self.id, at })?;
 
         Ok(())
     }
 
+    /// Plays effect on all associated gamepads after `delay`. See
+    /// [`play_at()`](#method.play_at) for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDelay` if `delay` is so long that time after it can't be
+    /// represented.
+    pub fn play_after(&self, delay: Duration) -> Result<(), Error> {
+        let at = Instant::now()
+            .checked_add(delay)
+            .ok_or(Error::InvalidDelay)?;
+
+        self.play_at(at)
+    }
+
     pub fn stop(&self) -> Result<(), Error> {
-        self.tx.send(Message::Stop { id: self.id })?;
+        self.tx.send(Message::Stop {
+            id: self.id,
+            weak: false,
+        })?;
 
         Ok(())
     }
@@ -207,6 +256,9 @@ impl Effect {
     }
 
     /// Changes position of the source of effect.
+    ///
+    /// Source is moved immediately and any movement started by
+    /// [`move_to()`](#method.move_to) is cancelled.
     pub fn set_position<Vec3f: Into<[f32; 3]>>(&self, position: Vec3f) -> Result<(), Error> {
         let position = position.into();
         self.tx.send(Message::SetPosition {
@@ -217,6 +269,28 @@ impl Effect {
         Ok(())
     }
 
+    /// Moves the source of effect to `position` over `over` time.
+    ///
+    /// Position is interpolated linearly from the current one on every tick, so distance
+    /// attenuation changes smoothly instead of jumping. Calling this again while the source is
+    /// still moving starts a new movement from wherever it currently is. Zero duration behaves
+    /// like [`set_position()`](#method.set_position). Duration is converted to [`Ticks`], so it
+    /// saturates at about 49 days.
+    pub fn move_to<Vec3f: Into<[f32; 3]>>(
+        &self,
+        position: Vec3f,
+        over: Duration,
+    ) -> Result<(), Error> {
+        let position = position.into();
+        self.tx.send(Message::MoveTo {
+            id: self.id,
+            position,
+            over: over.into(),
+        })?;
+
+        Ok(())
+    }
+
     /// Changes gain of the effect. `gain` will be clamped to \[0.0, f32::MAX\].
     pub fn set_gain(&self, gain: f32) -> Result<(), Error> {
         let gain = utils::clamp(gain, 0.0, f32::MAX);
@@ -226,6 +300,81 @@ impl Effect {
     }
 }
 
+/// Handle to force feedback effect that doesn't keep it alive. Created by
+/// [`Effect::weak()`](struct.Effect.html#method.weak).
+///
+/// Cloning and dropping `WeakEffect` doesn't change reference count of the effect, so it can be
+/// stored, for example in cache of effect handles, without affecting when the effect is removed.
+/// After all `Effect` handles are dropped, [`play()`](#method.play) and [`stop()`](#method.stop)
+/// do nothing and [`upgrade()`](#method.upgrade) returns `None`. Effect IDs are never reused, so
+/// weak handle can't refer to different effect.
+#[derive(Clone)]
+pub struct WeakEffect {
+    id: usize,
+    tx: Sender<Message>,
+}
+
+impl PartialEq for WeakEffect {
+    fn eq(&self, other: &WeakEffect) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for WeakEffect {}
+
+impl Hash for WeakEffect {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl WeakEffect {
+    /// Plays effect on all associated gamepads, like
+    /// [`Effect::play()`](struct.Effect.html#method.play). Does nothing if effect was dropped.
+    pub fn play(&self) -> Result<(), Error> {
+        self.tx.send(Message::Play {
+            id: self.id,
+            weak: true,
+        })?;
+
+        Ok(())
+    }
+
+    /// Stops effect. Does nothing if effect was dropped.
+    pub fn stop(&self) -> Result<(), Error> {
+        self.tx.send(Message::Stop {
+            id: self.id,
+            weak: true,
+        })?;
+
+        Ok(())
+    }
+
+    /// Returns new owning handle to the effect or `None` if all `Effect` handles were already
+    /// dropped.
+    ///
+    /// Answer comes from force feedback thread, so this can block for up to one tick (50 ms) if it
+    /// is processing other messages. Returns `None` if force feedback thread is not running.
+    pub fn upgrade(&self) -> Option<Effect> {
+        let (tx, rx) = mpsc::channel();
+        self.tx
+            .send(Message::Upgrade {
+                id: self.id,
+                ack: tx,
+            })
+            .ok()?;
+
+        if rx.recv().ok()? {
+            Some(Effect {
+                id: self.id,
+                tx: self.tx.clone(),
+            })
+        } else {
+            None
+        }
+    }
+}
+
 /// Creates new [`Effect`](struct.Effect.html).
 #[derive(Clone, PartialEq, Debug)]
 pub struct EffectBuilder {
@@ -235,12 +384,13 @@ pub struct EffectBuilder {
     dist_model: DistanceModel,
     position: [f32; 3],
     gain: f32,
+    priority: u8,
 }
 
 impl EffectBuilder {
     /// Creates new builder with following defaults: no gamepads, no base effects, repeat set to
-    /// infinitely, no distance model, position in (0.0, 0.0, 0.0) and gain 1.0. Use `finish()` to
-    /// create new effect.
+    /// infinitely, no distance model, position in (0.0, 0.0, 0.0), gain 1.0 and priority 0. Use
+    /// `finish()` to create new effect.
     pub fn new() -> Self {
         EffectBuilder {
             base_effects: Vec::new(),
@@ -249,6 +399,7 @@ impl EffectBuilder {
             dist_model: DistanceModel::None,
             position: [0.0, 0.0, 0.0],
             gain: 1.0,
+            priority: 0,
         }
     }
 
@@ -258,6 +409,32 @@ impl EffectBuilder {
         self
     }
 
+    /// Adds new [`BaseEffect`](struct.BaseEffect.html) that starts `offset` ticks later. See
+    /// [`BaseEffect::at_offset()`](struct.BaseEffect.html#method.at_offset).
+    pub fn add_effect_at(&mut self, effect: BaseEffect, offset: Ticks) -> &mut Self {
+        self.add_effect(effect.at_offset(offset))
+    }
+
+    /// Returns time after which first playback of all base effects ends, that is the largest
+    /// `scheduling.after + scheduling.play_for` of added base effects. Returns `Ticks(0)` if there
+    /// are no base effects. Sum saturates at `u32::MAX` ticks instead of overflowing.
+    ///
+    /// Base effects are repeated after `scheduling.with_delay` and how long the whole effect is
+    /// played is controlled by [`repeat()`](#method.repeat), so this is length of single pass
+    /// of the effect.
+    pub fn total_duration(&self) -> Ticks {
+        self.base_effects
+            .iter()
+            .map(|effect| {
+                effect
+                    .scheduling
+                    .after
+                    .saturating_add(effect.scheduling.play_for)
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
     /// Changes gamepads that are associated with effect. Effect will be only played on gamepads
     /// from last call to this function.
     pub fn gamepads(&mut self, ids: &[GamepadId]) -> &mut Self {
@@ -298,7 +475,15 @@ impl EffectBuilder {
         self
     }
 
-    /// Validates all parameters and creates new effect.
+    /// Changes priority of the effect. Priority is only used if gamepad's mixing policy is set to
+    /// [`FfMixing::Priority`](enum.FfMixing.html).
+    pub fn priority(&mut self, priority: u8) -> &mut Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Validates all parameters and creates new effect. Effect is created asynchronously by force
+    /// feedback thread, use [`finish_sync()`](Self::finish_sync) to wait for it.
     ///
     /// # Errors
     ///
@@ -308,6 +493,52 @@ impl EffectBuilder {
     /// Returns `Error::InvalidDistanceModel` if `model` is not valid. See
     /// [`DistanceModel`](enum.DistanceModelError.html) for details.
     pub fn finish(&mut self, gilrs: &mut Gilrs) -> Result<Effect, Error> {
+        let effect = self.build(gilrs)?;
+        let id = gilrs.next_ff_id();
+        let tx = gilrs.ff_sender();
+        tx.send(Message::Create {
+            id,
+            effect: Box::new(effect),
+            ack: None,
+        })?;
+        let effect = Effect { id, tx: tx.clone() };
+        gilrs.ff_effect_created();
+
+        Ok(effect)
+    }
+
+    /// Like [`finish()`](Self::finish), but blocks until force feedback thread creates the effect.
+    ///
+    /// `finish()` returns as soon as the effect is sent to force feedback thread, so if gamepad
+    /// was disconnected in the meantime or its device doesn't accept the effect, the effect is
+    /// silently never played. This function waits for the force feedback thread (usually less than
+    /// one tick), which writes current state to every device of the effect, and reports such
+    /// failures.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `finish()`. Additionally returns `Error::Disconnected(id)` if
+    /// force feedback thread doesn't have device for one of gamepads, `Error::UploadFailed(id)`
+    /// if writing to device failed and `Error::SendFailed` if force feedback thread is not
+    /// running.
+    pub fn finish_sync(&mut self, gilrs: &mut Gilrs) -> Result<Effect, Error> {
+        let effect = self.build(gilrs)?;
+        let id = gilrs.next_ff_id();
+        let tx = gilrs.ff_sender();
+        let (ack_tx, ack_rx) = mpsc::channel();
+        tx.send(Message::Create {
+            id,
+            effect: Box::new(effect),
+            ack: Some(ack_tx),
+        })?;
+        ack_rx.recv().map_err(|_| Error::SendFailed)??;
+        let effect = Effect { id, tx: tx.clone() };
+        gilrs.ff_effect_created();
+
+        Ok(effect)
+    }
+
+    fn build(&self, gilrs: &Gilrs) -> Result<EffectSource, Error> {
         for (dev, _) in &self.devices {
             let dev = GamepadId(dev);
             if !gilrs
@@ -321,21 +552,23 @@ impl EffectBuilder {
 
         self.dist_model.validate()?;
 
-        let effect = EffectSource::new(
+        if !self
+            .base_effects
+            .iter()
+            .all(|effect| effect.envelope.curve.is_valid())
+        {
+            return Err(Error::InvalidEnvelopeCurve);
+        }
+
+        Ok(EffectSource::new(
             self.base_effects.clone(),
             self.devices.clone(),
             self.repeat,
             self.dist_model,
             self.position,
             self.gain,
-        );
-        let id = gilrs.next_ff_id();
-        let tx = gilrs.ff_sender();
-        tx.send(Message::Create {
-            id,
-            effect: Box::new(effect),
-        })?;
-        Ok(Effect { id, tx: tx.clone() })
+            self.priority,
+        ))
     }
 }
 
@@ -353,8 +586,15 @@ pub enum Error {
     FfNotSupported(GamepadId),
     /// Device is not connected
     Disconnected(GamepadId),
+    /// Device rejected force feedback state, for example because it has no free slot for effect.
+    UploadFailed(GamepadId),
     /// Distance model is invalid.
     InvalidDistanceModel(DistanceModelError),
+    /// `EnvelopeCurve::Custom` has no control points, their times are not strictly increasing or
+    /// levels are outside of `0.0..=1.0` range.
+    InvalidEnvelopeCurve,
+    /// Delay passed to `Effect::play_after()` is too long.
+    InvalidDelay,
     /// The other end of channel was dropped.
     SendFailed,
     /// Unexpected error has occurred
@@ -385,7 +625,13 @@ impl fmt::Display for Error {
                 sbuf = format!("device with id {} is not connected.", id.0);
                 sbuf.as_ref()
             }
+            Error::UploadFailed(id) => {
+                sbuf = format!("failed to upload effect to device with id {}.", id.0);
+                sbuf.as_ref()
+            }
             Error::InvalidDistanceModel(_) => "distance model is invalid",
+            Error::InvalidEnvelopeCurve => "envelope curve is invalid",
+            Error::InvalidDelay => "delay is too long",
             Error::SendFailed => "receiving end of a channel is disconnected.",
             Error::Other => "unespected error has occurred.",
         };
@@ -408,16 +654,33 @@ impl From<DistanceModelError> for Error {
 
 #[cfg(test)]
 mod tests {
+    use super::effect_source::EffectState;
     use super::*;
 
+    #[test]
+    fn play_after_too_long_delay() {
+        let (tx, rx) = mpsc::channel();
+        let effect = Effect { id: 0, tx };
+        assert_eq!(effect.play_after(Duration::MAX), Err(Error::InvalidDelay));
+        assert!(rx.try_recv().is_err());
+
+        effect.play_after(Duration::from_millis(10)).unwrap();
+        assert!(matches!(rx.try_recv(), Ok(Message::PlayAt { id: 0, .. })));
+    }
+
+    #[test]
+    fn ticks_from_long_duration() {
+        assert_eq!(Ticks::from(Duration::from_secs(60)), Ticks(1200));
+        assert_eq!(
+            Ticks::from(Duration::from_secs(u64::from(u32::MAX))),
+            Ticks::from_ms(u32::MAX)
+        );
+        assert_eq!(Ticks::from(Duration::MAX), Ticks::from_ms(u32::MAX));
+    }
+
     #[test]
     fn envelope() {
-        let env = Envelope {
-            attack_length: Ticks(10),
-            attack_level: 0.2,
-            fade_length: Ticks(10),
-            fade_level: 0.2,
-        };
+        let env = Envelope::new(Ticks(10), 0.2, Ticks(10), 0.2);
         let dur = Ticks(40);
 
         assert_eq!(env.at(Ticks(0), dur), 0.2);
@@ -429,6 +692,73 @@ mod tests {
         assert_eq!(env.at(Ticks(40), dur), 0.19999999);
     }
 
+    #[test]
+    fn envelope_quadratic_and_cubic() {
+        let mut env =
+            Envelope::new(Ticks(10), 0.2, Ticks(10), 0.2).with_curve(EnvelopeCurve::Quadratic);
+        let dur = Ticks(40);
+
+        assert_eq!(env.at(Ticks(0), dur), 0.2);
+        assert_eq!(env.at(Ticks(5), dur), 0.4);
+        assert_eq!(env.at(Ticks(10), dur), 1.0);
+        assert_eq!(env.at(Ticks(30), dur), 1.0);
+        assert_eq!(env.at(Ticks(35), dur), 0.8);
+        assert_eq!(env.at(Ticks(40), dur), 0.19999999);
+
+        env.curve = EnvelopeCurve::Cubic;
+        assert_eq!(env.at(Ticks(0), dur), 0.2);
+        assert_eq!(env.at(Ticks(5), dur), 0.3);
+        assert_eq!(env.at(Ticks(10), dur), 1.0);
+        assert_eq!(env.at(Ticks(30), dur), 1.0);
+        assert_eq!(env.at(Ticks(35), dur), 0.9);
+        assert_eq!(env.at(Ticks(40), dur), 0.19999999);
+    }
+
+    #[test]
+    fn envelope_custom() {
+        let env = Envelope {
+            curve: EnvelopeCurve::Custom(vec![(Ticks(2), 0.0), (Ticks(6), 1.0), (Ticks(8), 0.5)]),
+            ..Default::default()
+        };
+        let dur = Ticks(40);
+
+        assert_eq!(env.at(Ticks(0), dur), 0.0);
+        assert_eq!(env.at(Ticks(2), dur), 0.0);
+        assert_eq!(env.at(Ticks(4), dur), 0.5);
+        assert_eq!(env.at(Ticks(6), dur), 1.0);
+        assert_eq!(env.at(Ticks(7), dur), 0.75);
+        assert_eq!(env.at(Ticks(8), dur), 0.5);
+        assert_eq!(env.at(Ticks(40), dur), 0.5);
+    }
+
+    #[test]
+    fn envelope_custom_validation() {
+        assert!(EnvelopeCurve::Custom(vec![(Ticks(0), 0.0), (Ticks(1), 1.0)]).is_valid());
+        assert!(!EnvelopeCurve::Custom(vec![]).is_valid());
+        assert!(!EnvelopeCurve::Custom(vec![(Ticks(1), 0.0), (Ticks(1), 1.0)]).is_valid());
+        assert!(!EnvelopeCurve::Custom(vec![(Ticks(2), 0.0), (Ticks(1), 1.0)]).is_valid());
+        assert!(!EnvelopeCurve::Custom(vec![(Ticks(0), 1.5)]).is_valid());
+        assert!(!EnvelopeCurve::Custom(vec![(Ticks(0), -0.1)]).is_valid());
+    }
+
+    #[cfg(feature = "serde-serialize")]
+    #[test]
+    fn envelope_curve_serde() {
+        for curve in [
+            EnvelopeCurve::Linear,
+            EnvelopeCurve::Quadratic,
+            EnvelopeCurve::Cubic,
+            EnvelopeCurve::Custom(vec![(Ticks(2), 0.0), (Ticks(6), 1.0), (Ticks(8), 0.5)]),
+        ] {
+            let json = serde_json::to_string(&curve).unwrap();
+            assert_eq!(serde_json::from_str::<EnvelopeCurve>(&json).unwrap(), curve);
+        }
+        assert_eq!(
+            serde_json::to_string(&EnvelopeCurve::Custom(vec![(Ticks(2), 0.5)])).unwrap(),
+            r#"{"Custom":[[2,0.5]]}"#
+        );
+    }
+
     #[test]
     fn envelope_default() {
         let env = Envelope::default();
@@ -455,4 +785,310 @@ mod tests {
         assert_eq!(replay.at(Ticks(60)), 0.0);
         assert_eq!(replay.at(Ticks(70)), 0.0);
     }
+
+    #[test]
+    fn total_duration() {
+        let effect = |after, play_for| BaseEffect {
+            scheduling: Replay {
+                after: Ticks(after),
+                play_for: Ticks(play_for),
+                with_delay: Ticks(100),
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(EffectBuilder::new().total_duration(), Ticks(0));
+
+        // Overlapping
+        let mut builder = EffectBuilder::new();
+        builder.add_effect(effect(0, 10)).add_effect(effect(5, 3));
+        assert_eq!(builder.total_duration(), Ticks(10));
+
+        // Sequential
+        let mut builder = EffectBuilder::new();
+        builder
+            .add_effect(effect(0, 10))
+            .add_effect_at(effect(0, 5), Ticks(10))
+            .add_effect_at(effect(2, 4), Ticks(15));
+        assert_eq!(builder.total_duration(), Ticks(21));
+
+        // Saturating
+        let mut builder = EffectBuilder::new();
+        builder
+            .add_effect(effect(u32::MAX - 1, 10))
+            .add_effect_at(effect(10, 0), Ticks(u32::MAX));
+        assert_eq!(builder.total_duration(), Ticks(u32::MAX));
+    }
+
+    #[test]
+    fn base_effect_offset() {
+        let buzz = BaseEffect {
+            kind: BaseEffectType::Strong { magnitude: 100 },
+            scheduling: Replay {
+                after: Ticks(0),
+                play_for: Ticks(2),
+                with_delay: Ticks(100),
+            },
+            ..Default::default()
+        };
+        let mut source = EffectSource::new(
+            vec![buzz.clone(), buzz.at_offset(Ticks(4))],
+            Default::default(),
+            Repeat::Infinitely,
+            DistanceModel::None,
+            [0.0, 0.0, 0.0],
+            1.0,
+            0,
+        );
+        source.state = EffectState::Playing { since: Ticks(0) };
+
+        let strong: Vec<_> = (0..8)
+            .map(|t| {
+                source
+                    .combine_base_effects(Ticks(t), [0.0, 0.0, 0.0])
+                    .strong
+            })
+            .collect();
+        assert_eq!(strong, [100, 100, 0, 0, 100, 100, 0, 0]);
+    }
+
+    fn playing_source(kind: BaseEffectType, after: u32, priority: u8) -> EffectSource {
+        let effect = BaseEffect {
+            kind,
+            scheduling: Replay {
+                after: Ticks(after),
+                play_for: Ticks(4),
+                with_delay: Ticks(100),
+            },
+            ..Default::default()
+        };
+        let mut source = EffectSource::new(
+            vec![effect],
+            Default::default(),
+            Repeat::Infinitely,
+            DistanceModel::None,
+            [0.0, 0.0, 0.0],
+            1.0,
+            priority,
+        );
+        source.state = EffectState::Playing { since: Ticks(0) };
+
+        source
+    }
+
+    // Strong ambient effect with low priority plays on ticks 0..4, weak cue with high priority on
+    // ticks 2..6.
+    fn mix_overlapping(mixing: FfMixing) -> Vec<(u16, u16)> {
+        let mut ambient = playing_source(BaseEffectType::Strong { magnitude: 40_000 }, 0, 0);
+        let mut cue = playing_source(BaseEffectType::Weak { magnitude: 30_000 }, 2, 10);
+        let mut strong_cue = playing_source(BaseEffectType::Strong { magnitude: 30_000 }, 2, 10);
+
+        (0..7)
+            .map(|t| {
+                let magnitudes = [&mut ambient, &mut cue, &mut strong_cue]
+                    .into_iter()
+                    .map(|s| (s.priority, s.combine_base_effects(Ticks(t), [0.0; 3])));
+                let m = mixing.mix(magnitudes);
+                (m.strong, m.weak)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ff_mixing_sum() {
+        assert_eq!(
+            mix_overlapping(FfMixing::Sum),
+            [
+                (40_000, 0),
+                (40_000, 0),
+                (65_535, 30_000),
+                (65_535, 30_000),
+                (30_000, 30_000),
+                (30_000, 30_000),
+                (0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn ff_mixing_max() {
+        assert_eq!(
+            mix_overlapping(FfMixing::Max),
+            [
+                (40_000, 0),
+                (40_000, 0),
+                (40_000, 30_000),
+                (40_000, 30_000),
+                (30_000, 30_000),
+                (30_000, 30_000),
+                (0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn ff_mixing_priority() {
+        assert_eq!(
+            mix_overlapping(FfMixing::Priority),
+            [
+                (40_000, 0),
+                (40_000, 0),
+                (30_000, 30_000),
+                (30_000, 30_000),
+                (30_000, 30_000),
+                (30_000, 30_000),
+                (0, 0),
+            ]
+        );
+    }
+
+    fn moving_source(model: DistanceModel, gain: f32) -> EffectSource {
+        let rumble = BaseEffect {
+            kind: BaseEffectType::Strong { magnitude: 1000 },
+            scheduling: Replay {
+                after: Ticks(0),
+                play_for: Ticks(100),
+                with_delay: Ticks(0),
+            },
+            ..Default::default()
+        };
+        let mut source = EffectSource::new(
+            vec![rumble],
+            Default::default(),
+            Repeat::Infinitely,
+            model,
+            [-2.0, 0.0, 0.0],
+            gain,
+            0,
+        );
+        source.state = EffectState::Playing { since: Ticks(0) };
+
+        source
+    }
+
+    const ALL_MODELS: [DistanceModel; 7] = [
+        DistanceModel::None,
+        DistanceModel::Linear {
+            ref_distance: 1.0,
+            rolloff_factor: 1.0,
+            max_distance: 4.0,
+        },
+        DistanceModel::LinearClamped {
+            ref_distance: 1.0,
+            rolloff_factor: 1.0,
+            max_distance: 4.0,
+        },
+        DistanceModel::Inverse {
+            ref_distance: 1.0,
+            rolloff_factor: 1.0,
+        },
+        DistanceModel::InverseClamped {
+            ref_distance: 1.0,
+            rolloff_factor: 1.0,
+            max_distance: 4.0,
+        },
+        DistanceModel::Exponential {
+            ref_distance: 1.0,
+            rolloff_factor: 1.0,
+        },
+        DistanceModel::ExponentialClamped {
+            ref_distance: 1.0,
+            rolloff_factor: 1.0,
+            max_distance: 4.0,
+        },
+    ];
+
+    #[test]
+    fn attenuation_at_zero_distance() {
+        for model in ALL_MODELS {
+            assert!(model.validate().is_ok());
+            let attenuation = model.attenuation(0.0);
+            assert!(
+                !attenuation.is_nan() && attenuation >= 0.0,
+                "{:?}: {}",
+                model,
+                attenuation
+            );
+
+            // Zero gain combined with diverging model must not end as NaN either.
+            for gain in [0.0, 1.0] {
+                let mut source = moving_source(model, gain);
+                source.set_position([0.0, 0.0, 0.0]);
+                let magnitude = source.combine_base_effects(Ticks(0), [0.0, 0.0, 0.0]);
+                if gain == 0.0 {
+                    assert!(magnitude.is_zero(), "{:?}", model);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn move_through_listener() {
+        let mut source = moving_source(
+            DistanceModel::LinearClamped {
+                ref_distance: 0.0,
+                rolloff_factor: 1.0,
+                max_distance: 4.0,
+            },
+            1.0,
+        );
+        source.move_to([2.0, 0.0, 0.0], Ticks(0), Ticks(8));
+
+        let strong: Vec<_> = (0..10)
+            .map(|t| {
+                source
+                    .combine_base_effects(Ticks(t), [0.0, 0.0, 0.0])
+                    .strong
+            })
+            .collect();
+        assert_eq!(strong, [500, 625, 750, 875, 1000, 875, 750, 625, 500, 500]);
+
+        // Snapping cancels movement.
+        source.move_to([-2.0, 0.0, 0.0], Ticks(10), Ticks(8));
+        source.set_position([0.0, 0.0, 0.0]);
+        assert_eq!(
+            source
+                .combine_base_effects(Ticks(14), [0.0, 0.0, 0.0])
+                .strong,
+            1000
+        );
+    }
+
+    #[test]
+    fn move_through_listener_all_models() {
+        for model in ALL_MODELS {
+            let mut source = moving_source(model, 1.0);
+            // Source passes exactly through listener position at tick 4.
+            source.move_to([2.0, 0.0, 0.0], Ticks(0), Ticks(8));
+            for t in 0..10 {
+                // Panics in debug builds if attenuation is NaN or negative.
+                source.combine_base_effects(Ticks(t), [0.0, 0.0, 0.0]);
+            }
+        }
+    }
+
+    #[test]
+    fn move_to_restarts_from_current_position() {
+        let mut source = moving_source(DistanceModel::None, 1.0);
+        source.move_to([2.0, 0.0, 0.0], Ticks(0), Ticks(4));
+        source.combine_base_effects(Ticks(2), [0.0, 0.0, 0.0]);
+        source.move_to([0.0, 4.0, 0.0], Ticks(2), Ticks(2));
+        assert_eq!(source.position(Ticks(3)), [0.0, 2.0, 0.0]);
+        assert_eq!(source.position(Ticks(4)), [0.0, 4.0, 0.0]);
+        assert_eq!(source.position(Ticks(40)), [0.0, 4.0, 0.0]);
+
+        source.move_to([1.0, 1.0, 1.0], Ticks(40), Ticks(0));
+        assert_eq!(source.position(Ticks(40)), [1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn move_to_over_long_duration() {
+        let mut source = moving_source(DistanceModel::None, 1.0);
+        // `Effect::move_to()` converts duration like this.
+        let over = Ticks::from(Duration::MAX);
+        source.move_to([2.0, 0.0, 0.0], Ticks(0), over);
+        let [x, y, z] = source.position(Ticks(1000));
+        assert!((x + 2.0).abs() < 0.001 && y == 0.0 && z == 0.0, "{}", x);
+        assert_eq!(source.position(over), [2.0, 0.0, 0.0]);
+    }
 }
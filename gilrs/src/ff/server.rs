@@ -5,13 +5,18 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use super::effect_source::{DistanceModel, EffectSource, EffectState, Magnitude};
+use super::effect_source::{DistanceModel, EffectSource, EffectState, FfMixing, Magnitude};
 use super::time::{Repeat, Ticks, TICK_DURATION};
+use super::Error;
 
+use std::collections::VecDeque;
+use std::io;
 use std::ops::{Deref, DerefMut};
-use std::sync::mpsc::{self, Receiver, Sender};
-use std::thread;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
+use std::{fmt, thread};
 
 use crate::gamepad::GamepadId;
 use crate::Event;
@@ -24,6 +29,9 @@ pub(crate) enum Message {
     Create {
         id: usize,
         effect: Box<EffectSource>,
+        /// If set, effect is only created if all its gamepads have force feedback device opened
+        /// that accepts writes, and the result is sent back.
+        ack: Option<Sender<Result<(), Error>>>,
     },
     HandleCloned {
         id: usize,
@@ -31,11 +39,23 @@ pub(crate) enum Message {
     HandleDropped {
         id: usize,
     },
+    /// Increases reference count and sends back `true` if effect still exists.
+    Upgrade {
+        id: usize,
+        ack: Sender<bool>,
+    },
+    /// `weak` is set when sent by `WeakEffect`, for which the effect may be already dropped.
     Play {
         id: usize,
+        weak: bool,
+    },
+    PlayAt {
+        id: usize,
+        at: Instant,
     },
     Stop {
         id: usize,
+        weak: bool,
     },
     Open {
         id: usize,
@@ -68,10 +88,30 @@ pub(crate) enum Message {
         id: usize,
         position: [f32; 3],
     },
+    MoveTo {
+        id: usize,
+        position: [f32; 3],
+        over: Ticks,
+    },
     SetGain {
         id: usize,
         gain: f32,
     },
+    SetMixing {
+        id: usize,
+        mixing: FfMixing,
+    },
+    /// Sends back number of effects that use gamepad `id`.
+    CountEffects {
+        id: usize,
+        ack: Sender<usize>,
+    },
+    /// Sends back (strong, weak) magnitude currently written to gamepad `id` or `None` if gamepad
+    /// is not opened.
+    CurrentMagnitude {
+        id: usize,
+        ack: Sender<Option<(u16, u16)>>,
+    },
 }
 
 pub(crate) enum FfMessage {
@@ -85,15 +125,56 @@ impl Message {
 
         matches!(
             self,
-            &SetListenerPosition { .. } | &HandleCloned { .. } | &HandleDropped { .. }
+            &SetListenerPosition { .. }
+                | &HandleCloned { .. }
+                | &HandleDropped { .. }
+                | &Upgrade { .. }
+                | &CountEffects { .. }
+                | &CurrentMagnitude { .. }
         )
     }
 }
 
+/// Device that force feedback state is written to. Implemented by `FfDevice` and by mock devices
+/// in tests.
+trait FfWrite {
+    fn set_ff_state(&mut self, strong: u16, weak: u16, min_duration: Duration) -> io::Result<()>;
+}
+
+impl FfWrite for FfDevice {
+    fn set_ff_state(&mut self, strong: u16, weak: u16, min_duration: Duration) -> io::Result<()> {
+        FfDevice::try_set_ff_state(self, strong, weak, min_duration)
+    }
+}
+
 #[derive(Debug)]
-struct Device {
-    inner: FfDevice,
+struct Device<D = FfDevice> {
+    inner: D,
     position: [f32; 3],
+    /// Last magnitude written to device and tick when it was written.
+    last_write: Option<(Magnitude, Ticks)>,
+}
+
+impl<D> Device<D> {
+    /// Returns magnitude that device currently outputs.
+    fn current_magnitude(&self) -> Magnitude {
+        self.last_write
+            .map_or(Magnitude::zero(), |(magnitude, _)| magnitude)
+    }
+
+    /// Returns `true` if `magnitude` differs from last written one or if last write is at least
+    /// `keep_alive` ticks old.
+    fn needs_write(&self, magnitude: Magnitude, tick: Ticks, keep_alive: Ticks) -> bool {
+        match self.last_write {
+            Some((last, written_at)) => {
+                last != magnitude
+                    || tick
+                        .checked_sub(written_at)
+                        .map_or(true, |t| t >= keep_alive)
+            }
+            None => true,
+        }
+    }
 }
 
 struct Effect {
@@ -134,25 +215,39 @@ impl DerefMut for Effect {
     }
 }
 
-impl From<FfDevice> for Device {
-    fn from(inner: FfDevice) -> Self {
+impl<D> From<D> for Device<D> {
+    fn from(inner: D) -> Self {
         Device {
             inner,
             position: [0.0, 0.0, 0.0],
+            last_write: None,
         }
     }
 }
 
-pub(crate) fn run(tx: Sender<FfMessage>, rx: Receiver<Message>) {
+/// Runs force feedback server. Unchanged state of device is written again only after
+/// `keep_alive` ticks; skipped writes are counted in `suppressed_writes`.
+pub(crate) fn run(
+    tx: Sender<FfMessage>,
+    rx: Receiver<Message>,
+    keep_alive: Ticks,
+    suppressed_writes: Arc<AtomicU64>,
+) {
     let mut effects = VecMap::<Effect>::new();
     let mut devices = VecMap::<Device>::new();
+    // Indexed by gamepad ID. Kept separately from devices so it survives reconnecting.
+    let mut mixing = VecMap::<FfMixing>::new();
     let sleep_dur = Duration::from_millis(TICK_DURATION.into());
     let mut tick = Ticks(0);
     let mut completion_events = Vec::<Event>::new();
+    // Time when current tick should be processed. Ticks that start scheduled effects are
+    // processed earlier, but following ticks keep the same pace.
+    let mut tick_time = Instant::now();
+    // Messages received while waiting for next tick.
+    let mut received = VecDeque::new();
 
     loop {
-        let t1 = Instant::now();
-        while let Ok(ev) = rx.try_recv() {
+        while let Some(ev) = received.pop_front().or_else(|| rx.try_recv().ok()) {
             if ev.use_trace_level() {
                 trace!("New ff event: {:?}", ev);
             } else {
@@ -160,20 +255,36 @@ pub(crate) fn run(tx: Sender<FfMessage>, rx: Receiver<Message>) {
             }
 
             match ev {
-                Message::Create { id, effect } => {
-                    effects.insert(id, (*effect).into());
-                }
-                Message::Play { id } => {
+                Message::Create { id, effect, ack } => match ack {
+                    Some(ack) => {
+                        let result = upload(&effect, &mut devices, keep_alive);
+                        if result.is_ok() {
+                            effects.insert(id, (*effect).into());
+                        }
+                        let _ = ack.send(result);
+                    }
+                    None => {
+                        effects.insert(id, (*effect).into());
+                    }
+                },
+                Message::Play { id, weak } => {
                     if let Some(effect) = effects.get_mut(id) {
                         effect.source.state = EffectState::Playing { since: tick }
+                    } else if !weak {
+                        error!("{:?} with wrong ID", ev);
+                    }
+                }
+                Message::PlayAt { id, at } => {
+                    if let Some(effect) = effects.get_mut(id) {
+                        effect.source.state = EffectState::Scheduled { at }
                     } else {
                         error!("{:?} with wrong ID", ev);
                     }
                 }
-                Message::Stop { id } => {
+                Message::Stop { id, weak } => {
                     if let Some(effect) = effects.get_mut(id) {
                         effect.source.state = EffectState::Stopped
-                    } else {
+                    } else if !weak {
                         error!("{:?} with wrong ID", ev);
                     }
                 }
@@ -197,6 +308,16 @@ pub(crate) fn run(tx: Sender<FfMessage>, rx: Receiver<Message>) {
                         error!("{:?} with wrong ID", ev);
                     }
                 }
+                Message::Upgrade { id, ack } => {
+                    let alive = match effects.get_mut(id) {
+                        Some(effect) => {
+                            effect.inc();
+                            true
+                        }
+                        None => false,
+                    };
+                    let _ = ack.send(alive);
+                }
                 Message::HandleDropped { id } => {
                     let mut drop = false;
                     if let Some(effect) = effects.get_mut(id) {
@@ -241,7 +362,14 @@ pub(crate) fn run(tx: Sender<FfMessage>, rx: Receiver<Message>) {
                 }
                 Message::SetPosition { id, position } => {
                     if let Some(eff) = effects.get_mut(id) {
-                        eff.source.position = position;
+                        eff.source.set_position(position);
+                    } else {
+                        error!("Invalid effect id {}.", id);
+                    }
+                }
+                Message::MoveTo { id, position, over } => {
+                    if let Some(eff) = effects.get_mut(id) {
+                        eff.source.move_to(position, tick, over);
                     } else {
                         error!("Invalid effect id {}.", id);
                     }
@@ -253,30 +381,77 @@ pub(crate) fn run(tx: Sender<FfMessage>, rx: Receiver<Message>) {
                         error!("Invalid effect id {} when changing effect gain.", id);
                     }
                 }
+                Message::SetMixing { id, mixing: m } => {
+                    mixing.insert(id, m);
+                }
+                Message::CountEffects { id, ack } => {
+                    let _ = ack.send(count_effects(&effects, id));
+                }
+                Message::CurrentMagnitude { id, ack } => {
+                    let _ = ack.send(current_magnitude(&devices, id));
+                }
             }
         }
 
-        combine_and_play(&mut effects, &mut devices, tick, &mut completion_events);
+        start_scheduled(&mut effects, tick, Instant::now());
+        combine_and_play(
+            &mut effects,
+            &mut devices,
+            &mixing,
+            tick,
+            keep_alive,
+            &mut completion_events,
+            &suppressed_writes,
+        );
         completion_events.iter().for_each(|ev| {
             let _ = tx.send(FfMessage::EffectCompleted { event: *ev });
         });
         completion_events.clear();
 
-        let dur = Instant::now().duration_since(t1);
-        if dur > sleep_dur {
+        let now = Instant::now();
+        tick_time += sleep_dur;
+        if now > tick_time {
             // TODO: Should we add dur - sleep_dur to next iteration's dur?
             warn!(
                 "One iteration of a force feedback loop took more than {}ms!",
                 TICK_DURATION
             );
+            tick_time = now;
         } else {
-            thread::sleep(sleep_dur - dur);
+            // Wait for next tick, but wake up earlier for effects scheduled in the meantime.
+            let mut wakeup = next_wakeup(&effects, tick_time);
+            while let Some(timeout) = wakeup.checked_duration_since(Instant::now()) {
+                match rx.recv_timeout(timeout) {
+                    // Answer queries immediately, someone is waiting for them. This is fine as long
+                    // as no earlier message, that could change the answer, waits in `received`.
+                    Ok(Message::CountEffects { id, ack }) if received.is_empty() => {
+                        let _ = ack.send(count_effects(&effects, id));
+                    }
+                    Ok(Message::CurrentMagnitude { id, ack }) if received.is_empty() => {
+                        let _ = ack.send(current_magnitude(&devices, id));
+                    }
+                    Ok(ev) => {
+                        if let Message::PlayAt { at, .. } = ev {
+                            wakeup = wakeup.min(at);
+                        }
+                        received.push_back(ev);
+                    }
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        thread::sleep(timeout);
+                        break;
+                    }
+                }
+            }
         }
         tick.inc();
     }
 }
 
-pub(crate) fn init() -> (Sender<Message>, Receiver<FfMessage>) {
+pub(crate) fn init(
+    keep_alive: Ticks,
+    suppressed_writes: Arc<AtomicU64>,
+) -> (Sender<Message>, Receiver<FfMessage>) {
     let (tx, _rx) = mpsc::channel();
     let (_tx2, rx2) = mpsc::channel();
 
@@ -284,25 +459,122 @@ pub(crate) fn init() -> (Sender<Message>, Receiver<FfMessage>) {
     #[cfg(not(target_arch = "wasm32"))]
     std::thread::Builder::new()
         .name("gilrs".to_owned())
-        .spawn(move || run(_tx2, _rx))
+        .spawn(move || run(_tx2, _rx, keep_alive, suppressed_writes))
         .expect("failed to spawn thread");
+    #[cfg(target_arch = "wasm32")]
+    let _ = (keep_alive, suppressed_writes);
 
     (tx, rx2)
 }
 
-fn combine_and_play(
+/// Starts playing scheduled effects whose deadline is not later than `now`.
+fn start_scheduled(effects: &mut VecMap<Effect>, tick: Ticks, now: Instant) {
+    for (_, effect) in effects.iter_mut() {
+        match effect.source.state {
+            EffectState::Scheduled { at } if at <= now => {
+                effect.source.state = EffectState::Playing { since: tick }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Returns number of effects that use gamepad `id`.
+fn count_effects(effects: &VecMap<Effect>, id: usize) -> usize {
+    effects
+        .values()
+        .filter(|effect| effect.devices.contains_key(id))
+        .count()
+}
+
+fn current_magnitude<D>(devices: &VecMap<Device<D>>, id: usize) -> Option<(u16, u16)> {
+    devices.get(id).map(|device| {
+        let magnitude = device.current_magnitude();
+        (magnitude.strong, magnitude.weak)
+    })
+}
+
+/// Returns when next tick should be processed: at `tick_time` or earlier, if any scheduled effect
+/// has to start before it.
+fn next_wakeup(effects: &VecMap<Effect>, tick_time: Instant) -> Instant {
+    effects
+        .values()
+        .filter_map(|effect| match effect.source.state {
+            EffectState::Scheduled { at } => Some(at),
+            _ => None,
+        })
+        .fold(tick_time, Instant::min)
+}
+
+/// Checks that every gamepad used by `effect` can play it. Effects are mixed in software, so
+/// nothing is uploaded ahead of time; instead, current state is written again to each device,
+/// which fails the same way as writes that will play the effect.
+///
+/// Returns `Error::Disconnected` for first gamepad that doesn't have force feedback device opened
+/// and `Error::UploadFailed` for first gamepad whose device rejected the write.
+fn upload<D: FfWrite>(
+    effect: &EffectSource,
+    devices: &mut VecMap<Device<D>>,
+    keep_alive: Ticks,
+) -> Result<(), Error> {
+    if let Some(id) = effect.devices.keys().find(|&id| !devices.contains_key(id)) {
+        return Err(Error::Disconnected(GamepadId(id)));
+    }
+
+    for id in effect.devices.keys() {
+        let device = &mut devices[id];
+        let magnitude = device.current_magnitude();
+        if let Err(e) =
+            device
+                .inner
+                .set_ff_state(magnitude.strong, magnitude.weak, write_duration(keep_alive))
+        {
+            warn!("Failed to upload effect to gamepad {}: {}", id, e);
+            return Err(Error::UploadFailed(GamepadId(id)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns how long written state must last: until next keep-alive write, plus a margin for late
+/// ticks.
+fn write_duration(keep_alive: Ticks) -> Duration {
+    Duration::from_millis(u64::from(TICK_DURATION) * (u64::from(keep_alive.0.max(1)) + 1))
+}
+
+/// Mixes all effects playing on each device and writes result to devices whose state changed or
+/// wasn't written for `keep_alive` ticks.
+fn combine_and_play<D: FfWrite + fmt::Debug>(
     effects: &mut VecMap<Effect>,
-    devices: &mut VecMap<Device>,
+    devices: &mut VecMap<Device<D>>,
+    mixing: &VecMap<FfMixing>,
     tick: Ticks,
+    keep_alive: Ticks,
     completion_events: &mut Vec<Event>,
+    suppressed_writes: &AtomicU64,
 ) {
+    let min_duration = write_duration(keep_alive);
+
     for (dev_id, dev) in devices {
-        let mut magnitude = Magnitude::zero();
-        for (_, ref mut effect) in effects.iter_mut() {
-            if effect.devices.contains_key(dev_id) {
-                magnitude += effect.combine_base_effects(tick, dev.position);
+        // Mixed lazily, so nothing is allocated on every tick.
+        let position = dev.position;
+        let magnitudes = effects
+            .iter_mut()
+            .filter(|(_, effect)| effect.devices.contains_key(dev_id))
+            .map(|(_, effect)| {
+                let magnitude = effect.combine_base_effects(tick, position);
                 completion_events.extend(effect.flush_completion_events());
-            }
+                (effect.priority, magnitude)
+            });
+        let magnitude = mixing
+            .get(dev_id)
+            .copied()
+            .unwrap_or_default()
+            .mix(magnitudes);
+        if !dev.needs_write(magnitude, tick, keep_alive) {
+            suppressed_writes.fetch_add(1, Ordering::Relaxed);
+            continue;
         }
         trace!(
             "({:?}) Setting ff state of {:?} to {:?}",
@@ -310,10 +582,289 @@ fn combine_and_play(
             dev,
             magnitude
         );
-        dev.inner.set_ff_state(
-            magnitude.strong,
-            magnitude.weak,
-            Duration::from_millis(u64::from(TICK_DURATION) * 2),
+        if let Err(e) = dev
+            .inner
+            .set_ff_state(magnitude.strong, magnitude.weak, min_duration)
+        {
+            error!("Failed to set ff state of gamepad {}: {}", dev_id, e);
+        }
+        dev.last_write = Some((magnitude, tick));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        combine_and_play, count_effects, current_magnitude, next_wakeup, start_scheduled, upload,
+        Device, Effect, FfWrite,
+    };
+    use crate::ff::effect_source::{DistanceModel, EffectSource, EffectState};
+    use crate::ff::{BaseEffect, BaseEffectType, Error, Repeat, Replay, Ticks, TICK_DURATION};
+    use crate::GamepadId;
+
+    use vec_map::VecMap;
+
+    use std::io;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::{Duration, Instant};
+
+    #[derive(Debug, Default)]
+    struct MockDevice {
+        writes: Vec<(u16, u16)>,
+        // Every write fails, like on device that ran out of effect slots.
+        broken: bool,
+    }
+
+    impl FfWrite for MockDevice {
+        fn set_ff_state(
+            &mut self,
+            strong: u16,
+            weak: u16,
+            _min_duration: Duration,
+        ) -> io::Result<()> {
+            if self.broken {
+                return Err(io::Error::other("no space left for effect"));
+            }
+            self.writes.push((strong, weak));
+
+            Ok(())
+        }
+    }
+
+    fn playing(kind: BaseEffectType, gamepads: &[usize], play_for: u32) -> Effect {
+        playing_after(kind, gamepads, play_for, Ticks(0))
+    }
+
+    fn playing_after(
+        kind: BaseEffectType,
+        gamepads: &[usize],
+        play_for: u32,
+        after: Ticks,
+    ) -> Effect {
+        let effect = BaseEffect {
+            kind,
+            scheduling: Replay {
+                after,
+                play_for: Ticks(play_for),
+                with_delay: Ticks(100),
+            },
+            ..Default::default()
+        };
+        let mut source = EffectSource::new(
+            vec![effect],
+            gamepads.iter().map(|&id| (id, ())).collect(),
+            Repeat::Infinitely,
+            DistanceModel::None,
+            [0.0, 0.0, 0.0],
+            1.0,
+            0,
+        );
+        source.state = EffectState::Playing { since: Ticks(0) };
+
+        source.into()
+    }
+
+    /// Runs `ticks` ticks and returns number of suppressed writes.
+    fn run_ticks(
+        effects: &mut VecMap<Effect>,
+        devices: &mut VecMap<Device<MockDevice>>,
+        ticks: u32,
+        keep_alive: u32,
+    ) -> u64 {
+        let suppressed = AtomicU64::new(0);
+        for t in 0..ticks {
+            combine_and_play(
+                effects,
+                devices,
+                &VecMap::new(),
+                Ticks(t),
+                Ticks(keep_alive),
+                &mut Vec::new(),
+                &suppressed,
+            );
+        }
+        suppressed.load(Ordering::Relaxed)
+    }
+
+    #[test]
+    fn effects_are_accumulated_per_device() {
+        let mut effects = VecMap::new();
+        effects.insert(
+            0,
+            playing(BaseEffectType::Strong { magnitude: 100 }, &[0, 1], 2),
+        );
+        effects.insert(1, playing(BaseEffectType::Weak { magnitude: 50 }, &[0], 4));
+        let mut devices = VecMap::new();
+        devices.insert(0, Device::from(MockDevice::default()));
+        devices.insert(1, Device::from(MockDevice::default()));
+
+        let suppressed = run_ticks(&mut effects, &mut devices, 6, 100);
+
+        // One write per change, never one per effect.
+        assert_eq!(devices[0].inner.writes, [(100, 50), (0, 50), (0, 0)]);
+        assert_eq!(devices[1].inner.writes, [(100, 0), (0, 0)]);
+        assert_eq!(suppressed, 12 - 5);
+    }
+
+    #[test]
+    fn current_magnitude_follows_writes() {
+        let mut effects = VecMap::new();
+        effects.insert(
+            0,
+            playing(BaseEffectType::Strong { magnitude: 100 }, &[0], 2),
+        );
+        effects.insert(1, playing(BaseEffectType::Weak { magnitude: 50 }, &[0], 4));
+        let mut devices = VecMap::new();
+        devices.insert(0, Device::from(MockDevice::default()));
+
+        assert_eq!(current_magnitude(&devices, 0), Some((0, 0)));
+        assert_eq!(current_magnitude(&devices, 1), None);
+
+        run_ticks(&mut effects, &mut devices, 1, 100);
+        assert_eq!(current_magnitude(&devices, 0), Some((100, 50)));
+        // Suppressed writes don't change reported magnitude.
+        run_ticks(&mut effects, &mut devices, 2, 100);
+        assert_eq!(current_magnitude(&devices, 0), Some((100, 50)));
+        run_ticks(&mut effects, &mut devices, 6, 100);
+        assert_eq!(current_magnitude(&devices, 0), Some((0, 0)));
+    }
+
+    #[test]
+    fn unchanged_state_is_written_after_keep_alive() {
+        let mut effects = VecMap::new();
+        effects.insert(
+            0,
+            playing(BaseEffectType::Strong { magnitude: 100 }, &[0], 100),
+        );
+        let mut devices = VecMap::new();
+        devices.insert(0, Device::from(MockDevice::default()));
+
+        let suppressed = run_ticks(&mut effects, &mut devices, 11, 5);
+
+        // Written on ticks 0, 5 and 10.
+        assert_eq!(devices[0].inner.writes, [(100, 0); 3]);
+        assert_eq!(suppressed, 8);
+
+        // Reconnected device is written immediately.
+        devices.insert(0, Device::from(MockDevice::default()));
+        run_ticks(&mut effects, &mut devices, 1, 5);
+        assert_eq!(devices[0].inner.writes, [(100, 0)]);
+    }
+
+    #[test]
+    fn play_after_matches_replay_after() {
+        let kind = BaseEffectType::Strong { magnitude: 100 };
+        let tick_dur = Duration::from_millis(TICK_DURATION.into());
+        let delay = tick_dur * 3 / 2;
+        let start = Instant::now();
+
+        let mut scheduled = VecMap::new();
+        let mut effect = playing(kind, &[0], 2);
+        effect.source.state = EffectState::Scheduled { at: start + delay };
+        scheduled.insert(0, effect);
+        let mut scheduled_devices = VecMap::new();
+        scheduled_devices.insert(0, Device::from(MockDevice::default()));
+
+        // Same steps as in `run()`, without sleeping.
+        let suppressed = AtomicU64::new(0);
+        let mut tick_time = start;
+        let mut now = start;
+        let mut wakeups = Vec::new();
+        for t in 0..6 {
+            start_scheduled(&mut scheduled, Ticks(t), now);
+            combine_and_play(
+                &mut scheduled,
+                &mut scheduled_devices,
+                &VecMap::new(),
+                Ticks(t),
+                Ticks(0),
+                &mut Vec::new(),
+                &suppressed,
+            );
+            tick_time += tick_dur;
+            now = next_wakeup(&scheduled, tick_time);
+            wakeups.push(now - start);
+        }
+
+        let mut delayed = VecMap::new();
+        delayed.insert(0, playing_after(kind, &[0], 2, Ticks::from(delay)));
+        let mut delayed_devices = VecMap::new();
+        delayed_devices.insert(0, Device::from(MockDevice::default()));
+        run_ticks(&mut delayed, &mut delayed_devices, 6, 0);
+
+        assert_eq!(
+            scheduled_devices[0].inner.writes,
+            delayed_devices[0].inner.writes
+        );
+        assert_eq!(
+            scheduled_devices[0].inner.writes,
+            [(0, 0), (0, 0), (100, 0), (100, 0), (0, 0), (0, 0)]
+        );
+        // Tick that starts the effect is processed exactly at the deadline, other ticks keep their
+        // time.
+        assert_eq!(
+            wakeups,
+            [
+                tick_dur,
+                delay,
+                tick_dur * 3,
+                tick_dur * 4,
+                tick_dur * 5,
+                tick_dur * 6
+            ]
+        );
+    }
+
+    #[test]
+    fn effects_are_counted_per_device() {
+        let mut effects = VecMap::new();
+        effects.insert(
+            0,
+            playing(BaseEffectType::Strong { magnitude: 100 }, &[0, 1], 2),
+        );
+        effects.insert(1, playing(BaseEffectType::Weak { magnitude: 50 }, &[0], 4));
+        // Stopped effects are counted too.
+        effects[1].source.state = EffectState::Stopped;
+
+        assert_eq!(count_effects(&effects, 0), 2);
+        assert_eq!(count_effects(&effects, 1), 1);
+        assert_eq!(count_effects(&effects, 2), 0);
+    }
+
+    #[test]
+    fn create_with_failing_device() {
+        let effect = |gamepads: &[usize]| {
+            EffectSource::new(
+                vec![],
+                gamepads.iter().map(|&id| (id, ())).collect(),
+                Repeat::Infinitely,
+                DistanceModel::None,
+                [0.0, 0.0, 0.0],
+                1.0,
+                0,
+            )
+        };
+        let mut devices = VecMap::<Device<MockDevice>>::new();
+
+        assert_eq!(upload(&effect(&[]), &mut devices, Ticks(0)), Ok(()));
+        assert_eq!(
+            upload(&effect(&[3]), &mut devices, Ticks(0)),
+            Err(Error::Disconnected(GamepadId(3)))
+        );
+
+        devices.insert(0, Device::from(MockDevice::default()));
+        devices.insert(
+            1,
+            Device::from(MockDevice {
+                broken: true,
+                ..Default::default()
+            }),
+        );
+        assert_eq!(upload(&effect(&[0]), &mut devices, Ticks(0)), Ok(()));
+        assert_eq!(devices[0].inner.writes, [(0, 0)]);
+        assert_eq!(
+            upload(&effect(&[0, 1]), &mut devices, Ticks(0)),
+            Err(Error::UploadFailed(GamepadId(1)))
         );
     }
 }
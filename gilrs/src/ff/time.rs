@@ -10,6 +10,9 @@ use std::time::Duration;
 
 use crate::utils;
 
+#[cfg(feature = "serde-serialize")]
+use serde::{Deserialize, Serialize};
+
 pub(crate) const TICK_DURATION: u32 = 50;
 
 /// Represents duration.
@@ -31,6 +34,7 @@ pub(crate) const TICK_DURATION: u32 = 50;
 /// assert_eq!(t1, t2);
 /// ```
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 pub struct Ticks(pub(super) u32);
 
 impl Ticks {
@@ -45,11 +49,16 @@ impl Ticks {
     pub(super) fn checked_sub(self, rhs: Ticks) -> Option<Ticks> {
         self.0.checked_sub(rhs.0).map(Ticks)
     }
+
+    pub(super) fn saturating_add(self, rhs: Ticks) -> Ticks {
+        Ticks(self.0.saturating_add(rhs.0))
+    }
 }
 
 impl From<Duration> for Ticks {
+    /// Durations longer than `u32::MAX` milliseconds (about 49 days) are saturated.
     fn from(dur: Duration) -> Self {
-        Ticks::from_ms(dur.as_secs() as u32 * 1000 + dur.subsec_millis())
+        Ticks::from_ms(u32::try_from(dur.as_millis()).unwrap_or(u32::MAX))
     }
 }
 
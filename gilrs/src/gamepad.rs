@@ -6,34 +6,49 @@
 // copied, modified, or distributed except according to those terms.
 
 use crate::{
+    diagnostics,
     ev::{
-        state::{AxisData, ButtonData, GamepadState},
-        Axis, AxisOrBtn, Button, Code, Event, EventType,
+        filter::{AxisRateLimit, Smoothing},
+        state::{AxisData, ButtonData, GamepadState, INVALID_COUNTER},
+        Axis, AxisOrBtn, Button, Code, DropReason, Event, EventSource, EventType,
     },
     ff::{
         server::{self, FfMessage, Message},
-        Error as FfError,
+        BaseEffect, BaseEffectType, Effect, EffectBuilder, Error as FfError, FfMixing, Repeat,
+        Replay, Ticks,
     },
-    mapping::{Mapping, MappingData, MappingDb},
-    utils, MappingError,
+    gate::{GateEstimate, StickGates},
+    latency::{LatencyHistogram, LatencyStats},
+    mapping::{is_hat, Mapping, MappingData, MappingDb, MappingPlatforms},
+    utils, MappingError, Stick,
 };
 
-use gilrs_core::{
-    self, AxisInfo, Error as PlatformError, Event as RawEvent, EventType as RawEventType,
-};
+use gilrs_core::{self, Error as PlatformError, Event as RawEvent, EventType as RawEventType};
 
 use uuid::Uuid;
 
 use std::cmp::Ordering;
 use std::{
+    cell::Cell,
     collections::VecDeque,
     error,
     fmt::{self, Display},
-    sync::mpsc::{Receiver, Sender},
-    time::Duration,
+    io::{self, Read, Write},
+    mem,
+    sync::mpsc::{self, Receiver, Sender},
+    sync::{
+        atomic::{AtomicU64, Ordering as AtomicOrdering},
+        Arc, PoisonError, RwLock,
+    },
+    time::{Duration, Instant, SystemTime},
 };
 
-pub use gilrs_core::PowerInfo;
+#[cfg(any(test, feature = "test-utils"))]
+pub use gilrs_core::ReplayDevice;
+pub use gilrs_core::{
+    AxisInfo, DeviceStatus, EnumeratedDevice, EnumerationReport, GrabError, GuidComponents,
+    PowerInfo, TimestampSource, VirtualReason,
+};
 
 #[cfg(feature = "serde-serialize")]
 use serde::{Deserialize, Serialize};
@@ -94,6 +109,11 @@ const DEFAULT_DEADZONE: f32 = 0.1;
 /// processed it. On the other hand, they are good when you want to implement key repeat or software
 /// debouncing.
 ///
+/// Checking `data.counter() == gilrs.counter()` is reliable even when counter wraps around or is
+/// reset with [`reset_counter()`](#method.reset_counter). In both cases counters saved in state
+/// are invalidated, so elements that didn't change since then will never look as changed in
+/// current iteration.
+///
 /// ```
 /// use gilrs::{Gilrs, Button};
 ///
@@ -136,18 +156,43 @@ pub struct Gilrs {
     tx: Sender<Message>,
     rx: Receiver<FfMessage>,
     counter: u64,
+    connection_generation: u64,
     mappings: MappingDb,
     default_filters: bool,
-    events: VecDeque<Event>,
+    events: EventQueue,
     axis_to_btn_pressed: f32,
     axis_to_btn_released: f32,
     pub(crate) update_state: bool,
     pub(crate) gamepads_data: Vec<GamepadData>,
+    // Returned by `gamepad()` for ids that were never observed.
+    not_observed: GamepadData,
+    shared_state: Option<Arc<RwLock<Vec<GamepadState>>>>,
+    // State of gamepads when events were suppressed.
+    suppressed: Option<Vec<GamepadState>>,
+    // Events describing changes of state while events were suppressed. They already match cached
+    // state, so they are returned before other events and bypass filters.
+    resync: VecDeque<Event>,
+    axis_rate_limit: Option<AxisRateLimit>,
+    axis_smoothing: Option<Smoothing>,
+    suspend_guard: Option<SuspendGuard>,
+    metrics: Option<Metrics>,
+    // Indexed by gamepad id.
+    latency: Option<Vec<LatencyHistogram>>,
+    // When backend event that produced event returned by `next_event_priv()` was read.
+    arrival: Option<Instant>,
+    last_event_latency: Option<Duration>,
+    // Updated by force feedback thread.
+    ff_writes_suppressed: Arc<AtomicU64>,
+    dpad_source: DPadSource,
+    pub(crate) dpad_axis_threshold: f32,
+    summary_power_cache_ttl: Duration,
+    idle_disconnect_timeout: Option<Duration>,
 }
 
 impl Gilrs {
     /// Creates new `Gilrs` with default settings. See [`GilrsBuilder`](struct.GilrsBuilder.html)
     /// for more details.
+    #[allow(clippy::result_large_err)]
     pub fn new() -> Result<Self, Error> {
         GilrsBuilder::new().build()
     }
@@ -170,6 +215,34 @@ impl Gilrs {
         self.next_event_inner(true, timeout)
     }
 
+    /// Processes pending events until there are no more of them or `deadline` passes and returns
+    /// number of processed events. Useful for engines that poll cached gamepad state once per
+    /// frame: flood of events can't take more than the given time budget (plus time of processing
+    /// one event).
+    ///
+    /// Events are processed the same way as by [`next_event()`](#method.next_event), including
+    /// filters and updating cached state, but they are not returned. Events left when deadline
+    /// passes stay queued and will be processed by next call.
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    ///
+    /// # let mut gilrs = gilrs::Gilrs::new().unwrap();
+    /// // Spend at most 1 ms of the frame on input.
+    /// gilrs.pump_until(Instant::now() + Duration::from_millis(1));
+    /// for (_id, gamepad) in gilrs.gamepads() {
+    ///     let _x = gamepad.value(gilrs::Axis::LeftStickX);
+    /// }
+    /// ```
+    pub fn pump_until(&mut self, deadline: Instant) -> usize {
+        let mut processed = 0;
+        while Instant::now() < deadline && self.next_event().is_some() {
+            processed += 1;
+        }
+
+        processed
+    }
+
     fn next_event_inner(
         &mut self,
         is_blocking: bool,
@@ -177,32 +250,120 @@ impl Gilrs {
     ) -> Option<Event> {
         use crate::ev::filter::{axis_dpad_to_button, deadzone, Filter, Jitter};
 
-        let ev = if self.default_filters {
-            let jitter_filter = Jitter::new();
-            loop {
-                let ev = self
-                    .next_event_priv(is_blocking, blocking_timeout)
-                    .filter_ev(&axis_dpad_to_button, self)
-                    .filter_ev(&jitter_filter, self)
-                    .filter_ev(&deadzone, self);
-
-                // Skip all dropped events, there is no reason to return them
-                match ev {
-                    Some(ev) if ev.is_dropped() => (),
-                    _ => break ev,
+        loop {
+            let ev = if let Some(ev) = self.resync.pop_front() {
+                Some(ev)
+            } else if self.default_filters
+                || self.axis_rate_limit.is_some()
+                || self.axis_smoothing.is_some()
+            {
+                let jitter_filter = Jitter::new();
+                loop {
+                    let rate_limit = self.axis_rate_limit.take();
+                    let smoothing = self.axis_smoothing.take();
+                    // Don't block longer than needed to emit coalesced or smoothed axis values.
+                    let mut timeout = blocking_timeout;
+                    if let Some(ref limit) = rate_limit {
+                        if is_blocking && limit.has_pending() {
+                            let interval = Duration::from_secs(1) / limit.max_rate.max(1);
+                            timeout = Some(timeout.map_or(interval, |t| t.min(interval)));
+                        }
+                    }
+                    if let Some(ref smoothing) = smoothing {
+                        if is_blocking && smoothing.has_pending() {
+                            let interval = Smoothing::UPDATE_INTERVAL;
+                            timeout = Some(timeout.map_or(interval, |t| t.min(interval)));
+                        }
+                    }
+
+                    let mut ev = self.next_event_priv(is_blocking, timeout);
+                    self.events.set_arrival(None);
+                    let is_new = ev.is_some();
+                    if self.default_filters {
+                        ev = ev
+                            .filter_ev(&axis_dpad_to_button, self)
+                            .filter_ev(&jitter_filter, self)
+                            .filter_ev(&deadzone, self);
+                    }
+                    if let Some(ref smoothing) = smoothing {
+                        ev = ev.filter_ev(smoothing, self);
+                    }
+                    if let Some(ref limit) = rate_limit {
+                        ev = ev.filter_ev(limit, self);
+                    }
+                    let keep_waiting = ev.is_none()
+                        && is_blocking
+                        && blocking_timeout.is_none()
+                        && (rate_limit.as_ref().is_some_and(|limit| limit.has_pending())
+                            || smoothing.as_ref().is_some_and(|s| s.has_pending()));
+                    self.axis_rate_limit = rate_limit;
+                    self.axis_smoothing = smoothing;
+                    self.record_metrics(is_new, ev.as_ref());
+
+                    // Skip all dropped events, there is no reason to return them
+                    match ev {
+                        Some(ev) if ev.is_dropped() => (),
+                        None if keep_waiting => (),
+                        _ => break ev,
+                    }
+                }
+            } else {
+                let ev = self.next_event_priv(is_blocking, blocking_timeout);
+                self.events.set_arrival(None);
+                self.record_metrics(ev.is_some(), ev.as_ref());
+                ev
+            };
+
+            if self.update_state {
+                if let Some(ref ev) = ev {
+                    self.update(ev);
                 }
             }
-        } else {
-            self.next_event_priv(is_blocking, blocking_timeout)
-        };
 
-        if self.update_state {
-            if let Some(ref ev) = ev {
-                self.update(ev);
+            // Suppressed events only update state.
+            match ev {
+                Some(Event {
+                    event:
+                        EventType::ButtonPressed(..)
+                        | EventType::ButtonRepeated(..)
+                        | EventType::ButtonReleased(..)
+                        | EventType::ButtonChanged(..)
+                        | EventType::AxisChanged(..)
+                        | EventType::Dropped(_),
+                    ..
+                }) if self.suppressed.is_some() => (),
+                _ => {
+                    self.record_latency(ev.as_ref());
+                    return ev;
+                }
             }
         }
+    }
+
+    fn record_latency(&mut self, ev: Option<&Event>) {
+        if let (Some(arrival), Some(_)) = (self.arrival.take(), ev) {
+            self.last_event_latency = Some(arrival.elapsed());
+        }
+
+        let (Some(latency), Some(ev)) = (self.latency.as_mut(), ev) else {
+            return;
+        };
+
+        if ev.source != EventSource::Hardware || ev.is_dropped() {
+            return;
+        }
 
-        ev
+        if latency.len() <= ev.id.0 {
+            latency.resize_with(ev.id.0 + 1, LatencyHistogram::new);
+        }
+        latency[ev.id.0].record(ev.time, SystemTime::now());
+    }
+
+    fn record_metrics(&mut self, is_new: bool, ev: Option<&Event>) {
+        if let Some(ref mut metrics) = self.metrics {
+            metrics.events += u64::from(is_new);
+            metrics.dropped_events += u64::from(ev.is_some_and(Event::is_dropped));
+        }
     }
 
     /// Returns next pending event.
@@ -211,16 +372,48 @@ impl Gilrs {
         is_blocking: bool,
         blocking_timeout: Option<Duration>,
     ) -> Option<Event> {
+        self.arrival = None;
+
+        if let Some(ref mut guard) = self.suspend_guard {
+            let now = Instant::now();
+            guard.check(now, utils::time_now());
+            if let Some(id) = guard.finish(now) {
+                return Some(
+                    Event::new(id, EventType::Rescanned).with_source(EventSource::Synthetic),
+                );
+            }
+        }
+
+        if let Some(ev) = self.idle_disconnect(Instant::now()) {
+            return Some(ev);
+        }
+
         if let Ok(msg) = self.rx.try_recv() {
             match msg {
                 FfMessage::EffectCompleted { event } => return Some(event),
             }
         }
-        if let Some(ev) = self.events.pop_front() {
+        if let Some((ev, arrival)) = self.events.pop_front() {
+            self.arrival = arrival;
             Some(ev)
         } else {
             let event = if is_blocking {
-                self.inner.next_event_blocking(blocking_timeout)
+                // Wake up in time to emit `Rescanned` or idle `Disconnected` event.
+                let now = Instant::now();
+                let remaining = self
+                    .suspend_guard
+                    .as_ref()
+                    .and_then(|guard| guard.remaining(now))
+                    .into_iter()
+                    .chain(self.idle_remaining(now))
+                    .min();
+                let timeout = match remaining {
+                    Some(remaining) => {
+                        Some(blocking_timeout.map_or(remaining, |t| t.min(remaining)))
+                    }
+                    None => blocking_timeout,
+                };
+                self.inner.next_event_blocking(timeout)
             } else {
                 self.inner.next_event()
             };
@@ -234,14 +427,53 @@ impl Gilrs {
                 }) => {
                     trace!("Original event: {:?}", event);
                     let id = GamepadId(id);
+                    if self.latency.is_some() {
+                        self.arrival = Some(Instant::now());
+                        self.events.set_arrival(self.arrival);
+                    }
+
+                    let is_hotplug = matches!(
+                        event_type,
+                        RawEventType::Connected | RawEventType::Disconnected
+                    );
+                    if let (true, Some(metrics)) = (is_hotplug, self.metrics.as_mut()) {
+                        metrics.hotplug_events += 1;
+                    }
+                    if let (false, Some(data)) = (is_hotplug, self.gamepads_data.get_mut(id.0)) {
+                        data.last_input = Instant::now();
+                    }
+                    let drop_reason = if is_hotplug {
+                        None
+                    } else if self
+                        .gamepads_data
+                        .get(id.0)
+                        .is_some_and(|data| !data.enabled)
+                    {
+                        Some(DropReason::Disabled)
+                    } else {
+                        let dual_dpad = self.gamepads_data.get(id.0).is_some_and(|d| d.dual_dpad);
+                        is_ignored_dpad_event(dual_dpad, self.dpad_source, &event_type)
+                            .then_some(DropReason::Duplicate)
+                    };
+                    if let Some(reason) = drop_reason {
+                        return Some(Event {
+                            id,
+                            source: EventSource::Hardware,
+                            event: EventType::Dropped(Some(reason)),
+                            time,
+                        });
+                    }
 
+                    let queued = self.events.len();
                     let event = match event_type {
                         RawEventType::ButtonPressed(nec) => {
                             let nec = Code(nec);
+                            self.add_late_button(id, nec);
                             match self.gamepad(id).axis_or_btn_name(nec) {
                                 Some(AxisOrBtn::Btn(b)) => {
                                     self.events.push_back(Event {
                                         id,
+                                        source: EventSource::Hardware,
                                         time,
                                         event: EventType::ButtonChanged(b, 1.0, nec),
                                     });
@@ -252,6 +484,7 @@ impl Gilrs {
                                 None => {
                                     self.events.push_back(Event {
                                         id,
+                                        source: EventSource::Hardware,
                                         time,
                                         event: EventType::ButtonChanged(Button::Unknown, 1.0, nec),
                                     });
@@ -262,10 +495,12 @@ impl Gilrs {
                         }
                         RawEventType::ButtonReleased(nec) => {
                             let nec = Code(nec);
+                            self.add_late_button(id, nec);
                             match self.gamepad(id).axis_or_btn_name(nec) {
                                 Some(AxisOrBtn::Btn(b)) => {
                                     self.events.push_back(Event {
                                         id,
+                                        source: EventSource::Hardware,
                                         time,
                                         event: EventType::ButtonChanged(b, 0.0, nec),
                                     });
@@ -276,6 +511,7 @@ impl Gilrs {
                                 None => {
                                     self.events.push_back(Event {
                                         id,
+                                        source: EventSource::Hardware,
                                         time,
                                         event: EventType::ButtonChanged(Button::Unknown, 0.0, nec),
                                     });
@@ -288,16 +524,19 @@ impl Gilrs {
                             // Let's trust at least our backend code
                             let axis_info = *self.gamepad(id).inner.axis_info(nec).unwrap();
                             let nec = Code(nec);
+                            self.gamepads_data[id.0].record_axis(nec, &axis_info, val);
+                            let data = &self.gamepads_data[id.0];
 
                             match self.gamepad(id).axis_or_btn_name(nec) {
                                 Some(AxisOrBtn::Btn(b)) => {
-                                    let val = btn_value(&axis_info, val);
+                                    let val = data.btn_value(&axis_info, nec, val);
 
                                     if val >= self.axis_to_btn_pressed
                                         && !self.gamepad(id).state().is_pressed(nec)
                                     {
                                         self.events.push_back(Event {
                                             id,
+                                            source: EventSource::Hardware,
                                             time,
                                             event: EventType::ButtonChanged(b, val, nec),
                                         });
@@ -308,6 +547,7 @@ impl Gilrs {
                                     {
                                         self.events.push_back(Event {
                                             id,
+                                            source: EventSource::Hardware,
                                             time,
                                             event: EventType::ButtonChanged(b, val, nec),
                                         });
@@ -318,11 +558,14 @@ impl Gilrs {
                                     }
                                 }
                                 Some(AxisOrBtn::Axis(a)) => {
-                                    EventType::AxisChanged(a, axis_value(&axis_info, val, a), nec)
+                                    let val = data.axis_value(&axis_info, nec, val, a);
+                                    // Before filters, deadzone limits magnitude to 1.0.
+                                    self.gamepads_data[id.0].gates.record(a, val, time);
+                                    EventType::AxisChanged(a, val, nec)
                                 }
                                 None => EventType::AxisChanged(
                                     Axis::Unknown,
-                                    axis_value(&axis_info, val, Axis::Unknown),
+                                    data.axis_value(&axis_info, nec, val, Axis::Unknown),
                                     nec,
                                 ),
                             }
@@ -336,14 +579,28 @@ impl Gilrs {
                                         self.inner.gamepad(id.0).unwrap(),
                                         &self.mappings,
                                     ));
+                                    self.preallocate_state(id);
                                 }
                                 Ordering::Less => {
+                                    let enabled = self.gamepads_data[id.0].enabled;
+                                    let calibration =
+                                        mem::take(&mut self.gamepads_data[id.0].calibration);
+                                    let gates = mem::take(&mut self.gamepads_data[id.0].gates);
                                     self.gamepads_data[id.0] = GamepadData::new(
                                         id,
                                         self.tx.clone(),
                                         self.inner.gamepad(id.0).unwrap(),
                                         &self.mappings,
                                     );
+                                    self.gamepads_data[id.0].calibration = calibration;
+                                    self.gamepads_data[id.0].gates = gates;
+                                    self.preallocate_state(id);
+
+                                    // Reconnecting doesn't enable gamepad.
+                                    if !enabled {
+                                        self.gamepads_data[id.0].enabled = false;
+                                        let _ = self.tx.send(Message::Close { id: id.0 });
+                                    }
                                 }
                                 Ordering::Greater => {
                                     error!(
@@ -355,57 +612,259 @@ impl Gilrs {
                                 }
                             }
 
+                            self.connection_generation = self.connection_generation.wrapping_add(1);
+
                             EventType::Connected
                         }
                         RawEventType::Disconnected => {
                             let _ = self.tx.send(Message::Close { id: id.0 });
+                            self.connection_generation = self.connection_generation.wrapping_add(1);
 
-                            EventType::Disconnected
+                            match self.gamepads_data.get_mut(id.0) {
+                                // `Disconnected` was already emitted when gamepad become idle.
+                                Some(data) if data.idle => {
+                                    data.idle = false;
+                                    EventType::Dropped(Some(DropReason::Duplicate))
+                                }
+                                _ => EventType::Disconnected,
+                            }
                         }
                         _ => {
                             unimplemented!()
                         }
                     };
 
-                    Some(Event { id, event, time })
+                    // Element mapped to more than one button emits the same events for each of
+                    // them.
+                    let aliases = match event {
+                        EventType::ButtonPressed(_, nec)
+                        | EventType::ButtonReleased(_, nec)
+                        | EventType::ButtonChanged(_, _, nec) => {
+                            self.gamepads_data[id.0].mapping.aliases(&nec.0)
+                        }
+                        _ => &[],
+                    };
+                    let end = self.events.len();
+                    for &alias in aliases {
+                        self.events.push_back(Event {
+                            id,
+                            event: with_button(event, alias),
+                            time,
+                            source: EventSource::Hardware,
+                        });
+                        for i in queued..end {
+                            if let Some(ev) = self.events.get(i) {
+                                self.events.push_back(Event {
+                                    event: with_button(ev.event, alias),
+                                    ..ev
+                                });
+                            }
+                        }
+                    }
+
+                    if !is_hotplug && self.gamepads_data.get(id.0).is_some_and(|d| d.idle) {
+                        self.events.insert(
+                            queued,
+                            Event {
+                                id,
+                                event,
+                                time,
+                                source: EventSource::Hardware,
+                            },
+                        );
+
+                        return Some(self.wake_idle(id));
+                    }
+
+                    if is_hotplug {
+                        if let Some(guard) = self.suspend_guard.as_mut().filter(|g| g.is_batching())
+                        {
+                            guard.absorb(id);
+
+                            return Some(Event {
+                                id,
+                                source: EventSource::Hardware,
+                                event: EventType::Dropped(Some(DropReason::Duplicate)),
+                                time,
+                            });
+                        }
+                    }
+
+                    // Backend events are ordered by the same rules as queued events.
+                    self.events.insert(
+                        queued,
+                        Event {
+                            id,
+                            event,
+                            time,
+                            source: EventSource::Hardware,
+                        },
+                    );
+                    self.events.pop_front().map(|(ev, _)| ev)
                 }
-                None => None,
+                None => self
+                    .suspend_guard
+                    .as_mut()
+                    .and_then(|guard| guard.finish(Instant::now()))
+                    .map(|id| {
+                        Event::new(id, EventType::Rescanned).with_source(EventSource::Synthetic)
+                    })
+                    // Blocking call could have waited until gamepad became idle.
+                    .or_else(|| self.idle_disconnect(Instant::now())),
+            }
+        }
+    }
+
+    /// Maps button that backend added after gamepad was connected, see
+    /// [`Mapping::add_late_button()`].
+    fn add_late_button(&mut self, id: GamepadId, nec: Code) {
+        let data = &mut self.gamepads_data[id.0];
+        // Checked only on first event from the element.
+        if data.state.button_data(nec).is_none() {
+            data.mapping.add_late_button(nec.0);
+            if DPAD_BUTTONS.contains(&nec.0) {
+                let gamepad = self.inner.gamepad_or_placeholder(id.0);
+                data.dual_dpad = has_dual_dpad(gamepad.buttons(), gamepad.axes());
+            }
+        }
+    }
+
+    /// Marks gamepad that didn't send any input for `idle_disconnect_timeout` as idle and returns
+    /// `Disconnected` event for it.
+    fn idle_disconnect(&mut self, now: Instant) -> Option<Event> {
+        let timeout = self.idle_disconnect_timeout?;
+        let inner = &self.inner;
+        let data = self.gamepads_data.iter_mut().find(|data| {
+            data.can_become_idle(inner) && now.saturating_duration_since(data.last_input) >= timeout
+        })?;
+
+        data.idle = true;
+        let _ = self.tx.send(Message::Close { id: data.id.0 });
+        self.connection_generation = self.connection_generation.wrapping_add(1);
+
+        Some(Event::new(data.id, EventType::Disconnected).with_source(EventSource::Synthetic))
+    }
+
+    /// Returns time after which next gamepad becomes idle.
+    fn idle_remaining(&self, now: Instant) -> Option<Duration> {
+        let timeout = self.idle_disconnect_timeout?;
+
+        self.gamepads_data
+            .iter()
+            .filter(|data| data.can_become_idle(&self.inner))
+            .map(|data| timeout.saturating_sub(now.saturating_duration_since(data.last_input)))
+            .min()
+    }
+
+    /// Returns `Connected` event for idle gamepad that sent input.
+    fn wake_idle(&mut self, id: GamepadId) -> Event {
+        let data = &mut self.gamepads_data[id.0];
+        data.idle = false;
+        data.last_input = Instant::now();
+
+        let gamepad = self.inner.gamepad_or_placeholder(id.0);
+        if data.enabled && gamepad.is_ff_supported() && gamepad.is_connected() {
+            if let Some(device) = gamepad.ff_device() {
+                let _ = self.tx.send(Message::Open { id: id.0, device });
             }
         }
+        self.connection_generation = self.connection_generation.wrapping_add(1);
+
+        Event::new(id, EventType::Connected).with_source(EventSource::Synthetic)
     }
 
-    /// Updates internal state according to `event`.
+    /// Updates internal state according to `event`. Returns `true` if cached state actually
+    /// changed, for example `ButtonChanged` event with the same value as cached one returns `false`.
+    /// `Connected`, `Disconnected` and `Rescanned` events always count as a change.
     ///
     /// Please note, that it's not necessary to call this function unless you modify events by using
     /// additional filters and disabled automatic updates when creating `Gilrs`.
-    pub fn update(&mut self, event: &Event) {
-        use crate::EventType::*;
-
+    pub fn update(&mut self, event: &Event) -> bool {
         let counter = self.counter;
 
         let data = match self.gamepads_data.get_mut(event.id.0) {
             Some(d) => d,
-            None => return,
+            None => return false,
         };
 
-        match event.event {
-            ButtonPressed(_, nec) => {
-                data.state.set_btn_pressed(nec, true, counter, event.time);
-            }
-            ButtonReleased(_, nec) => {
-                data.state.set_btn_pressed(nec, false, counter, event.time);
-            }
-            ButtonRepeated(_, nec) => {
-                data.state.set_btn_repeating(nec, counter, event.time);
-            }
-            ButtonChanged(_, value, nec) => {
-                data.state.set_btn_value(nec, value, counter, event.time);
+        let changed = data.state.update(event, counter);
+
+        if let Some(ref shared) = self.shared_state {
+            let mut states = shared.write().unwrap_or_else(PoisonError::into_inner);
+            if states.len() <= event.id.0 {
+                states.resize_with(event.id.0 + 1, GamepadState::new);
             }
-            AxisChanged(_, value, nec) => {
-                data.state
-                    .update_axis(nec, AxisData::new(value, counter, event.time));
+            match event.event {
+                // State may be replaced when gamepad connects, so copy it whole. Hot plug events
+                // are rare, other events are applied to shared state the same way.
+                EventType::Connected | EventType::Disconnected | EventType::Rescanned => {
+                    states[event.id.0] = data.state.clone();
+                }
+                _ => {
+                    states[event.id.0].update(event, counter);
+                }
             }
-            Disconnected | Connected | Dropped | ForceFeedbackEffectCompleted => (),
+        }
+
+        changed
+    }
+
+    /// Returns devices found when `Gilrs` was created and results of opening them. If no gamepads
+    /// are connected, use it to show user actionable message, for example when
+    /// [`EnumerationReport::is_permission_denied()`](struct.EnumerationReport.html#method.is_permission_denied)
+    /// returns `true`, application likely doesn't have access to input devices (it runs in
+    /// sandbox or udev rule is missing).
+    ///
+    /// Only Linux backend reports devices, other backends return empty report.
+    pub fn enumeration_report(&self) -> &EnumerationReport {
+        self.inner.enumeration_report()
+    }
+
+    /// Returns which time is used as [`Event::time`](struct.Event.html#structfield.time), see
+    /// [`GilrsBuilder::timestamp_source()`](struct.GilrsBuilder.html#method.timestamp_source).
+    pub fn timestamp_source(&self) -> TimestampSource {
+        self.inner.timestamp_source()
+    }
+
+    /// Returns name of the backend used to communicate with gamepads, for example `"linux"` or
+    /// `"wgi"`.
+    pub fn backend_name(&self) -> &'static str {
+        gilrs_core::BACKEND_NAME
+    }
+
+    /// Returns human-readable report with backend and every connected gamepad: its names, UUID,
+    /// mapping source, force feedback support, power information and all native buttons and axes
+    /// with names they are mapped to. It is meant to be attached to bug reports.
+    ///
+    /// This is [`diagnostics::header()`](crate::diagnostics::header) followed by
+    /// [`diagnostics::gamepad_report()`](crate::diagnostics::gamepad_report) of each gamepad.
+    pub fn diagnostics_report(&self) -> String {
+        let mut report = diagnostics::header(self);
+        for (_, gamepad) in self.gamepads() {
+            report.push_str(&diagnostics::gamepad_report(&gamepad));
+        }
+
+        report
+    }
+
+    /// Returns `Send + Sync` handle that can be used to read cached gamepad state from other
+    /// threads.
+    ///
+    /// Handle's state is updated together with state of `Gilrs`, that is, every time
+    /// [`update()`](#method.update) is called (by default, this happens in `next_event()`).
+    pub fn state_handle(&mut self) -> GilrsStateHandle {
+        let gamepads_data = &self.gamepads_data;
+        let shared = self.shared_state.get_or_insert_with(|| {
+            Arc::new(RwLock::new(
+                gamepads_data
+                    .iter()
+                    .map(|data| data.state.clone())
+                    .collect(),
+            ))
+        });
+
+        GilrsStateHandle {
+            inner: shared.clone(),
         }
     }
 
@@ -413,23 +872,41 @@ impl Gilrs {
     /// determine when last event happened. You probably want to use this function in your update
     /// loop after processing events.
     pub fn inc(&mut self) {
-        // Counter is 62bit. See `ButtonData`.
-        if self.counter == 0x3FFF_FFFF_FFFF_FFFF {
-            self.counter = 0;
+        if self.counter == MAX_COUNTER {
+            self.reset_counter();
         } else {
             self.counter += 1;
         }
     }
 
+    /// Returns value that changes every time a gamepad is connected or disconnected. Comparing it
+    /// with previously returned value is a cheap way to check if list of connected gamepads
+    /// changed.
+    pub fn connection_generation(&self) -> u64 {
+        self.connection_generation
+    }
+
     /// Returns counter. Counter data is stored with state and can be used to determine when last
     /// event happened.
     pub fn counter(&self) -> u64 {
         self.counter
     }
 
-    /// Sets counter to 0.
+    /// Sets counter to 0. Counters saved in state are invalidated, so they will not be equal to
+    /// any future value of counter.
     pub fn reset_counter(&mut self) {
         self.counter = 0;
+
+        for data in &mut self.gamepads_data {
+            data.state.invalidate_counters();
+        }
+
+        if let Some(ref shared) = self.shared_state {
+            let mut states = shared.write().unwrap_or_else(PoisonError::into_inner);
+            for (state, data) in states.iter_mut().zip(&self.gamepads_data) {
+                *state = data.state.clone();
+            }
+        }
     }
 
     fn finish_gamepads_creation(&mut self) {
@@ -441,12 +918,28 @@ impl Gilrs {
                 tx.clone(),
                 gamepad,
                 &self.mappings,
-            ))
+            ));
+            self.preallocate_state(GamepadId(id));
+        }
+    }
+
+    /// Reserves state of every element of gamepad `id` if
+    /// [`GilrsBuilder::preallocate_events()`] was used.
+    fn preallocate_state(&mut self, id: GamepadId) {
+        if !self.events.is_preallocated() {
+            return;
+        }
+
+        let gamepad = self.inner.gamepad_or_placeholder(id.0);
+        let elements = gamepad.buttons().len() + gamepad.axes().len();
+        if let Some(data) = self.gamepads_data.get_mut(id.0) {
+            data.state.reserve(elements);
         }
     }
 
     /// Returns handle to gamepad with given ID. Unlike `connected_gamepad()`, this function will
-    /// also return handle to gamepad that is currently disconnected.
+    /// also return handle to gamepad that is currently disconnected. If `id` was never observed,
+    /// returns disconnected gamepad without any buttons or axes.
     ///
     /// ```
     /// # let mut gilrs = gilrs::Gilrs::new().unwrap();
@@ -467,10 +960,12 @@ impl Gilrs {
     ///     # break;
     /// }
     /// ```
-    pub fn gamepad(&self, id: GamepadId) -> Gamepad {
+    pub fn gamepad(&self, id: GamepadId) -> Gamepad<'_> {
         Gamepad {
-            inner: self.inner.gamepad(id.0).unwrap(),
-            data: &self.gamepads_data[id.0],
+            id,
+            inner: self.inner.gamepad_or_placeholder(id.0),
+            data: self.gamepads_data.get(id.0).unwrap_or(&self.not_observed),
+            ff_tx: &self.tx,
         }
     }
 
@@ -479,10 +974,15 @@ impl Gilrs {
         // Make sure that it will not panic even with invalid GamepadId, so ConnectedGamepadIterator
         // will always work.
         if let Some(data) = self.gamepads_data.get(id.0) {
-            let inner = self.inner.gamepad(id.0)?;
+            let inner = self.inner.gamepad_or_placeholder(id.0);
 
-            if inner.is_connected() {
-                Some(Gamepad { inner, data })
+            if (inner.is_connected() || data.test_gamepad.is_some()) && !data.idle {
+                Some(Gamepad {
+                    id,
+                    inner,
+                    data,
+                    ff_tx: &self.tx,
+                })
             } else {
                 None
             }
@@ -493,6 +993,14 @@ impl Gilrs {
 
     /// Returns iterator over all connected gamepads and their ids.
     ///
+    /// Gamepads are returned in order of increasing id, which is the order in which backend first
+    /// saw them, not anything meaningful to the user. Id of disconnected gamepad is reused when
+    /// backend thinks that it connected again, which doesn't mean it's the same device: on Linux
+    /// it's any device with the same UUID (for example another gamepad of the same model) and
+    /// with XInput any gamepad in the same slot. Until then there is a gap and ids of connected
+    /// gamepads are not contiguous; don't use `0..gamepads().count()` as ids. Use
+    /// [`gamepads_sorted_by()`](#method.gamepads_sorted_by) for other order.
+    ///
     /// ```
     /// # let gilrs = gilrs::Gilrs::new().unwrap();
     /// for (id, gamepad) in gilrs.gamepads() {
@@ -505,11 +1013,192 @@ impl Gilrs {
         ConnectedGamepadsIterator(self, 0)
     }
 
+    /// Returns all connected gamepads sorted with `cmp`. Sort is stable, so gamepads that compare
+    /// equal stay in order of [`gamepads()`](#method.gamepads).
+    ///
+    /// For example, player list can be ordered by physical port, with gamepads for which port is
+    /// unknown at the end:
+    ///
+    /// ```
+    /// # let gilrs = gilrs::Gilrs::new().unwrap();
+    /// let players = gilrs.gamepads_sorted_by(|(_, a), (_, b)| {
+    ///     match (a.connection_port(), b.connection_port()) {
+    ///         (Some(a), Some(b)) => a.cmp(b),
+    ///         (a, b) => b.is_some().cmp(&a.is_some()),
+    ///     }
+    /// });
+    /// for (player, (_, gamepad)) in players.iter().enumerate() {
+    ///     println!("Player {}: {}", player + 1, gamepad.name());
+    /// }
+    /// ```
+    pub fn gamepads_sorted_by<F>(&self, mut cmp: F) -> Vec<(GamepadId, Gamepad<'_>)>
+    where
+        F: FnMut(&(GamepadId, Gamepad<'_>), &(GamepadId, Gamepad<'_>)) -> Ordering,
+    {
+        let mut gamepads: Vec<_> = self.gamepads().collect();
+        gamepads.sort_by(|a, b| cmp(a, b));
+
+        gamepads
+    }
+
+    /// Returns mapping source and name of SDL mapping for every connected gamepad.
+    ///
+    /// Mappings are looked up in the mapping database directly, so result is available before
+    /// `Connected` events are processed and agrees with
+    /// [`Gamepad::mapping_source()`](struct.Gamepad.html#method.mapping_source) and
+    /// [`Gamepad::map_name()`](struct.Gamepad.html#method.map_name) after they are. Mappings set
+    /// with [`set_mapping()`](#method.set_mapping) and
+    /// [`set_axis_inverted()`](#method.set_axis_inverted) are in database, but mappings changed
+    /// with [`swap_codes()`](#method.swap_codes) and similar functions are not taken into account.
+    pub fn mapping_status(&self) -> Vec<(GamepadId, MappingSource, Option<String>)> {
+        let len = self.inner.last_gamepad_hint().max(self.gamepads_data.len());
+
+        (0..len)
+            .filter_map(|idx| {
+                let id = GamepadId(idx);
+                let status = |mapping: &Mapping| {
+                    let name = if mapping.is_default() {
+                        None
+                    } else {
+                        Some(mapping.name().to_owned())
+                    };
+
+                    (id, mapping_source(mapping), name)
+                };
+
+                match self.gamepads_data.get(idx) {
+                    Some(data) if data.test_gamepad.is_some() => Some(status(&data.mapping)),
+                    _ => {
+                        let gamepad = self.inner.gamepad(idx).filter(|gp| gp.is_connected())?;
+                        let uuid = Uuid::from_bytes(gamepad.uuid());
+                        if self.mappings.get_for(uuid, gamepad.name()).is_none() {
+                            return Some((id, MappingSource::Driver, None));
+                        }
+
+                        Some(status(&GamepadData::find_mapping(gamepad, &self.mappings)))
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Returns owned snapshot of metadata of all connected gamepads, for example to display list
+    /// of gamepads in game's menu. Unlike [`gamepads()`](#method.gamepads), returned value doesn't
+    /// borrow `Gilrs`.
+    ///
+    /// This is cheap enough to be called every frame. Power information is cached and refreshed
+    /// at most once per
+    /// [`GilrsBuilder::summary_power_cache_ttl()`](struct.GilrsBuilder.html#method.summary_power_cache_ttl)
+    /// (one second by default), so it may be slightly outdated. Use
+    /// [`Gamepad::power_info()`](struct.Gamepad.html#method.power_info) to get current value.
+    ///
+    /// ```
+    /// # let gilrs = gilrs::Gilrs::new().unwrap();
+    /// for summary in gilrs.gamepad_summaries() {
+    ///     println!("{}: {} ({:?})", summary.id, summary.name, summary.power_info);
+    /// }
+    /// ```
+    pub fn gamepad_summaries(&self) -> Vec<GamepadSummary> {
+        self.gamepads()
+            .map(|(id, gamepad)| GamepadSummary {
+                id,
+                name: gamepad.name().to_owned(),
+                os_name: gamepad.os_name().to_owned(),
+                power_info: gamepad.cached_power_info(self.summary_power_cache_ttl),
+                mapping_source: gamepad.mapping_source(),
+                is_ff_supported: gamepad.is_ff_supported(),
+            })
+            .collect()
+    }
+
+    /// Adds always connected gamepad that is not backed by any device and returns its ID. Gamepad
+    /// uses default mapping, so all buttons and axes can be queried. `Connected` event for it is
+    /// added to the event queue.
+    ///
+    /// Together with [`insert_event()`](#method.insert_event) this can be used to test input
+    /// handling code without real device:
+    ///
+    /// ```
+    /// # #[cfg(feature = "test-utils")] {
+    /// use gilrs::{Axis, Button, Event, EventType, Gilrs};
+    ///
+    /// let mut gilrs = match Gilrs::new() {
+    ///     Ok(gilrs) | Err(gilrs::Error::NotImplemented(gilrs)) => gilrs,
+    ///     Err(e) => panic!("{}", e),
+    /// };
+    /// let id = gilrs.register_test_gamepad("Test gamepad", [0; 16]);
+    /// let south = gilrs.gamepad(id).button_code(Button::South).unwrap();
+    ///
+    /// gilrs.insert_event(Event::new(id, EventType::ButtonPressed(Button::South, south)));
+    /// while let Some(ev) = gilrs.next_event() {
+    ///     // Pass events to game code…
+    /// }
+    ///
+    /// assert!(gilrs.gamepad(id).is_pressed(Button::South));
+    /// # }
+    /// ```
+    ///
+    /// Cached state is only updated if automatic state updates are enabled (the default). If
+    /// backend later reports real gamepad with the same ID, it replaces the test gamepad.
+    ///
+    /// Available with `test-utils` feature.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn register_test_gamepad(&mut self, name: &str, uuid: [u8; 16]) -> GamepadId {
+        let id = GamepadId(self.gamepads_data.len());
+        let mut data = GamepadData::new(
+            id,
+            self.tx.clone(),
+            self.inner.gamepad_or_placeholder(id.0),
+            &self.mappings,
+        );
+        data.mapping = Mapping::default_all();
+        data.test_gamepad = Some((name.to_owned(), uuid));
+
+        self.gamepads_data.push(data);
+        self.connection_generation = self.connection_generation.wrapping_add(1);
+        self.events
+            .push_back(Event::new(id, EventType::Connected).with_source(EventSource::Synthetic));
+
+        id
+    }
+
     /// Adds `ev` at the end of internal event queue. It can later be retrieved with `next_event()`.
+    ///
+    /// Events of gamepad are never returned before its `Connected` or after its `Disconnected`
+    /// event: event queued before `Connected` of the same gamepad is returned after it and event
+    /// queued after `Disconnected`, if there is no `Connected` later, is returned before it. These
+    /// rules also apply to events from backend.
     pub fn insert_event(&mut self, ev: Event) {
         self.events.push_back(ev);
     }
 
+    /// Adds gamepad with name, UUID and elements of `device` and returns its ID. Unlike
+    /// [`register_test_gamepad()`](#method.register_test_gamepad), its events are raw backend
+    /// events inserted with [`insert_raw_event()`](#method.insert_raw_event), so they go through
+    /// mapping, axis normalization and filters the same way as events of real gamepad. This can
+    /// be used to replay events recorded from real device. Gamepad is connected when its
+    /// `Connected` event is processed.
+    ///
+    /// IDs are assigned by backend, so this shouldn't be combined with test gamepads.
+    ///
+    /// Returns `None` if backend doesn't support replaying, currently only Linux backend does.
+    ///
+    /// Available with `test-utils` feature.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn add_replay_gamepad(&mut self, device: &ReplayDevice) -> Option<GamepadId> {
+        self.inner.add_replay_gamepad(device).map(GamepadId)
+    }
+
+    /// Adds raw backend `event` of gamepad added by
+    /// [`add_replay_gamepad()`](#method.add_replay_gamepad). It is processed by `next_event()`
+    /// before events of real gamepads.
+    ///
+    /// Available with `test-utils` feature.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn insert_raw_event(&mut self, event: RawEvent) {
+        self.inner.insert_replay_event(event);
+    }
+
     pub(crate) fn ff_sender(&self) -> &Sender<Message> {
         &self.tx
     }
@@ -521,6 +1210,13 @@ impl Gilrs {
     /// The `name` argument can be a string slice with custom gamepad name or `None`. If `None`,
     /// gamepad name reported by driver will be used.
     ///
+    /// Mapping is also added to mapping database, so it's used again when gamepad reconnects and
+    /// is saved by [`export_mappings()`](#method.export_mappings). Because database is keyed by
+    /// UUID, the mapping also becomes the newest entry for every other gamepad with the same UUID
+    /// and is used when such gamepad connects, unless another user-supplied entry matches its name.
+    /// Gamepads that are already connected keep their mappings. Mapping for gamepad with nil UUID
+    /// (backend can't identify it, for example on Windows) is not added to database.
+    ///
     /// # Errors
     ///
     /// This function return error if `name` contains comma, `mapping` have axis and button entry
@@ -564,17 +1260,17 @@ impl Gilrs {
                 None => gamepad.name(),
             };
 
-            let (mapping, s) = Mapping::from_data(
-                mapping,
-                gamepad.buttons(),
-                gamepad.axes(),
-                name,
-                Uuid::from_bytes(gamepad.uuid()),
-            )?;
+            let uuid = Uuid::from_bytes(gamepad.uuid());
+            let (mapping, s) =
+                Mapping::from_data(mapping, gamepad.buttons(), gamepad.axes(), name, uuid)?;
 
             // We checked if gamepad is connected, so it should never panic
             let data = &mut self.gamepads_data[gamepad_id];
             data.mapping = mapping;
+            // Every unidentified gamepad has nil UUID, so mapping would be used for all of them.
+            if !uuid.is_nil() {
+                self.mappings.insert(&s);
+            }
 
             Ok(s)
         } else {
@@ -587,73 +1283,532 @@ impl Gilrs {
     ///
     /// # Errors
     ///
-    /// Returns `MappingError::NotSdl2Compatible` if `mapping` have an entry for `Button::{C, Z}`
-    /// or `Axis::{LeftZ, RightZ}`.
+    /// Returns `MappingError::NotSdl2Compatible` if `mapping` have an entry for button or axis
+    /// that is not SDL2 compatible (see
+    /// [`Button::is_sdl2_compatible()`](enum.Button.html#method.is_sdl2_compatible)), like
+    /// `Button::{C, Z}` or `Axis::{LeftZ, RightZ}`, or if it maps one element to more than one
+    /// button (see [`Mapping::is_one_to_one()`](struct.Mapping.html#method.is_one_to_one)).
     pub fn set_mapping_strict<'b, O: Into<Option<&'b str>>>(
         &mut self,
         gamepad_id: usize,
         mapping: &MappingData,
         name: O,
     ) -> Result<String, MappingError> {
-        if mapping.button(Button::C).is_some()
-            || mapping.button(Button::Z).is_some()
-            || mapping.axis(Axis::LeftZ).is_some()
-            || mapping.axis(Axis::RightZ).is_some()
-        {
-            Err(MappingError::NotSdl2Compatible)
-        } else {
-            self.set_mapping(gamepad_id, mapping, name)
-        }
+        mapping.check_sdl2_compatible()?;
+        self.set_mapping(gamepad_id, mapping, name)
     }
 
-    pub(crate) fn next_ff_id(&mut self) -> usize {
-        // TODO: reuse free ids
-        let id = self.next_id;
-        self.next_id = match self.next_id.checked_add(1) {
-            Some(x) => x,
-            None => panic!("Failed to assign ID to new effect"),
-        };
-        id
+    /// Writes all mappings from database to `writer`, one SDL mapping per line, sorted by GUID.
+    /// This includes mappings from included database (if `include_bundled` is true),
+    /// `SDL_GAMECONTROLLERCONFIG`, [`GilrsBuilder::add_mappings()`], [`import_mappings()`] and
+    /// [`set_mapping()`]. Output is stable, so it can be saved and later loaded with
+    /// [`import_mappings()`] to keep user's custom mappings.
+    ///
+    /// Mappings changed with [`swap_codes()`] and similar functions are not part of database,
+    /// unless [`set_axis_inverted()`] was later called for the same gamepad.
+    ///
+    /// ```
+    /// # let gilrs = gilrs::Gilrs::new().unwrap();
+    /// let mut file = Vec::new();
+    /// gilrs.export_mappings(&mut file, false).unwrap();
+    /// ```
+    ///
+    /// [`GilrsBuilder::add_mappings()`]: struct.GilrsBuilder.html#method.add_mappings
+    /// [`import_mappings()`]: #method.import_mappings
+    /// [`set_axis_inverted()`]: #method.set_axis_inverted
+    /// [`set_mapping()`]: #method.set_mapping
+    /// [`swap_codes()`]: #method.swap_codes
+    pub fn export_mappings(
+        &self,
+        writer: &mut impl Write,
+        include_bundled: bool,
+    ) -> io::Result<()> {
+        self.mappings.export(writer, include_bundled)
     }
-}
 
-/// Allow to create `Gilrs ` with customized behaviour.
-pub struct GilrsBuilder {
-    mappings: MappingDb,
-    default_filters: bool,
-    axis_to_btn_pressed: f32,
-    axis_to_btn_released: f32,
-    update_state: bool,
-    env_mappings: bool,
-    included_mappings: bool,
-}
+    /// Reads SDL mappings from `reader` and adds them to database, like
+    /// [`GilrsBuilder::add_mappings()`](struct.GilrsBuilder.html#method.add_mappings). Mapping
+    /// replaces existing one with the same GUID and name. Invalid lines are ignored.
+    ///
+    /// New mappings are used by gamepads connected later; already connected gamepads keep their
+    /// mapping until they reconnect.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if reading fails or data is not valid UTF-8. Nothing is imported then.
+    pub fn import_mappings(&mut self, reader: &mut impl Read) -> io::Result<()> {
+        let mut mappings = String::new();
+        reader.read_to_string(&mut mappings)?;
+        self.mappings.insert(&mappings);
 
-impl GilrsBuilder {
-    /// Create builder with default settings. Use `build()` to create `Gilrs`.
-    pub fn new() -> Self {
-        GilrsBuilder {
-            mappings: MappingDb::new(),
-            default_filters: true,
-            axis_to_btn_pressed: 0.75,
-            axis_to_btn_released: 0.65,
-            update_state: true,
-            env_mappings: true,
-            included_mappings: true,
-        }
+        Ok(())
     }
 
-    /// If `true`, use [`axis_dpad_to_button`](ev/filter/fn.axis_dpad_to_button.html),
-    /// [`Jitter`](ev/filter/struct.Jitter.html) and [`deadzone`](ev/filter/fn.deadzone.html)
-    /// filters with default parameters. Defaults to `true`.
-    pub fn with_default_filters(mut self, default_filters: bool) -> Self {
-        self.default_filters = default_filters;
-
-        self
+    /// Exchanges buttons or axes that elements `a` and `b` of gamepad `id` are mapped to, for
+    /// example to fix two swapped face buttons without creating whole [`Mapping`](crate::Mapping).
+    ///
+    /// Changed mapping is used for all following events. Like after
+    /// [`set_mapping()`](#method.set_mapping), `Gamepad::mapping_source()` returns
+    /// `MappingSource::SdlMappings` and, if gamepad used default mapping, its name becomes mapping
+    /// name. Changes are lost when gamepad reconnects.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MappingError::NotConnected` if gamepad is not connected.
+    pub fn swap_codes(&mut self, id: GamepadId, a: Code, b: Code) -> Result<(), MappingError> {
+        self.tweak_mapping(id, |mapping| mapping.swap(a.0, b.0))
     }
 
-    /// Adds SDL mappings.
-    pub fn add_mappings(mut self, mappings: &str) -> Self {
-        self.mappings.insert(mappings);
+    /// Maps element `from` of gamepad `id` to `to`, replacing element it was mapped to. Other
+    /// elements already mapped to `to` stay mapped, use [`swap_codes()`](#method.swap_codes) to
+    /// exchange elements instead.
+    ///
+    /// Changed mapping is used the same way as after [`swap_codes()`](#method.swap_codes).
+    ///
+    /// # Errors
+    ///
+    /// Returns `MappingError::NotConnected` if gamepad is not connected and
+    /// `MappingError::UnknownElement` if `to` is `Button::Unknown` or `Axis::Unknown`.
+    pub fn remap_code(
+        &mut self,
+        id: GamepadId,
+        from: Code,
+        to: AxisOrBtn,
+    ) -> Result<(), MappingError> {
+        if matches!(
+            to,
+            AxisOrBtn::Btn(Button::Unknown) | AxisOrBtn::Axis(Axis::Unknown)
+        ) {
+            return Err(MappingError::UnknownElement);
+        }
+
+        self.tweak_mapping(id, |mapping| mapping.remap(from.0, to))
+    }
+
+    /// Sets whether value of axis `code` of gamepad `id` is negated, for example to correct
+    /// physically inverted axis. Inversion is applied to events of mapped axes before filters.
+    ///
+    /// Changed mapping is used the same way as after [`swap_codes()`](#method.swap_codes), but it's
+    /// also added to mapping database like by [`set_mapping()`](#method.set_mapping) (with `~`
+    /// suffix for inverted axes), so inversion is kept when gamepad reconnects and saved by
+    /// [`export_mappings()`](#method.export_mappings). Database line contains whole current
+    /// mapping, including changes made by `swap_codes()`, except elements that SDL mappings can't
+    /// express, like paddles.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MappingError::NotConnected` if gamepad is not connected.
+    pub fn set_axis_inverted(
+        &mut self,
+        id: GamepadId,
+        code: Code,
+        inverted: bool,
+    ) -> Result<(), MappingError> {
+        self.tweak_mapping(id, |mapping| mapping.set_inverted(code.0, inverted))?;
+
+        let gamepad = self.gamepad(id);
+        let uuid = Uuid::from_bytes(gamepad.uuid());
+        let line =
+            gamepad
+                .data
+                .mapping
+                .to_sdl_line(uuid, gamepad.inner.buttons(), gamepad.inner.axes());
+        // Like in `set_mapping()`, mapping for nil UUID would be used by every unidentified gamepad.
+        if let Some(line) = line.filter(|_| !uuid.is_nil()) {
+            self.mappings.insert(&line);
+        }
+
+        Ok(())
+    }
+
+    fn tweak_mapping(
+        &mut self,
+        id: GamepadId,
+        tweak: impl FnOnce(&mut Mapping),
+    ) -> Result<(), MappingError> {
+        let name = match self.connected_gamepad(id) {
+            Some(gamepad) => gamepad.os_name().to_owned(),
+            None => return Err(MappingError::NotConnected),
+        };
+
+        let mapping = &mut self.gamepads_data[id.0].mapping;
+        tweak(mapping);
+        mapping.mark_custom(&name);
+
+        Ok(())
+    }
+
+    /// Sets how force feedback effects playing at the same time on the gamepad are combined.
+    /// Default is [`FfMixing::Sum`](ff/enum.FfMixing.html). The setting is kept when the gamepad
+    /// reconnects.
+    pub fn set_ff_mixing(&mut self, id: GamepadId, mixing: FfMixing) -> Result<(), FfError> {
+        self.tx.send(Message::SetMixing { id: id.0, mixing })?;
+        Ok(())
+    }
+
+    /// Plays short, recognizable vibration pattern (two pulses on both motors) on gamepad with
+    /// `id`, for example for "test rumble" button in controller setup screen. Dropping returned
+    /// effect stops it.
+    ///
+    /// This is a method of `Gilrs` rather than `Gamepad`, because creating effect requires mutable
+    /// access to the context.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Disconnected(id)` if gamepad is not connected and
+    /// `Error::FfNotSupported(id)` if it doesn't support force feedback.
+    pub fn vibration_test(&mut self, id: GamepadId) -> Result<Effect, FfError> {
+        let pulse = Ticks::from_ms(150);
+        let scheduling = Replay {
+            play_for: pulse,
+            with_delay: pulse,
+            ..Default::default()
+        };
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong { magnitude: 40_000 },
+                scheduling,
+                ..Default::default()
+            })
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Weak { magnitude: 40_000 },
+                scheduling,
+                ..Default::default()
+            })
+            .gamepads(&[id])
+            .repeat(Repeat::For(pulse * 3))
+            .finish(self)?;
+        effect.play()?;
+
+        Ok(effect)
+    }
+
+    /// Sets time constants of axis smoothing for gamepad with `id`, overriding value set with
+    /// [`GilrsBuilder::axis_smoothing()`](struct.GilrsBuilder.html#method.axis_smoothing).
+    /// `sticks` is used for sticks and `triggers` for analog triggers, zero disables smoothing. See
+    /// [`Smoothing`](ev/filter/struct.Smoothing.html) for details.
+    pub fn set_axis_smoothing(&mut self, id: GamepadId, sticks: Duration, triggers: Duration) {
+        self.axis_smoothing
+            .get_or_insert_with(|| Smoothing::new(Duration::ZERO))
+            .set_gamepad_time_constants(id, sticks, triggers);
+    }
+
+    /// Suppresses or resumes delivery of input events, for example when application loses focus.
+    ///
+    /// While suppressed, button and axis events are still used to update cached gamepad state, but
+    /// are not returned by [`next_event()`](#method.next_event). `Connected`, `Disconnected` and
+    /// `ForceFeedbackEffectCompleted` events are delivered normally. When suppression ends, events
+    /// describing the difference between current state and state from the moment suppression
+    /// started are returned by following calls to `next_event()`, before other events and without
+    /// passing them through filters. A button that was pressed and released in the meantime doesn't
+    /// generate any event, and a button that is still held generates `ButtonPressed`.
+    ///
+    /// Cached state is only updated if automatic state updates are enabled (the default).
+    pub fn set_suppressed(&mut self, suppressed: bool) {
+        if suppressed {
+            if self.suppressed.is_none() {
+                self.suppressed = Some(
+                    self.gamepads_data
+                        .iter()
+                        .map(|data| data.state.clone())
+                        .collect(),
+                );
+            }
+        } else if let Some(before) = self.suppressed.take() {
+            let empty = GamepadState::new();
+            for data in self.gamepads_data.iter().filter(|data| data.enabled) {
+                let before = before.get(data.id.0).unwrap_or(&empty);
+                for event in data.state.diff(before) {
+                    self.resync
+                        .push_back(Event::new(data.id, event).with_source(EventSource::Synthetic));
+                }
+            }
+        }
+    }
+
+    /// Returns `true` if input events are suppressed. See
+    /// [`set_suppressed()`](#method.set_suppressed).
+    pub fn is_suppressed(&self) -> bool {
+        self.suppressed.is_some()
+    }
+
+    /// Enables or disables gamepad.
+    ///
+    /// Disabled gamepad is still listed by [`gamepads()`](#method.gamepads), but all its events,
+    /// except `Connected` and `Disconnected`, are dropped and force feedback effects are not played
+    /// on it. Cached state of gamepad is cleared when it's disabled. Gamepad stays disabled after
+    /// reconnecting.
+    ///
+    /// Does nothing if `id` is invalid.
+    pub fn set_gamepad_enabled(&mut self, id: GamepadId, enabled: bool) {
+        let data = match self.gamepads_data.get_mut(id.0) {
+            Some(d) if d.enabled != enabled => d,
+            _ => return,
+        };
+        data.enabled = enabled;
+        data.last_input = Instant::now();
+
+        if enabled {
+            let gamepad = self.inner.gamepad_or_placeholder(id.0);
+            if gamepad.is_ff_supported() && gamepad.is_connected() {
+                if let Some(device) = gamepad.ff_device() {
+                    let _ = self.tx.send(Message::Open { id: id.0, device });
+                }
+            }
+        } else {
+            let _ = self.tx.send(Message::Close { id: id.0 });
+            data.state = GamepadState::new();
+
+            if let Some(ref shared) = self.shared_state {
+                let mut states = shared.write().unwrap_or_else(PoisonError::into_inner);
+                if let Some(state) = states.get_mut(id.0) {
+                    *state = GamepadState::new();
+                }
+            }
+        }
+    }
+
+    /// Starts recording minimal and maximal values of every axis of gamepad. Move all sticks and
+    /// triggers to their extremes and then call
+    /// [`finish_axis_calibration()`](#method.finish_axis_calibration).
+    ///
+    /// Calling it again restarts recording. Previous calibration stays applied until calibration
+    /// is finished. Does nothing if `id` is invalid.
+    pub fn start_axis_calibration(&mut self, id: GamepadId) {
+        if let Some(data) = self.gamepads_data.get_mut(id.0) {
+            data.calibrating = Some(Vec::new());
+        }
+    }
+
+    /// Finishes calibration started by
+    /// [`start_axis_calibration()`](#method.start_axis_calibration), applies it and returns it, so
+    /// it can be saved and later restored with
+    /// [`apply_axis_calibration()`](#method.apply_axis_calibration).
+    ///
+    /// Observed extremes of each axis are, after moving them slightly towards center, mapped to
+    /// -1.0 and 1.0 (or 0.0 and 1.0 for axes mapped to buttons). Values outside of calibrated
+    /// range are clamped. Each side of axis is calibrated separately, and side that was not moved
+    /// by at least quarter of its range keeps range reported by the device, as do axes that were
+    /// not moved at all, so noise of axis at rest doesn't make tiny range that saturates
+    /// immediately.
+    ///
+    /// Returns `None` if calibration was not started or `id` is invalid.
+    pub fn finish_axis_calibration(&mut self, id: GamepadId) -> Option<AxisCalibration> {
+        let data = self.gamepads_data.get_mut(id.0)?;
+        let mut axes: Vec<_> = data
+            .calibrating
+            .take()?
+            .into_iter()
+            .map(|(code, info, min, max)| {
+                let (min, max) = calibrated_range(&info, min, max);
+                (code, min, max)
+            })
+            .collect();
+        axes.sort_by_key(|&(code, ..)| code.into_u32());
+
+        data.calibration = AxisCalibration { axes };
+
+        Some(data.calibration.clone())
+    }
+
+    /// Applies calibration previously returned by
+    /// [`finish_axis_calibration()`](#method.finish_axis_calibration), replacing current one.
+    /// `AxisCalibration::default()` removes calibration. Calibration is kept when gamepad
+    /// reconnects.
+    ///
+    /// Does nothing if `id` is invalid.
+    pub fn apply_axis_calibration(&mut self, id: GamepadId, calibration: &AxisCalibration) {
+        if let Some(data) = self.gamepads_data.get_mut(id.0) {
+            data.calibration = calibration.clone();
+        }
+    }
+
+    /// Restores gate estimate of `stick` previously returned by
+    /// [`Gamepad::stick_gate()`](struct.Gamepad.html#method.stick_gate), for example from saved
+    /// profile. It is reported until enough new input is observed to classify the stick again.
+    /// Like calibration, it is kept when gamepad reconnects.
+    ///
+    /// Does nothing if `id` is invalid.
+    pub fn apply_stick_gate(&mut self, id: GamepadId, stick: Stick, estimate: &GateEstimate) {
+        if let Some(data) = self.gamepads_data.get_mut(id.0) {
+            data.gates.restore(stick, *estimate);
+        }
+    }
+
+    /// Returns counters collected since `Gilrs` was created. All counters are zero unless enabled
+    /// with [`GilrsBuilder::enable_metrics()`](struct.GilrsBuilder.html#method.enable_metrics).
+    pub fn metrics(&self) -> Metrics {
+        match self.metrics {
+            Some(metrics) => Metrics {
+                ff_writes_suppressed: self.ff_writes_suppressed.load(AtomicOrdering::Relaxed),
+                ..metrics
+            },
+            None => Metrics::default(),
+        }
+    }
+
+    /// Returns input latency of gamepad `id` measured since `Gilrs` was created or since last
+    /// [`reset_latency_stats()`](#method.reset_latency_stats). Only events with
+    /// `EventSource::Hardware` are measured. Returns `LatencyStats::default()` unless enabled
+    /// with [`GilrsBuilder::measure_latency()`](struct.GilrsBuilder.html#method.measure_latency).
+    pub fn latency_stats(&self, id: GamepadId) -> LatencyStats {
+        self.latency
+            .as_ref()
+            .and_then(|latency| latency.get(id.0))
+            .map(LatencyHistogram::stats)
+            .unwrap_or_default()
+    }
+
+    /// Returns time between the moment backend read event last returned by `next_event()` and the
+    /// moment it was returned. Unlike [`latency_stats()`](#method.latency_stats), this includes
+    /// only time spent in gilrs (queueing and filtering), not in OS or driver.
+    ///
+    /// Events that were not read from backend (for example inserted with
+    /// [`insert_event()`](#method.insert_event)) don't change returned value. Returns `None` if
+    /// no such event was returned yet or latency measurement is not enabled with
+    /// [`GilrsBuilder::measure_latency()`](struct.GilrsBuilder.html#method.measure_latency).
+    pub fn last_event_latency(&self) -> Option<Duration> {
+        self.last_event_latency
+    }
+
+    /// Discards latency measured for gamepad `id`.
+    pub fn reset_latency_stats(&mut self, id: GamepadId) {
+        if let Some(hist) = self.latency.as_mut().and_then(|l| l.get_mut(id.0)) {
+            *hist = LatencyHistogram::new();
+        }
+    }
+
+    /// Frees memory and other resources held by disconnected gamepads. They are remembered so
+    /// that they get the same `GamepadId` when they reconnect, which in long-running applications
+    /// with many reconnect cycles (for example with wireless receivers) can add up.
+    ///
+    /// Name, UUID and mapping of disconnected gamepads are kept, but their state is reset as if
+    /// they never sent any event. Connected gamepads are not affected.
+    pub fn compact(&mut self) {
+        self.inner.compact();
+
+        for data in &mut self.gamepads_data {
+            let connected = data.test_gamepad.is_some()
+                || self
+                    .inner
+                    .gamepad(data.id.0)
+                    .is_some_and(|gamepad| gamepad.is_connected());
+            if connected {
+                continue;
+            }
+
+            data.state = GamepadState::new();
+            data.calibrating = None;
+            data.power_info_cache.set(None);
+
+            if let Some(ref shared) = self.shared_state {
+                let mut states = shared.write().unwrap_or_else(PoisonError::into_inner);
+                if let Some(state) = states.get_mut(data.id.0) {
+                    *state = GamepadState::new();
+                }
+            }
+        }
+    }
+
+    pub(crate) fn ff_effect_created(&mut self) {
+        if let Some(ref mut metrics) = self.metrics {
+            metrics.ff_effects_created += 1;
+        }
+    }
+
+    pub(crate) fn next_ff_id(&mut self) -> usize {
+        // TODO: reuse free ids
+        let id = self.next_id;
+        self.next_id = match self.next_id.checked_add(1) {
+            Some(x) => x,
+            None => panic!("Failed to assign ID to new effect"),
+        };
+        id
+    }
+}
+
+/// Allow to create `Gilrs ` with customized behaviour.
+pub struct GilrsBuilder {
+    mappings: MappingDb,
+    default_filters: bool,
+    axis_to_btn_pressed: f32,
+    axis_to_btn_released: f32,
+    update_state: bool,
+    env_mappings: bool,
+    included_mappings: bool,
+    max_axis_event_rate: Option<u32>,
+    axis_smoothing: Option<Duration>,
+    suppress_suspend_events: bool,
+    enable_metrics: bool,
+    measure_latency: bool,
+    dpad_source: DPadSource,
+    dpad_axis_threshold: f32,
+    summary_power_cache_ttl: Duration,
+    idle_disconnect_timeout: Option<Duration>,
+    ff_keep_alive_interval: Duration,
+    event_capacity: usize,
+    backend_options: gilrs_core::BackendOptions,
+}
+
+impl GilrsBuilder {
+    /// Create builder with default settings. Use `build()` to create `Gilrs`.
+    pub fn new() -> Self {
+        GilrsBuilder {
+            mappings: MappingDb::new(),
+            default_filters: true,
+            axis_to_btn_pressed: 0.75,
+            axis_to_btn_released: 0.65,
+            update_state: true,
+            env_mappings: true,
+            included_mappings: true,
+            max_axis_event_rate: None,
+            axis_smoothing: None,
+            suppress_suspend_events: false,
+            enable_metrics: false,
+            measure_latency: false,
+            dpad_source: DPadSource::Buttons,
+            dpad_axis_threshold: 1.0,
+            summary_power_cache_ttl: DEFAULT_SUMMARY_POWER_CACHE_TTL,
+            idle_disconnect_timeout: None,
+            ff_keep_alive_interval: DEFAULT_FF_KEEP_ALIVE_INTERVAL,
+            event_capacity: 0,
+            backend_options: Default::default(),
+        }
+    }
+
+    /// If `true`, use [`axis_dpad_to_button`](ev/filter/fn.axis_dpad_to_button.html),
+    /// [`Jitter`](ev/filter/struct.Jitter.html) and [`deadzone`](ev/filter/fn.deadzone.html)
+    /// filters with default parameters. Defaults to `true`.
+    pub fn with_default_filters(mut self, default_filters: bool) -> Self {
+        self.default_filters = default_filters;
+
+        self
+    }
+
+    /// Adds SDL mappings.
+    pub fn add_mappings(mut self, mappings: &str) -> Self {
+        self.mappings.insert(mappings);
+
+        self
+    }
+
+    /// Makes SDL mappings written for other platform usable. With `Some(platform)` mappings with
+    /// `platform:` field equal to `platform` (for example `"Windows"` or `"Mac OS X"`) are
+    /// accepted, with `None` mappings for all platforms are accepted. Mappings for current platform
+    /// are always preferred.
+    ///
+    /// This is useful for controllers that only have mappings for other OS, but button and axis
+    /// indices of these mappings may not match on current platform. By default only mappings for
+    /// current platform are used.
+    ///
+    /// Bundled mappings only contain current platform, unless `all-platform-mappings` feature is
+    /// enabled. Without it, this only affects mappings from
+    /// [`add_mappings()`](Self::add_mappings) and `SDL_GAMECONTROLLERCONFIG`.
+    pub fn force_platform(mut self, platform: Option<&str>) -> Self {
+        self.mappings.set_platforms(match platform {
+            Some(platform) => MappingPlatforms::With(platform.to_owned()),
+            None => MappingPlatforms::All,
+        });
 
         self
     }
@@ -693,7 +1848,210 @@ impl GilrsBuilder {
         self
     }
 
+    /// Limits number of `AxisChanged` events to at most `hz` per second for every axis, using
+    /// [`AxisRateLimit`](ev/filter/struct.AxisRateLimit.html) filter. The filter is applied after
+    /// default filters, so small changes dropped by `Jitter` are not counted. Axis values that
+    /// arrive too early are coalesced and the latest one is emitted at the end of the interval.
+    /// Button events and zero crossings are never delayed.
+    ///
+    /// Disabled by default.
+    pub fn max_axis_event_rate(mut self, hz: u32) -> Self {
+        self.max_axis_event_rate = Some(hz);
+
+        self
+    }
+
+    /// Smooths values of sticks and analog triggers with exponential moving average, using
+    /// [`Smoothing`](ev/filter/struct.Smoothing.html) filter with `time_constant`. The filter is
+    /// applied after default filters and before
+    /// [`max_axis_event_rate()`](#method.max_axis_event_rate), so cached state holds smoothed
+    /// values. Time constants can be changed for individual gamepads with
+    /// [`Gilrs::set_axis_smoothing()`](struct.Gilrs.html#method.set_axis_smoothing).
+    ///
+    /// Disabled by default.
+    pub fn axis_smoothing(mut self, time_constant: Duration) -> Self {
+        self.axis_smoothing = Some(time_constant);
+
+        self
+    }
+
+    /// If `true`, `Gilrs` counts processed events, see
+    /// [`Gilrs::metrics()`](struct.Gilrs.html#method.metrics). Defaults to `false`.
+    pub fn enable_metrics(mut self, enabled: bool) -> Self {
+        self.enable_metrics = enabled;
+
+        self
+    }
+
+    /// If `true`, `Gilrs` measures time between event timestamp and the moment it's returned from
+    /// `next_event()`, see [`Gilrs::latency_stats()`](struct.Gilrs.html#method.latency_stats) and
+    /// [`Gilrs::last_event_latency()`](struct.Gilrs.html#method.last_event_latency). Defaults to
+    /// `false`.
+    pub fn measure_latency(mut self, enabled: bool) -> Self {
+        self.measure_latency = enabled;
+
+        self
+    }
+
+    /// Sets which events are used for gamepads that report dpad both as buttons and hat (dpad
+    /// axes). Events from the other source are dropped, so every dpad press is reported only once.
+    /// Defaults to [`DPadSource::Buttons`](enum.DPadSource.html#variant.Buttons).
+    pub fn dpad_source(mut self, source: DPadSource) -> Self {
+        self.dpad_source = source;
+
+        self
+    }
+
+    /// Sets absolute value of dpad axis at which
+    /// [`axis_dpad_to_button`](ev/filter/fn.axis_dpad_to_button.html) filter reports direction as
+    /// pressed. Lower values make dpads that report intermediate values usable. `build()` will
+    /// return error if `threshold` is outside (0.0, 1.0].
+    ///
+    /// Defaults to 1.0.
+    pub fn dpad_axis_threshold(mut self, threshold: f32) -> Self {
+        self.dpad_axis_threshold = threshold;
+
+        self
+    }
+
+    /// Sets how long power information returned by
+    /// [`Gilrs::gamepad_summaries()`](struct.Gilrs.html#method.gamepad_summaries) is cached before
+    /// it's read from the device again. Defaults to one second. Zero is the minimal value and means
+    /// that it is read every time. Larger values, like 30 seconds, reduce overhead when summaries
+    /// are requested every frame.
+    ///
+    /// Only `gamepad_summaries()` is affected. Backends don't poll battery state in background
+    /// and [`Gamepad::power_info()`](struct.Gamepad.html#method.power_info) always reads current
+    /// value.
+    pub fn summary_power_cache_ttl(mut self, interval: Duration) -> Self {
+        self.summary_power_cache_ttl = interval;
+
+        self
+    }
+
+    /// If set, gamepad that doesn't send any input for `timeout` is treated as disconnected: it
+    /// emits `Disconnected` event, is not listed by [`gamepads()`](struct.Gilrs.html#method.gamepads)
+    /// and force feedback is stopped on it. Backend still listens for its events, so on next
+    /// input `Connected` event is emitted, followed by that input. `Disconnected` and `Connected`
+    /// events emitted this way have [`EventSource::Synthetic`](enum.EventSource.html) source.
+    ///
+    /// This is meant for applications that want to release idle wireless gamepads to save power.
+    /// Disabled gamepads are never treated as idle. Defaults to `None` (disabled).
+    pub fn idle_disconnect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.idle_disconnect_timeout = timeout;
+
+        self
+    }
+
+    /// Force feedback state is written to a device only when it changes, or when it wasn't written
+    /// for `interval`, because some drivers stop vibration when they don't receive updates. Shorter
+    /// interval means more writes. Number of skipped writes is reported by
+    /// [`Gilrs::metrics()`](struct.Gilrs.html#method.metrics). Defaults to 500ms. Intervals
+    /// longer than 60s are clamped, because on Linux written state can't last longer than about
+    /// 65s.
+    pub fn ff_keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.ff_keep_alive_interval = interval.min(MAX_FF_KEEP_ALIVE_INTERVAL);
+
+        self
+    }
+
+    /// If `true`, Windows Gaming Input backend reads all controllers as `RawGameController` and
+    /// uses SDL mappings for them, even if they are supported by WGI `Gamepad` API. Useful for
+    /// controllers with wrong button layout in WGI `Gamepad`. Other backends ignore this option.
+    /// Defaults to `false`.
+    pub fn prefer_raw_readings(mut self, prefer: bool) -> Self {
+        self.backend_options.prefer_raw_readings = prefer;
+
+        self
+    }
+
+    /// If `true`, every gamepad is grabbed (see [`Gamepad::grab()`](struct.Gamepad.html#method.grab))
+    /// when it's opened, so events are not delivered twice when application also uses another
+    /// input library, like SDL, that reads the same devices. Failed grabs don't prevent using the
+    /// gamepad and are reported by
+    /// [`Gamepad::grab_error()`](struct.Gamepad.html#method.grab_error).
+    ///
+    /// Grabbed devices don't send events to any other reader, including desktop compositor and
+    /// X server, so gamepads that are also used as mouse or keyboard stop controlling the desktop
+    /// until `Gilrs` is dropped. Grab fails with `GrabError::Busy` if other application (for
+    /// example Steam) already grabbed the device.
+    ///
+    /// Only supported on Linux, ignored by other backends. Defaults to `false`.
+    pub fn exclusive_device_access(mut self, exclusive: bool) -> Self {
+        self.backend_options.exclusive_device_access = exclusive;
+
+        self
+    }
+
+    /// If `true`, devices that have only buttons are also accepted as gamepads. By default, gamepad
+    /// must have at least two axes, so digital-only devices like some arcade sticks (with stick
+    /// and dpad reported as buttons) are ignored. Only devices with joystick or gamepad buttons
+    /// are accepted, never keyboards or mice.
+    ///
+    /// Only used on Linux, other backends ignore it. Defaults to `false`.
+    pub fn accept_buttons_only(mut self, accept: bool) -> Self {
+        self.backend_options.accept_buttons_only = accept;
+
+        self
+    }
+
+    /// Selects which time is used as [`Event::time`](struct.Event.html#structfield.time).
+    ///
+    /// `TimestampSource::Hardware` is time when the kernel received input from the device, so it's
+    /// not affected by how often application polls for events. It's supported only on Linux.
+    /// `TimestampSource::Arrival` is time when event was read by the backend; this is what all
+    /// other backends (and Linux with this option) report. Use
+    /// [`Gilrs::timestamp_source()`](struct.Gilrs.html#method.timestamp_source) to check which
+    /// one is in effect.
+    ///
+    /// `Connected` and `Disconnected` events and events created by filters always use arrival
+    /// time. Defaults to `TimestampSource::Hardware`.
+    pub fn timestamp_source(mut self, source: TimestampSource) -> Self {
+        self.backend_options.timestamp_source = source;
+
+        self
+    }
+
+    /// Reserves space for `capacity` queued events and for state of every button and axis of
+    /// gamepads when they connect, so that [`Gilrs::next_event()`] doesn't allocate once all
+    /// elements sent their first event. Events created by filters with
+    /// [`Gilrs::insert_event()`] use the same queue. If more than `capacity` events have to be
+    /// queued, queue grows and warning is logged.
+    ///
+    /// Some operations can still allocate:
+    ///
+    /// - connecting and disconnecting gamepads,
+    /// - first event of element that gamepad didn't advertise,
+    /// - first event of every element with [`Gilrs::state_handle()`],
+    /// - first event of every gamepad with [`measure_latency()`](Self::measure_latency) and of
+    ///   every axis with rate limiting or smoothing enabled,
+    /// - force feedback,
+    /// - backend itself, depending on platform.
+    ///
+    /// Defaults to 0, which doesn't reserve anything.
+    pub fn preallocate_events(mut self, capacity: usize) -> Self {
+        self.event_capacity = capacity;
+
+        self
+    }
+
+    /// If `true`, `Connected` and `Disconnected` events that follow system resume from suspend are
+    /// replaced by single [`EventType::Rescanned`](enum.EventType.html#variant.Rescanned) event.
+    /// After it is received, [`gamepads()`](struct.Gilrs.html#method.gamepads) reflects gamepads
+    /// that are connected after resume.
+    ///
+    /// Suspend is detected as a gap between wall clock and monotonic clock, which doesn't advance
+    /// while system is suspended on Linux and macOS. Hotplug events are then batched for few
+    /// seconds. Changing system time may also be treated as suspend. Not supported on web.
+    /// Defaults to `false`.
+    pub fn suppress_suspend_events(mut self, suppress: bool) -> Self {
+        self.suppress_suspend_events = suppress;
+
+        self
+    }
+
     /// Creates `Gilrs`.
+    #[allow(clippy::result_large_err)]
     pub fn build(mut self) -> Result<Gilrs, Error> {
         if self.included_mappings {
             self.mappings.add_included_mappings();
@@ -714,8 +2072,12 @@ impl GilrsBuilder {
             return Err(Error::InvalidAxisToBtn);
         }
 
+        if !(self.dpad_axis_threshold > 0.0 && self.dpad_axis_threshold <= 1.0) {
+            return Err(Error::InvalidDPadAxisThreshold);
+        }
+
         let mut is_dummy = false;
-        let inner = match gilrs_core::Gilrs::new() {
+        let inner = match gilrs_core::Gilrs::with_options(&self.backend_options) {
             Ok(g) => g,
             Err(PlatformError::NotImplemented(g)) => {
                 is_dummy = true;
@@ -726,21 +2088,64 @@ impl GilrsBuilder {
             Err(_) => unimplemented!(),
         };
 
-        let (tx, rx) = server::init();
-
+        let ff_writes_suppressed = Arc::new(AtomicU64::new(0));
+        let (tx, rx) = server::init(
+            self.ff_keep_alive_interval.into(),
+            ff_writes_suppressed.clone(),
+        );
+
+        let not_observed = GamepadData::new(
+            GamepadId(usize::MAX),
+            tx.clone(),
+            inner.gamepad_or_placeholder(usize::MAX),
+            &MappingDb::new(),
+        );
         let mut gilrs = Gilrs {
             inner,
             next_id: 0,
             tx,
             rx,
             counter: 0,
+            connection_generation: 0,
             mappings: self.mappings,
             default_filters: self.default_filters,
-            events: VecDeque::new(),
+            events: EventQueue::with_capacity(self.event_capacity),
             axis_to_btn_pressed: self.axis_to_btn_pressed,
             axis_to_btn_released: self.axis_to_btn_released,
             update_state: self.update_state,
             gamepads_data: Vec::new(),
+            not_observed,
+            shared_state: None,
+            suppressed: None,
+            resync: VecDeque::new(),
+            axis_rate_limit: self.max_axis_event_rate.map(|hz| {
+                let mut limit = AxisRateLimit::new();
+                limit.max_rate = hz;
+                limit
+            }),
+            axis_smoothing: self.axis_smoothing.map(Smoothing::new),
+            suspend_guard: if self.suppress_suspend_events && !cfg!(target_arch = "wasm32") {
+                Some(SuspendGuard::new())
+            } else {
+                None
+            },
+            metrics: if self.enable_metrics {
+                Some(Metrics::default())
+            } else {
+                None
+            },
+            latency: if self.measure_latency {
+                Some(Vec::new())
+            } else {
+                None
+            },
+            arrival: None,
+            last_event_latency: None,
+            ff_writes_suppressed,
+            dpad_source: self.dpad_source,
+            dpad_axis_threshold: self.dpad_axis_threshold,
+            summary_power_cache_ttl: self.summary_power_cache_ttl,
+            idle_disconnect_timeout: self.idle_disconnect_timeout,
         };
         gilrs.finish_gamepads_creation();
 
@@ -766,7 +2171,13 @@ impl<'a> Iterator for ConnectedGamepadsIterator<'a> {
 
     fn next(&mut self) -> Option<(GamepadId, Gamepad<'a>)> {
         loop {
-            if self.1 == self.0.inner.last_gamepad_hint() {
+            if self.1
+                >= self
+                    .0
+                    .inner
+                    .last_gamepad_hint()
+                    .max(self.0.gamepads_data.len())
+            {
                 return None;
             }
 
@@ -787,11 +2198,13 @@ impl<'a> Iterator for ConnectedGamepadsIterator<'a> {
 /// or UUID and manage force feedback effects.
 #[derive(Debug, Copy, Clone)]
 pub struct Gamepad<'a> {
+    id: GamepadId,
     data: &'a GamepadData,
     inner: &'a gilrs_core::Gamepad,
+    ff_tx: &'a Sender<Message>,
 }
 
-impl Gamepad<'_> {
+impl<'a> Gamepad<'a> {
     /// Returns the mapping name if it exists otherwise returns the os provided name.
     pub fn name(&self) -> &str {
         if let Some(map_name) = self.map_name() {
@@ -808,8 +2221,25 @@ impl Gamepad<'_> {
     }
 
     /// Returns the name of the gamepad supplied by the OS.
+    ///
+    /// On Linux, name is read once when gamepad is opened and converted to UTF-8 lossily, so
+    /// names in other encodings may contain `U+FFFD` replacement characters. Use
+    /// [`raw_name()`](#method.raw_name) to decode it yourself.
     pub fn os_name(&self) -> &str {
-        self.inner.name()
+        match self.data.test_gamepad {
+            Some((ref name, _)) => name,
+            None => self.inner.name(),
+        }
+    }
+
+    /// Returns the name of the gamepad supplied by the OS, as bytes captured when gamepad was
+    /// opened, before UTF-8 conversion. Only differs from `os_name().as_bytes()` on Linux for
+    /// names that are not valid UTF-8.
+    pub fn raw_name(&self) -> &[u8] {
+        match self.data.test_gamepad {
+            Some((ref name, _)) => name.as_bytes(),
+            None => self.inner.raw_name(),
+        }
     }
 
     /// Returns gamepad's UUID.
@@ -817,7 +2247,21 @@ impl Gamepad<'_> {
     /// It is recommended to process with the [UUID crate](https://crates.io/crates/uuid).
     /// Use `Uuid::from_bytes` method to create a `Uuid` from the returned bytes.
     pub fn uuid(&self) -> [u8; 16] {
-        self.inner.uuid()
+        match self.data.test_gamepad {
+            Some((_, uuid)) => uuid,
+            None => self.inner.uuid(),
+        }
+    }
+
+    /// Returns gamepad's UUID formatted as SDL GUID, the same way as it appears in SDL mappings.
+    pub fn sdl_guid(&self) -> String {
+        Uuid::from_bytes(self.uuid()).as_simple().to_string()
+    }
+
+    /// Returns bustype, vendor ID, product ID and version encoded in [`uuid()`](#method.uuid).
+    /// Fields that backend doesn't provide are zero, for example all of them for nil UUID.
+    pub fn guid_components(&self) -> GuidComponents {
+        GuidComponents::from_uuid(self.uuid())
     }
 
     /// Returns the vendor ID, as assigned by the USB-IF, when available.
@@ -830,6 +2274,34 @@ impl Gamepad<'_> {
         self.inner.product_id()
     }
 
+    /// Returns platform specific path or identifier of the device, like `/dev/input/event5` on
+    /// Linux or `xinput/0` with XInput backend. See
+    /// [`gilrs_core::Gamepad::device_path()`] for details of every platform.
+    pub fn device_path(&self) -> Option<&str> {
+        self.inner.device_path()
+    }
+
+    /// Returns platform specific identifier of the physical port the gamepad is plugged into.
+    /// Unlike [`device_path()`](#method.device_path), it's the same for every gamepad plugged into
+    /// the same port, so it can be used for "port 1 is player 1" setups. See
+    /// [`gilrs_core::Gamepad::connection_port()`] for details of every platform.
+    pub fn connection_port(&self) -> Option<&str> {
+        self.inner.connection_port()
+    }
+
+    /// Returns `true` if gamepad is virtual device, for example one created by Steam Input that
+    /// remaps input of another gamepad. Games can use it to avoid offering their own remapping or
+    /// to warn about double mapping. See [`virtual_reason()`](#method.virtual_reason).
+    pub fn is_virtual(&self) -> bool {
+        self.virtual_reason().is_some()
+    }
+
+    /// Returns why gamepad is considered virtual. Detection is heuristic, see
+    /// [`gilrs_core::Gamepad::virtual_reason()`] for details of every platform.
+    pub fn virtual_reason(&self) -> Option<VirtualReason> {
+        self.inner.virtual_reason()
+    }
+
     /// Returns cached gamepad state.
     pub fn state(&self) -> &GamepadState {
         &self.data.state
@@ -837,7 +2309,20 @@ impl Gamepad<'_> {
 
     /// Returns true if gamepad is connected.
     pub fn is_connected(&self) -> bool {
-        self.inner.is_connected()
+        (self.inner.is_connected() || self.data.test_gamepad.is_some()) && !self.data.idle
+    }
+
+    /// Returns true if gamepad is connected and its events can be received. Gamepad can be
+    /// connected but not receiving events if backend failed to start listening for them. In this
+    /// case it is retried automatically by `next_event()`.
+    pub fn is_receiving_events(&self) -> bool {
+        self.inner.is_receiving_events() || self.data.test_gamepad.is_some()
+    }
+
+    /// Returns false if gamepad was disabled by
+    /// [`Gilrs::set_gamepad_enabled()`](struct.Gilrs.html#method.set_gamepad_enabled).
+    pub fn is_enabled(&self) -> bool {
+        self.data.enabled
     }
 
     /// Examines cached gamepad state to check if given button is pressed. Panics if `btn` is
@@ -850,10 +2335,28 @@ impl Gamepad<'_> {
         self.data.is_pressed(btn)
     }
 
-    /// Examines cached gamepad state to check axis's value. Panics if `axis` is `Unknown`.
-    ///
-    /// If you know `Code` of the element that you want to examine, it's recommended to use methods
-    /// directly on `State`, because this version have to check which `Code` is mapped to element of
+    /// Examines cached gamepad state to check button's value. For analog buttons (like triggers)
+    /// it's in range [0.0, 1.0], digital buttons are either 0.0 or 1.0. Panics if `btn` is
+    /// `Unknown`.
+    pub fn button_value(&self, btn: Button) -> f32 {
+        self.data.button_value(btn)
+    }
+
+    /// Returns `true` if button's value is greater than or equal to `threshold`. Panics if `btn` is
+    /// `Unknown`.
+    ///
+    /// Unlike [`is_pressed()`](#method.is_pressed), which uses thresholds set by
+    /// [`GilrsBuilder::set_axis_to_btn()`](struct.GilrsBuilder.html#method.set_axis_to_btn), this
+    /// allows to use different thresholds for different actions, for example half and full pull of
+    /// a trigger.
+    pub fn is_pressed_with_threshold(&self, btn: Button, threshold: f32) -> bool {
+        self.data.is_pressed_with_threshold(btn, threshold)
+    }
+
+    /// Examines cached gamepad state to check axis's value. Panics if `axis` is `Unknown`.
+    ///
+    /// If you know `Code` of the element that you want to examine, it's recommended to use methods
+    /// directly on `State`, because this version have to check which `Code` is mapped to element of
     /// gamepad.
     pub fn value(&self, axis: Axis) -> f32 {
         self.data.value(axis)
@@ -882,6 +2385,19 @@ impl Gamepad<'_> {
         self.inner.power_info()
     }
 
+    /// Like `power_info()`, but reuses value read less than `max_age` ago.
+    fn cached_power_info(&self, max_age: Duration) -> PowerInfo {
+        let now = utils::time_now();
+        match self.data.power_info_cache.get() {
+            Some((time, info)) if now.duration_since(time).is_ok_and(|d| d < max_age) => info,
+            _ => {
+                let info = self.power_info();
+                self.data.power_info_cache.set(Some((now, info)));
+                info
+            }
+        }
+    }
+
     /// Returns source of gamepad mapping. Can be used to filter gamepads which do not provide
     /// unified controller layout.
     ///
@@ -896,19 +2412,100 @@ impl Gamepad<'_> {
     /// }
     /// ```
     pub fn mapping_source(&self) -> MappingSource {
-        if self.data.mapping.is_default() {
-            // TODO: check if it's Driver or None
-            MappingSource::Driver
-        } else {
-            MappingSource::SdlMappings
+        mapping_source(&self.data.mapping)
+    }
+
+    /// Returns read-only view of the mapping used by the gamepad. Intended for custom filters that
+    /// have to know how elements are mapped, see [`MappingInfo`].
+    pub fn mapping_info(&self) -> MappingInfo<'a> {
+        MappingInfo {
+            mapping: &self.data.mapping,
+            dual_dpad: self.has_dual_dpad(),
         }
     }
 
+    /// Returns SDL mapping entries (like `b12` or `a7`) that were skipped, because gamepad
+    /// doesn't have referenced button or axis. Elements that these entries would map are reported
+    /// as unmapped.
+    pub fn unresolved_mapping_entries(&self) -> &[String] {
+        self.data.mapping.unresolved()
+    }
+
     /// Returns true if force feedback is supported by device.
     pub fn is_ff_supported(&self) -> bool {
         self.inner.is_ff_supported()
     }
 
+    /// Returns number of force feedback effects that use this gamepad, both playing and stopped.
+    /// Effects are counted until all their handles are dropped.
+    ///
+    /// This asks force feedback thread and waits for the answer, which can take up to one tick
+    /// (50 ms) if it is processing other messages. Returns 0 if force feedback is not available.
+    pub fn active_effect_count(&self) -> usize {
+        let (tx, rx) = mpsc::channel();
+        let msg = Message::CountEffects {
+            id: self.id.0,
+            ack: tx,
+        };
+        if self.ff_tx.send(msg).is_err() {
+            return 0;
+        }
+
+        rx.recv().unwrap_or(0)
+    }
+
+    /// Returns magnitudes of strong and weak motor, in range \[0.0, 1.0\], that force feedback
+    /// currently outputs to this gamepad after mixing all its playing effects. Both are 0.0 when
+    /// nothing is playing. Use it to show vibration on screen in sync with the device or for
+    /// debugging.
+    ///
+    /// Value is updated every tick (50 ms). Like
+    /// [`active_effect_count()`](Self::active_effect_count), this asks force feedback thread and
+    /// waits for the answer. Returns `None` if force feedback is not supported or gamepad is
+    /// disconnected or disabled.
+    pub fn current_rumble(&self) -> Option<(f32, f32)> {
+        let (tx, rx) = mpsc::channel();
+        let msg = Message::CurrentMagnitude {
+            id: self.id.0,
+            ack: tx,
+        };
+        if self.ff_tx.send(msg).is_err() {
+            return None;
+        }
+
+        let (strong, weak) = rx.recv().ok()??;
+        let max = f32::from(u16::MAX);
+        Some((f32::from(strong) / max, f32::from(weak) / max))
+    }
+
+    /// Returns shape of physical gate of `stick` learned from input received so far. Unlike
+    /// axis calibration, it doesn't need any special mode; see [`GateEstimate`] for details.
+    pub fn stick_gate(&self, stick: Stick) -> GateEstimate {
+        self.data.gates.estimate(stick)
+    }
+
+    /// Takes exclusive access to the device, so other applications (including desktop
+    /// environment) will not receive its events. This is useful for example for full-screen
+    /// arcade frontends. Grab is released by [`ungrab()`](#method.ungrab) or when gamepad is
+    /// disconnected or `Gilrs` is dropped.
+    ///
+    /// Only supported on Linux, other backends return `GrabError::NotSupported`.
+    pub fn grab(&self) -> Result<(), GrabError> {
+        self.inner.grab()
+    }
+
+    /// Releases exclusive access taken by [`grab()`](#method.grab).
+    pub fn ungrab(&self) -> Result<(), GrabError> {
+        self.inner.ungrab()
+    }
+
+    /// Returns why gamepad couldn't be grabbed when it was opened, if
+    /// [`GilrsBuilder::exclusive_device_access()`](struct.GilrsBuilder.html#method.exclusive_device_access)
+    /// is enabled. `None` if grab succeeded or wasn't requested.
+    pub fn grab_error(&self) -> Option<&GrabError> {
+        self.inner.grab_error()
+    }
+
     /// Change gamepad position used by force feedback effects.
     pub fn set_listener_position<Vec3: Into<[f32; 3]>>(
         &self,
@@ -920,7 +2517,7 @@ impl Gamepad<'_> {
             Err(FfError::FfNotSupported(self.id()))
         } else {
             self.data.tx.send(Message::SetListenerPosition {
-                id: self.data.id.0,
+                id: self.id.0,
                 position: position.into(),
             })?;
             Ok(())
@@ -928,10 +2525,45 @@ impl Gamepad<'_> {
     }
 
     /// Returns `AxisOrBtn` mapped to `Code`.
+    ///
+    /// Elements without gilrs equivalent, like paddles from SDL mappings, are mapped to
+    /// `Button::Unknown`, which events of unmapped elements use too. Use
+    /// [`code_usage()`](#method.code_usage) to tell these cases apart.
     pub fn axis_or_btn_name(&self, ec: Code) -> Option<AxisOrBtn> {
         self.data.axis_or_btn_name(ec)
     }
 
+    /// Like [`axis_or_btn_name()`](#method.axis_or_btn_name), but also names `BTN_DPAD_*` codes
+    /// used by dpad buttons that [`axis_dpad_to_button`](crate::ev::filter::axis_dpad_to_button)
+    /// creates from hat. They are not part of mapping of gamepads with hat dpad.
+    pub(crate) fn event_element(&self, code: Code) -> Option<AxisOrBtn> {
+        use gilrs_core::native_ev_codes as necs;
+
+        self.axis_or_btn_name(code).or_else(|| {
+            [
+                (necs::BTN_DPAD_UP, Button::DPadUp),
+                (necs::BTN_DPAD_DOWN, Button::DPadDown),
+                (necs::BTN_DPAD_LEFT, Button::DPadLeft),
+                (necs::BTN_DPAD_RIGHT, Button::DPadRight),
+            ]
+            .iter()
+            .find(|&&(nec, _)| Code(nec) == code)
+            .map(|&(_, btn)| AxisOrBtn::Btn(btn))
+        })
+    }
+
+    /// Returns whether current mapping maps `code` to some element. Unlike events, which report
+    /// unmapped elements as `Button::Unknown` or `Axis::Unknown`, this is based only on mapping, so
+    /// rebinding UI can offer unmapped codes and warn about conflicts for mapped ones (including
+    /// `Button::Mode`, which is often reserved by the system). `BTN_DPAD_*` codes of dpad buttons
+    /// created from hat are reported as mapped to them.
+    pub fn code_usage(&self, code: Code) -> CodeUsage {
+        match self.event_element(code) {
+            Some(el) => CodeUsage::MappedTo(el),
+            None => CodeUsage::Unmapped,
+        }
+    }
+
     /// Returns `Code` associated with `btn`.
     pub fn button_code(&self, btn: Button) -> Option<Code> {
         self.data.button_code(btn)
@@ -942,6 +2574,68 @@ impl Gamepad<'_> {
         self.data.axis_code(axis)
     }
 
+    /// Returns true if gamepad has element mapped to `btn`.
+    ///
+    /// Some backends can't report all buttons even if the device has them. For example, with
+    /// Windows Gaming Input the guide button (`Button::Mode`) is reserved by the system and not
+    /// reported for Xbox-like gamepads, so this function returns `false` for it.
+    pub fn supports_button(&self, btn: Button) -> bool {
+        self.button_code(btn).is_some_and(|code| {
+            self.inner.buttons().contains(&code.0) || self.inner.axes().contains(&code.0)
+        })
+    }
+
+    /// Returns number of distinct standard buttons (other than `Button::Unknown`) that gamepad's
+    /// elements are mapped to. Unlike number of elements returned by
+    /// [`buttons()`](#method.buttons), it doesn't include unmapped elements, so it can be used to
+    /// tell full gamepad with 17 buttons from simple one with only few of them.
+    pub fn mapped_button_count(&self) -> usize {
+        self.data.mapping.button_count()
+    }
+
+    /// Returns number of distinct standard axes (other than `Axis::Unknown`) that gamepad's
+    /// elements are mapped to. See [`mapped_button_count()`](#method.mapped_button_count).
+    pub fn mapped_axis_count(&self) -> usize {
+        self.data.mapping.axis_count()
+    }
+
+    /// Returns native codes of all buttons reported by the device together with elements they are
+    /// mapped to.
+    pub fn buttons(&self) -> impl Iterator<Item = (Code, Option<AxisOrBtn>)> + '_ {
+        self.inner
+            .buttons()
+            .iter()
+            .map(move |&nec| (Code(nec), self.axis_or_btn_name(Code(nec))))
+    }
+
+    /// Returns native codes of all axes reported by the device together with elements they are
+    /// mapped to.
+    pub fn axes(&self) -> impl Iterator<Item = (Code, Option<AxisOrBtn>)> + '_ {
+        self.inner
+            .axes()
+            .iter()
+            .map(move |&nec| (Code(nec), self.axis_or_btn_name(Code(nec))))
+    }
+
+    /// Returns native codes of hat axes that are not mapped to any `Axis` or `Button`, in order
+    /// reported by the device; each hat has two axes, horizontal first. Devices with more than
+    /// one hat, like flight sticks and arcade panels, use the first one as dpad and report the
+    /// rest here. Their changes are `AxisChanged(Axis::Unknown, value, code)` events with value
+    /// -1.0, 0.0 or 1.0, and current value is available with
+    /// [`state().value(code)`](GamepadState::value).
+    pub fn hat_axes(&self) -> impl Iterator<Item = Code> + '_ {
+        self.axes()
+            .filter(move |&(code, mapped)| {
+                mapped.is_none() && self.axis_info(code).is_some_and(is_hat)
+            })
+            .map(|(code, _)| code)
+    }
+
+    /// Returns expected range and deadzone of axis reported by the device.
+    pub fn axis_info(&self, axis: Code) -> Option<&AxisInfo> {
+        self.inner.axis_info(axis.0)
+    }
+
     /// Returns area in which axis events should be ignored.
     pub fn deadzone(&self, axis: Code) -> Option<f32> {
         self.inner.axis_info(axis.0).map(|i| {
@@ -959,22 +2653,85 @@ impl Gamepad<'_> {
 
     /// Returns ID of gamepad.
     pub fn id(&self) -> GamepadId {
-        self.data.id
+        self.id
     }
 
-    pub(crate) fn mapping(&self) -> &Mapping {
-        &self.data.mapping
+    /// Returns `true` if gamepad reports dpad both as buttons and hat.
+    pub(crate) fn has_dual_dpad(&self) -> bool {
+        self.data.dual_dpad
     }
 }
 
+const DPAD_BUTTONS: [gilrs_core::EvCode; 4] = [
+    gilrs_core::native_ev_codes::BTN_DPAD_UP,
+    gilrs_core::native_ev_codes::BTN_DPAD_DOWN,
+    gilrs_core::native_ev_codes::BTN_DPAD_LEFT,
+    gilrs_core::native_ev_codes::BTN_DPAD_RIGHT,
+];
+const DPAD_AXES: [gilrs_core::EvCode; 2] = [
+    gilrs_core::native_ev_codes::AXIS_DPADX,
+    gilrs_core::native_ev_codes::AXIS_DPADY,
+];
+
+fn has_dual_dpad(buttons: &[gilrs_core::EvCode], axes: &[gilrs_core::EvCode]) -> bool {
+    buttons.iter().any(|nec| DPAD_BUTTONS.contains(nec))
+        && axes.iter().any(|nec| DPAD_AXES.contains(nec))
+}
+
+/// Returns button event with `btn` instead of original button.
+fn with_button(event: EventType, btn: Button) -> EventType {
+    match event {
+        EventType::ButtonPressed(_, nec) => EventType::ButtonPressed(btn, nec),
+        EventType::ButtonReleased(_, nec) => EventType::ButtonReleased(btn, nec),
+        EventType::ButtonChanged(_, val, nec) => EventType::ButtonChanged(btn, val, nec),
+        event => event,
+    }
+}
+
+/// Returns `true` if gamepad reports dpad both as buttons and hat (`dual_dpad`) and `event` comes
+/// from the source that is not preferred.
+fn is_ignored_dpad_event(dual_dpad: bool, source: DPadSource, event: &RawEventType) -> bool {
+    let (nec, is_button) = match *event {
+        RawEventType::ButtonPressed(nec) | RawEventType::ButtonReleased(nec) => (nec, true),
+        RawEventType::AxisValueChanged(_, nec) => (nec, false),
+        _ => return false,
+    };
+
+    dual_dpad
+        && (DPAD_BUTTONS.contains(&nec) || DPAD_AXES.contains(&nec))
+        && match source {
+            DPadSource::Buttons => !is_button,
+            DPadSource::Hat => is_button,
+        }
+}
+
 #[derive(Debug)]
 pub(crate) struct GamepadData {
     state: GamepadState,
     mapping: Mapping,
     tx: Sender<Message>,
     id: GamepadId,
+    enabled: bool,
+    // Name and UUID of gamepad added by `register_test_gamepad()`.
+    test_gamepad: Option<(String, [u8; 16])>,
     // Flags used by the deadzone filter.
     pub(crate) have_sent_nonzero_for_axis: [bool; 6],
+    // Dpad buttons pressed by the axis_dpad_to_button filter.
+    pub(crate) dpad_pressed: [bool; 4],
+    // Used by `Gilrs::gamepad_summaries()`.
+    power_info_cache: Cell<Option<(SystemTime, PowerInfo)>>,
+    // Applied axis calibration.
+    calibration: AxisCalibration,
+    // Axis info and observed minimum and maximum of axes while calibration is in progress.
+    calibrating: Option<Vec<(Code, AxisInfo, i32, i32)>>,
+    // Used by `Gamepad::stick_gate()`.
+    gates: StickGates,
+    // Used by `GilrsBuilder::idle_disconnect_timeout()`.
+    last_input: Instant,
+    idle: bool,
+    // Gamepad reports dpad both as buttons and hat. Checked on connection and when backend adds
+    // dpad button later, not on every event.
+    dual_dpad: bool,
 }
 
 impl GamepadData {
@@ -984,23 +2741,7 @@ impl GamepadData {
         gamepad: &gilrs_core::Gamepad,
         db: &MappingDb,
     ) -> Self {
-        let mapping = db
-            .get(Uuid::from_bytes(gamepad.uuid()))
-            .map(
-                |s| match Mapping::parse_sdl_mapping(s, gamepad.buttons(), gamepad.axes()) {
-                    Ok(result) => result,
-                    Err(e) => {
-                        warn!(
-                            "Unable to parse SDL mapping for UUID {}\n\t{:?}\n\tDefault mapping \
-                             will be used.",
-                            Uuid::from_bytes(gamepad.uuid()),
-                            e
-                        );
-                        Mapping::default(gamepad)
-                    }
-                },
-            )
-            .unwrap_or_else(|| Mapping::default(gamepad));
+        let mapping = Self::find_mapping(gamepad, db);
 
         if gamepad.is_ff_supported() && gamepad.is_connected() {
             if let Some(device) = gamepad.ff_device() {
@@ -1013,10 +2754,87 @@ impl GamepadData {
             mapping,
             tx,
             id,
+            enabled: true,
+            test_gamepad: None,
             have_sent_nonzero_for_axis: Default::default(),
+            dpad_pressed: Default::default(),
+            power_info_cache: Cell::new(None),
+            calibration: AxisCalibration::default(),
+            calibrating: None,
+            gates: StickGates::default(),
+            last_input: Instant::now(),
+            idle: false,
+            dual_dpad: has_dual_dpad(gamepad.buttons(), gamepad.axes()),
+        }
+    }
+
+    fn can_become_idle(&self, inner: &gilrs_core::Gilrs) -> bool {
+        self.enabled
+            && !self.idle
+            && (inner.gamepad_or_placeholder(self.id.0).is_connected()
+                || self.test_gamepad.is_some())
+    }
+
+    fn record_axis(&mut self, code: Code, info: &AxisInfo, val: i32) {
+        let observed = match self.calibrating {
+            Some(ref mut observed) => observed,
+            None => return,
+        };
+
+        match observed.iter_mut().find(|(c, ..)| *c == code) {
+            Some((_, _, min, max)) => {
+                *min = (*min).min(val);
+                *max = (*max).max(val);
+            }
+            None => observed.push((code, *info, val, val)),
+        }
+    }
+
+    fn axis_value(&self, info: &AxisInfo, code: Code, val: i32, axis: Axis) -> f32 {
+        let val = match self.calibration.range(code) {
+            Some(range) => calibrated_axis_value(info, range, val, axis),
+            None => axis_value(info, val, axis),
+        };
+
+        if self.mapping.is_inverted(&code.0) {
+            -val
+        } else {
+            val
+        }
+    }
+
+    fn btn_value(&self, info: &AxisInfo, code: Code, val: i32) -> f32 {
+        match self.calibration.range(code) {
+            Some((min, max)) => btn_value(&AxisInfo { min, max, ..*info }, val),
+            None => btn_value(info, val),
         }
     }
 
+    /// Returns mapping from `db` or default mapping if there is no valid one.
+    fn find_mapping(gamepad: &gilrs_core::Gamepad, db: &MappingDb) -> Mapping {
+        db.get_for(Uuid::from_bytes(gamepad.uuid()), gamepad.name())
+            .map(|s| {
+                match Mapping::parse_sdl_mapping_for(
+                    s,
+                    gamepad.buttons(),
+                    gamepad.axes(),
+                    db.platforms(),
+                ) {
+                    Ok(result) => result.with_sdl_line(s),
+                    Err(e) => {
+                        warn!(
+                            "Unable to parse SDL mapping for UUID {}\n\t{:?}\n\tDefault mapping \
+                             will be used.",
+                            Uuid::from_bytes(gamepad.uuid()),
+                            e
+                        );
+                        Mapping::default(gamepad)
+                    }
+                }
+            })
+            .unwrap_or_else(|| Mapping::default(gamepad))
+    }
+
     /// if `mapping_source()` is `SdlMappings` returns the name of the mapping used by the gamepad.
     /// Otherwise returns `None`.
     ///
@@ -1040,11 +2858,27 @@ impl GamepadData {
         assert_ne!(btn, Button::Unknown);
 
         self.button_code(btn)
-            .or_else(|| btn.to_nec())
+            .or_else(|| btn.to_standard_code())
             .map(|nec| self.state.is_pressed(nec))
             .unwrap_or(false)
     }
 
+    /// Examines cached gamepad state to check button's value. Panics if `btn` is `Unknown`.
+    pub fn button_value(&self, btn: Button) -> f32 {
+        assert_ne!(btn, Button::Unknown);
+
+        self.button_code(btn)
+            .or_else(|| btn.to_standard_code())
+            .map(|nec| self.state.value(nec))
+            .unwrap_or(0.0)
+    }
+
+    /// Returns `true` if button's value is greater than or equal to `threshold`. Panics if `btn` is
+    /// `Unknown`.
+    pub fn is_pressed_with_threshold(&self, btn: Button, threshold: f32) -> bool {
+        self.button_value(btn) >= threshold
+    }
+
     /// Examines cached gamepad state to check axis's value. Panics if `axis` is `Unknown`.
     ///
     /// If you know `Code` of the element that you want to examine, it's recommended to use methods
@@ -1094,8 +2928,87 @@ impl GamepadData {
     }
 }
 
+/// Owned snapshot of gamepad's metadata. See
+/// [`Gilrs::gamepad_summaries()`](struct.Gilrs.html#method.gamepad_summaries).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct GamepadSummary {
+    pub id: GamepadId,
+    /// Same as [`Gamepad::name()`](struct.Gamepad.html#method.name).
+    pub name: String,
+    /// Same as [`Gamepad::os_name()`](struct.Gamepad.html#method.os_name).
+    pub os_name: String,
+    /// Value of [`Gamepad::power_info()`](struct.Gamepad.html#method.power_info). Can be as old as
+    /// [`GilrsBuilder::summary_power_cache_ttl()`](struct.GilrsBuilder.html#method.summary_power_cache_ttl).
+    pub power_info: PowerInfo,
+    pub mapping_source: MappingSource,
+    pub is_ff_supported: bool,
+}
+
+/// Axis ranges measured by
+/// [`Gilrs::finish_axis_calibration()`](struct.Gilrs.html#method.finish_axis_calibration).
+///
+/// Ranges are stored in raw values reported by the device and are keyed by [`Code`], so, like
+/// `Code`, serialized calibration is platform-specific.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct AxisCalibration {
+    axes: Vec<(Code, i32, i32)>,
+}
+
+impl AxisCalibration {
+    /// Returns calibrated minimum and maximum of axis or `None` if axis is not calibrated.
+    pub fn range(&self, code: Code) -> Option<(i32, i32)> {
+        self.axes
+            .iter()
+            .find(|&&(c, ..)| c == code)
+            .map(|&(_, min, max)| (min, max))
+    }
+
+    /// Returns `true` if no axis is calibrated.
+    pub fn is_empty(&self) -> bool {
+        self.axes.is_empty()
+    }
+}
+
+/// Counters returned by [`Gilrs::metrics()`](struct.Gilrs.html#method.metrics).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct Metrics {
+    /// Number of events received from gamepads and force feedback thread. Events created by
+    /// filters are not counted.
+    pub events: u64,
+    /// Number of events dropped by filters or because gamepad was disabled.
+    pub dropped_events: u64,
+    /// Number of `Connected` and `Disconnected` events received from the platform backend,
+    /// including ones suppressed after resume from suspend.
+    pub hotplug_events: u64,
+    /// Number of force feedback effects created with
+    /// [`EffectBuilder`](ff/struct.EffectBuilder.html).
+    pub ff_effects_created: u64,
+    /// Number of times force feedback state wasn't written to a device, because it didn't change
+    /// since last write. See
+    /// [`GilrsBuilder::ff_keep_alive_interval()`](struct.GilrsBuilder.html#method.ff_keep_alive_interval).
+    pub ff_writes_suppressed: u64,
+}
+
+/// Source of dpad events for gamepads that report dpad both as buttons and hat. See
+/// [`GilrsBuilder::dpad_source()`](struct.GilrsBuilder.html#method.dpad_source).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum DPadSource {
+    /// Use `BTN_DPAD_*` buttons and ignore hat.
+    Buttons,
+    /// Use hat and ignore `BTN_DPAD_*` buttons. Hat events are converted to button events by
+    /// [`axis_dpad_to_button`](ev/filter/fn.axis_dpad_to_button.html) filter.
+    Hat,
+}
+
 /// Source of gamepad mappings.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 pub enum MappingSource {
     /// Gamepad uses SDL mappings.
     SdlMappings,
@@ -1106,10 +3019,96 @@ pub enum MappingSource {
     None,
 }
 
+/// How element is used by gamepad's mapping. Returned by
+/// [`Gamepad::code_usage()`](struct.Gamepad.html#method.code_usage).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CodeUsage {
+    /// Element is mapped, possibly to `Button::Unknown` if it doesn't have gilrs equivalent (for
+    /// example paddles).
+    MappedTo(AxisOrBtn),
+    /// Mapping doesn't contain the element.
+    Unmapped,
+}
+
+/// Read-only view of gamepad's mapping. See
+/// [`Gamepad::mapping_info()`](struct.Gamepad.html#method.mapping_info).
+///
+/// ```
+/// use gilrs::{Button, Event, Gilrs};
+///
+/// // Custom filter that converts left stick to dpad only if gamepad doesn't have real one.
+/// fn needs_virtual_dpad(ev: &Event, gilrs: &Gilrs) -> bool {
+///     let info = gilrs.gamepad(ev.id).mapping_info();
+///     info.hats_mapped() == 0 && !info.is_button_mapped(Button::DPadUp)
+/// }
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct MappingInfo<'a> {
+    mapping: &'a Mapping,
+    dual_dpad: bool,
+}
+
+impl MappingInfo<'_> {
+    /// Hat directions mapped by SDL mapping as bit field: `1` is up, `2` is right, `4` is down and
+    /// `8` is left. Always `0` if `source()` is not `SdlMappings`.
+    pub fn hats_mapped(&self) -> u8 {
+        self.mapping.hats_mapped()
+    }
+
+    /// Returns `true` if some element of the gamepad is mapped to `btn`.
+    pub fn is_button_mapped(&self, btn: Button) -> bool {
+        self.mapping.map_rev(&AxisOrBtn::Btn(btn)).is_some()
+    }
+
+    /// Returns `true` if some element of the gamepad is mapped to `axis`.
+    pub fn is_axis_mapped(&self, axis: Axis) -> bool {
+        self.mapping.map_rev(&AxisOrBtn::Axis(axis)).is_some()
+    }
+
+    /// Returns `true` if mapping negates value of axis `code`, see
+    /// [`Gilrs::set_axis_inverted()`](struct.Gilrs.html#method.set_axis_inverted).
+    pub fn is_axis_inverted(&self, code: Code) -> bool {
+        self.mapping.is_inverted(&code.0)
+    }
+
+    /// Same as [`Gamepad::map_name()`](struct.Gamepad.html#method.map_name).
+    pub fn name(&self) -> Option<&str> {
+        if self.mapping.is_default() {
+            None
+        } else {
+            Some(self.mapping.name())
+        }
+    }
+
+    /// Returns line from mapping database that was applied to the gamepad. If database has
+    /// several lines for gamepad's UUID, user-supplied lines win over included ones and among them
+    /// the one whose name is part of gamepad's OS name is used.
+    /// `None` if mapping didn't come from database, for example if it was set with
+    /// [`Gilrs::set_mapping()`](struct.Gilrs.html#method.set_mapping).
+    pub fn sdl_line(&self) -> Option<&str> {
+        self.mapping.sdl_line()
+    }
+
+    /// Same as [`Gamepad::mapping_source()`](struct.Gamepad.html#method.mapping_source).
+    pub fn source(&self) -> MappingSource {
+        mapping_source(self.mapping)
+    }
+
+    /// Returns `true` if gamepad reports dpad both as buttons and hat. Only one of them is used
+    /// (see [`GilrsBuilder::dpad_source()`](struct.GilrsBuilder.html#method.dpad_source)), so
+    /// filters can convert hat events without producing duplicated button events.
+    pub fn has_dual_dpad(&self) -> bool {
+        self.dual_dpad
+    }
+}
+
 /// Gamepad ID.
 ///
 /// It's not possible to create instance of this type directly, but you can obtain one from Gamepad
-/// handle or any event. ID is valid for entire lifetime of `Gilrs` context.
+/// handle or any event. ID is valid for entire lifetime of `Gilrs` context. After gamepad is
+/// disconnected, the same ID can be given to another device, see
+/// [`Gilrs::gamepads()`](struct.Gilrs.html#method.gamepads).
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 pub struct GamepadId(pub(crate) usize);
@@ -1126,22 +3125,75 @@ impl Display for GamepadId {
     }
 }
 
+/// Returns value of resting axis or `None` if range doesn't fit in `i32`.
+fn axis_center(info: &AxisInfo) -> Option<i32> {
+    let i_range = info.max.checked_sub(info.min)?;
+
+    // Symmetric signed ranges (like -512..=511 of flight stick's twist) are centered at 0, other
+    // at the middle (like 127 for 0..=255), which may not be exact middle of the range.
+    if info.min < 0 && matches!(info.min.checked_add(info.max), Some(0 | -1)) {
+        Some(0)
+    } else {
+        Some(info.min + i_range / 2)
+    }
+}
+
+/// Scales both halves separately, so center is mapped to 0.0 and both ends to -1.0 and 1.0.
+fn scale_axis(min: i32, center: i32, max: i32, val: i32) -> f32 {
+    if val < center {
+        (val as f32 - center as f32) / (center as f32 - min as f32)
+    } else if val > center {
+        (val as f32 - center as f32) / (max as f32 - center as f32)
+    } else {
+        0.0
+    }
+}
+
 fn axis_value(info: &AxisInfo, val: i32, axis: Axis) -> f32 {
-    let mut range = info.max as f32 - info.min as f32;
-    let mut val = val as f32 - info.min as f32;
+    let val = if let Some(center) = axis_center(info) {
+        scale_axis(info.min, center, info.max, val)
+    } else {
+        // Range is so large that exact center doesn't matter.
+        let range = info.max as f32 - info.min as f32;
+        (val as f32 - info.min as f32) / range * 2.0 - 1.0
+    };
+
+    finish_axis_value(val, axis)
+}
+
+/// Like `axis_value()`, but `min` and `max` of axis are replaced by calibrated `range`. Center
+/// stays the same as in `info`.
+fn calibrated_axis_value(info: &AxisInfo, range: (i32, i32), val: i32, axis: Axis) -> f32 {
+    let center = axis_center(info).unwrap_or(info.min / 2 + info.max / 2);
 
-    if let Some(i_range) = info.max.checked_sub(info.min) {
-        // Only consider adjusting range & val if calculating the range doesn't cause overflow.  If
-        // the range is so large overflow occurs, adjusting values by 1.0 would be insignificant.
-        if i_range % 2 == 1 {
-            // Add one to range and val, so value at center (like 127/255) will be mapped 0.0
-            range += 1.0;
-            val += 1.0;
+    finish_axis_value(scale_axis(range.0, center, range.1, val), axis)
+}
+
+/// Part of calibrated range that is cut off on each side of axis, so that observed extremes
+/// reliably reach -1.0 and 1.0.
+const CALIBRATION_MARGIN: f64 = 0.05;
+
+/// Part of device's range on one side of center that axis has to travel for that side to be
+/// calibrated. Smaller movement is most likely just noise of axis at rest.
+const MIN_CALIBRATION_TRAVEL: f64 = 0.25;
+
+/// Returns calibrated range of axis which was observed between `min` and `max`.
+fn calibrated_range(info: &AxisInfo, min: i32, max: i32) -> (i32, i32) {
+    let center = axis_center(info).unwrap_or(info.min / 2 + info.max / 2);
+    let calibrate = |extreme: i32, device_extreme: i32| {
+        let center = center as f64;
+        let travel = (extreme as f64 - center) / (device_extreme as f64 - center);
+        if travel >= MIN_CALIBRATION_TRAVEL {
+            (center + (extreme as f64 - center) * (1.0 - CALIBRATION_MARGIN)).round() as i32
+        } else {
+            device_extreme
         }
-    }
+    };
 
-    val = val / range * 2.0 - 1.0;
+    (calibrate(min, info.min), calibrate(max, info.max))
+}
 
+fn finish_axis_value(mut val: f32, axis: Axis) -> f32 {
     if gilrs_core::IS_Y_AXIS_REVERSED
         && (axis == Axis::LeftStickY || axis == Axis::RightStickY || axis == Axis::DPadY)
         && val != 0.0
@@ -1152,6 +3204,15 @@ fn axis_value(info: &AxisInfo, val: i32, axis: Axis) -> f32 {
     utils::clamp(val, -1.0, 1.0)
 }
 
+fn mapping_source(mapping: &Mapping) -> MappingSource {
+    if mapping.is_default() {
+        // TODO: check if it's Driver or None
+        MappingSource::Driver
+    } else {
+        MappingSource::SdlMappings
+    }
+}
+
 fn btn_value(info: &AxisInfo, val: i32) -> f32 {
     let range = info.max as f32 - info.min as f32;
     let mut val = val as f32 - info.min as f32;
@@ -1160,15 +3221,98 @@ fn btn_value(info: &AxisInfo, val: i32) -> f32 {
     utils::clamp(val, 0.0, 1.0)
 }
 
+/// Largest value of counter, `inc()` wraps to 0 after it.
+const MAX_COUNTER: u64 = INVALID_COUNTER - 1;
+
+/// Default of `GilrsBuilder::summary_power_cache_ttl()`.
+const DEFAULT_SUMMARY_POWER_CACHE_TTL: Duration = Duration::from_secs(1);
+/// Default of `GilrsBuilder::ff_keep_alive_interval()`.
+const DEFAULT_FF_KEEP_ALIVE_INTERVAL: Duration = Duration::from_millis(500);
+/// Longest `GilrsBuilder::ff_keep_alive_interval()`. Linux limits length of written state to
+/// `u16::MAX` milliseconds and it has to last until next keep-alive write.
+const MAX_FF_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Minimal difference between wall clock and monotonic clock that is treated as suspend.
+const SUSPEND_THRESHOLD: Duration = Duration::from_secs(5);
+/// How long hotplug events are batched after resume.
+const RESUME_BATCH_PERIOD: Duration = Duration::from_secs(3);
+
+/// Detects system suspend and batches hotplug events that follow it.
+#[derive(Debug)]
+struct SuspendGuard {
+    last_poll: Option<(Instant, SystemTime)>,
+    batch_until: Option<Instant>,
+    // First gamepad whose hotplug event was batched.
+    batched: Option<GamepadId>,
+}
+
+impl SuspendGuard {
+    fn new() -> Self {
+        SuspendGuard {
+            last_poll: None,
+            batch_until: None,
+            batched: None,
+        }
+    }
+
+    /// Starts batching if wall clock advanced more than monotonic clock since last call.
+    fn check(&mut self, now: Instant, wall: SystemTime) {
+        if let Some((last, last_wall)) = self.last_poll {
+            let elapsed = now.duration_since(last);
+            let suspended = wall
+                .duration_since(last_wall)
+                .is_ok_and(|wall_elapsed| wall_elapsed > elapsed + SUSPEND_THRESHOLD);
+
+            if suspended {
+                debug!("System resumed from suspend, batching hotplug events");
+                self.batch_until = Some(now + RESUME_BATCH_PERIOD);
+            }
+        }
+
+        self.last_poll = Some((now, wall));
+    }
+
+    fn is_batching(&self) -> bool {
+        self.batch_until.is_some()
+    }
+
+    fn absorb(&mut self, id: GamepadId) {
+        self.batched.get_or_insert(id);
+    }
+
+    /// Time left until batch can be finished, if there is anything to finish.
+    fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.batched?;
+        self.batch_until
+            // Some backends round timeout down to milliseconds.
+            .map(|until| until.saturating_duration_since(now) + Duration::from_millis(1))
+    }
+
+    /// Ends batching period if it elapsed. Returns ID to use for `Rescanned` event if any hotplug
+    /// event was batched.
+    fn finish(&mut self, now: Instant) -> Option<GamepadId> {
+        match self.batch_until {
+            Some(until) if now >= until => {
+                self.batch_until = None;
+                self.batched.take()
+            }
+            _ => None,
+        }
+    }
+}
+
 /// Error type which can be returned when creating `Gilrs`.
 #[non_exhaustive]
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum Error {
     /// Gilrs does not support current platform, but you can use dummy context from this error if
     /// gamepad input is not essential.
     NotImplemented(Gilrs),
     /// Either `pressed ≤ released` or one of values is outside [0.0, 1.0] range.
     InvalidAxisToBtn,
+    /// Dpad axis threshold is outside (0.0, 1.0] range.
+    InvalidDPadAxisThreshold,
     /// Platform specific error.
     Other(Box<dyn error::Error + Send + Sync + 'static>),
 }
@@ -1180,6 +3324,9 @@ impl Display for Error {
             Error::InvalidAxisToBtn => f.write_str(
                 "Either `pressed ≤ released` or one of values is outside [0.0, 1.0] range.",
             ),
+            Error::InvalidDPadAxisThreshold => {
+                f.write_str("Dpad axis threshold is outside (0.0, 1.0] range.")
+            }
             Error::Other(ref e) => e.fmt(f),
         }
     }
@@ -1194,39 +3341,2240 @@ impl error::Error for Error {
     }
 }
 
-const _: () = {
-    const fn assert_send<T: Send>() {}
+/// Queue of pending events that doesn't deliver events of gamepad before its `Connected` or after
+/// its `Disconnected` event.
+///
+/// Events are stored in order they were added and the rules are applied when they are removed:
+/// event queued before `Connected` of its gamepad is delayed until that `Connected` is returned and
+/// event queued after `Disconnected` of its gamepad, that is not followed by `Connected`, is
+/// returned before that `Disconnected`. Events are never discarded.
+#[derive(Debug, Default)]
+struct EventQueue {
+    // Events with time when backend event they were created from was read.
+    events: VecDeque<(Event, Option<Instant>)>,
+    // Number of queued `Connected` and `Disconnected` events. Ordering rules only have to be
+    // checked if it's not zero.
+    hotplug: usize,
+    arrival: Option<Instant>,
+    // Capacity reserved by `GilrsBuilder::preallocate_events()`, 0 if nothing was reserved.
+    preallocated: usize,
+}
 
-    #[cfg(not(target_arch = "wasm32"))]
-    assert_send::<Gilrs>();
-};
+impl EventQueue {
+    fn with_capacity(capacity: usize) -> Self {
+        let events = VecDeque::with_capacity(capacity);
+        EventQueue {
+            preallocated: if capacity > 0 { events.capacity() } else { 0 },
+            events,
+            hotplug: 0,
+            arrival: None,
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::{axis_value, btn_value, Axis, AxisInfo};
+    fn is_preallocated(&self) -> bool {
+        self.preallocated > 0
+    }
 
-    #[test]
-    fn axis_value_documented_case() {
-        let info = AxisInfo {
-            min: 0,
-            max: 255,
-            deadzone: None,
-        };
-        let axis = Axis::LeftStickY;
-        assert_eq!(0., axis_value(&info, 127, axis));
+    fn check_capacity(&mut self) {
+        // Burst of events larger than reserved capacity is valid, queue just has to grow.
+        if self.is_preallocated() && self.events.capacity() > self.preallocated {
+            warn!(
+                "More events queued than reserved with GilrsBuilder::preallocate_events(), queue \
+                 grew from {} to {} events.",
+                self.preallocated,
+                self.events.capacity()
+            );
+            self.preallocated = self.events.capacity();
+        }
     }
 
-    #[test]
-    fn axis_value_overflow() {
-        let info = AxisInfo {
-            min: i32::MIN,
-            max: i32::MAX,
-            deadzone: None,
-        };
-        let axis = Axis::LeftStickY;
+    /// Sets arrival time of events pushed until next call.
+    fn set_arrival(&mut self, arrival: Option<Instant>) {
+        self.arrival = arrival;
+    }
 
-        assert_eq!(0., axis_value(&info, -1, axis));
-        assert_eq!(0., axis_value(&info, 0, axis));
+    fn is_hotplug(ev: &Event) -> bool {
+        matches!(ev.event, EventType::Connected | EventType::Disconnected)
+    }
+
+    fn push_back(&mut self, ev: Event) {
+        self.insert(self.events.len(), ev);
+    }
+
+    fn pop_front(&mut self) -> Option<(Event, Option<Instant>)> {
+        let idx = if self.hotplug > 0 { self.next_idx() } else { 0 };
+        let entry = self.events.remove(idx)?;
+        if Self::is_hotplug(&entry.0) {
+            self.hotplug -= 1;
+        }
+
+        Some(entry)
+    }
+
+    /// Returns index of event that should be returned next.
+    fn next_idx(&self) -> usize {
+        let front = match self.events.front() {
+            Some((front, _)) => *front,
+            None => return 0,
+        };
+        let same_gamepad = self
+            .events
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, (ev, _))| ev.id == front.id);
+
+        match front.event {
+            EventType::Disconnected => {
+                let mut first = None;
+                for (idx, (ev, _)) in same_gamepad {
+                    match ev.event {
+                        // Events before `Connected` belong to the next connection.
+                        EventType::Connected => return 0,
+                        EventType::Disconnected | EventType::Rescanned => (),
+                        _ => first = first.or(Some(idx)),
+                    }
+                }
+
+                first.unwrap_or(0)
+            }
+            EventType::Connected | EventType::Rescanned => 0,
+            _ => {
+                for (idx, (ev, _)) in same_gamepad {
+                    match ev.event {
+                        EventType::Connected => return idx,
+                        EventType::Disconnected => return 0,
+                        _ => (),
+                    }
+                }
+
+                0
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    fn insert(&mut self, idx: usize, ev: Event) {
+        if Self::is_hotplug(&ev) {
+            self.hotplug += 1;
+        }
+        self.events.insert(idx, (ev, self.arrival));
+        self.check_capacity();
+    }
+
+    fn get(&self, idx: usize) -> Option<Event> {
+        self.events.get(idx).map(|&(ev, _)| ev)
+    }
+}
+
+/// Read-only view of cached gamepad state that can be shared between threads.
+///
+/// Created by [`Gilrs::state_handle()`](struct.Gilrs.html#method.state_handle). Cloning the handle
+/// is cheap.
+#[derive(Clone, Debug)]
+pub struct GilrsStateHandle {
+    inner: Arc<RwLock<Vec<GamepadState>>>,
+}
+
+impl GilrsStateHandle {
+    /// Returns copy of cached state of gamepad with given ID or `None` if gamepad was not observed
+    /// by `Gilrs` yet.
+    pub fn state(&self, id: GamepadId) -> Option<GamepadState> {
+        self.read(|states| states.get(id.0).cloned())
+    }
+
+    /// Returns `true` if given button of gamepad is pressed.
+    pub fn is_pressed(&self, id: GamepadId, btn: Code) -> bool {
+        self.read(|states| states.get(id.0).is_some_and(|s| s.is_pressed(btn)))
+    }
+
+    /// Returns value of given axis or button of gamepad or 0.0 when there is no information
+    /// about it.
+    pub fn value(&self, id: GamepadId, el: Code) -> f32 {
+        self.read(|states| states.get(id.0).map_or(0.0, |s| s.value(el)))
+    }
+
+    fn read<T>(&self, f: impl FnOnce(&[GamepadState]) -> T) -> T {
+        f(&self.inner.read().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+const _: () = {
+    const fn assert_send<T: Send>() {}
+    const fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<GilrsStateHandle>();
+
+    // `Gilrs` is documented to be `Send` on all platforms other than wasm.
+    #[cfg(not(target_arch = "wasm32"))]
+    assert_send::<Gilrs>();
+    #[cfg(not(target_arch = "wasm32"))]
+    assert_send::<GilrsBuilder>();
+};
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        axis_value, btn_value, calibrated_axis_value, calibrated_range, has_dual_dpad,
+        is_ignored_dpad_event, Axis, AxisCalibration, AxisInfo, AxisOrBtn, Button, CodeUsage,
+        DPadSource, Error, Event, EventSource, EventType, Gamepad, GamepadId, Gilrs, GilrsBuilder,
+        GilrsStateHandle, GuidComponents, LatencyStats, Mapping, MappingData, MappingError,
+        MappingSource, Metrics, PowerInfo, RawEvent, RawEventType, ReplayDevice, SuspendGuard,
+        TimestampSource, Uuid, INVALID_COUNTER, MAX_COUNTER, RESUME_BATCH_PERIOD,
+    };
+    use crate::ev::{state::GamepadState, Code, DropReason};
+
+    use gilrs_core::native_ev_codes as nec;
+
+    use std::cmp::Ordering;
+    use std::iter;
+    use std::sync::{Arc, RwLock};
+    use std::thread;
+    use std::time::{Duration, Instant, SystemTime};
+
+    fn gilrs() -> Gilrs {
+        match Gilrs::new() {
+            Ok(gilrs) | Err(Error::NotImplemented(gilrs)) => gilrs,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    fn build(builder: GilrsBuilder) -> Gilrs {
+        match builder.build() {
+            Ok(gilrs) | Err(Error::NotImplemented(gilrs)) => gilrs,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    #[test]
+    fn test_gamepad() {
+        let mut gilrs = gilrs();
+        let id = gilrs.register_test_gamepad("Test gamepad", [1; 16]);
+        let south = gilrs.gamepad(id).button_code(Button::South).unwrap();
+        let lstick_x = gilrs.gamepad(id).axis_code(Axis::LeftStickX).unwrap();
+
+        assert_eq!(
+            Some(EventType::Connected),
+            gilrs.next_event().map(|ev| ev.event)
+        );
+        let gamepad = gilrs.connected_gamepad(id).unwrap();
+        assert_eq!("Test gamepad", gamepad.name());
+        assert_eq!([1; 16], gamepad.uuid());
+        assert!(gilrs.gamepads().any(|(gp_id, _)| gp_id == id));
+
+        gilrs.insert_event(Event::new(
+            id,
+            EventType::ButtonPressed(Button::South, south),
+        ));
+        gilrs.insert_event(Event::new(
+            id,
+            EventType::AxisChanged(Axis::LeftStickX, 0.5, lstick_x),
+        ));
+        while gilrs.next_event().is_some() {}
+        assert!(gilrs.gamepad(id).is_pressed(Button::South));
+        assert_eq!(0.5, gilrs.gamepad(id).value(Axis::LeftStickX));
+
+        gilrs.insert_event(Event::new(
+            id,
+            EventType::ButtonReleased(Button::South, south),
+        ));
+        gilrs.insert_event(Event::new(
+            id,
+            EventType::AxisChanged(Axis::LeftStickX, 0.0, lstick_x),
+        ));
+        while gilrs.next_event().is_some() {}
+        assert!(!gilrs.gamepad(id).is_pressed(Button::South));
+        assert_eq!(0.0, gilrs.gamepad(id).value(Axis::LeftStickX));
+
+        // There is no device to grab.
+        assert!(gilrs.gamepad(id).grab().is_err());
+    }
+
+    #[test]
+    fn latency_stats() {
+        let mut measured = build(GilrsBuilder::new().measure_latency(true));
+        let id = measured.register_test_gamepad("Test gamepad", [3; 16]);
+        while measured.next_event().is_some() {}
+
+        measured.insert_event(Event {
+            id,
+            event: EventType::ButtonPressed(Button::South, Code(nec::BTN_SOUTH)),
+            time: SystemTime::now() - Duration::from_millis(20),
+            source: EventSource::Hardware,
+        });
+        measured.insert_event(Event {
+            id,
+            event: EventType::ButtonReleased(Button::South, Code(nec::BTN_SOUTH)),
+            time: SystemTime::now() + Duration::from_secs(1),
+            source: EventSource::Hardware,
+        });
+        // Not measured.
+        measured.insert_event(Event::new(
+            id,
+            EventType::ButtonPressed(Button::East, Code(nec::BTN_EAST)),
+        ));
+        while measured.next_event().is_some() {}
+
+        let stats = measured.latency_stats(id);
+        assert_eq!(stats.samples, 2);
+        assert_eq!(stats.clock_skew, 1);
+        assert!(stats.max >= Duration::from_millis(20), "{:?}", stats.max);
+        assert_eq!(
+            measured.latency_stats(GamepadId(7)),
+            LatencyStats::default()
+        );
+
+        measured.reset_latency_stats(id);
+        assert_eq!(measured.latency_stats(id), LatencyStats::default());
+
+        let mut disabled = gilrs();
+        let id = disabled.register_test_gamepad("Test gamepad", [3; 16]);
+        disabled.insert_event(Event {
+            id,
+            event: EventType::ButtonPressed(Button::South, Code(nec::BTN_SOUTH)),
+            time: SystemTime::now(),
+            source: EventSource::Hardware,
+        });
+        while disabled.next_event().is_some() {}
+        assert_eq!(disabled.latency_stats(id), LatencyStats::default());
+        assert_eq!(disabled.last_event_latency(), None);
+    }
+
+    #[test]
+    fn last_event_latency() {
+        let mut measured = build(GilrsBuilder::new().measure_latency(true));
+        let id = measured.register_test_gamepad("Test gamepad", [3; 16]);
+        while measured.next_event().is_some() {}
+        assert_eq!(measured.last_event_latency(), None);
+
+        // Events queued while processing backend event share its arrival time.
+        measured
+            .events
+            .set_arrival(Some(Instant::now() - Duration::from_millis(20)));
+        measured.events.push_back(Event::new(
+            id,
+            EventType::ButtonPressed(Button::South, Code(nec::BTN_SOUTH)),
+        ));
+        measured.events.set_arrival(None);
+        assert!(measured.next_event().is_some());
+        let latency = measured.last_event_latency().unwrap();
+        assert!(latency >= Duration::from_millis(20), "{:?}", latency);
+
+        // Inserted events keep previous value.
+        measured.insert_event(Event::new(
+            id,
+            EventType::ButtonReleased(Button::South, Code(nec::BTN_SOUTH)),
+        ));
+        assert!(measured.next_event().is_some());
+        assert_eq!(measured.last_event_latency(), Some(latency));
+    }
+
+    #[test]
+    fn compact() {
+        let mut gilrs = gilrs();
+        let connected = gilrs.register_test_gamepad("Connected", [1; 16]);
+        let disconnected = gilrs.register_test_gamepad("Disconnected", [2; 16]);
+        for id in [connected, disconnected] {
+            gilrs.insert_event(Event::new(
+                id,
+                EventType::ButtonPressed(Button::South, Code(nec::BTN_SOUTH)),
+            ));
+        }
+        while gilrs.next_event().is_some() {}
+        // Test gamepads are never disconnected by backend.
+        gilrs.gamepads_data[disconnected.0].test_gamepad = None;
+
+        gilrs.compact();
+
+        assert!(gilrs.gamepad(connected).is_pressed(Button::South));
+        let gamepad = gilrs.gamepad(disconnected);
+        assert!(!gamepad.is_pressed(Button::South));
+        assert!(gamepad.state().buttons().next().is_none());
+        assert_eq!(
+            gamepad.mapping_source(),
+            gilrs.gamepad(connected).mapping_source()
+        );
+    }
+
+    #[test]
+    fn metrics() {
+        let mut with_metrics = build(GilrsBuilder::new().enable_metrics(true));
+        let id = with_metrics.register_test_gamepad("Test gamepad", [3; 16]);
+        let code = Code(nec::BTN_SOUTH);
+
+        assert_eq!(with_metrics.metrics(), Metrics::default());
+        with_metrics.insert_event(Event::new(id, EventType::Dropped(None)));
+        with_metrics.insert_event(Event::new(
+            id,
+            EventType::ButtonPressed(Button::South, code),
+        ));
+        while with_metrics.next_event().is_some() {}
+
+        let metrics = with_metrics.metrics();
+        // `Connected` from `register_test_gamepad()` is not reported by backend, so it is not
+        // counted as hotplug event.
+        assert_eq!(metrics.events, 3);
+        assert_eq!(metrics.dropped_events, 1);
+        assert_eq!(metrics.hotplug_events, 0);
+        assert_eq!(metrics.ff_effects_created, 0);
+
+        // Disabled by default.
+        let mut gilrs = gilrs();
+        let id = gilrs.register_test_gamepad("Test gamepad", [3; 16]);
+        gilrs.insert_event(Event::new(id, EventType::Dropped(None)));
+        while gilrs.next_event().is_some() {}
+        assert_eq!(gilrs.metrics(), Metrics::default());
+    }
+
+    #[test]
+    fn drop_reason() {
+        use crate::ev::filter::{Filter, Jitter};
+        use crate::DropReason;
+
+        let mut gilrs = gilrs();
+        let id = gilrs.register_test_gamepad("Test gamepad", [3; 16]);
+        while gilrs.next_event().is_some() {}
+        let code = Code(nec::AXIS_LSTICKX);
+        let axis = |val| Event::new(id, EventType::AxisChanged(Axis::LeftStickX, val, code));
+        let drop_all = |ev: Option<Event>, _: &mut Gilrs| ev.map(Event::drop);
+
+        gilrs.update(&axis(0.5));
+        assert_eq!(
+            axis(0.505)
+                .filter_ev(&Jitter::new(), &mut gilrs)
+                .unwrap()
+                .event,
+            EventType::Dropped(Some(DropReason::Jitter))
+        );
+        assert_eq!(
+            axis(0.7).filter_ev(&drop_all, &mut gilrs).unwrap().event,
+            EventType::Dropped(Some(DropReason::UserFilter))
+        );
+        // Reason given by previous filter is kept.
+        assert_eq!(
+            axis(0.7)
+                .drop_with(DropReason::Jitter)
+                .filter_ev(&drop_all, &mut gilrs)
+                .unwrap()
+                .event,
+            EventType::Dropped(Some(DropReason::Jitter))
+        );
+    }
+
+    #[test]
+    fn dual_dpad() {
+        use gilrs_core::EventType as Raw;
+
+        let hat_only = [nec::AXIS_LSTICKX, nec::AXIS_DPADX, nec::AXIS_DPADY];
+        let buttons = [nec::BTN_SOUTH, nec::BTN_DPAD_UP, nec::BTN_DPAD_DOWN];
+        let btn_ev = Raw::ButtonPressed(nec::BTN_DPAD_UP);
+        let hat_ev = Raw::AxisValueChanged(-1, nec::AXIS_DPADY);
+        let other_ev = Raw::AxisValueChanged(-1, nec::AXIS_LSTICKX);
+
+        assert!(!has_dual_dpad(&[], &hat_only));
+        assert!(!has_dual_dpad(&buttons, &[]));
+        assert!(has_dual_dpad(&buttons, &hat_only));
+
+        // Only hat or only buttons.
+        for source in [DPadSource::Buttons, DPadSource::Hat] {
+            assert!(!is_ignored_dpad_event(false, source, &hat_ev));
+            assert!(!is_ignored_dpad_event(false, source, &btn_ev));
+            assert!(!is_ignored_dpad_event(true, source, &other_ev));
+        }
+
+        // Both, only preferred source is used.
+        let source = DPadSource::Buttons;
+        assert!(!is_ignored_dpad_event(true, source, &btn_ev));
+        assert!(is_ignored_dpad_event(true, source, &hat_ev));
+        let source = DPadSource::Hat;
+        assert!(is_ignored_dpad_event(true, source, &btn_ev));
+        assert!(!is_ignored_dpad_event(true, source, &hat_ev));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn dual_dpad_events() {
+        let hat = AxisInfo {
+            min: -1,
+            max: 1,
+            deadzone: None,
+        };
+        let device = ReplayDevice {
+            name: "Dual dpad".into(),
+            uuid: [13; 16],
+            buttons: vec![nec::BTN_SOUTH, nec::BTN_DPAD_UP],
+            axes: vec![(nec::AXIS_DPADX, hat), (nec::AXIS_DPADY, hat)],
+        };
+
+        for source in [DPadSource::Buttons, DPadSource::Hat] {
+            let mut gilrs = build(GilrsBuilder::new().dpad_source(source));
+            while gilrs.next_event().is_some() {}
+            let id = gilrs.add_replay_gamepad(&device).unwrap();
+            gilrs.insert_raw_event(RawEvent::new(id.0, RawEventType::Connected));
+            while gilrs.next_event().is_some() {}
+            assert!(gilrs.gamepad(id).mapping_info().has_dual_dpad());
+
+            let mut events = |event| {
+                gilrs.insert_raw_event(RawEvent::new(id.0, event));
+                iter::from_fn(|| gilrs.next_event())
+                    .map(|ev| ev.event)
+                    .collect::<Vec<_>>()
+            };
+            // Both sources report the same press and only one of them is used.
+            let pressed = [
+                events(RawEventType::ButtonPressed(nec::BTN_DPAD_UP)),
+                events(RawEventType::AxisValueChanged(-1, nec::AXIS_DPADY)),
+            ];
+            let released = [
+                events(RawEventType::ButtonReleased(nec::BTN_DPAD_UP)),
+                events(RawEventType::AxisValueChanged(0, nec::AXIS_DPADY)),
+            ];
+
+            let up = Code(nec::BTN_DPAD_UP);
+            let used = match source {
+                DPadSource::Buttons => 0,
+                DPadSource::Hat => 1,
+            };
+            assert_eq!(
+                pressed[used],
+                [
+                    EventType::ButtonPressed(Button::DPadUp, up),
+                    EventType::ButtonChanged(Button::DPadUp, 1.0, up)
+                ]
+            );
+            assert_eq!(
+                released[used],
+                [
+                    EventType::ButtonReleased(Button::DPadUp, up),
+                    EventType::ButtonChanged(Button::DPadUp, 0.0, up)
+                ]
+            );
+            assert_eq!(pressed[1 - used], []);
+            assert_eq!(released[1 - used], []);
+        }
+    }
+
+    #[test]
+    fn pump_until() {
+        let mut gilrs = gilrs();
+        let id = gilrs.register_test_gamepad("Test gamepad", [4; 16]);
+        while gilrs.next_event().is_some() {}
+        let south = gilrs.gamepad(id).button_code(Button::South).unwrap();
+        for _ in 0..10 {
+            gilrs.insert_event(Event::new(
+                id,
+                EventType::ButtonPressed(Button::South, south),
+            ));
+            gilrs.insert_event(Event::new(
+                id,
+                EventType::ButtonReleased(Button::South, south),
+            ));
+        }
+        gilrs.insert_event(Event::new(
+            id,
+            EventType::ButtonPressed(Button::South, south),
+        ));
+
+        // Deadline already passed, everything stays queued.
+        assert_eq!(gilrs.pump_until(Instant::now()), 0);
+        assert!(!gilrs.gamepad(id).is_pressed(Button::South));
+
+        assert_eq!(
+            gilrs.pump_until(Instant::now() + Duration::from_secs(60)),
+            21
+        );
+        assert!(gilrs.gamepad(id).is_pressed(Button::South));
+        assert!(gilrs.next_event().is_none());
+    }
+
+    #[test]
+    fn ff_keep_alive_interval() {
+        let builder = GilrsBuilder::new().ff_keep_alive_interval(Duration::from_secs(10));
+        assert_eq!(builder.ff_keep_alive_interval, Duration::from_secs(10));
+        let builder = GilrsBuilder::new().ff_keep_alive_interval(Duration::from_secs(70));
+        assert_eq!(builder.ff_keep_alive_interval, Duration::from_secs(60));
+        let builder = GilrsBuilder::new().ff_keep_alive_interval(Duration::MAX);
+        assert_eq!(builder.ff_keep_alive_interval, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn not_observed_gamepad() {
+        let gilrs = gilrs();
+        let id = GamepadId(usize::MAX / 2);
+        let gamepad = gilrs.gamepad(id);
+        assert_eq!(gamepad.id(), id);
+        assert!(!gamepad.is_connected());
+        assert_eq!(gamepad.buttons().count(), 0);
+        assert_eq!(gamepad.axes().count(), 0);
+        assert!(!gamepad.is_pressed(Button::South));
+        assert!(gilrs.connected_gamepad(id).is_none());
+    }
+
+    #[test]
+    fn preallocate_events() {
+        let mut preallocated = build(GilrsBuilder::new().preallocate_events(32));
+        let id = preallocated.register_test_gamepad("Test gamepad", [3; 16]);
+        while preallocated.next_event().is_some() {}
+        let capacity = preallocated.events.events.capacity();
+        assert!(capacity >= 32);
+        assert!(preallocated.events.is_preallocated());
+
+        for _ in 0..capacity {
+            preallocated.insert_event(Event::new(
+                id,
+                EventType::ButtonPressed(Button::South, Code(nec::BTN_SOUTH)),
+            ));
+        }
+        while preallocated.next_event().is_some() {}
+        assert_eq!(preallocated.events.events.capacity(), capacity);
+
+        // Burst larger than reserved capacity only grows the queue.
+        for _ in 0..capacity + 1 {
+            preallocated.insert_event(Event::new(
+                id,
+                EventType::ButtonPressed(Button::South, Code(nec::BTN_SOUTH)),
+            ));
+        }
+        assert_eq!(
+            iter::from_fn(|| preallocated.next_event()).count(),
+            capacity + 1
+        );
+        assert!(preallocated.events.preallocated > capacity);
+
+        assert!(!gilrs().events.is_preallocated());
+    }
+
+    #[test]
+    fn event_ordering() {
+        let mut gilrs = gilrs();
+        let a = gilrs.register_test_gamepad("Test gamepad", [3; 16]);
+        let b = gilrs.register_test_gamepad("Test gamepad", [4; 16]);
+        while gilrs.next_event().is_some() {}
+        let press = EventType::ButtonPressed(Button::South, Code(nec::BTN_SOUTH));
+        let release = EventType::ButtonReleased(Button::South, Code(nec::BTN_SOUTH));
+        let mut deliver = |events: &[(GamepadId, EventType)]| {
+            for &(id, event) in events {
+                gilrs.insert_event(Event::new(id, event));
+            }
+            iter::from_fn(|| gilrs.next_event())
+                .map(|ev| (ev.id, ev.event))
+                .collect::<Vec<_>>()
+        };
+
+        // Interleaved batch, `a` disconnects with queued events.
+        assert_eq!(
+            deliver(&[
+                (a, press),
+                (b, press),
+                (a, EventType::Disconnected),
+                (a, release),
+                (b, release),
+            ]),
+            [
+                (a, press),
+                (b, press),
+                (a, release),
+                (a, EventType::Disconnected),
+                (b, release),
+            ]
+        );
+
+        // `Connected` arrives after events of the same gamepad.
+        assert_eq!(
+            deliver(&[
+                (a, press),
+                (b, press),
+                (a, EventType::Connected),
+                (a, release)
+            ]),
+            [
+                (a, EventType::Connected),
+                (a, press),
+                (b, press),
+                (a, release)
+            ]
+        );
+
+        // Reconnection in one batch.
+        assert_eq!(
+            deliver(&[
+                (a, release),
+                (a, EventType::Disconnected),
+                (b, release),
+                (a, press),
+                (a, EventType::Connected),
+            ]),
+            [
+                (a, release),
+                (a, EventType::Disconnected),
+                (b, release),
+                (a, EventType::Connected),
+                (a, press),
+            ]
+        );
+    }
+
+    #[test]
+    fn dpad_axis_threshold() {
+        use crate::ev::filter::{axis_dpad_to_button, Filter};
+
+        for threshold in [0.0, -0.5, 1.5, f32::NAN] {
+            assert!(matches!(
+                GilrsBuilder::new().dpad_axis_threshold(threshold).build(),
+                Err(Error::InvalidDPadAxisThreshold)
+            ));
+        }
+
+        let right = Code(nec::BTN_DPAD_RIGHT);
+        let pressed = EventType::ButtonPressed(Button::DPadRight, right);
+        let released = EventType::ButtonReleased(Button::DPadRight, right);
+
+        for (threshold, expected) in [(None, None), (Some(0.5), Some(pressed))] {
+            let mut builder = GilrsBuilder::new().with_default_filters(false);
+            if let Some(threshold) = threshold {
+                builder = builder.dpad_axis_threshold(threshold);
+            }
+            let mut gilrs = build(builder);
+            let id = gilrs.register_test_gamepad("Test gamepad", [5; 16]);
+            gilrs.gamepads_data[id.0].mapping = Mapping::parse_sdl_mapping(
+                "03000000000000000000000000000000,Test gamepad,\
+                 dpup:h0.1,dpdown:h0.4,dpleft:h0.8,dpright:h0.2,",
+                &[],
+                &[],
+            )
+            .unwrap();
+            while gilrs.next_event().is_some() {}
+
+            let mut dpad_x = |val| {
+                let ev = Event::new(
+                    id,
+                    EventType::AxisChanged(Axis::DPadX, val, Code(nec::AXIS_DPADX)),
+                );
+                let ev = ev.filter_ev(&axis_dpad_to_button, &mut gilrs).unwrap();
+                while gilrs.next_event().is_some() {}
+                Some(ev).filter(|ev| !ev.is_dropped()).map(|ev| ev.event)
+            };
+
+            // Hat that reports 0.7 at full deflection.
+            assert_eq!(dpad_x(0.7), expected);
+            assert_eq!(dpad_x(0.7), None);
+            assert_eq!(dpad_x(0.0), expected.map(|_| released));
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn hat_dpad_repeat() {
+        use crate::ev::filter::{Filter, Repeat};
+        use std::thread;
+        use std::time::Duration;
+
+        let mut gilrs = gilrs();
+        while gilrs.next_event().is_some() {}
+
+        let hat = AxisInfo {
+            min: -1,
+            max: 1,
+            deadzone: None,
+        };
+        let device = ReplayDevice {
+            name: "Hat gamepad".into(),
+            uuid: [7; 16],
+            buttons: vec![nec::BTN_SOUTH],
+            axes: vec![(nec::AXIS_DPADX, hat), (nec::AXIS_DPADY, hat)],
+        };
+        let id = gilrs.add_replay_gamepad(&device).unwrap();
+        gilrs.insert_raw_event(RawEvent::new(id.0, RawEventType::Connected));
+        while gilrs.next_event().is_some() {}
+
+        let repeat = Repeat {
+            after: Duration::from_millis(20),
+            every: Duration::from_millis(10),
+        };
+        let events = |gilrs: &mut Gilrs| {
+            let mut events = Vec::new();
+            while let Some(ev) = gilrs.next_event().filter_ev(&repeat, gilrs) {
+                gilrs.update(&ev);
+                events.push(ev.event);
+            }
+            events
+        };
+        let right = Code(nec::BTN_DPAD_RIGHT);
+
+        gilrs.insert_raw_event(RawEvent::new(
+            id.0,
+            RawEventType::AxisValueChanged(1, nec::AXIS_DPADX),
+        ));
+        assert_eq!(
+            events(&mut gilrs),
+            [
+                EventType::ButtonPressed(Button::DPadRight, right),
+                EventType::ButtonChanged(Button::DPadRight, 1.0, right),
+            ]
+        );
+
+        thread::sleep(Duration::from_millis(50));
+        let repeated = events(&mut gilrs);
+        assert!(!repeated.is_empty());
+        assert!(repeated
+            .iter()
+            .all(|ev| *ev == EventType::ButtonRepeated(Button::DPadRight, right)));
+
+        gilrs.insert_raw_event(RawEvent::new(
+            id.0,
+            RawEventType::AxisValueChanged(0, nec::AXIS_DPADX),
+        ));
+        assert_eq!(
+            events(&mut gilrs),
+            [
+                EventType::ButtonReleased(Button::DPadRight, right),
+                EventType::ButtonChanged(Button::DPadRight, 0.0, right),
+            ]
+        );
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(events(&mut gilrs), []);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn code_usage() {
+        let mut gilrs = gilrs();
+        while gilrs.next_event().is_some() {}
+
+        let buttons = [nec::BTN_SOUTH, nec::BTN_MODE, nec::BTN_EAST, nec::BTN_WEST];
+        let device = ReplayDevice {
+            name: "Partially mapped gamepad".into(),
+            uuid: [8; 16],
+            buttons: buttons.to_vec(),
+            axes: vec![],
+        };
+        let id = gilrs.add_replay_gamepad(&device).unwrap();
+        gilrs.insert_raw_event(RawEvent::new(id.0, RawEventType::Connected));
+        while gilrs.next_event().is_some() {}
+        gilrs.gamepads_data[id.0].mapping = Mapping::parse_sdl_mapping(
+            "03000000000000000000000000000000,Partially mapped gamepad,\
+             a:b0,guide:b1,paddle1:b2,",
+            &buttons,
+            &[],
+        )
+        .unwrap();
+
+        let (south, mode, east, west) = (
+            Code(nec::BTN_SOUTH),
+            Code(nec::BTN_MODE),
+            Code(nec::BTN_EAST),
+            Code(nec::BTN_WEST),
+        );
+        let gamepad = gilrs.gamepad(id);
+        assert_eq!(
+            gamepad.code_usage(south),
+            CodeUsage::MappedTo(AxisOrBtn::Btn(Button::South))
+        );
+        assert_eq!(
+            gamepad.code_usage(mode),
+            CodeUsage::MappedTo(AxisOrBtn::Btn(Button::Mode))
+        );
+        // Paddle is mapped, even though events name it `Button::Unknown`.
+        assert_eq!(
+            gamepad.code_usage(east),
+            CodeUsage::MappedTo(AxisOrBtn::Btn(Button::Unknown))
+        );
+        assert_eq!(gamepad.code_usage(west), CodeUsage::Unmapped);
+        assert_eq!(
+            gamepad.code_usage(Code(nec::AXIS_LSTICKX)),
+            CodeUsage::Unmapped
+        );
+
+        // Events of unmapped element don't change mapping.
+        for event in [
+            RawEventType::ButtonPressed(nec::BTN_WEST),
+            RawEventType::ButtonReleased(nec::BTN_WEST),
+        ] {
+            gilrs.insert_raw_event(RawEvent::new(id.0, event));
+        }
+        let events: Vec<_> = iter::from_fn(|| gilrs.next_event())
+            .map(|ev| ev.event)
+            .collect();
+        assert_eq!(events[0], EventType::ButtonPressed(Button::Unknown, west));
+        assert_eq!(gilrs.gamepad(id).code_usage(west), CodeUsage::Unmapped);
+
+        // Dpad buttons created from hat use codes that are not in mapping.
+        let hat = AxisInfo {
+            min: -1,
+            max: 1,
+            deadzone: None,
+        };
+        let device = ReplayDevice {
+            name: "Hat gamepad".into(),
+            uuid: [12; 16],
+            buttons: vec![nec::BTN_SOUTH],
+            axes: vec![(nec::AXIS_DPADX, hat), (nec::AXIS_DPADY, hat)],
+        };
+        let id = gilrs.add_replay_gamepad(&device).unwrap();
+        gilrs.insert_raw_event(RawEvent::new(id.0, RawEventType::Connected));
+        gilrs.insert_raw_event(RawEvent::new(
+            id.0,
+            RawEventType::AxisValueChanged(-1, nec::AXIS_DPADX),
+        ));
+        let code = iter::from_fn(|| gilrs.next_event())
+            .find_map(|ev| match ev.event {
+                EventType::ButtonPressed(Button::DPadLeft, code) => Some(code),
+                _ => None,
+            })
+            .unwrap();
+        let gamepad = gilrs.gamepad(id);
+        assert_eq!(gamepad.axis_or_btn_name(code), None);
+        assert_eq!(
+            gamepad.code_usage(code),
+            CodeUsage::MappedTo(AxisOrBtn::Btn(Button::DPadLeft))
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn inverted_axis() {
+        let mut gilrs = gilrs();
+        while gilrs.next_event().is_some() {}
+
+        let device = ReplayDevice {
+            name: "Inverted gamepad".into(),
+            uuid: [9; 16],
+            buttons: vec![],
+            axes: vec![(
+                nec::AXIS_LSTICKX,
+                AxisInfo {
+                    min: 0,
+                    max: 255,
+                    deadzone: None,
+                },
+            )],
+        };
+        let id = gilrs.add_replay_gamepad(&device).unwrap();
+        gilrs.insert_raw_event(RawEvent::new(id.0, RawEventType::Connected));
+        while gilrs.next_event().is_some() {}
+        let x = Code(nec::AXIS_LSTICKX);
+
+        let axis_x = |gilrs: &mut Gilrs, val| {
+            gilrs.insert_raw_event(RawEvent::new(
+                id.0,
+                RawEventType::AxisValueChanged(val, nec::AXIS_LSTICKX),
+            ));
+            let ev = gilrs.next_event().unwrap();
+            while gilrs.next_event().is_some() {}
+            ev.event
+        };
+
+        assert_eq!(
+            axis_x(&mut gilrs, 255),
+            EventType::AxisChanged(Axis::LeftStickX, 1.0, x)
+        );
+        gilrs.set_axis_inverted(id, x, true).unwrap();
+        assert!(gilrs.gamepad(id).mapping_info().is_axis_inverted(x));
+        assert_eq!(
+            axis_x(&mut gilrs, 255),
+            EventType::AxisChanged(Axis::LeftStickX, -1.0, x)
+        );
+        assert_eq!(gilrs.gamepad(id).value(Axis::LeftStickX), -1.0);
+
+        // Inversion is saved to database, so it's kept after reconnecting.
+        let mut exported = Vec::new();
+        gilrs.export_mappings(&mut exported, false).unwrap();
+        assert_eq!(
+            String::from_utf8(exported).unwrap(),
+            "09090909090909090909090909090909,Inverted gamepad,leftx:a0~,\n"
+        );
+        for event in [RawEventType::Disconnected, RawEventType::Connected] {
+            gilrs.insert_raw_event(RawEvent::new(id.0, event));
+        }
+        while gilrs.next_event().is_some() {}
+        assert!(gilrs.gamepad(id).mapping_info().is_axis_inverted(x));
+        assert_eq!(
+            axis_x(&mut gilrs, 255),
+            EventType::AxisChanged(Axis::LeftStickX, -1.0, x)
+        );
+
+        // Inversion is part of mapping set from `Mapping`.
+        gilrs.set_axis_inverted(id, x, false).unwrap();
+        let mut data = crate::Mapping::new();
+        data.insert_axis(x, Axis::LeftStickX);
+        data.set_axis_inverted(Axis::LeftStickX, true);
+        let sdl = gilrs.set_mapping(id.0, &data, None).unwrap();
+        assert!(sdl.ends_with(",leftx:a0~,"));
+        assert_eq!(
+            axis_x(&mut gilrs, 0),
+            EventType::AxisChanged(Axis::LeftStickX, 1.0, x)
+        );
+    }
+
+    #[test]
+    fn code_remapping() {
+        let mut gilrs = gilrs();
+        let id = gilrs.register_test_gamepad("Test gamepad", [6; 16]);
+        while gilrs.next_event().is_some() {}
+        let south = Code(nec::BTN_SOUTH);
+        let east = Code(nec::BTN_EAST);
+        assert_eq!(gilrs.gamepad(id).mapping_source(), MappingSource::Driver);
+
+        gilrs.swap_codes(id, south, east).unwrap();
+        let gamepad = gilrs.gamepad(id);
+        assert_eq!(gamepad.mapping_source(), MappingSource::SdlMappings);
+        assert_eq!(gamepad.map_name(), Some("Test gamepad"));
+        assert_eq!(gamepad.mapping_info().sdl_line(), None);
+        // Events are named by `axis_or_btn_name()`.
+        assert_eq!(
+            gamepad.axis_or_btn_name(south),
+            Some(AxisOrBtn::Btn(Button::East))
+        );
+        assert_eq!(gamepad.button_code(Button::South), Some(east));
+
+        gilrs
+            .remap_code(id, south, AxisOrBtn::Btn(Button::Mode))
+            .unwrap();
+        let gamepad = gilrs.gamepad(id);
+        assert_eq!(
+            gamepad.axis_or_btn_name(south),
+            Some(AxisOrBtn::Btn(Button::Mode))
+        );
+        assert_eq!(gamepad.button_code(Button::East), None);
+
+        assert_eq!(
+            gilrs.remap_code(id, south, AxisOrBtn::Axis(Axis::Unknown)),
+            Err(MappingError::UnknownElement)
+        );
+        assert_eq!(
+            gilrs.swap_codes(GamepadId(9), south, east),
+            Err(MappingError::NotConnected)
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn idle_disconnect_timeout() {
+        let timeout = Duration::from_millis(100);
+        let mut gilrs = build(GilrsBuilder::new().idle_disconnect_timeout(Some(timeout)));
+        let device = ReplayDevice {
+            name: "Idle gamepad".into(),
+            uuid: [7; 16],
+            buttons: vec![nec::BTN_SOUTH],
+            axes: vec![],
+        };
+        let id = gilrs.add_replay_gamepad(&device).unwrap();
+        let connected = Instant::now();
+        gilrs.insert_raw_event(RawEvent::new(id.0, RawEventType::Connected));
+        while gilrs.next_event().is_some() {}
+        assert!(gilrs.gamepad(id).is_connected());
+
+        // Blocking call wakes up when gamepad becomes idle.
+        let ev = loop {
+            if let Some(ev) = gilrs.next_event_blocking(Some(10 * timeout)) {
+                break ev;
+            }
+        };
+        assert!(connected.elapsed() >= timeout);
+        assert_eq!(ev.id, id);
+        assert_eq!(ev.event, EventType::Disconnected);
+        assert_eq!(ev.source, EventSource::Synthetic);
+        assert!(!gilrs.gamepad(id).is_connected());
+        assert!(gilrs.connected_gamepad(id).is_none());
+        assert!(gilrs.next_event().is_none());
+
+        // Input wakes gamepad up.
+        gilrs.insert_raw_event(RawEvent::new(
+            id.0,
+            RawEventType::ButtonPressed(nec::BTN_SOUTH),
+        ));
+        let ev = gilrs.next_event().unwrap();
+        assert_eq!(ev.event, EventType::Connected);
+        assert_eq!(ev.source, EventSource::Synthetic);
+        assert!(gilrs.gamepad(id).is_connected());
+        let ev = gilrs.next_event().unwrap();
+        assert!(matches!(
+            ev.event,
+            EventType::ButtonPressed(Button::South, _)
+        ));
+        assert_eq!(ev.source, EventSource::Hardware);
+        while gilrs.next_event().is_some() {}
+        assert!(gilrs.gamepad(id).is_connected());
+
+        // Disabled gamepads are never idle.
+        gilrs.set_gamepad_enabled(id, false);
+        thread::sleep(timeout);
+        assert!(gilrs.next_event().is_none());
+        assert!(gilrs.gamepad(id).is_connected());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn disabled_gamepad() {
+        let mut gilrs = build(GilrsBuilder::new().with_default_filters(false));
+        let device = ReplayDevice {
+            name: "Disabled gamepad".into(),
+            uuid: [12; 16],
+            buttons: vec![nec::BTN_SOUTH],
+            axes: vec![],
+        };
+        let id = gilrs.add_replay_gamepad(&device).unwrap();
+        let events = |gilrs: &mut Gilrs, raw: &[RawEventType]| {
+            for event in raw {
+                gilrs.insert_raw_event(RawEvent::new(id.0, *event));
+            }
+            iter::from_fn(|| gilrs.next_event())
+                .map(|ev| ev.event)
+                .collect::<Vec<_>>()
+        };
+        let south = Code(nec::BTN_SOUTH);
+
+        events(&mut gilrs, &[RawEventType::Connected]);
+        events(&mut gilrs, &[RawEventType::ButtonPressed(nec::BTN_SOUTH)]);
+        assert!(gilrs.gamepad(id).is_pressed(Button::South));
+
+        // State is cleared and events are dropped, but gamepad is still listed.
+        gilrs.set_gamepad_enabled(id, false);
+        assert!(!gilrs.gamepad(id).is_enabled());
+        assert!(!gilrs.gamepad(id).is_pressed(Button::South));
+        assert!(gilrs.gamepads().any(|(gp_id, _)| gp_id == id));
+        assert_eq!(
+            events(&mut gilrs, &[RawEventType::ButtonReleased(nec::BTN_SOUTH)]),
+            [EventType::Dropped(Some(DropReason::Disabled))]
+        );
+
+        // Hotplug events are reported and gamepad stays disabled after reconnecting.
+        assert_eq!(
+            events(
+                &mut gilrs,
+                &[
+                    RawEventType::Disconnected,
+                    RawEventType::Connected,
+                    RawEventType::ButtonPressed(nec::BTN_SOUTH),
+                ]
+            ),
+            [
+                EventType::Disconnected,
+                EventType::Connected,
+                EventType::Dropped(Some(DropReason::Disabled)),
+            ]
+        );
+        assert!(!gilrs.gamepad(id).is_enabled());
+        assert!(!gilrs.gamepad(id).is_pressed(Button::South));
+
+        gilrs.set_gamepad_enabled(id, true);
+        assert_eq!(
+            events(&mut gilrs, &[RawEventType::ButtonReleased(nec::BTN_SOUTH)]),
+            [
+                EventType::ButtonReleased(Button::South, south),
+                EventType::ButtonChanged(Button::South, 0.0, south),
+            ]
+        );
+    }
+
+    #[test]
+    fn event_source() {
+        use crate::ev::filter::{axis_dpad_to_button, Filter};
+
+        let mut gilrs = build(GilrsBuilder::new().with_default_filters(false));
+        let id = gilrs.register_test_gamepad("Test gamepad", [6; 16]);
+        gilrs.gamepads_data[id.0].mapping = Mapping::parse_sdl_mapping(
+            "03000000000000000000000000000000,Test gamepad,\
+             dpup:h0.1,dpdown:h0.4,dpleft:h0.8,dpright:h0.2,",
+            &[],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            gilrs.next_event().map(|ev| ev.source),
+            Some(EventSource::Synthetic)
+        );
+
+        let south = Code(nec::BTN_SOUTH);
+        gilrs.insert_event(Event::new(
+            id,
+            EventType::ButtonPressed(Button::South, south),
+        ));
+        assert_eq!(
+            gilrs.next_event().map(|ev| ev.source),
+            Some(EventSource::Injected)
+        );
+
+        let ev = Event::new(
+            id,
+            EventType::AxisChanged(Axis::DPadX, 1.0, Code(nec::AXIS_DPADX)),
+        )
+        .with_source(EventSource::Hardware);
+        let ev = ev.filter_ev(&axis_dpad_to_button, &mut gilrs).unwrap();
+        assert_eq!(ev.source, EventSource::Filter);
+        // Events that are not remapped keep their source.
+        let ev = Event::new(id, EventType::ButtonPressed(Button::South, south))
+            .with_source(EventSource::Hardware)
+            .filter_ev(&axis_dpad_to_button, &mut gilrs)
+            .unwrap();
+        assert_eq!(ev.source, EventSource::Hardware);
+    }
+
+    #[test]
+    fn axis_dpad_to_button_without_state_updates() {
+        use crate::ev::filter::{axis_dpad_to_button, Filter};
+
+        let mut gilrs = build(
+            GilrsBuilder::new()
+                .with_default_filters(false)
+                .set_update_state(false),
+        );
+        let id = gilrs.register_test_gamepad("Test gamepad", [3; 16]);
+        gilrs.gamepads_data[id.0].mapping = Mapping::parse_sdl_mapping(
+            "03000000000000000000000000000000,Test gamepad,\
+             dpup:h0.1,dpdown:h0.4,dpleft:h0.8,dpright:h0.2,",
+            &[],
+            &[],
+        )
+        .unwrap();
+        while gilrs.next_event().is_some() {}
+
+        let mut dpad_x = |val| {
+            let ev = Event::new(
+                id,
+                EventType::AxisChanged(Axis::DPadX, val, Code(nec::AXIS_DPADX)),
+            );
+            let mut events = Vec::new();
+            if let Some(ev) = ev.filter_ev(&axis_dpad_to_button, &mut gilrs) {
+                if !ev.is_dropped() {
+                    events.push(ev.event);
+                }
+            }
+            while let Some(ev) = gilrs.next_event() {
+                events.push(ev.event);
+            }
+            events
+        };
+        let right = Code(nec::BTN_DPAD_RIGHT);
+        let left = Code(nec::BTN_DPAD_LEFT);
+
+        assert_eq!(
+            dpad_x(1.0),
+            [
+                EventType::ButtonPressed(Button::DPadRight, right),
+                EventType::ButtonChanged(Button::DPadRight, 1.0, right),
+            ]
+        );
+        // No doubled press.
+        assert_eq!(dpad_x(1.0), []);
+        // Right is released even though cached state was never updated.
+        assert_eq!(
+            dpad_x(-1.0),
+            [
+                EventType::ButtonReleased(Button::DPadRight, right),
+                EventType::ButtonChanged(Button::DPadLeft, 1.0, left),
+                EventType::ButtonPressed(Button::DPadLeft, left),
+                EventType::ButtonChanged(Button::DPadRight, 0.0, right),
+            ]
+        );
+        assert_eq!(
+            dpad_x(0.0),
+            [
+                EventType::ButtonReleased(Button::DPadLeft, left),
+                EventType::ButtonChanged(Button::DPadLeft, 0.0, left),
+            ]
+        );
+        assert_eq!(dpad_x(0.0), []);
+    }
+
+    #[test]
+    fn vibration_test() {
+        let mut gilrs = gilrs();
+        let id = gilrs.register_test_gamepad("Test gamepad", [6; 16]);
+        while gilrs.next_event().is_some() {}
+
+        assert!(!gilrs.gamepad(id).is_ff_supported());
+        assert!(matches!(
+            gilrs.vibration_test(id),
+            Err(crate::ff::Error::FfNotSupported(i)) if i == id
+        ));
+        assert!(matches!(
+            gilrs.vibration_test(GamepadId(100)),
+            Err(crate::ff::Error::Disconnected(GamepadId(100)))
+        ));
+        assert_eq!(gilrs.metrics().ff_effects_created, 0);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn same_uuid_mappings() {
+        let uuid = Uuid::from_bytes([8; 16]);
+        let original = format!("{},Retro Pad,a:b0,", uuid.as_simple());
+        let clone = format!("{},Cheap Clone,a:b1,", uuid.as_simple());
+        let mut gilrs = build(
+            GilrsBuilder::new()
+                .add_mappings(&original)
+                .add_mappings(&clone),
+        );
+
+        let mut add_gamepad = |name: &str| {
+            let device = ReplayDevice {
+                name: name.into(),
+                uuid: [8; 16],
+                buttons: vec![nec::BTN_SOUTH, nec::BTN_EAST],
+                axes: vec![],
+            };
+            let id = gilrs.add_replay_gamepad(&device).unwrap();
+            gilrs.insert_raw_event(RawEvent::new(id.0, RawEventType::Connected));
+            id
+        };
+        let original_id = add_gamepad("ACME Retro Pad");
+        let clone_id = add_gamepad("cheap clone gamepad");
+        let unknown_id = add_gamepad("Unknown");
+        while gilrs.next_event().is_some() {}
+
+        let info = gilrs.gamepad(original_id).mapping_info();
+        assert_eq!(info.name(), Some("Retro Pad"));
+        assert_eq!(info.sdl_line(), Some(original.as_str()));
+        assert_eq!(
+            gilrs.gamepad(original_id).button_code(Button::South),
+            Some(Code(nec::BTN_SOUTH))
+        );
+        let info = gilrs.gamepad(clone_id).mapping_info();
+        assert_eq!(info.name(), Some("Cheap Clone"));
+        assert_eq!(info.sdl_line(), Some(clone.as_str()));
+        assert_eq!(
+            gilrs.gamepad(clone_id).button_code(Button::South),
+            Some(Code(nec::BTN_EAST))
+        );
+        // Newest line is used when name doesn't match.
+        let info = gilrs.gamepad(unknown_id).mapping_info();
+        assert_eq!(info.sdl_line(), Some(clone.as_str()));
+
+        let report = crate::diagnostics::gamepad_report(&gilrs.gamepad(clone_id));
+        assert!(report.contains(&format!("  sdl mapping: {:?}\n", Some(&clone))));
+    }
+
+    #[test]
+    fn active_effect_count() {
+        let mut gilrs = gilrs();
+        let id = gilrs.register_test_gamepad("Test gamepad", [7; 16]);
+
+        // Answered by force feedback thread.
+        assert_eq!(gilrs.gamepad(id).active_effect_count(), 0);
+        // Test gamepad doesn't support force feedback, so it's never opened.
+        assert_eq!(gilrs.gamepad(id).current_rumble(), None);
+    }
+
+    #[test]
+    fn weak_effect() {
+        use crate::ff::{BaseEffect, EffectBuilder};
+
+        let mut gilrs = gilrs();
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect::default())
+            .finish(&mut gilrs)
+            .unwrap();
+        let weak = effect.weak();
+        let cached = weak.clone();
+        assert!(weak == cached);
+
+        // Upgraded handle owns the effect like a clone.
+        let upgraded = weak.upgrade().unwrap();
+        assert!(upgraded == effect);
+        drop(effect);
+        assert!(cached.upgrade().is_some());
+        weak.play().unwrap();
+        weak.stop().unwrap();
+
+        // Dropping weak handles doesn't affect the effect.
+        drop(cached);
+        assert!(weak.upgrade().is_some());
+
+        drop(upgraded);
+        assert!(weak.upgrade().is_none());
+        weak.play().unwrap();
+        weak.stop().unwrap();
+    }
+
+    #[test]
+    fn default_code_constants() {
+        use crate::ev::{code, AxisOrBtn};
+
+        let mut gilrs = gilrs();
+        let id = gilrs.register_test_gamepad("Test gamepad", [6; 16]);
+        gilrs.gamepads_data[id.0].mapping = Mapping::default_all();
+
+        let expected = [
+            (code::AXIS_LSTICKX, AxisOrBtn::Axis(Axis::LeftStickX)),
+            (code::AXIS_LSTICKY, AxisOrBtn::Axis(Axis::LeftStickY)),
+            (code::AXIS_LEFTZ, AxisOrBtn::Axis(Axis::LeftZ)),
+            (code::AXIS_RSTICKX, AxisOrBtn::Axis(Axis::RightStickX)),
+            (code::AXIS_RSTICKY, AxisOrBtn::Axis(Axis::RightStickY)),
+            (code::AXIS_RIGHTZ, AxisOrBtn::Axis(Axis::RightZ)),
+            (code::AXIS_DPADX, AxisOrBtn::Axis(Axis::DPadX)),
+            (code::AXIS_DPADY, AxisOrBtn::Axis(Axis::DPadY)),
+            (code::AXIS_RT, AxisOrBtn::Btn(Button::RightTrigger)),
+            (code::AXIS_LT, AxisOrBtn::Btn(Button::LeftTrigger)),
+            (code::AXIS_RT2, AxisOrBtn::Btn(Button::RightTrigger2)),
+            (code::AXIS_LT2, AxisOrBtn::Btn(Button::LeftTrigger2)),
+            (code::BTN_SOUTH, AxisOrBtn::Btn(Button::South)),
+            (code::BTN_EAST, AxisOrBtn::Btn(Button::East)),
+            (code::BTN_C, AxisOrBtn::Btn(Button::C)),
+            (code::BTN_NORTH, AxisOrBtn::Btn(Button::North)),
+            (code::BTN_WEST, AxisOrBtn::Btn(Button::West)),
+            (code::BTN_Z, AxisOrBtn::Btn(Button::Z)),
+            (code::BTN_LT, AxisOrBtn::Btn(Button::LeftTrigger)),
+            (code::BTN_RT, AxisOrBtn::Btn(Button::RightTrigger)),
+            (code::BTN_LT2, AxisOrBtn::Btn(Button::LeftTrigger2)),
+            (code::BTN_RT2, AxisOrBtn::Btn(Button::RightTrigger2)),
+            (code::BTN_SELECT, AxisOrBtn::Btn(Button::Select)),
+            (code::BTN_START, AxisOrBtn::Btn(Button::Start)),
+            (code::BTN_MODE, AxisOrBtn::Btn(Button::Mode)),
+            (code::BTN_LTHUMB, AxisOrBtn::Btn(Button::LeftThumb)),
+            (code::BTN_RTHUMB, AxisOrBtn::Btn(Button::RightThumb)),
+            (code::BTN_DPAD_UP, AxisOrBtn::Btn(Button::DPadUp)),
+            (code::BTN_DPAD_DOWN, AxisOrBtn::Btn(Button::DPadDown)),
+            (code::BTN_DPAD_LEFT, AxisOrBtn::Btn(Button::DPadLeft)),
+            (code::BTN_DPAD_RIGHT, AxisOrBtn::Btn(Button::DPadRight)),
+            (code::BTN_MISC1, AxisOrBtn::Btn(Button::Misc1)),
+        ];
+
+        let gamepad = gilrs.gamepad(id);
+        for (code, element) in expected {
+            assert_eq!(gamepad.axis_or_btn_name(code), Some(element), "{}", code);
+        }
+    }
+
+    #[test]
+    fn mapping_info() {
+        use crate::ev::filter::Filter;
+
+        // Filter that could be written outside of gilrs: drops hat events unless all hat
+        // directions are mapped.
+        fn only_mapped_hats(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event> {
+            let ev = ev?;
+            let info = gilrs.gamepad(ev.id).mapping_info();
+            match ev.event {
+                EventType::AxisChanged(Axis::DPadX | Axis::DPadY, ..)
+                    if info.hats_mapped() != 0b0000_1111 =>
+                {
+                    Some(ev.drop())
+                }
+                _ => Some(ev),
+            }
+        }
+
+        let mut gilrs = gilrs();
+        let id = gilrs.register_test_gamepad("Test gamepad", [5; 16]);
+        while gilrs.next_event().is_some() {}
+
+        let info = gilrs.gamepad(id).mapping_info();
+        assert_eq!(info.source(), MappingSource::Driver);
+        assert_eq!(info.name(), None);
+        assert_eq!(info.hats_mapped(), 0);
+        assert!(!info.has_dual_dpad());
+
+        let hat = Event::new(
+            id,
+            EventType::AxisChanged(Axis::DPadX, 1.0, Code(nec::AXIS_DPADX)),
+        );
+        assert!(hat
+            .filter_ev(&only_mapped_hats, &mut gilrs)
+            .unwrap()
+            .is_dropped());
+
+        gilrs.gamepads_data[id.0].mapping = Mapping::parse_sdl_mapping(
+            "03000000000000000000000000000000,Test gamepad,\
+             dpup:h0.1,dpdown:h0.4,dpleft:h0.8,dpright:h0.2,",
+            &[],
+            &[],
+        )
+        .unwrap();
+
+        let info = gilrs.gamepad(id).mapping_info();
+        assert_eq!(info.source(), MappingSource::SdlMappings);
+        assert_eq!(info.name(), Some("Test gamepad"));
+        assert_eq!(info.hats_mapped(), 0b0000_1111);
+        assert!(info.is_button_mapped(Button::DPadUp));
+        assert!(info.is_axis_mapped(Axis::DPadX));
+        assert!(!info.is_button_mapped(Button::South));
+        assert!(!info.is_axis_mapped(Axis::LeftStickX));
+        assert!(!hat
+            .filter_ev(&only_mapped_hats, &mut gilrs)
+            .unwrap()
+            .is_dropped());
+    }
+
+    #[test]
+    fn update_returns_changed() {
+        let mut gilrs = gilrs();
+        let id = gilrs.register_test_gamepad("Test gamepad", [4; 16]);
+        let south = Code(nec::BTN_SOUTH);
+        let lstick_x = Code(nec::AXIS_LSTICKX);
+        let mut update = |event| gilrs.update(&Event::new(id, event));
+
+        assert!(update(EventType::Connected));
+        assert!(update(EventType::ButtonPressed(Button::South, south)));
+        assert!(!update(EventType::ButtonPressed(Button::South, south)));
+        assert!(update(EventType::ButtonRepeated(Button::South, south)));
+        assert!(!update(EventType::ButtonRepeated(Button::South, south)));
+        assert!(update(EventType::ButtonChanged(Button::South, 0.5, south)));
+        assert!(!update(EventType::ButtonChanged(Button::South, 0.5, south)));
+        assert!(update(EventType::ButtonReleased(Button::South, south)));
+        assert!(!update(EventType::AxisChanged(
+            Axis::LeftStickX,
+            0.0,
+            lstick_x
+        )));
+        assert!(update(EventType::AxisChanged(
+            Axis::LeftStickX,
+            0.3,
+            lstick_x
+        )));
+        assert!(!update(EventType::AxisChanged(
+            Axis::LeftStickX,
+            0.3,
+            lstick_x
+        )));
+        assert!(!update(EventType::Dropped(None)));
+        assert!(update(EventType::Disconnected));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn counter_wrap(
+            start in proptest::prop_oneof![MAX_COUNTER - 4..=MAX_COUNTER, 0..=MAX_COUNTER],
+            ops in proptest::collection::vec(0..3u8, 1..32),
+        ) {
+            let mut gilrs = gilrs();
+            let id = gilrs.register_test_gamepad("Test gamepad", [5; 16]);
+            let south = Code(nec::BTN_SOUTH);
+            gilrs.counter = start;
+
+            for op in ops {
+                let changed_in_this_iteration = match op {
+                    0 => {
+                        gilrs.update(&Event::new(
+                            id,
+                            EventType::ButtonPressed(Button::South, south),
+                        ));
+                        true
+                    }
+                    1 => {
+                        gilrs.inc();
+                        false
+                    }
+                    _ => {
+                        gilrs.reset_counter();
+                        false
+                    }
+                };
+
+                proptest::prop_assert_ne!(gilrs.counter(), INVALID_COUNTER);
+                let is_current = gilrs
+                    .gamepad(id)
+                    .button_data(Button::South)
+                    .is_some_and(|data| data.counter() == gilrs.counter());
+                proptest::prop_assert_eq!(is_current, changed_in_this_iteration);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn mapping_roundtrips_for_gamepad() {
+        let mut gilrs = gilrs();
+        while gilrs.next_event().is_some() {}
+        let stick = AxisInfo {
+            min: -32768,
+            max: 32767,
+            deadzone: None,
+        };
+        let mut add_gamepad = |name: &str, uuid| {
+            let device = ReplayDevice {
+                name: name.into(),
+                uuid,
+                buttons: vec![nec::BTN_SOUTH, nec::BTN_EAST],
+                axes: vec![(nec::AXIS_LSTICKX, stick)],
+            };
+            let id = gilrs.add_replay_gamepad(&device).unwrap();
+            gilrs.insert_raw_event(RawEvent::new(id.0, RawEventType::Connected));
+            while gilrs.next_event().is_some() {}
+            id
+        };
+        let plain = add_gamepad("Roundtrip pad", [14; 16]);
+        // Gamepad name is used as mapping name, but commas can't be saved in SDL mappings.
+        let comma = add_gamepad("Pad, with comma", [15; 16]);
+
+        let mut data = MappingData::new();
+        data.insert_btn(Code(nec::BTN_SOUTH), Button::South);
+        data.insert_btn(Code(nec::BTN_EAST), Button::East);
+        data.insert_axis(Code(nec::AXIS_LSTICKX), Axis::LeftStickX);
+        assert!(data.roundtrips_for(&gilrs.gamepad(plain)));
+        assert!(!data.roundtrips_for(&gilrs.gamepad(comma)));
+
+        // Result agrees with `set_mapping()`.
+        assert!(gilrs.set_mapping(plain.0, &data, None).is_ok());
+        assert_eq!(
+            gilrs.set_mapping(comma.0, &data, None),
+            Err(MappingError::InvalidName)
+        );
+
+        // Mapping can't be applied to gamepad without mapped button.
+        data.insert_btn(Code(nec::BTN_NORTH), Button::North);
+        assert!(!data.roundtrips_for(&gilrs.gamepad(plain)));
+        assert!(gilrs.set_mapping(plain.0, &data, None).is_err());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn mapping_status() {
+        let matched_uuid = [
+            0x03, 0, 0, 0, 0x11, 0x22, 0, 0, 0x33, 0x44, 0, 0, 0, 0, 0, 0,
+        ];
+        let mut gilrs = build(
+            GilrsBuilder::new()
+                .add_included_mappings(false)
+                .add_env_mappings(false)
+                .add_mappings(&format!(
+                    "{},Matched gamepad,a:b0,b:b1,",
+                    Uuid::from_bytes(matched_uuid).simple()
+                )),
+        );
+        let mut add_gamepad = |name: &str, uuid| {
+            let device = ReplayDevice {
+                name: name.into(),
+                uuid,
+                buttons: vec![nec::BTN_SOUTH, nec::BTN_EAST],
+                axes: vec![],
+            };
+            let id = gilrs.add_replay_gamepad(&device).unwrap();
+            gilrs.insert_raw_event(RawEvent::new(id.0, RawEventType::Connected));
+            id
+        };
+        let matched = add_gamepad("Matched", matched_uuid);
+        let unmatched = add_gamepad("Unmatched", [6; 16]);
+
+        // Status is available when backend already reported gamepads, but `Connected` events were
+        // not processed yet.
+        let raw: Vec<_> = iter::from_fn(|| gilrs.inner.next_event()).take(2).collect();
+        assert_eq!(gilrs.gamepads_data.len(), 0);
+        let before = gilrs.mapping_status();
+        assert!(before.contains(&(
+            matched,
+            MappingSource::SdlMappings,
+            Some("Matched gamepad".to_owned())
+        )));
+        assert!(before.contains(&(unmatched, MappingSource::Driver, None)));
+
+        for event in raw {
+            gilrs.insert_raw_event(event);
+        }
+        while gilrs.next_event().is_some() {}
+        let status = gilrs.mapping_status();
+        assert_eq!(status, before);
+        assert_eq!(
+            gilrs.gamepad(matched).button_code(Button::East),
+            Some(Code(nec::BTN_EAST))
+        );
+
+        for (id, source, name) in status {
+            assert_eq!(source, gilrs.gamepad(id).mapping_source());
+            assert_eq!(name.as_deref(), gilrs.gamepad(id).map_name());
+        }
+    }
+
+    #[test]
+    fn export_mappings() {
+        let builder = || {
+            build(
+                GilrsBuilder::new()
+                    .add_included_mappings(false)
+                    .add_env_mappings(false),
+            )
+        };
+        let export = |gilrs: &Gilrs| {
+            let mut out = Vec::new();
+            gilrs.export_mappings(&mut out, false).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let line = "03000000112200003344000000000000,Imported gamepad,a:b0,b:b1,";
+
+        let mut gilrs = builder();
+        while gilrs.next_event().is_some() {}
+        gilrs.import_mappings(&mut line.as_bytes()).unwrap();
+        assert_eq!(export(&gilrs), format!("{}\n", line));
+        // Invalid data is not imported.
+        assert!(gilrs.import_mappings(&mut &b"\xff\n"[..]).is_err());
+        assert_eq!(export(&gilrs), format!("{}\n", line));
+
+        let device = ReplayDevice {
+            name: "Custom gamepad".into(),
+            uuid: [11; 16],
+            buttons: vec![nec::BTN_SOUTH, nec::BTN_EAST],
+            axes: vec![],
+        };
+        let id = match gilrs.add_replay_gamepad(&device) {
+            Some(id) => id,
+            None => return,
+        };
+        gilrs.insert_raw_event(RawEvent::new(id.0, RawEventType::Connected));
+        while gilrs.next_event().is_some() {}
+        let mut data = MappingData::new();
+        data.insert_btn(Code(nec::BTN_EAST), Button::South);
+        let custom = gilrs.set_mapping(id.0, &data, "Custom").unwrap();
+
+        // Mappings set at runtime are exported, sorted by GUID.
+        let exported = export(&gilrs);
+        assert_eq!(exported, format!("{}\n{}\n", line, custom));
+
+        // Mapping for gamepad with nil UUID is not saved.
+        let device = ReplayDevice {
+            uuid: [0; 16],
+            ..device
+        };
+        let id = gilrs.add_replay_gamepad(&device).unwrap();
+        gilrs.insert_raw_event(RawEvent::new(id.0, RawEventType::Connected));
+        while gilrs.next_event().is_some() {}
+        gilrs.set_mapping(id.0, &data, "Unidentified").unwrap();
+        assert_eq!(export(&gilrs), exported);
+
+        let mut imported = builder();
+        imported.import_mappings(&mut exported.as_bytes()).unwrap();
+        assert_eq!(export(&imported), exported);
+        gilrs.import_mappings(&mut exported.as_bytes()).unwrap();
+        assert_eq!(export(&gilrs), exported);
+    }
+
+    #[test]
+    fn diagnostics_report() {
+        let mut gilrs = gilrs();
+        let id = gilrs.register_test_gamepad("Test gamepad", [3; 16]);
+
+        let report = gilrs.diagnostics_report();
+        assert!(report.starts_with(&crate::diagnostics::header(&gilrs)));
+        assert!(report.contains(&crate::diagnostics::gamepad_report(&gilrs.gamepad(id))));
+        assert!(report.contains("  name: Test gamepad\n"));
+        assert!(report.contains(&format!("  uuid: {}\n", Uuid::from_bytes([3; 16]))));
+    }
+
+    #[test]
+    fn timestamp_source() {
+        for source in [TimestampSource::Hardware, TimestampSource::Arrival] {
+            let gilrs = build(GilrsBuilder::new().timestamp_source(source));
+
+            if cfg!(target_os = "linux") {
+                assert_eq!(gilrs.timestamp_source(), source);
+            } else {
+                assert_eq!(gilrs.timestamp_source(), TimestampSource::Arrival);
+            }
+        }
+    }
+
+    #[test]
+    fn summary_power_cache_ttl() {
+        let stale = PowerInfo::Discharging(5);
+        for (interval, cached) in [(Duration::from_secs(60), true), (Duration::ZERO, false)] {
+            let mut gilrs = build(GilrsBuilder::new().summary_power_cache_ttl(interval));
+            let id = gilrs.register_test_gamepad("Test gamepad", [2; 16]);
+            let read_at = SystemTime::now() - Duration::from_secs(1);
+            gilrs.gamepads_data[id.0]
+                .power_info_cache
+                .set(Some((read_at, stale)));
+
+            let summaries = gilrs.gamepad_summaries();
+            let summary = summaries.iter().find(|s| s.id == id).unwrap();
+            if cached {
+                assert_eq!(summary.power_info, stale);
+            } else {
+                assert_eq!(summary.power_info, gilrs.gamepad(id).power_info());
+            }
+        }
+    }
+
+    #[test]
+    fn gamepad_summaries() {
+        let mut gilrs = gilrs();
+        let id = gilrs.register_test_gamepad("Test gamepad", [2; 16]);
+        let summaries = gilrs.gamepad_summaries();
+
+        assert_eq!(summaries.len(), gilrs.gamepads().count());
+        for summary in &summaries {
+            let gamepad = gilrs.gamepad(summary.id);
+            assert_eq!(summary.name, gamepad.name());
+            assert_eq!(summary.os_name, gamepad.os_name());
+            assert_eq!(summary.power_info, gamepad.power_info());
+            assert_eq!(summary.mapping_source, gamepad.mapping_source());
+            assert_eq!(summary.is_ff_supported, gamepad.is_ff_supported());
+        }
+
+        let summary = summaries.iter().find(|s| s.id == id).unwrap();
+        assert_eq!(summary.os_name, "Test gamepad");
+        // Snapshot doesn't borrow `Gilrs`.
+        gilrs.set_suppressed(true);
+        assert_eq!(summary.id, id);
+    }
+
+    #[test]
+    fn suppressed_events() {
+        let mut gilrs = gilrs();
+        let id = gilrs.register_test_gamepad("Test gamepad", [3; 16]);
+        while gilrs.next_event().is_some() {}
+        let south = Code(nec::BTN_SOUTH);
+        let lstick_x = Code(nec::AXIS_LSTICKX);
+
+        gilrs.set_suppressed(true);
+        gilrs.insert_event(Event::new(
+            id,
+            EventType::ButtonPressed(Button::South, south),
+        ));
+        gilrs.insert_event(Event::new(
+            id,
+            EventType::AxisChanged(Axis::LeftStickX, 0.5, lstick_x),
+        ));
+        assert_eq!(gilrs.next_event(), None);
+        let value = gilrs.gamepad(id).value(Axis::LeftStickX);
+        assert_ne!(value, 0.0);
+
+        // Events describing changed state are not dropped by default filters.
+        gilrs.set_suppressed(false);
+        let events: Vec<_> = iter::from_fn(|| gilrs.next_event())
+            .map(|ev| ev.event)
+            .collect();
+        assert!(events.contains(&EventType::ButtonPressed(Button::South, south)));
+        assert!(events.contains(&EventType::AxisChanged(Axis::LeftStickX, value, lstick_x)));
+        assert_eq!(gilrs.next_event(), None);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn gamepads_sorted_by() {
+        let mut gilrs = gilrs();
+        while gilrs.next_event().is_some() {}
+
+        let mut connect = |name: &str| {
+            let device = ReplayDevice {
+                name: name.into(),
+                uuid: [10; 16],
+                buttons: vec![nec::BTN_SOUTH],
+                axes: vec![],
+            };
+            let id = gilrs.add_replay_gamepad(&device).unwrap();
+            gilrs.insert_raw_event(RawEvent::new(id.0, RawEventType::Connected));
+            while gilrs.next_event().is_some() {}
+
+            id
+        };
+        let (b, gone, a, c) = (connect("B"), connect("Gone"), connect("A"), connect("C"));
+        gilrs.insert_raw_event(RawEvent::new(gone.0, RawEventType::Disconnected));
+        while gilrs.next_event().is_some() {}
+
+        let ids = |gamepads: Vec<(GamepadId, Gamepad<'_>)>| -> Vec<_> {
+            gamepads
+                .into_iter()
+                .map(|(id, _)| id)
+                .filter(|id| [a, b, c].contains(id))
+                .collect()
+        };
+        // Ids are in connection order and have gap after disconnected gamepad.
+        assert_eq!(ids(gilrs.gamepads().collect()), [b, a, c]);
+        assert!(gilrs.gamepads().all(|(id, _)| id != gone));
+
+        let sorted = gilrs.gamepads_sorted_by(|(_, x), (_, y)| x.os_name().cmp(y.os_name()));
+        assert_eq!(ids(sorted), [a, b, c]);
+        // Equal elements keep id order.
+        assert_eq!(
+            ids(gilrs.gamepads_sorted_by(|_, _| Ordering::Equal)),
+            [b, a, c]
+        );
+    }
+
+    #[test]
+    fn suspend_guard() {
+        let mut guard = SuspendGuard::new();
+        let now = Instant::now();
+        let wall = SystemTime::now();
+
+        guard.check(now, wall);
+        // Both clocks advanced the same, no suspend.
+        let now = now + Duration::from_secs(60);
+        let wall = wall + Duration::from_secs(60);
+        guard.check(now, wall);
+        assert!(!guard.is_batching());
+
+        // Monotonic clock stopped during suspend.
+        let now = now + Duration::from_millis(10);
+        let wall = wall + Duration::from_secs(600);
+        guard.check(now, wall);
+        assert!(guard.is_batching());
+        assert_eq!(guard.remaining(now), None);
+
+        guard.absorb(GamepadId(1));
+        guard.absorb(GamepadId(0));
+        assert!(guard.remaining(now).is_some());
+        assert_eq!(guard.finish(now), None);
+
+        let now = now + RESUME_BATCH_PERIOD;
+        assert_eq!(guard.finish(now), Some(GamepadId(1)));
+        assert!(!guard.is_batching());
+        assert_eq!(guard.finish(now), None);
+    }
+
+    #[test]
+    fn axis_value_documented_case() {
+        let info = AxisInfo {
+            min: 0,
+            max: 255,
+            deadzone: None,
+        };
+        let axis = Axis::LeftStickY;
+        assert_eq!(0., axis_value(&info, 127, axis));
+    }
+
+    #[test]
+    fn calibrated_axis_value_asymmetric() {
+        let info = AxisInfo {
+            min: -32768,
+            max: 32767,
+            deadzone: None,
+        };
+        let axis = Axis::LeftStickX;
+
+        let range = calibrated_range(&info, -20000, 18000);
+        assert_eq!(range, (-19000, 17100));
+        assert_eq!(-1.0, calibrated_axis_value(&info, range, -20000, axis));
+        assert_eq!(-1.0, calibrated_axis_value(&info, range, -19000, axis));
+        assert_eq!(-0.5, calibrated_axis_value(&info, range, -9500, axis));
+        assert_eq!(0.0, calibrated_axis_value(&info, range, 0, axis));
+        assert_eq!(0.5, calibrated_axis_value(&info, range, 8550, axis));
+        assert_eq!(1.0, calibrated_axis_value(&info, range, 17100, axis));
+        assert_eq!(1.0, calibrated_axis_value(&info, range, 32767, axis));
+
+        // Side that was never moved keeps device's range.
+        let range = calibrated_range(&info, 0, 18000);
+        assert_eq!(range, (-32768, 17100));
+        assert_eq!(
+            axis_value(&info, -16384, axis),
+            calibrated_axis_value(&info, range, -16384, axis)
+        );
+
+        // Noise of axis at rest is not calibrated.
+        assert_eq!(calibrated_range(&info, -300, 250), (-32768, 32767));
+        assert_eq!(calibrated_range(&info, -8000, 8500), (-32768, 8075));
+
+        let info = AxisInfo {
+            min: 0,
+            max: 255,
+            deadzone: None,
+        };
+        let range = calibrated_range(&info, 27, 227);
+        assert_eq!(range, (32, 222));
+        assert_eq!(-1.0, calibrated_axis_value(&info, range, 27, Axis::LeftZ));
+        assert_eq!(0.0, calibrated_axis_value(&info, range, 127, Axis::LeftZ));
+        assert_eq!(1.0, calibrated_axis_value(&info, range, 227, Axis::LeftZ));
+    }
+
+    #[test]
+    fn axis_calibration() {
+        let mut gilrs = gilrs();
+        let id = gilrs.register_test_gamepad("Test gamepad", [4; 16]);
+        let info = AxisInfo {
+            min: -32768,
+            max: 32767,
+            deadzone: None,
+        };
+        let x = Code(nec::AXIS_LSTICKX);
+        let y = Code(nec::AXIS_LSTICKY);
+
+        assert_eq!(gilrs.finish_axis_calibration(id), None);
+
+        gilrs.start_axis_calibration(id);
+        for val in [0, -20000, 5000, 18000] {
+            gilrs.gamepads_data[id.0].record_axis(x, &info, val);
+        }
+        let calibration = gilrs.finish_axis_calibration(id).unwrap();
+        assert_eq!(calibration.range(x), Some((-19000, 17100)));
+        assert_eq!(calibration.range(y), None);
+        assert_eq!(
+            gilrs.gamepads_data[id.0].axis_value(&info, x, 17100, Axis::LeftStickX),
+            1.0
+        );
+        assert_eq!(gilrs.gamepads_data[id.0].btn_value(&info, x, -19000), 0.0);
+
+        // Recording after calibration is finished doesn't change it.
+        gilrs.gamepads_data[id.0].record_axis(x, &info, 30000);
+        assert_eq!(gilrs.finish_axis_calibration(id), None);
+
+        // Re-calibration replaces previous calibration.
+        gilrs.start_axis_calibration(id);
+        gilrs.gamepads_data[id.0].record_axis(y, &info, -30000);
+        gilrs.gamepads_data[id.0].record_axis(y, &info, 30000);
+        assert_eq!(gilrs.gamepads_data[id.0].calibration, calibration);
+        let recalibrated = gilrs.finish_axis_calibration(id).unwrap();
+        assert_eq!(recalibrated.range(x), None);
+        assert_eq!(recalibrated.range(y), Some((-28500, 28500)));
+
+        gilrs.apply_axis_calibration(id, &calibration);
+        assert_eq!(gilrs.gamepads_data[id.0].calibration, calibration);
+        gilrs.apply_axis_calibration(id, &AxisCalibration::default());
+        assert!(gilrs.gamepads_data[id.0].calibration.is_empty());
+        assert_eq!(
+            gilrs.gamepads_data[id.0].axis_value(&info, x, 16384, Axis::LeftStickX),
+            axis_value(&info, 16384, Axis::LeftStickX)
+        );
+    }
+
+    #[test]
+    fn guid_components() {
+        let mut gilrs = gilrs();
+        let uuid = Uuid::parse_str("030000005e0400008e02000020200000").unwrap();
+        let id = gilrs.register_test_gamepad("Xbox 360", *uuid.as_bytes());
+        let components = gilrs.gamepad(id).guid_components();
+        assert_eq!(components.bustype, 0x3);
+        assert_eq!(components.vendor, 0x045e);
+        assert_eq!(components.product, 0x028e);
+        assert_eq!(components.version, 0x2020);
+
+        let id = gilrs.register_test_gamepad("No UUID", [0; 16]);
+        assert_eq!(
+            gilrs.gamepad(id).guid_components(),
+            GuidComponents::default()
+        );
+    }
+
+    #[test]
+    fn raw_name() {
+        let mut gilrs = gilrs();
+        let id = gilrs.register_test_gamepad("Manette sans fil é", [4; 16]);
+        let gamepad = gilrs.gamepad(id);
+        assert_eq!(gamepad.raw_name(), "Manette sans fil é".as_bytes());
+        assert_eq!(gamepad.raw_name(), gamepad.os_name().as_bytes());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn replay_gamepad() {
+        let mut gilrs = gilrs();
+        while gilrs.next_event().is_some() {}
+
+        let device = ReplayDevice {
+            name: "Replayed gamepad".into(),
+            uuid: [5; 16],
+            buttons: vec![nec::BTN_SOUTH],
+            axes: vec![(
+                nec::AXIS_LSTICKX,
+                AxisInfo {
+                    min: 0,
+                    max: 255,
+                    deadzone: None,
+                },
+            )],
+        };
+        let id = gilrs.add_replay_gamepad(&device).unwrap();
+
+        for event in [
+            RawEventType::Connected,
+            RawEventType::ButtonPressed(nec::BTN_SOUTH),
+            RawEventType::AxisValueChanged(255, nec::AXIS_LSTICKX),
+        ] {
+            gilrs.insert_raw_event(RawEvent::new(id.0, event));
+        }
+        let events: Vec<_> = iter::from_fn(|| gilrs.next_event())
+            .filter(|ev| ev.id == id)
+            .map(|ev| ev.event)
+            .collect();
+        assert_eq!(
+            events,
+            [
+                EventType::Connected,
+                EventType::ButtonPressed(Button::South, Code(nec::BTN_SOUTH)),
+                EventType::ButtonChanged(Button::South, 1.0, Code(nec::BTN_SOUTH)),
+                EventType::AxisChanged(Axis::LeftStickX, 1.0, Code(nec::AXIS_LSTICKX)),
+            ]
+        );
+        let gamepad = gilrs.gamepad(id);
+        assert!(gamepad.is_connected());
+        assert_eq!(gamepad.os_name(), "Replayed gamepad");
+        assert!(gamepad.is_pressed(Button::South));
+
+        gilrs.insert_raw_event(RawEvent::new(id.0, RawEventType::Disconnected));
+        assert_eq!(gilrs.next_event().unwrap().event, EventType::Disconnected);
+        assert!(!gilrs.gamepad(id).is_connected());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn multiple_hats() {
+        let mut gilrs = gilrs();
+        while gilrs.next_event().is_some() {}
+
+        let hat = AxisInfo {
+            min: -1,
+            max: 1,
+            deadzone: None,
+        };
+        let trigger = AxisInfo {
+            min: 0,
+            max: 255,
+            deadzone: None,
+        };
+        // Flight stick with three hats and analog throttle on the code of left trigger.
+        let device = ReplayDevice {
+            name: "Flight stick".into(),
+            uuid: [6; 16],
+            buttons: vec![nec::BTN_SOUTH],
+            axes: vec![
+                (nec::AXIS_LSTICKX, trigger),
+                (nec::AXIS_DPADX, hat),
+                (nec::AXIS_DPADY, hat),
+                (nec::AXIS_RT, hat),
+                (nec::AXIS_LT, trigger),
+                (nec::AXIS_RT2, hat),
+                (nec::AXIS_LT2, hat),
+            ],
+        };
+        let id = gilrs.add_replay_gamepad(&device).unwrap();
+        gilrs.insert_raw_event(RawEvent::new(id.0, RawEventType::Connected));
+        while gilrs.next_event().is_some() {}
+
+        let gamepad = gilrs.gamepad(id);
+        // First hat is dpad and axis with positive range is still a trigger.
+        assert_eq!(gamepad.axis_code(Axis::DPadX), Some(Code(nec::AXIS_DPADX)));
+        assert_eq!(
+            gamepad.button_code(Button::LeftTrigger),
+            Some(Code(nec::AXIS_LT))
+        );
+        assert_eq!(gamepad.button_code(Button::RightTrigger), None);
+        assert_eq!(
+            gamepad.hat_axes().collect::<Vec<_>>(),
+            [Code(nec::AXIS_RT), Code(nec::AXIS_RT2), Code(nec::AXIS_LT2)]
+        );
+
+        for event in [
+            RawEventType::AxisValueChanged(-1, nec::AXIS_RT),
+            RawEventType::AxisValueChanged(1, nec::AXIS_LT2),
+            RawEventType::AxisValueChanged(0, nec::AXIS_RT),
+        ] {
+            gilrs.insert_raw_event(RawEvent::new(id.0, event));
+        }
+        let events: Vec<_> = iter::from_fn(|| gilrs.next_event())
+            .map(|ev| ev.event)
+            .collect();
+        assert_eq!(
+            events,
+            [
+                EventType::AxisChanged(Axis::Unknown, -1.0, Code(nec::AXIS_RT)),
+                EventType::AxisChanged(Axis::Unknown, 1.0, Code(nec::AXIS_LT2)),
+                EventType::AxisChanged(Axis::Unknown, 0.0, Code(nec::AXIS_RT)),
+            ]
+        );
+        let gamepad = gilrs.gamepad(id);
+        let state = gamepad.state();
+        assert_eq!(state.value(Code(nec::AXIS_LT2)), 1.0);
+        assert_eq!(state.value(Code(nec::AXIS_RT)), 0.0);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn stick_gate() {
+        use crate::{GateEstimate, GateShape, Stick};
+
+        let mut gilrs = gilrs();
+        let info = AxisInfo {
+            min: -1000,
+            max: 1000,
+            deadzone: None,
+        };
+        let device = ReplayDevice {
+            name: "Gate gamepad".into(),
+            uuid: [4; 16],
+            buttons: vec![],
+            axes: vec![(nec::AXIS_LSTICKX, info), (nec::AXIS_LSTICKY, info)],
+        };
+        let id = gilrs.add_replay_gamepad(&device).unwrap();
+        gilrs.insert_raw_event(RawEvent::new(id.0, RawEventType::Connected));
+        while gilrs.next_event().is_some() {}
+        assert_eq!(
+            gilrs.gamepad(id).stick_gate(Stick::Left),
+            GateEstimate::default()
+        );
+
+        // Kernel reports both axes of one frame with the same time.
+        let mut frame = 0;
+        let mut move_stick = |gilrs: &mut Gilrs, x, y| {
+            let time = SystemTime::UNIX_EPOCH + Duration::from_millis(frame);
+            frame += 1;
+            for (val, code) in [(x, nec::AXIS_LSTICKX), (y, nec::AXIS_LSTICKY)] {
+                let mut ev = RawEvent::new(id.0, RawEventType::AxisValueChanged(val, code));
+                ev.time = time;
+                gilrs.insert_raw_event(ev);
+            }
+            while gilrs.next_event().is_some() {}
+        };
+
+        // Moving between cardinal directions updates one axis before the other, but stick
+        // never reaches diagonals.
+        for _ in 0..10 {
+            for (x, y) in [(1000, 0), (0, 1000), (-1000, 0), (0, -1000)] {
+                move_stick(&mut gilrs, x, y);
+            }
+        }
+        let estimate = gilrs.gamepad(id).stick_gate(Stick::Left);
+        assert_eq!(estimate.classification, GateShape::Unknown);
+        assert_eq!(estimate.max_observed_magnitude, 1.0);
+
+        for _ in 0..10 {
+            for (x, y) in [(1000, 1000), (-1000, 1000), (-1000, -1000), (1000, -1000)] {
+                move_stick(&mut gilrs, x, y);
+            }
+        }
+        let square = gilrs.gamepad(id).stick_gate(Stick::Left);
+        assert_eq!(square.classification, GateShape::Square);
+        assert_eq!(square.max_observed_magnitude, 2f32.sqrt());
+
+        gilrs.apply_stick_gate(id, Stick::Right, &square);
+        assert_eq!(gilrs.gamepad(id).stick_gate(Stick::Right), square);
+    }
+
+    #[test]
+    fn axis_value_z_axes() {
+        let info = |min, max| AxisInfo {
+            min,
+            max,
+            deadzone: None,
+        };
+
+        for axis in [Axis::LeftZ, Axis::RightZ] {
+            // Unsigned twist with odd range.
+            assert_eq!(-1.0, axis_value(&info(0, 255), 0, axis));
+            assert_eq!(0.0, axis_value(&info(0, 255), 127, axis));
+            assert_eq!(1.0, axis_value(&info(0, 255), 255, axis));
+            // Unsigned twist with even range.
+            assert_eq!(-1.0, axis_value(&info(0, 1024), 0, axis));
+            assert_eq!(0.0, axis_value(&info(0, 1024), 512, axis));
+            assert_eq!(1.0, axis_value(&info(0, 1024), 1024, axis));
+            // Signed twist, centered at 0.
+            assert_eq!(-1.0, axis_value(&info(-512, 511), -512, axis));
+            assert_eq!(0.0, axis_value(&info(-512, 511), 0, axis));
+            assert_eq!(1.0, axis_value(&info(-512, 511), 511, axis));
+            assert_eq!(0.5, axis_value(&info(-512, 511), -256, axis).abs());
+            assert_eq!(-1.0, axis_value(&info(-32768, 32767), -32768, axis));
+            assert_eq!(0.0, axis_value(&info(-32768, 32767), 0, axis));
+            assert_eq!(1.0, axis_value(&info(-32768, 32767), 32767, axis));
+        }
+
+        // Z axes are never reversed, unlike Y axes.
+        assert!(axis_value(&info(-512, 511), 100, Axis::RightZ) > 0.0);
+    }
+
+    #[test]
+    fn axis_value_overflow() {
+        let info = AxisInfo {
+            min: i32::MIN,
+            max: i32::MAX,
+            deadzone: None,
+        };
+        let axis = Axis::LeftStickY;
+
+        assert_eq!(0., axis_value(&info, -1, axis));
+        assert_eq!(0., axis_value(&info, 0, axis));
         assert_eq!(0., axis_value(&info, 1, axis));
 
         assert_eq!(1.0, axis_value(&info, i32::MIN, axis));
@@ -1248,4 +5596,71 @@ mod tests {
         assert_eq!(0.0, btn_value(&info, i32::MIN));
         assert_eq!(1.0, btn_value(&info, i32::MAX));
     }
+
+    #[test]
+    fn state_handle_across_threads() {
+        let mut state = GamepadState::new();
+        state.set_btn_pressed(
+            crate::Button::South,
+            Code(nec::BTN_SOUTH),
+            true,
+            0,
+            SystemTime::now(),
+        );
+        let handle = GilrsStateHandle {
+            inner: Arc::new(RwLock::new(vec![GamepadState::new(), state])),
+        };
+
+        let h = handle.clone();
+        thread::spawn(move || {
+            assert!(h.is_pressed(GamepadId(1), Code(nec::BTN_SOUTH)));
+            assert!(!h.is_pressed(GamepadId(0), Code(nec::BTN_SOUTH)));
+            assert!(h.state(GamepadId(2)).is_none());
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(handle.value(GamepadId(1), Code(nec::BTN_SOUTH)), 1.0);
+    }
+
+    #[test]
+    fn state_handle_updates() {
+        let mut gilrs = build(GilrsBuilder::new().with_default_filters(false));
+        let id = gilrs.register_test_gamepad("Test gamepad", [3; 16]);
+        let handle = gilrs.state_handle();
+        let south = Code(nec::BTN_SOUTH);
+        let stick = Code(nec::AXIS_LSTICKX);
+        gilrs.insert_event(Event::new(
+            id,
+            EventType::ButtonPressed(Button::South, south),
+        ));
+        gilrs.insert_event(Event::new(
+            id,
+            EventType::AxisChanged(Axis::LeftStickX, 0.5, stick),
+        ));
+        gilrs.insert_event(Event::new(
+            id,
+            EventType::AxisChanged(Axis::LeftStickX, -0.25, stick),
+        ));
+        while gilrs.next_event().is_some() {}
+
+        assert!(handle.is_pressed(id, south));
+        assert_eq!(handle.value(id, stick), -0.25);
+        let shared = handle.state(id).unwrap();
+        let gamepad = gilrs.gamepad(id);
+        let state = gamepad.state();
+        assert_eq!(shared.axes_sorted().len(), 1);
+        for (shared, state) in shared.axes_sorted().into_iter().zip(state.axes_sorted()) {
+            assert_eq!((shared.0, shared.1), (state.0, state.1));
+            assert_eq!(shared.2.value(), state.2.value());
+            assert_eq!(shared.2.counter(), state.2.counter());
+        }
+
+        gilrs.insert_event(Event::new(
+            id,
+            EventType::ButtonReleased(Button::South, south),
+        ));
+        while gilrs.next_event().is_some() {}
+        assert!(!handle.is_pressed(id, south));
+    }
 }
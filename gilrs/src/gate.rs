@@ -0,0 +1,401 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Passive detection of stick gate shape, see `Gamepad::stick_gate()`.
+
+use crate::ev::Axis;
+
+use std::time::SystemTime;
+
+#[cfg(feature = "serde-serialize")]
+use serde::{Deserialize, Serialize};
+
+// Sample is near the gate if it's at least this far from center.
+const RIM: f32 = 0.9;
+// Sample is diagonal if it's at most 15° away from 45° line of its quadrant (tan(30°)).
+const DIAGONAL_RATIO: f32 = 0.577_350_3;
+// Number of samples near the gate needed before diagonal is used for classification.
+const MIN_SAMPLES: u32 = 5;
+// Number of diagonals that have to be explored before stick is classified.
+const MIN_DIAGONALS: usize = 2;
+// Square gate lets stick reach √2 on diagonals, circular only 1.0. Anything in between is
+// `Unknown`.
+const CIRCULAR_MAX: f32 = 1.12;
+const SQUARE_MIN: f32 = 1.25;
+
+/// Analog stick of a gamepad.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum Stick {
+    Left,
+    Right,
+}
+
+impl Stick {
+    /// Returns horizontal and vertical axis of the stick.
+    pub fn axes(self) -> (Axis, Axis) {
+        match self {
+            Stick::Left => (Axis::LeftStickX, Axis::LeftStickY),
+            Stick::Right => (Axis::RightStickX, Axis::RightStickY),
+        }
+    }
+
+    fn idx(self) -> usize {
+        match self {
+            Stick::Left => 0,
+            Stick::Right => 1,
+        }
+    }
+}
+
+/// Shape of physical gate that limits movement of a stick. See [`GateEstimate`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum GateShape {
+    /// Not enough input was observed yet or it doesn't clearly match any shape.
+    #[default]
+    Unknown,
+    /// Distance from center is at most 1.0 in every direction, like sticks of Xbox controllers.
+    Circular,
+    /// Stick can reach corners, so diagonal input exceeds 1.0 (up to √2) before it is clamped.
+    /// Common on fight pads and arcade sticks.
+    Square,
+}
+
+/// Shape of stick gate learned from normal input. Returned by
+/// [`Gamepad::stick_gate()`](struct.Gamepad.html#method.stick_gate).
+///
+/// Stick is classified after it was pushed to the edge several times in at least two diagonal
+/// directions. Position is sampled once per input frame (events with the same timestamp), so
+/// stick moving quickly between cardinal directions isn't mistaken for diagonal input. Estimate can change as more input is observed; for example square gate that was
+/// never pushed fully into a corner looks circular. Values are taken before filters (including
+/// deadzone, which limits magnitude to 1.0) are applied.
+///
+/// Estimate can be saved (with `serde-serialize` feature) and restored with
+/// [`Gilrs::apply_stick_gate()`](struct.Gilrs.html#method.apply_stick_gate).
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct GateEstimate {
+    /// Largest distance from center observed so far.
+    pub max_observed_magnitude: f32,
+    pub classification: GateShape,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+struct Diagonal {
+    max_magnitude: f32,
+    samples: u32,
+}
+
+#[derive(Clone, Debug, Default)]
+struct GateTracker {
+    x: f32,
+    y: f32,
+    // Time of input frame that changed position and wasn't sampled yet.
+    frame: Option<SystemTime>,
+    max_magnitude: f32,
+    // Indexed by quadrant.
+    diagonals: [Diagonal; 4],
+    restored: Option<GateEstimate>,
+}
+
+impl GateTracker {
+    fn record(&mut self, x: Option<f32>, y: Option<f32>, time: SystemTime) {
+        // Both axes of one frame have to be updated before position is sampled, otherwise
+        // intermediate position can be far from real one.
+        if self.frame.is_some_and(|frame| frame != time) {
+            self.sample();
+        }
+
+        self.x = x.unwrap_or(self.x);
+        self.y = y.unwrap_or(self.y);
+        self.frame = Some(time);
+    }
+
+    fn sample(&mut self) {
+        self.frame = None;
+
+        let magnitude = (self.x * self.x + self.y * self.y).sqrt();
+        if magnitude.is_nan() {
+            return;
+        }
+        self.max_magnitude = self.max_magnitude.max(magnitude);
+
+        let (ax, ay) = (self.x.abs(), self.y.abs());
+        if magnitude < RIM || ax.min(ay) < ax.max(ay) * DIAGONAL_RATIO {
+            return;
+        }
+
+        let quadrant = usize::from(self.x < 0.0) | usize::from(self.y < 0.0) << 1;
+        let diagonal = &mut self.diagonals[quadrant];
+        diagonal.max_magnitude = diagonal.max_magnitude.max(magnitude);
+        diagonal.samples = diagonal.samples.saturating_add(1);
+    }
+
+    fn classification(&self) -> GateShape {
+        let explored = self
+            .diagonals
+            .iter()
+            .filter(|diagonal| diagonal.samples >= MIN_SAMPLES);
+        let (count, sum) = explored.fold((0, 0.0), |(count, sum), diagonal| {
+            (count + 1, sum + diagonal.max_magnitude)
+        });
+        if count < MIN_DIAGONALS {
+            return GateShape::Unknown;
+        }
+
+        let average = sum / count as f32;
+        if average >= SQUARE_MIN {
+            GateShape::Square
+        } else if average <= CIRCULAR_MAX {
+            GateShape::Circular
+        } else {
+            GateShape::Unknown
+        }
+    }
+
+    fn estimate(&self) -> GateEstimate {
+        if self.frame.is_some() {
+            // Frame is complete until event with different time arrives.
+            let mut tracker = self.clone();
+            tracker.sample();
+            return tracker.estimate();
+        }
+
+        let restored = self.restored.unwrap_or_default();
+        let classification = match self.classification() {
+            GateShape::Unknown => restored.classification,
+            shape => shape,
+        };
+
+        GateEstimate {
+            max_observed_magnitude: self.max_magnitude.max(restored.max_observed_magnitude),
+            classification,
+        }
+    }
+}
+
+/// Gate trackers of both sticks of one gamepad.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StickGates([GateTracker; 2]);
+
+impl StickGates {
+    /// Records new value of `axis` from event with `time`. Axes other than sticks are ignored.
+    pub fn record(&mut self, axis: Axis, value: f32, time: SystemTime) {
+        let (stick, x, y) = match axis {
+            Axis::LeftStickX => (Stick::Left, Some(value), None),
+            Axis::LeftStickY => (Stick::Left, None, Some(value)),
+            Axis::RightStickX => (Stick::Right, Some(value), None),
+            Axis::RightStickY => (Stick::Right, None, Some(value)),
+            _ => return,
+        };
+
+        self.0[stick.idx()].record(x, y, time);
+    }
+
+    pub fn estimate(&self, stick: Stick) -> GateEstimate {
+        self.0[stick.idx()].estimate()
+    }
+
+    /// Uses `estimate` until enough input is observed to classify the stick again.
+    pub fn restore(&mut self, stick: Stick, estimate: GateEstimate) {
+        self.0[stick.idx()].restored = Some(estimate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn frame(n: u32) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(n.into())
+    }
+
+    /// Records every value in separate input frame, starting with `first`.
+    fn record_each(gates: &mut StickGates, first: u32, values: &[(Axis, f32)]) {
+        for (n, &(axis, value)) in (first..).zip(values) {
+            gates.record(axis, value, frame(n));
+        }
+    }
+
+    /// Feeds `laps` laps of stick moving along the gate, both axes in one input frame. `radius`
+    /// returns distance of the gate from center in given direction.
+    fn trace(gates: &mut StickGates, laps: u32, radius: impl Fn(f32) -> f32) {
+        for step in 0..laps * 120 {
+            let angle = (step as f32 * 3.0).to_radians();
+            let r = radius(angle);
+            let x = (angle.cos() * r).clamp(-1.0, 1.0);
+            let y = (angle.sin() * r).clamp(-1.0, 1.0);
+            gates.record(Axis::LeftStickX, x, frame(step));
+            gates.record(Axis::LeftStickY, y, frame(step));
+        }
+    }
+
+    fn circle(_: f32) -> f32 {
+        1.0
+    }
+
+    fn square(angle: f32) -> f32 {
+        1.0 / angle.cos().abs().max(angle.sin().abs())
+    }
+
+    #[test]
+    fn circular_gate() {
+        let mut gates = StickGates::default();
+        trace(&mut gates, 2, circle);
+
+        let estimate = gates.estimate(Stick::Left);
+        assert_eq!(estimate.classification, GateShape::Circular);
+        assert!((estimate.max_observed_magnitude - 1.0).abs() < 1e-5);
+        // Other stick didn't move.
+        assert_eq!(gates.estimate(Stick::Right), GateEstimate::default());
+    }
+
+    #[test]
+    fn square_gate() {
+        let mut gates = StickGates::default();
+        trace(&mut gates, 2, square);
+
+        let estimate = gates.estimate(Stick::Left);
+        assert_eq!(estimate.classification, GateShape::Square);
+        assert!(estimate.max_observed_magnitude > 1.41);
+    }
+
+    #[test]
+    fn classification_thresholds() {
+        // Reaches each diagonal only few times.
+        let mut gates = StickGates::default();
+        for i in 0..MIN_SAMPLES - 1 {
+            let values = [
+                (Axis::LeftStickX, 1.0),
+                (Axis::LeftStickY, 1.0),
+                (Axis::LeftStickX, -1.0),
+                (Axis::LeftStickY, 0.0),
+            ];
+            record_each(&mut gates, i * 4, &values);
+        }
+        assert_eq!(
+            gates.estimate(Stick::Left).classification,
+            GateShape::Unknown
+        );
+
+        // Only cardinal directions, returning to center in between.
+        let mut gates = StickGates::default();
+        let mut n = 0;
+        for _ in 0..10 {
+            for (x, y) in [(1.0, 0.0), (0.0, 1.0), (-1.0, 0.0), (0.0, -1.0)] {
+                let values = [
+                    (Axis::LeftStickX, x),
+                    (Axis::LeftStickY, y),
+                    (Axis::LeftStickX, 0.0),
+                    (Axis::LeftStickY, 0.0),
+                ];
+                record_each(&mut gates, n, &values);
+                n += 4;
+            }
+        }
+        assert_eq!(
+            gates.estimate(Stick::Left).classification,
+            GateShape::Unknown
+        );
+
+        // Diagonals only slightly beyond circle, for example worn stick or octagonal gate.
+        let mut gates = StickGates::default();
+        trace(&mut gates, 2, |angle| {
+            circle(angle).max(square(angle) * 0.76)
+        });
+        assert_eq!(
+            gates.estimate(Stick::Left).classification,
+            GateShape::Circular
+        );
+
+        // Between thresholds.
+        let mut gates = StickGates::default();
+        trace(&mut gates, 2, |angle| {
+            circle(angle).max(square(angle) * 0.84)
+        });
+        assert_eq!(
+            gates.estimate(Stick::Left).classification,
+            GateShape::Unknown
+        );
+
+        // Square gate with rounded corners.
+        let mut gates = StickGates::default();
+        trace(&mut gates, 2, |angle| square(angle) * 0.92);
+        assert_eq!(
+            gates.estimate(Stick::Left).classification,
+            GateShape::Square
+        );
+    }
+
+    #[test]
+    fn frames() {
+        // Stick moves between cardinal directions without returning to center. Both axes change
+        // in every frame, intermediate positions like (-1, 1) are never reached.
+        let mut gates = StickGates::default();
+        for i in 0..40 {
+            let (x, y) = [(1.0, 0.0), (0.0, 1.0), (-1.0, 0.0), (0.0, -1.0)][i % 4];
+            gates.record(Axis::LeftStickX, x, frame(i as u32));
+            gates.record(Axis::LeftStickY, y, frame(i as u32));
+        }
+        let estimate = gates.estimate(Stick::Left);
+        assert_eq!(estimate.classification, GateShape::Unknown);
+        assert_eq!(estimate.max_observed_magnitude, 1.0);
+
+        // Last frame is used by estimate before next frame starts.
+        gates.record(Axis::LeftStickX, 1.0, frame(40));
+        gates.record(Axis::LeftStickY, 1.0, frame(40));
+        assert_eq!(
+            gates.estimate(Stick::Left).max_observed_magnitude,
+            2f32.sqrt()
+        );
+    }
+
+    #[test]
+    fn right_stick_and_other_axes() {
+        let mut gates = StickGates::default();
+        for i in 0..10 {
+            let values = [
+                (Axis::LeftZ, 1.0),
+                (Axis::RightStickX, 1.0),
+                (Axis::RightStickY, -1.0),
+                (Axis::RightStickX, -1.0),
+                (Axis::RightStickY, 1.0),
+            ];
+            record_each(&mut gates, i * 5, &values);
+        }
+
+        assert_eq!(gates.estimate(Stick::Left), GateEstimate::default());
+        assert_eq!(
+            gates.estimate(Stick::Right).classification,
+            GateShape::Square
+        );
+    }
+
+    #[test]
+    fn restore() {
+        let mut gates = StickGates::default();
+        let saved = GateEstimate {
+            max_observed_magnitude: 1.4,
+            classification: GateShape::Square,
+        };
+        gates.restore(Stick::Left, saved);
+        assert_eq!(gates.estimate(Stick::Left), saved);
+
+        // Restored estimate is used until there is enough new input.
+        gates.record(Axis::LeftStickX, 1.0, frame(1000));
+        assert_eq!(gates.estimate(Stick::Left), saved);
+
+        trace(&mut gates, 2, circle);
+        let estimate = gates.estimate(Stick::Left);
+        assert_eq!(estimate.classification, GateShape::Circular);
+        assert_eq!(estimate.max_observed_magnitude, 1.4);
+    }
+}
@@ -0,0 +1,208 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Histogram of input latency, see `GilrsBuilder::measure_latency()`.
+
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "serde-serialize")]
+use serde::{Deserialize, Serialize};
+
+// Latencies below `LINEAR` µs have their own buckets. Above it, every power of two is split into
+// `SUB_BUCKETS` buckets, so reported percentiles are at most 12.5% larger than real ones.
+const LINEAR: u32 = 16;
+const SUB_BUCKET_BITS: u32 = 3;
+const SUB_BUCKETS: u32 = 1 << SUB_BUCKET_BITS;
+const LINEAR_BITS: u32 = LINEAR.trailing_zeros();
+// Latencies are clamped to `u32::MAX` µs (over an hour).
+const BUCKETS: usize = (LINEAR + (32 - LINEAR_BITS) * SUB_BUCKETS) as usize;
+
+/// Input latency of one gamepad returned by
+/// [`Gilrs::latency_stats()`](struct.Gilrs.html#method.latency_stats).
+///
+/// Latency is time between [`Event::time`](struct.Event.html#structfield.time) and the moment
+/// event was returned by `Gilrs::next_event()`. Percentiles are approximate; they can be up to
+/// 12.5% larger than real value, but never larger than `max`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct LatencyStats {
+    /// Median latency.
+    pub p50: Duration,
+    /// 95th percentile of latency.
+    pub p95: Duration,
+    /// Highest latency.
+    pub max: Duration,
+    /// Number of measured events.
+    pub samples: u64,
+    /// Number of events with timestamp in the future, for example because timestamps come from
+    /// different clock. They are counted in `samples` with zero latency.
+    pub clock_skew: u64,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct LatencyHistogram {
+    buckets: Box<[u64; BUCKETS]>,
+    samples: u64,
+    clock_skew: u64,
+    // In microseconds.
+    max: u32,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        LatencyHistogram {
+            buckets: Box::new([0; BUCKETS]),
+            samples: 0,
+            clock_skew: 0,
+            max: 0,
+        }
+    }
+
+    /// Records latency of event with timestamp `time` observed at `now`.
+    pub fn record(&mut self, time: SystemTime, now: SystemTime) {
+        let micros = match now.duration_since(time) {
+            Ok(latency) => latency.as_micros().min(u32::MAX.into()) as u32,
+            Err(_) => {
+                self.clock_skew += 1;
+                0
+            }
+        };
+
+        self.buckets[bucket(micros)] += 1;
+        self.samples += 1;
+        self.max = self.max.max(micros);
+    }
+
+    pub fn stats(&self) -> LatencyStats {
+        LatencyStats {
+            p50: self.percentile(0.5),
+            p95: self.percentile(0.95),
+            max: Duration::from_micros(self.max.into()),
+            samples: self.samples,
+            clock_skew: self.clock_skew,
+        }
+    }
+
+    fn percentile(&self, q: f64) -> Duration {
+        if self.samples == 0 {
+            return Duration::ZERO;
+        }
+
+        let rank = ((self.samples as f64 * q).ceil() as u64).max(1);
+        let mut count = 0;
+        for (idx, &n) in self.buckets.iter().enumerate() {
+            count += n;
+            if count >= rank {
+                let micros = bucket_upper_bound(idx).min(self.max);
+                return Duration::from_micros(micros.into());
+            }
+        }
+
+        Duration::from_micros(self.max.into())
+    }
+}
+
+fn bucket(micros: u32) -> usize {
+    if micros < LINEAR {
+        return micros as usize;
+    }
+
+    let exp = 31 - micros.leading_zeros();
+    let sub = (micros >> (exp - SUB_BUCKET_BITS)) & (SUB_BUCKETS - 1);
+
+    (LINEAR + (exp - LINEAR_BITS) * SUB_BUCKETS + sub) as usize
+}
+
+/// Returns highest latency that falls into bucket `idx`.
+fn bucket_upper_bound(idx: usize) -> u32 {
+    let idx = idx as u32;
+    if idx < LINEAR {
+        return idx;
+    }
+
+    let exp = (idx - LINEAR) / SUB_BUCKETS + LINEAR_BITS;
+    let sub = (idx - LINEAR) % SUB_BUCKETS;
+    let width = 1 << (exp - SUB_BUCKET_BITS);
+    let lower = (SUB_BUCKETS + sub) << (exp - SUB_BUCKET_BITS);
+
+    lower + (width - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bucket, bucket_upper_bound, LatencyHistogram, LatencyStats, BUCKETS};
+
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn buckets() {
+        assert_eq!(bucket(0), 0);
+        assert_eq!(bucket(15), 15);
+        assert_eq!(bucket(16), 16);
+        assert_eq!(bucket(17), 16);
+        assert_eq!(bucket(31), 23);
+        assert_eq!(bucket(32), 24);
+        assert_eq!(bucket(u32::MAX), BUCKETS - 1);
+
+        for micros in [0, 15, 16, 17, 100, 1_000, 12_345, 1_000_000, u32::MAX] {
+            let upper = bucket_upper_bound(bucket(micros));
+            assert!(upper >= micros);
+            assert!(
+                u64::from(upper) <= u64::from(micros) * 9 / 8 + 1,
+                "{}",
+                micros
+            );
+            assert_eq!(bucket(upper), bucket(micros));
+        }
+    }
+
+    #[test]
+    fn percentiles() {
+        let now = SystemTime::now();
+        let mut hist = LatencyHistogram::new();
+        assert_eq!(hist.stats(), LatencyStats::default());
+
+        for ms in 1..=100 {
+            hist.record(now - Duration::from_millis(ms), now);
+        }
+
+        let stats = hist.stats();
+        assert_eq!(stats.samples, 100);
+        assert_eq!(stats.clock_skew, 0);
+        assert_eq!(stats.max, Duration::from_millis(100));
+        assert!(stats.p50 >= Duration::from_millis(50), "{:?}", stats.p50);
+        assert!(
+            stats.p50 <= Duration::from_micros(56_250),
+            "{:?}",
+            stats.p50
+        );
+        assert!(stats.p95 >= Duration::from_millis(95), "{:?}", stats.p95);
+        assert!(stats.p95 <= Duration::from_millis(100), "{:?}", stats.p95);
+
+        // Small latencies are exact.
+        let mut hist = LatencyHistogram::new();
+        hist.record(now - Duration::from_micros(5), now);
+        assert_eq!(hist.stats().p50, Duration::from_micros(5));
+        assert_eq!(hist.stats().p95, Duration::from_micros(5));
+    }
+
+    #[test]
+    fn clock_skew() {
+        let now = SystemTime::now();
+        let mut hist = LatencyHistogram::new();
+        hist.record(now + Duration::from_millis(3), now);
+        hist.record(now + Duration::from_millis(1), now);
+        hist.record(now - Duration::from_millis(10), now);
+
+        let stats = hist.stats();
+        assert_eq!(stats.samples, 3);
+        assert_eq!(stats.clock_skew, 2);
+        assert_eq!(stats.p50, Duration::ZERO);
+        assert_eq!(stats.max, Duration::from_millis(10));
+    }
+}
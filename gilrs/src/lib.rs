@@ -79,11 +79,29 @@
 //! support loading mappings from environment variable `SDL_GAMECONTROLLERCONFIG` (which Steam
 //! use).
 //!
+//! Threads
+//! -------
+//!
+//! `Gilrs` is `Send` on all platforms except Wasm, so it can be created on one thread and moved to
+//! another one, for example to dedicated input thread. It's not `Sync`. If other threads only need
+//! to read gamepad state, use [`Gilrs::state_handle()`](struct.Gilrs.html#method.state_handle).
+//!
+//! On Wasm, `Gilrs` uses JavaScript objects that can't leave thread on which they were created.
+//!
 //! Cargo features
 //! --------------
 //!
 //! - `serde-serialize` - enable deriving of serde's `Serialize` and `Deserialize` for
 //!   various types.
+//! - `wgi-no-thread` - Windows Gaming Input backend doesn't start background thread that reads
+//!   gamepads every 8 ms. Instead, readings are compared in
+//!   [`Gilrs::next_event()`](struct.Gilrs.html#method.next_event), so events are only as fresh
+//!   as your polling cadence and their timestamps are time of the call, not of the input. Useful
+//!   for UWP applications.
+//! - `all-platform-mappings` - include bundled SDL mappings for all platforms instead of only
+//!   the current one, so they can be used with
+//!   [`GilrsBuilder::force_platform()`](struct.GilrsBuilder.html#method.force_platform).
+//!   Increases binary size.
 //!
 //! Platform specific notes
 //! ======================
@@ -113,16 +131,36 @@ extern crate log;
 
 mod constants;
 mod gamepad;
+mod gate;
+mod latency;
 mod mapping;
 mod utils;
 
 pub mod ev;
 pub mod ff;
 
+#[doc(hidden)]
+pub mod diagnostics;
+
 pub use crate::ev::filter::Filter;
-pub use crate::ev::{Axis, Button, Event, EventType};
+pub use crate::ev::{Axis, Button, DropReason, Event, EventSource, EventType};
+#[cfg(any(test, feature = "test-utils"))]
+pub use crate::gamepad::ReplayDevice;
 pub use crate::gamepad::{
-    ConnectedGamepadsIterator, Error, Gamepad, GamepadId, Gilrs, GilrsBuilder, MappingSource,
-    PowerInfo,
+    AxisCalibration, AxisInfo, CodeUsage, ConnectedGamepadsIterator, DPadSource, DeviceStatus,
+    EnumeratedDevice, EnumerationReport, Error, Gamepad, GamepadId, GamepadSummary, Gilrs,
+    GilrsBuilder, GilrsStateHandle, GrabError, GuidComponents, MappingInfo, MappingSource, Metrics,
+    PowerInfo, TimestampSource, VirtualReason,
+};
+pub use crate::gate::{GateEstimate, GateShape, Stick};
+pub use crate::latency::LatencyStats;
+pub use crate::mapping::{
+    bundled_mappings_info, MappingData as Mapping, MappingError, MappingsDbInfo,
 };
-pub use crate::mapping::{MappingData as Mapping, MappingError};
+#[cfg(any(test, feature = "test-utils"))]
+pub use gilrs_core::{Event as RawEvent, EventType as RawEventType};
+
+/// Returns version of gilrs crate.
+pub fn version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
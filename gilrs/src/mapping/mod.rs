@@ -8,20 +8,25 @@
 
 mod parser;
 
-use crate::ev::{self, Axis, AxisOrBtn, Button};
+use crate::ev::{self, Axis, AxisOrBtn, Button, SdlAxis, SdlButton};
+use crate::gamepad::Gamepad;
 use crate::utils::PATH_SEPARATOR;
 use gilrs_core::native_ev_codes as nec;
-use gilrs_core::EvCode;
+use gilrs_core::{AxisInfo, EvCode};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
 use std::env;
 use std::error::Error;
 use std::fmt::{Display, Formatter, Result as FmtResult, Write as _};
+use std::io::{self, Write};
 
-use fnv::FnvHashMap;
+use fnv::{FnvHashMap, FnvHashSet};
 use uuid::Uuid;
 use vec_map::VecMap;
 
+use self::parser::{sdl_axis, sdl_button};
+pub(crate) use self::parser::{sdl_element, sdl_key_to_element};
 use self::parser::{Error as ParserError, ErrorKind as ParserErrorKind, Parser, Token};
 
 /// Platform name used by SDL mappings
@@ -38,29 +43,132 @@ const SDL_PLATFORM_NAME: &str = "Windows";
 ))]
 const SDL_PLATFORM_NAME: &str = "Unknown";
 
-#[derive(Debug)]
-#[cfg_attr(test, derive(PartialEq))]
-/// Store mappings from one `EvCode` (`u16`) to another.
+/// Platforms for which SDL mappings are used.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum MappingPlatforms {
+    /// Only mappings for current platform or without `platform` field.
+    #[default]
+    Host,
+    /// Mappings for current platform and for given one. Mappings for current platform are
+    /// preferred.
+    With(String),
+    /// Mappings for all platforms. Mappings for current platform are preferred.
+    All,
+}
+
+impl MappingPlatforms {
+    /// Returns `true` if mappings with `platform` field equal to `platform` should be used.
+    pub fn accepts(&self, platform: &str) -> bool {
+        platform == SDL_PLATFORM_NAME
+            || match self {
+                MappingPlatforms::Host => false,
+                MappingPlatforms::With(p) => p == platform,
+                MappingPlatforms::All => true,
+            }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// Store mappings from one `EvCode` to another. Keys include kind of element, so axis and button
+/// with the same numeric code never collide. Button element can be additionally mapped to more
+/// than one `Button`, extra buttons are stored in `aliases`.
 ///
 /// This struct is internal, `MappingData` is exported in public interface as `Mapping`.
 pub struct Mapping {
     mappings: FnvHashMap<EvCode, AxisOrBtn>,
+    aliases: FnvHashMap<EvCode, Vec<Button>>,
+    // Axes whose value is negated, `~` suffix in SDL mapping.
+    inverted: FnvHashSet<EvCode>,
     name: String,
     default: bool,
     hats_mapped: u8,
+    unresolved: Vec<String>,
+    // Line from `MappingDb` that this mapping was created from.
+    sdl_line: Option<String>,
 }
 
 impl Mapping {
     pub fn new() -> Self {
         Mapping {
             mappings: FnvHashMap::default(),
+            aliases: FnvHashMap::default(),
+            inverted: FnvHashSet::default(),
             name: String::new(),
             default: false,
             hats_mapped: 0,
+            unresolved: Vec::new(),
+            sdl_line: None,
         }
     }
 
     pub fn default(gamepad: &gilrs_core::Gamepad) -> Self {
+        let mut mapping = Self::default_all();
+        let mappings = &mut mapping.mappings;
+
+        // Remove all mappings that don't have corresponding element in gamepad. Partial fix to #83
+        let axes = [
+            nec::AXIS_DPADX,
+            nec::AXIS_DPADY,
+            nec::AXIS_LEFTZ,
+            nec::AXIS_LSTICKX,
+            nec::AXIS_LSTICKY,
+            nec::AXIS_RSTICKX,
+            nec::AXIS_RSTICKY,
+            nec::AXIS_LT,
+            nec::AXIS_LT2,
+            nec::AXIS_RT,
+            nec::AXIS_RT2,
+            nec::AXIS_RIGHTZ,
+        ];
+        let btns = [
+            nec::BTN_SOUTH,
+            nec::BTN_NORTH,
+            nec::BTN_WEST,
+            nec::BTN_WEST,
+            nec::BTN_C,
+            nec::BTN_Z,
+            nec::BTN_LT,
+            nec::BTN_LT2,
+            nec::BTN_RT,
+            nec::BTN_RT2,
+            nec::BTN_SELECT,
+            nec::BTN_START,
+            nec::BTN_MODE,
+            nec::BTN_LTHUMB,
+            nec::BTN_RTHUMB,
+            nec::BTN_DPAD_DOWN,
+            nec::BTN_DPAD_LEFT,
+            nec::BTN_DPAD_RIGHT,
+            nec::BTN_DPAD_UP,
+            nec::BTN_MISC1,
+        ];
+
+        for axis in &axes {
+            if !gamepad.axes().contains(axis) {
+                mappings.remove(axis);
+            }
+        }
+
+        // Some backends report analog triggers on the same codes as additional hats (ABS_HAT1X–
+        // ABS_HAT2Y on Linux). Trigger can't have negative values, so these are real hats of
+        // multi-hat devices (flight sticks, arcade panels) and are left unmapped.
+        for axis in &[nec::AXIS_LT, nec::AXIS_LT2, nec::AXIS_RT, nec::AXIS_RT2] {
+            if gamepad.axis_info(*axis).is_some_and(is_hat) {
+                mappings.remove(axis);
+            }
+        }
+
+        for btn in &btns {
+            if !gamepad.buttons().contains(btn) {
+                mappings.remove(btn);
+            }
+        }
+
+        mapping
+    }
+
+    /// Returns default mapping with all elements, regardless of what gamepad has.
+    pub fn default_all() -> Self {
         use self::Axis as Ax;
         use self::AxisOrBtn::*;
 
@@ -77,7 +185,7 @@ impl Mapping {
             };
         }
 
-        let mut mappings = fnv_map![
+        let mappings = fnv_map![
             nec::BTN_SOUTH => Btn(Button::South),
             nec::BTN_EAST => Btn(Button::East),
             nec::BTN_C => Btn(Button::C),
@@ -97,6 +205,7 @@ impl Mapping {
             nec::BTN_DPAD_DOWN => Btn(Button::DPadDown),
             nec::BTN_DPAD_LEFT => Btn(Button::DPadLeft),
             nec::BTN_DPAD_RIGHT => Btn(Button::DPadRight),
+            nec::BTN_MISC1 => Btn(Button::Misc1),
 
             nec::AXIS_LT => Btn(Button::LeftTrigger),
             nec::AXIS_RT => Btn(Button::RightTrigger),
@@ -113,60 +222,15 @@ impl Mapping {
             nec::AXIS_DPADY => Axis(Ax::DPadY)
         ];
 
-        // Remove all mappings that don't have corresponding element in gamepad. Partial fix to #83
-        let axes = [
-            nec::AXIS_DPADX,
-            nec::AXIS_DPADY,
-            nec::AXIS_LEFTZ,
-            nec::AXIS_LSTICKX,
-            nec::AXIS_LSTICKY,
-            nec::AXIS_RSTICKX,
-            nec::AXIS_RSTICKY,
-            nec::AXIS_LT,
-            nec::AXIS_LT2,
-            nec::AXIS_RT,
-            nec::AXIS_RT2,
-            nec::AXIS_RIGHTZ,
-        ];
-        let btns = [
-            nec::BTN_SOUTH,
-            nec::BTN_NORTH,
-            nec::BTN_WEST,
-            nec::BTN_WEST,
-            nec::BTN_C,
-            nec::BTN_Z,
-            nec::BTN_LT,
-            nec::BTN_LT2,
-            nec::BTN_RT,
-            nec::BTN_RT2,
-            nec::BTN_SELECT,
-            nec::BTN_START,
-            nec::BTN_MODE,
-            nec::BTN_LTHUMB,
-            nec::BTN_RTHUMB,
-            nec::BTN_DPAD_DOWN,
-            nec::BTN_DPAD_LEFT,
-            nec::BTN_DPAD_RIGHT,
-            nec::BTN_DPAD_UP,
-        ];
-
-        for axis in &axes {
-            if !gamepad.axes().contains(axis) {
-                mappings.remove(axis);
-            }
-        }
-
-        for btn in &btns {
-            if !gamepad.buttons().contains(btn) {
-                mappings.remove(btn);
-            }
-        }
-
         Mapping {
             mappings,
+            aliases: FnvHashMap::default(),
+            inverted: FnvHashSet::default(),
             name: String::new(),
             default: true,
             hats_mapped: 0,
+            unresolved: Vec::new(),
+            sdl_line: None,
         }
     }
 
@@ -174,6 +238,19 @@ impl Mapping {
         &self.name
     }
 
+    /// Adds default mapping for button that gamepad didn't report when it was connected, so it's
+    /// mapped the same way as after reconnecting. Does nothing if mapping is not default or `code`
+    /// is already mapped.
+    pub fn add_late_button(&mut self, code: EvCode) {
+        if !self.default || self.mappings.contains_key(&code) {
+            return;
+        }
+
+        if let Some(btn @ AxisOrBtn::Btn(_)) = Self::default_all().map(&code) {
+            self.mappings.insert(code, btn);
+        }
+    }
+
     pub fn from_data(
         data: &MappingData,
         buttons: &[EvCode],
@@ -181,13 +258,16 @@ impl Mapping {
         name: &str,
         uuid: Uuid,
     ) -> Result<(Self, String), MappingError> {
-        use crate::constants::*;
-
         if !Self::is_name_valid(name) {
             return Err(MappingError::InvalidName);
         }
 
-        let mut mappings = FnvHashMap::default();
+        data.validate(buttons, axes)?;
+
+        let mut mapping = Mapping {
+            name: name.to_owned(),
+            ..Mapping::new()
+        };
         let mut sdl_mappings = format!("{},{},", uuid.as_simple(), name);
 
         {
@@ -197,78 +277,125 @@ impl Mapping {
                     ev_code,
                     mapped_btn,
                     buttons,
+                    axes,
                     &mut sdl_mappings,
-                    &mut mappings,
+                    &mut mapping,
                 )
             };
 
-            for (button, &ev_code) in &data.buttons {
-                match button as u16 {
-                    BTN_SOUTH => add_button("a", ev_code, Button::South)?,
-                    BTN_EAST => add_button("b", ev_code, Button::East)?,
-                    BTN_WEST => add_button("x", ev_code, Button::West)?,
-                    BTN_NORTH => add_button("y", ev_code, Button::North)?,
-                    BTN_LT => add_button("leftshoulder", ev_code, Button::LeftTrigger)?,
-                    BTN_RT => add_button("rightshoulder", ev_code, Button::RightTrigger)?,
-                    BTN_LT2 => add_button("lefttrigger", ev_code, Button::LeftTrigger2)?,
-                    BTN_RT2 => add_button("righttrigger", ev_code, Button::RightTrigger2)?,
-                    BTN_SELECT => add_button("back", ev_code, Button::Select)?,
-                    BTN_START => add_button("start", ev_code, Button::Start)?,
-                    BTN_MODE => add_button("guide", ev_code, Button::Mode)?,
-                    BTN_LTHUMB => add_button("leftstick", ev_code, Button::LeftThumb)?,
-                    BTN_RTHUMB => add_button("rightstick", ev_code, Button::RightThumb)?,
-                    BTN_DPAD_UP => add_button("dpup", ev_code, Button::DPadUp)?,
-                    BTN_DPAD_DOWN => add_button("dpdown", ev_code, Button::DPadDown)?,
-                    BTN_DPAD_LEFT => add_button("dpleft", ev_code, Button::DPadLeft)?,
-                    BTN_DPAD_RIGHT => add_button("dpright", ev_code, Button::DPadRight)?,
-                    BTN_C => add_button("c", ev_code, Button::C)?,
-                    BTN_Z => add_button("z", ev_code, Button::Z)?,
-                    BTN_UNKNOWN => return Err(MappingError::UnknownElement),
-                    _ => unreachable!(),
-                }
+            for (idx, &ev_code) in &data.buttons {
+                let (button, el) = sdl_button(idx).ok_or(MappingError::UnknownElement)?;
+                add_button(el.name, ev_code, button)?;
             }
         }
 
-        {
-            let mut add_axis = |ident, ev_code, mapped_axis| {
-                Self::add_axis(
-                    ident,
-                    ev_code,
-                    mapped_axis,
-                    axes,
-                    &mut sdl_mappings,
-                    &mut mappings,
-                )
+        for (idx, &ev_code) in &data.axes {
+            let (axis, el) = sdl_axis(idx).ok_or(MappingError::UnknownElement)?;
+            Self::add_axis(
+                el.name,
+                ev_code,
+                axis,
+                data.is_axis_inverted(axis),
+                axes,
+                &mut sdl_mappings,
+                &mut mapping,
+            )?;
+        }
+
+        Ok((mapping, sdl_mappings))
+    }
+
+    /// Returns SDL mapping with current state of this mapping, for example after it was changed by
+    /// `set_inverted()`. Elements that SDL mappings can't express (`Button::Unknown` and axes
+    /// without SDL name, except dpad hat) are skipped. Returns `None` if name contains comma.
+    pub(crate) fn to_sdl_line(
+        &self,
+        uuid: Uuid,
+        buttons: &[EvCode],
+        axes: &[EvCode],
+    ) -> Option<String> {
+        if !Self::is_name_valid(&self.name) {
+            return None;
+        }
+
+        let mut entries = Vec::new();
+        for (code, &el) in &self.mappings {
+            match el {
+                AxisOrBtn::Axis(Axis::DPadX) if *code == nec::AXIS_DPADX => {
+                    entries.extend(["dpleft:h0.8".to_owned(), "dpright:h0.2".to_owned()]);
+                    continue;
+                }
+                AxisOrBtn::Axis(Axis::DPadY) if *code == nec::AXIS_DPADY => {
+                    entries.extend(["dpup:h0.1".to_owned(), "dpdown:h0.4".to_owned()]);
+                    continue;
+                }
+                _ => (),
+            }
+
+            let from = if let Some(idx) = buttons.iter().position(|c| c == code) {
+                format!("b{}", idx)
+            } else if let Some(idx) = axes.iter().position(|c| c == code) {
+                let inverted = !el.is_button() && self.is_inverted(code);
+                format!("a{}{}", idx, if inverted { "~" } else { "" })
+            } else {
+                // For example `BTN_DPAD_*` codes of hat.
+                continue;
             };
 
-            for (axis, &ev_code) in &data.axes {
-                match axis as u16 {
-                    AXIS_LSTICKX => add_axis("leftx", ev_code, Axis::LeftStickX)?,
-                    AXIS_LSTICKY => add_axis("lefty", ev_code, Axis::LeftStickY)?,
-                    AXIS_RSTICKX => add_axis("rightx", ev_code, Axis::RightStickX)?,
-                    AXIS_RSTICKY => add_axis("righty", ev_code, Axis::RightStickY)?,
-                    AXIS_LEFTZ => add_axis("leftz", ev_code, Axis::LeftZ)?,
-                    AXIS_RIGHTZ => add_axis("rightz", ev_code, Axis::RightZ)?,
-                    AXIS_UNKNOWN => return Err(MappingError::UnknownElement),
-                    _ => unreachable!(),
+            let aliases = self.aliases(code).iter().map(|&btn| AxisOrBtn::Btn(btn));
+            for el in std::iter::once(el).chain(aliases) {
+                if let Some(el) = sdl_element(el) {
+                    entries.push(format!("{}:{}", el.name, from));
                 }
             }
         }
+        entries.sort_unstable();
 
-        let mapping = Mapping {
-            mappings,
-            name: name.to_owned(),
-            default: false,
-            hats_mapped: 0,
-        };
+        let mut line = format!("{},{},", uuid.as_simple(), self.name);
+        for entry in entries {
+            let _ = write!(line, "{},", entry);
+        }
 
-        Ok((mapping, sdl_mappings))
+        Some(line)
     }
 
+    /// Parses SDL mapping. Entries that reference buttons or axes that gamepad doesn't have are
+    /// skipped and can be later inspected with `unresolved()`.
     pub fn parse_sdl_mapping(
         line: &str,
         buttons: &[EvCode],
         axes: &[EvCode],
+    ) -> Result<Self, ParseSdlMappingError> {
+        Self::parse_sdl_mapping_impl(line, buttons, axes, false, &MappingPlatforms::Host)
+    }
+
+    /// Like `parse_sdl_mapping()`, but doesn't warn about mappings for any of `platforms`.
+    pub fn parse_sdl_mapping_for(
+        line: &str,
+        buttons: &[EvCode],
+        axes: &[EvCode],
+        platforms: &MappingPlatforms,
+    ) -> Result<Self, ParseSdlMappingError> {
+        Self::parse_sdl_mapping_impl(line, buttons, axes, false, platforms)
+    }
+
+    /// Like `parse_sdl_mapping()`, but fails if any entry references button or axis that gamepad
+    /// doesn't have.
+    #[cfg(test)]
+    pub fn parse_sdl_mapping_strict(
+        line: &str,
+        buttons: &[EvCode],
+        axes: &[EvCode],
+    ) -> Result<Self, ParseSdlMappingError> {
+        Self::parse_sdl_mapping_impl(line, buttons, axes, true, &MappingPlatforms::Host)
+    }
+
+    fn parse_sdl_mapping_impl(
+        line: &str,
+        buttons: &[EvCode],
+        axes: &[EvCode],
+        strict: bool,
+        platforms: &MappingPlatforms,
     ) -> Result<Self, ParseSdlMappingError> {
         let mut mapping = Mapping::new();
         let mut parser = Parser::new(line);
@@ -285,38 +412,42 @@ impl Mapping {
 
             match token {
                 Token::Platform(platform) => {
-                    if platform != SDL_PLATFORM_NAME {
+                    if !platforms.accepts(platform) {
                         warn!("Mappings for different platform – {}", platform);
                     }
                 }
                 Token::Uuid(v) => uuid = Some(v),
 
                 Token::Name(name) => mapping.name = name.to_owned(),
-                Token::AxisMapping { from, to, .. } => {
+                Token::AxisMapping {
+                    from, to, inverted, ..
+                } => {
                     let axis = axes.get(from as usize).cloned();
                     if let Some(axis) = axis {
                         mapping.mappings.insert(axis, to);
+                        if !to.is_button() {
+                            mapping.set_inverted(axis, inverted);
+                        }
+                    } else if strict {
+                        return Err(ParseSdlMappingError::InvalidAxis(from));
                     } else {
-                        warn!(
-                            "SDL-mapping {} {}: Unknown axis a{}",
-                            uuid.unwrap(),
-                            mapping.name,
-                            from
-                        )
+                        mapping.unresolved.push(format!("a{}", from));
                     }
                 }
                 Token::ButtonMapping { from, to, .. } => {
                     let btn = buttons.get(from as usize).cloned();
 
                     if let Some(btn) = btn {
-                        mapping.mappings.insert(btn, to);
+                        match to {
+                            AxisOrBtn::Btn(to) => mapping.insert_btn(btn, to),
+                            _ => {
+                                mapping.mappings.insert(btn, to);
+                            }
+                        }
+                    } else if strict {
+                        return Err(ParseSdlMappingError::InvalidButton(from));
                     } else {
-                        warn!(
-                            "SDL-mapping {} {}: Unknown button b{}",
-                            uuid.unwrap(),
-                            mapping.name,
-                            from
-                        )
+                        mapping.unresolved.push(format!("b{}", from));
                     }
                 }
                 Token::HatMapping {
@@ -368,6 +499,15 @@ impl Mapping {
             }
         }
 
+        if !mapping.unresolved.is_empty() {
+            warn!(
+                "SDL-mapping {} {}: skipped entries that reference missing elements: {}",
+                uuid.unwrap_or_else(Uuid::nil),
+                mapping.name,
+                mapping.unresolved.join(", ")
+            );
+        }
+
         Ok(mapping)
     }
 
@@ -376,15 +516,21 @@ impl Mapping {
         ev_code: EvCode,
         mapped_btn: Button,
         buttons: &[EvCode],
+        axes: &[EvCode],
         sdl_mappings: &mut String,
-        mappings: &mut FnvHashMap<EvCode, AxisOrBtn>,
+        mapping: &mut Mapping,
     ) -> Result<(), MappingError> {
-        let n_btn = buttons
-            .iter()
-            .position(|&x| x == ev_code)
-            .ok_or(MappingError::InvalidCode(ev::Code(ev_code)))?;
-        let _ = write!(sdl_mappings, "{}:b{},", ident, n_btn);
-        mappings.insert(ev_code, AxisOrBtn::Btn(mapped_btn));
+        if let Some(n_btn) = buttons.iter().position(|&x| x == ev_code) {
+            let _ = write!(sdl_mappings, "{}:b{},", ident, n_btn);
+        } else {
+            // Analog shoulder or trigger
+            let n_axis = axes
+                .iter()
+                .position(|&x| x == ev_code)
+                .ok_or(MappingError::InvalidCode(ev::Code(ev_code)))?;
+            let _ = write!(sdl_mappings, "{}:a{},", ident, n_axis);
+        }
+        mapping.insert_btn(ev_code, mapped_btn);
         Ok(())
     }
 
@@ -392,19 +538,93 @@ impl Mapping {
         ident: &str,
         ev_code: EvCode,
         mapped_axis: Axis,
+        inverted: bool,
         axes: &[EvCode],
         sdl_mappings: &mut String,
-        mappings: &mut FnvHashMap<EvCode, AxisOrBtn>,
+        mapping: &mut Mapping,
     ) -> Result<(), MappingError> {
         let n_axis = axes
             .iter()
             .position(|&x| x == ev_code)
             .ok_or(MappingError::InvalidCode(ev::Code(ev_code)))?;
-        let _ = write!(sdl_mappings, "{}:a{},", ident, n_axis);
-        mappings.insert(ev_code, AxisOrBtn::Axis(mapped_axis));
+        let suffix = if inverted { "~" } else { "" };
+        let _ = write!(sdl_mappings, "{}:a{}{},", ident, n_axis, suffix);
+        mapping
+            .mappings
+            .insert(ev_code, AxisOrBtn::Axis(mapped_axis));
+        mapping.set_inverted(ev_code, inverted);
         Ok(())
     }
 
+    /// Maps `code` to `btn`. If `code` is already mapped to other button, `btn` is added as its
+    /// alias. `Button::Unknown` is never an alias and is replaced when `code` is also mapped to
+    /// known button, so events are not emitted twice.
+    fn insert_btn(&mut self, code: EvCode, btn: Button) {
+        match self.mappings.get(&code) {
+            Some(&AxisOrBtn::Btn(primary)) if primary == btn || btn == Button::Unknown => (),
+            Some(AxisOrBtn::Btn(Button::Unknown)) => {
+                self.mappings.insert(code, AxisOrBtn::Btn(btn));
+            }
+            Some(AxisOrBtn::Btn(_)) => {
+                let aliases = self.aliases.entry(code).or_default();
+                if !aliases.contains(&btn) {
+                    aliases.push(btn);
+                }
+            }
+            _ => {
+                self.mappings.insert(code, AxisOrBtn::Btn(btn));
+            }
+        }
+    }
+
+    /// Returns `true` if value of axis `code` is negated.
+    pub fn is_inverted(&self, code: &EvCode) -> bool {
+        self.inverted.contains(code)
+    }
+
+    /// Sets whether value of axis `code` is negated. Inversion belongs to the element, so it's
+    /// kept when `code` is remapped or swapped.
+    pub fn set_inverted(&mut self, code: EvCode, inverted: bool) {
+        if inverted {
+            self.inverted.insert(code);
+        } else {
+            self.inverted.remove(&code);
+        }
+    }
+
+    /// Maps `code` to `el`, replacing element and aliases it was mapped to.
+    pub fn remap(&mut self, code: EvCode, el: AxisOrBtn) {
+        self.aliases.remove(&code);
+        self.mappings.insert(code, el);
+    }
+
+    /// Exchanges elements, together with aliases, that `a` and `b` are mapped to.
+    pub fn swap(&mut self, a: EvCode, b: EvCode) {
+        fn swap_entries<V>(map: &mut FnvHashMap<EvCode, V>, a: EvCode, b: EvCode) {
+            let a_val = map.remove(&a);
+            let b_val = map.remove(&b);
+            if let Some(val) = a_val {
+                map.insert(b, val);
+            }
+            if let Some(val) = b_val {
+                map.insert(a, val);
+            }
+        }
+
+        swap_entries(&mut self.mappings, a, b);
+        swap_entries(&mut self.aliases, a, b);
+    }
+
+    /// Marks mapping as changed by user. Default mapping gets `name`, other mappings keep theirs.
+    /// Mapping no longer corresponds to database line, so it's forgotten.
+    pub(crate) fn mark_custom(&mut self, name: &str) {
+        if self.default {
+            self.name = name.to_owned();
+            self.default = false;
+        }
+        self.sdl_line = None;
+    }
+
     fn is_name_valid(name: &str) -> bool {
         !name.chars().any(|x| x == ',')
     }
@@ -413,24 +633,84 @@ impl Mapping {
         self.mappings.get(code).cloned()
     }
 
+    /// Returns buttons, other than one returned by `map()`, that `code` is mapped to.
+    pub fn aliases(&self, code: &EvCode) -> &[Button] {
+        self.aliases.get(code).map_or(&[], Vec::as_slice)
+    }
+
     pub fn map_rev(&self, el: &AxisOrBtn) -> Option<EvCode> {
-        self.mappings.iter().find(|x| x.1 == el).map(|x| *x.0)
+        self.mappings
+            .iter()
+            .find(|x| x.1 == el)
+            .map(|x| *x.0)
+            .or_else(|| match el {
+                AxisOrBtn::Btn(btn) => self
+                    .aliases
+                    .iter()
+                    .find(|x| x.1.contains(btn))
+                    .map(|x| *x.0),
+                AxisOrBtn::Axis(_) => None,
+            })
     }
 
     pub fn is_default(&self) -> bool {
         self.default
     }
 
+    /// Remembers database line that mapping was created from.
+    pub(crate) fn with_sdl_line(mut self, line: &str) -> Self {
+        self.sdl_line = Some(line.to_owned());
+        self
+    }
+
+    /// Returns database line that mapping was created from.
+    pub fn sdl_line(&self) -> Option<&str> {
+        self.sdl_line.as_deref()
+    }
+
+    /// Returns number of distinct `Button`s, other than `Button::Unknown`, in mapping.
+    pub fn button_count(&self) -> usize {
+        self.mappings
+            .values()
+            .filter_map(|el| match el {
+                AxisOrBtn::Btn(btn) if *btn != Button::Unknown => Some(*btn),
+                _ => None,
+            })
+            .chain(self.aliases.values().flatten().copied())
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// Returns number of distinct `Axis`es, other than `Axis::Unknown`, in mapping.
+    pub fn axis_count(&self) -> usize {
+        self.mappings
+            .values()
+            .filter_map(|el| match el {
+                AxisOrBtn::Axis(axis) if *axis != Axis::Unknown => Some(*axis),
+                _ => None,
+            })
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
     /// Return bit field with mapped hats. Only for mappings created from SDL format this function
     /// can return non-zero value.
     pub fn hats_mapped(&self) -> u8 {
         self.hats_mapped
     }
+
+    /// Returns SDL references (like `b12` or `a7`) that were skipped while parsing, because
+    /// gamepad doesn't have such element.
+    pub fn unresolved(&self) -> &[String] {
+        &self.unresolved
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum ParseSdlMappingError {
     UnknownHatDirection,
+    InvalidButton(u16),
+    InvalidAxis(u16),
     ParseError(ParserError),
 }
 
@@ -456,29 +736,53 @@ impl Display for ParseSdlMappingError {
             ParseSdlMappingError::UnknownHatDirection => {
                 fmt.write_str("hat direction wasn't 1, 2, 4 or 8")
             }
+            ParseSdlMappingError::InvalidButton(idx) => {
+                write!(fmt, "gamepad doesn't have button b{}", idx)
+            }
+            ParseSdlMappingError::InvalidAxis(idx) => {
+                write!(fmt, "gamepad doesn't have axis a{}", idx)
+            }
             ParseSdlMappingError::ParseError(_) => fmt.write_str("parsing error"),
         }
     }
 }
 
+#[derive(Debug)]
+struct MappingLine {
+    line: String,
+    // Line comes from database included in gilrs, not from user.
+    bundled: bool,
+}
+
 #[derive(Debug)]
 pub struct MappingDb {
-    mappings: HashMap<Uuid, String>,
+    // Several lines can share UUID (for example, if clones reuse VID/PID of other gamepad); they
+    // are kept newest first and distinguished by name.
+    mappings: HashMap<Uuid, Vec<MappingLine>>,
+    // Mappings for other platforms, used only if `platforms` allows it.
+    other_platforms: HashMap<Uuid, Vec<(String, String)>>,
+    platforms: MappingPlatforms,
 }
 
 impl MappingDb {
     pub fn new() -> Self {
         MappingDb {
             mappings: HashMap::new(),
+            other_platforms: HashMap::new(),
+            platforms: MappingPlatforms::Host,
         }
     }
 
+    pub fn set_platforms(&mut self, platforms: MappingPlatforms) {
+        self.platforms = platforms;
+    }
+
+    pub fn platforms(&self) -> &MappingPlatforms {
+        &self.platforms
+    }
+
     pub fn add_included_mappings(&mut self) {
-        self.insert(include_str!(concat!(
-            env!("OUT_DIR"),
-            PATH_SEPARATOR!(),
-            "gamecontrollerdb.txt"
-        )));
+        self.insert_lines(BUNDLED_MAPPINGS, true);
     }
 
     pub fn add_env_mappings(&mut self) {
@@ -488,46 +792,226 @@ impl MappingDb {
     }
 
     pub fn insert(&mut self, s: &str) {
+        self.insert_lines(s, false);
+    }
+
+    fn insert_lines(&mut self, s: &str, bundled: bool) {
         for mapping in s.lines() {
+            let uuid = match mapping
+                .split(',')
+                .next()
+                .and_then(|s| Uuid::parse_str(s).ok())
+            {
+                Some(uuid) => uuid,
+                None => continue,
+            };
+
             let pat = "platform:";
             if let Some(offset) = mapping.find(pat).map(|o| o + pat.len()) {
                 let s = &mapping[offset..];
                 let end = s.find(',').unwrap_or(s.len());
+                let platform = &s[..end];
 
-                if &s[..end] != SDL_PLATFORM_NAME {
+                if platform != SDL_PLATFORM_NAME {
+                    let lines = self.other_platforms.entry(uuid).or_default();
+                    lines.retain(|(_, line)| line != mapping);
+                    lines.push((platform.to_owned(), mapping.to_owned()));
                     continue;
                 }
             }
 
-            mapping
-                .split(',')
-                .next()
-                .and_then(|s| Uuid::parse_str(s).ok())
-                .and_then(|uuid| self.mappings.insert(uuid, mapping.to_owned()));
+            // Line with the same name and origin is replaced, so newer database or user mapping
+            // wins.
+            let lines = self.mappings.entry(uuid).or_default();
+            lines.retain(|l| l.bundled != bundled || sdl_name(&l.line) != sdl_name(mapping));
+            lines.insert(
+                0,
+                MappingLine {
+                    line: mapping.to_owned(),
+                    bundled,
+                },
+            );
         }
     }
 
+    /// Returns newest mapping for `uuid`.
+    #[cfg(test)]
     pub fn get(&self, uuid: Uuid) -> Option<&str> {
-        self.mappings.get(&uuid).map(String::as_ref)
+        self.get_for(uuid, "")
+    }
+
+    /// Returns mapping for `uuid`. Mappings added by user (from environment variable,
+    /// `add_mappings()`, `set_mapping()` or import) always win over included ones. If there are
+    /// several mappings with the same origin, the newest one whose name is case-insensitive
+    /// substring of `os_name` is preferred over the newest one.
+    ///
+    /// Like SDL, mapping for GUID with CRC16 of `os_name` embedded (see [`with_name_crc()`]) is
+    /// preferred over mapping for GUID without it.
+    pub fn get_for(&self, uuid: Uuid, os_name: &str) -> Option<&str> {
+        with_name_crc(uuid, os_name)
+            .and_then(|crc_uuid| self.get_exact(crc_uuid, os_name))
+            .or_else(|| self.get_exact(uuid, os_name))
+    }
+
+    fn get_exact(&self, uuid: Uuid, os_name: &str) -> Option<&str> {
+        let os_name = os_name.to_lowercase();
+        let host = self.mappings.get(&uuid).and_then(|lines| {
+            [false, true].into_iter().find_map(|bundled| {
+                let mut lines = lines
+                    .iter()
+                    .filter(|l| l.bundled == bundled)
+                    .map(|l| &l.line);
+                let newest = lines.clone().next()?;
+                lines
+                    .find(|line| {
+                        let name = sdl_name(line).to_lowercase();
+                        !name.is_empty() && os_name.contains(&name)
+                    })
+                    .or(Some(newest))
+            })
+        });
+
+        host.map(String::as_ref).or_else(|| {
+            self.other_platforms
+                .get(&uuid)?
+                .iter()
+                .rev()
+                .find(|(platform, _)| self.platforms.accepts(platform))
+                .map(|(_, mapping)| mapping.as_ref())
+        })
     }
 
     pub fn len(&self) -> usize {
         self.mappings.len()
     }
-}
 
-/// Stores data used to map gamepad buttons and axes.
-///
-/// After you add all mappings, use
-/// [`Gamepad::set_mapping(…)`](struct.Gamepad.html#method.set_mapping) to change mapping of
-/// existing gamepad.
-///
-/// See `examples/mapping.rs` for more detailed example.
+    /// Writes every mapping, one per line, sorted by UUID. Lines with the same UUID are written
+    /// oldest first, so inserting the output into another database gives them the same priority.
+    /// If `include_bundled` is false, lines that are also in the included database are skipped.
+    pub fn export(&self, writer: &mut impl Write, include_bundled: bool) -> io::Result<()> {
+        let bundled: HashSet<_> = if include_bundled {
+            HashSet::new()
+        } else {
+            BUNDLED_MAPPINGS.lines().collect()
+        };
+
+        let mut uuids: Vec<_> = self
+            .mappings
+            .keys()
+            .chain(self.other_platforms.keys())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        uuids.sort();
+
+        for uuid in uuids {
+            let host = self
+                .mappings
+                .get(uuid)
+                .into_iter()
+                .flatten()
+                .rev()
+                .map(|l| &l.line);
+            let other = self.other_platforms.get(uuid).into_iter().flatten();
+            for line in host.chain(other.map(|(_, line)| line)) {
+                if !bundled.contains(line.as_str()) {
+                    writeln!(writer, "{}", line)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+const BUNDLED_MAPPINGS: &str = include_str!(concat!(
+    env!("OUT_DIR"),
+    PATH_SEPARATOR!(),
+    "gamecontrollerdb.txt"
+));
+
+include!(concat!(
+    env!("OUT_DIR"),
+    PATH_SEPARATOR!(),
+    "mappings_info.rs"
+));
+
+/// Information about SDL mapping database compiled into gilrs, returned by
+/// [`bundled_mappings_info()`](fn.bundled_mappings_info.html).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MappingsDbInfo {
+    /// Number of included mappings. Only mappings for current platform are included.
+    pub entries: usize,
+    /// Hex-encoded 64-bit FNV-1a hash of lines of the whole `gamecontrollerdb.txt`, before
+    /// mappings for other platforms were removed. It identifies database snapshot.
+    pub hash: &'static str,
+    /// UTC date (YYYY-MM-DD) when database was processed by build script.
+    pub build_date: &'static str,
+}
+
+/// Returns information about SDL mapping database compiled into gilrs. Useful in bug reports,
+/// because mappings are often fixed upstream.
+pub fn bundled_mappings_info() -> MappingsDbInfo {
+    MappingsDbInfo {
+        entries: BUNDLED_ENTRIES,
+        hash: BUNDLED_HASH,
+        build_date: BUILD_DATE,
+    }
+}
+
+/// Returns `uuid` with CRC16 of `name` in bytes 2 and 3, the same way as SDL 2.26+ creates GUIDs
+/// to tell apart devices with the same bus, VID and PID. Returns `None` if `name` is empty or
+/// `uuid` already has something in these bytes (it's not SDL GUID with bus type or has CRC).
+fn with_name_crc(uuid: Uuid, name: &str) -> Option<Uuid> {
+    let mut bytes = *uuid.as_bytes();
+    if name.is_empty() || bytes[2..4] != [0, 0] {
+        return None;
+    }
+
+    bytes[2..4].copy_from_slice(&crc16(name.as_bytes()).to_le_bytes());
+    Some(Uuid::from_bytes(bytes))
+}
+
+/// CRC-16/ARC, the variant used by `SDL_crc16()`.
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0, |crc, &byte| {
+        let mut crc = crc ^ u16::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xa001
+            } else {
+                crc >> 1
+            };
+        }
+        crc
+    })
+}
+
+/// Returns true if axis with `info` is one axis of a hat (or switch), which reports only -1, 0
+/// and 1.
+pub(crate) fn is_hat(info: &AxisInfo) -> bool {
+    info.min == -1 && info.max == 1
+}
+
+/// Returns name field of SDL mapping line.
+fn sdl_name(line: &str) -> &str {
+    line.split(',').nth(1).unwrap_or_default()
+}
+
+/// Stores data used to map gamepad buttons and axes.
+///
+/// After you add all mappings, use
+/// [`Gamepad::set_mapping(…)`](struct.Gamepad.html#method.set_mapping) to change mapping of
+/// existing gamepad.
+///
+/// See `examples/mapping.rs` for more detailed example.
 #[derive(Debug, Clone, Default)]
 // Re-exported as Mapping
 pub struct MappingData {
     buttons: VecMap<EvCode>,
     axes: VecMap<EvCode>,
+    inverted_axes: VecMap<()>,
 }
 
 impl MappingData {
@@ -536,6 +1020,7 @@ impl MappingData {
         MappingData {
             buttons: VecMap::with_capacity(18),
             axes: VecMap::with_capacity(11),
+            inverted_axes: VecMap::new(),
         }
     }
 
@@ -566,8 +1051,127 @@ impl MappingData {
 
     /// Removes axis and returns associated `NativEvCode`.
     pub fn remove_axis(&mut self, idx: Axis) -> Option<ev::Code> {
+        self.inverted_axes.remove(idx as usize);
         self.axes.remove(idx as usize).map(ev::Code)
     }
+
+    /// Sets whether value of `axis` is negated, for example to correct physically inverted axis.
+    /// Inversion is saved in SDL mapping string as `~` suffix, so it persists like the rest of
+    /// mapping. It only has effect if `axis` is mapped.
+    pub fn set_axis_inverted(&mut self, axis: Axis, inverted: bool) {
+        if inverted {
+            self.inverted_axes.insert(axis as usize, ());
+        } else {
+            self.inverted_axes.remove(axis as usize);
+        }
+    }
+
+    /// Returns `true` if value of `axis` is negated.
+    pub fn is_axis_inverted(&self, axis: Axis) -> bool {
+        self.inverted_axes.contains_key(axis as usize)
+    }
+
+    /// Checks if mapping can be applied to `gamepad`, without changing it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Gilrs::set_mapping()`](struct.Gilrs.html#method.set_mapping)
+    /// would for invalid mapping: `UnknownElement` if mapping contains `Button::Unknown` or
+    /// `Axis::Unknown`, `InvalidCode` if gamepad does not have referenced element and
+    /// `DuplicatedEntry` if the same element is used by an axis and by any other button or axis.
+    /// Several buttons can be mapped to the same element.
+    pub fn validate_for(&self, gamepad: &Gamepad<'_>) -> Result<(), MappingError> {
+        let buttons: Vec<_> = gamepad.buttons().map(|(code, _)| code.0).collect();
+        let axes: Vec<_> = gamepad.axes().map(|(code, _)| code.0).collect();
+
+        self.validate(&buttons, &axes)
+    }
+
+    pub(crate) fn validate(&self, buttons: &[EvCode], axes: &[EvCode]) -> Result<(), MappingError> {
+        let mut used = HashSet::new();
+
+        for (btn, &ev_code) in &self.buttons {
+            if btn == Button::Unknown as usize {
+                return Err(MappingError::UnknownElement);
+            }
+            // Analog shoulders and triggers are reported as axes, so buttons can also be mapped
+            // from axis codes.
+            if !buttons.contains(&ev_code) && !axes.contains(&ev_code) {
+                return Err(MappingError::InvalidCode(ev::Code(ev_code)));
+            }
+            used.insert(ev_code);
+        }
+
+        for (axis, &ev_code) in &self.axes {
+            if axis == Axis::Unknown as usize {
+                return Err(MappingError::UnknownElement);
+            }
+            if !axes.contains(&ev_code) {
+                return Err(MappingError::InvalidCode(ev::Code(ev_code)));
+            }
+            if !used.insert(ev_code) {
+                return Err(MappingError::DuplicatedEntry);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if mapping applied to `gamepad` produces SDL string that, when parsed back,
+    /// gives exactly the same mapping. Use it to warn user before saving mapping that won't
+    /// survive serialization. Gamepad name is used as mapping name, like in `set_mapping()` called
+    /// with `None`. Returns `false` if mapping can't be applied to `gamepad` at all.
+    pub fn roundtrips_for(&self, gamepad: &Gamepad<'_>) -> bool {
+        let buttons: Vec<_> = gamepad.buttons().map(|(code, _)| code.0).collect();
+        let axes: Vec<_> = gamepad.axes().map(|(code, _)| code.0).collect();
+        let uuid = Uuid::from_bytes(gamepad.uuid());
+
+        self.roundtrips(&buttons, &axes, gamepad.os_name(), uuid)
+    }
+
+    pub(crate) fn roundtrips(
+        &self,
+        buttons: &[EvCode],
+        axes: &[EvCode],
+        name: &str,
+        uuid: Uuid,
+    ) -> bool {
+        match Mapping::from_data(self, buttons, axes, name, uuid) {
+            Ok((mapping, sdl)) => Mapping::parse_sdl_mapping(&sdl, buttons, axes)
+                .map(|parsed| parsed == mapping)
+                .unwrap_or(false),
+            Err(_) => false,
+        }
+    }
+
+    /// Returns `true` if every element is mapped to at most one button or axis.
+    pub fn is_one_to_one(&self) -> bool {
+        let mut used = HashSet::new();
+
+        self.buttons
+            .values()
+            .chain(self.axes.values())
+            .all(|&ev_code| used.insert(ev_code))
+    }
+
+    /// Returns `MappingError::NotSdl2Compatible` if mapping has button or axis that can't be used
+    /// in SDL2 mappings or maps one element to more than one button or axis.
+    pub(crate) fn check_sdl2_compatible(&self) -> Result<(), MappingError> {
+        for idx in self.buttons.keys() {
+            let (btn, _) = sdl_button(idx).ok_or(MappingError::NotSdl2Compatible)?;
+            SdlButton::try_from(btn)?;
+        }
+        for idx in self.axes.keys() {
+            let (axis, _) = sdl_axis(idx).ok_or(MappingError::NotSdl2Compatible)?;
+            SdlAxis::try_from(axis)?;
+        }
+
+        if self.is_one_to_one() {
+            Ok(())
+        } else {
+            Err(MappingError::NotSdl2Compatible)
+        }
+    }
 }
 
 /// The error type for functions related to gamepad mapping.
@@ -586,7 +1190,8 @@ pub enum MappingError {
     DuplicatedEntry,
     /// `Mapping` with `Button::Unknown` or `Axis::Unknown`.
     UnknownElement,
-    /// `Mapping` have button or axis that are not present in SDL2.
+    /// `Mapping` have button or axis that are not present in SDL2 or maps one element to more
+    /// than one button.
     NotSdl2Compatible,
 }
 
@@ -609,7 +1214,7 @@ impl Display for MappingError {
                 "same gamepad element is referenced by axis and button"
             }
             MappingError::UnknownElement => "Button::Unknown and Axis::Unknown are not allowed",
-            MappingError::NotSdl2Compatible => "one of buttons or axes is not compatible with SDL2",
+            MappingError::NotSdl2Compatible => "mapping is not compatible with SDL2",
         };
 
         f.write_str(s)
@@ -665,7 +1270,42 @@ mod tests {
 
     #[test]
     fn mapping() {
-        Mapping::parse_sdl_mapping(TEST_STR, &BUTTONS, &AXES).unwrap();
+        let mapping = Mapping::parse_sdl_mapping_strict(TEST_STR, &BUTTONS, &AXES).unwrap();
+        assert!(mapping.unresolved().is_empty());
+    }
+
+    #[test]
+    fn partial_mapping() {
+        let buttons = &BUTTONS[..4];
+        let axes = &AXES[..3];
+
+        let mapping = Mapping::parse_sdl_mapping(TEST_STR, buttons, axes).unwrap();
+        assert_eq!(
+            Some(AxisOrBtn::Btn(Button::South)),
+            mapping.map(&BUTTONS[0])
+        );
+        assert_eq!(Some(AxisOrBtn::Btn(Button::East)), mapping.map(&BUTTONS[2]));
+        assert_eq!(
+            Some(AxisOrBtn::Axis(Axis::RightStickX)),
+            mapping.map(&AXES[2])
+        );
+        assert_eq!(None, mapping.map(&AXES[3]));
+        assert_eq!(None, mapping.map_rev(&AxisOrBtn::Btn(Button::Start)));
+        assert_eq!(None, mapping.map_rev(&AxisOrBtn::Axis(Axis::RightStickY)));
+        assert_eq!(mapping.unresolved(), ["b7", "b6", "a3", "a4", "a5"]);
+        // Face buttons and dpad.
+        assert_eq!(mapping.button_count(), 8);
+        // Left stick, right stick X and dpad.
+        assert_eq!(mapping.axis_count(), 5);
+
+        assert_eq!(
+            Err(ParseSdlMappingError::InvalidButton(7)),
+            Mapping::parse_sdl_mapping_strict(TEST_STR, buttons, axes)
+        );
+        assert_eq!(
+            Err(ParseSdlMappingError::InvalidAxis(3)),
+            Mapping::parse_sdl_mapping_strict(TEST_STR, &BUTTONS, axes)
+        );
     }
 
     #[test]
@@ -697,6 +1337,8 @@ mod tests {
             Mapping::from_data(&data, &BUTTONS, &AXES, name, uuid).unwrap();
         let sdl_mappings = Mapping::parse_sdl_mapping(&sdl_mappings, &BUTTONS, &AXES).unwrap();
         assert_eq!(mappings, sdl_mappings);
+        assert!(data.roundtrips(&BUTTONS, &AXES, name, uuid));
+        assert!(!data.roundtrips(&BUTTONS, &AXES, "Inval,id name", uuid));
 
         let incorrect_mappings = Mapping::from_data(&data, &BUTTONS, &AXES, "Inval,id name", uuid);
         assert_eq!(Err(MappingError::InvalidName), incorrect_mappings);
@@ -713,6 +1355,296 @@ mod tests {
         assert_eq!(Err(MappingError::UnknownElement), incorrect_mappings);
     }
 
+    #[test]
+    fn validate() {
+        let mut data = MappingData::new();
+        data.insert_btn(ev::Code(BUTTONS[0]), Button::South);
+        data.insert_axis(ev::Code(AXES[0]), Axis::LeftStickX);
+        assert_eq!(Ok(()), data.validate(&BUTTONS, &AXES));
+
+        // Many buttons can share element, but axis can't.
+        data.insert_btn(ev::Code(BUTTONS[0]), Button::East);
+        assert_eq!(Ok(()), data.validate(&BUTTONS, &AXES));
+        data.insert_btn(ev::Code(AXES[0]), Button::North);
+        assert_eq!(
+            Err(MappingError::DuplicatedEntry),
+            data.validate(&BUTTONS, &AXES)
+        );
+        assert_eq!(
+            Err(MappingError::DuplicatedEntry),
+            Mapping::from_data(&data, &BUTTONS, &AXES, "name", Uuid::nil()).map(|_| ())
+        );
+        data.remove_button(Button::North);
+        data.remove_button(Button::East);
+
+        assert_eq!(
+            Err(MappingError::InvalidCode(ev::Code(AXES[0]))),
+            data.validate(&BUTTONS, &AXES[1..])
+        );
+    }
+
+    #[test]
+    fn inverted_axes() {
+        let uuid = Uuid::nil();
+        let name = "Inverted";
+
+        let mut data = MappingData::new();
+        data.insert_axis(ev::Code(AXES[0]), Axis::LeftStickX);
+        data.insert_axis(ev::Code(AXES[1]), Axis::LeftStickY);
+        data.set_axis_inverted(Axis::LeftStickY, true);
+        // Unmapped axis is ignored.
+        data.set_axis_inverted(Axis::RightStickX, true);
+        assert!(data.is_axis_inverted(Axis::LeftStickY));
+        assert!(!data.is_axis_inverted(Axis::LeftStickX));
+
+        let (mapping, sdl) = Mapping::from_data(&data, &BUTTONS, &AXES, name, uuid).unwrap();
+        assert_eq!(
+            sdl,
+            "00000000000000000000000000000000,Inverted,leftx:a0,lefty:a1~,"
+        );
+        assert!(mapping.is_inverted(&AXES[1]));
+        assert!(!mapping.is_inverted(&AXES[0]));
+        assert_eq!(
+            Mapping::parse_sdl_mapping(&sdl, &BUTTONS, &AXES).unwrap(),
+            mapping
+        );
+        assert!(data.roundtrips(&BUTTONS, &AXES, name, uuid));
+
+        // Inversion of axis mapped to button is ignored.
+        let parsed = Mapping::parse_sdl_mapping(
+            "00000000000000000000000000000000,Inverted,rightx:a3~,lefttrigger:a9~,",
+            &BUTTONS,
+            &AXES,
+        )
+        .unwrap();
+        assert!(parsed.is_inverted(&AXES[3]));
+        assert!(!parsed.is_inverted(&AXES[9]));
+
+        data.remove_axis(Axis::LeftStickY);
+        assert!(!data.is_axis_inverted(Axis::LeftStickY));
+    }
+
+    #[test]
+    fn to_sdl_line() {
+        let uuid = Uuid::parse_str("03000000260900008888000000010001").unwrap();
+        let line = "03000000260900008888000000010001,Pad,a:b0,b:b1,dpdown:h0.4,dpleft:h0.8,\
+                    dpright:h0.2,dpup:h0.1,lefttrigger:a11,leftx:a0,lefty:a1~,paddle1:b2,x:b1,";
+        let mut mapping = Mapping::parse_sdl_mapping(line, &BUTTONS, &AXES).unwrap();
+        // Paddles have no gilrs equivalent.
+        let expected = line.replace("paddle1:b2,", "");
+        assert_eq!(
+            mapping.to_sdl_line(uuid, &BUTTONS, &AXES).as_deref(),
+            Some(expected.as_str())
+        );
+
+        mapping.set_inverted(AXES[1], false);
+        mapping.set_inverted(AXES[0], true);
+        let line = mapping.to_sdl_line(uuid, &BUTTONS, &AXES).unwrap();
+        assert!(line.contains(",leftx:a0~,lefty:a1,"));
+        let reparsed = Mapping::parse_sdl_mapping(&line, &BUTTONS, &AXES).unwrap();
+        assert!(reparsed.is_inverted(&AXES[0]));
+        assert!(!reparsed.is_inverted(&AXES[1]));
+
+        mapping.name = "Comma, pad".to_owned();
+        assert_eq!(mapping.to_sdl_line(uuid, &BUTTONS, &AXES), None);
+    }
+
+    #[test]
+    fn analog_shoulders_and_triggers() {
+        let uuid = Uuid::nil();
+        let name = "Analog";
+
+        let mut data = MappingData::new();
+        data.insert_axis(ev::Code(nec::AXIS_LSTICKX), Axis::LeftStickX);
+        data.insert_axis(ev::Code(nec::AXIS_LSTICKY), Axis::LeftStickY);
+        data.insert_btn(ev::Code(BUTTONS[0]), Button::South);
+        data.insert_btn(ev::Code(nec::AXIS_LT), Button::LeftTrigger);
+        data.insert_btn(ev::Code(nec::AXIS_LT2), Button::LeftTrigger2);
+        data.insert_btn(ev::Code(nec::AXIS_RT), Button::RightTrigger);
+        data.insert_btn(ev::Code(nec::AXIS_RT2), Button::RightTrigger2);
+        assert_eq!(Ok(()), data.validate(&BUTTONS, &AXES));
+
+        let (mapping, sdl_mapping) =
+            Mapping::from_data(&data, &BUTTONS, &AXES, name, uuid).unwrap();
+        assert_eq!(
+            Some(AxisOrBtn::Btn(Button::LeftTrigger)),
+            mapping.map(&nec::AXIS_LT)
+        );
+        assert_eq!(
+            Some(AxisOrBtn::Btn(Button::LeftTrigger2)),
+            mapping.map(&nec::AXIS_LT2)
+        );
+        assert!(sdl_mapping.contains("leftshoulder:a9,"));
+        assert!(sdl_mapping.contains("lefttrigger:a11,"));
+
+        let parsed = Mapping::parse_sdl_mapping_strict(&sdl_mapping, &BUTTONS, &AXES).unwrap();
+        assert_eq!(mapping, parsed);
+
+        // Analog trigger can't be also used as axis.
+        data.insert_axis(ev::Code(nec::AXIS_LT), Axis::LeftZ);
+        assert_eq!(
+            Err(MappingError::DuplicatedEntry),
+            data.validate(&BUTTONS, &AXES)
+        );
+    }
+
+    #[test]
+    fn late_button() {
+        let mut mapping = Mapping::default_all();
+        mapping.mappings.remove(&nec::BTN_MODE);
+        mapping.add_late_button(nec::BTN_MODE);
+        assert_eq!(
+            Some(AxisOrBtn::Btn(Button::Mode)),
+            mapping.map(&nec::BTN_MODE)
+        );
+
+        // Custom mappings are not changed.
+        let mut mapping = Mapping::parse_sdl_mapping_strict(TEST_STR, &BUTTONS, &AXES).unwrap();
+        mapping.add_late_button(nec::BTN_MODE);
+        assert_eq!(None, mapping.map(&nec::BTN_MODE));
+    }
+
+    #[test]
+    fn one_element_many_buttons() {
+        let mut data = MappingData::new();
+        data.insert_btn(ev::Code(BUTTONS[0]), Button::South);
+        data.insert_btn(ev::Code(BUTTONS[1]), Button::East);
+        data.insert_axis(ev::Code(AXES[0]), Axis::LeftStickX);
+        assert!(data.is_one_to_one());
+        data.insert_btn(ev::Code(BUTTONS[0]), Button::Start);
+        assert!(!data.is_one_to_one());
+
+        let (mapping, sdl_mapping) =
+            Mapping::from_data(&data, &BUTTONS, &AXES, "Paddle", Uuid::nil()).unwrap();
+        assert_eq!(
+            Some(AxisOrBtn::Btn(Button::South)),
+            mapping.map(&BUTTONS[0])
+        );
+        assert_eq!(&[Button::Start], mapping.aliases(&BUTTONS[0]));
+        assert!(mapping.aliases(&BUTTONS[1]).is_empty());
+        assert_eq!(
+            Some(BUTTONS[0]),
+            mapping.map_rev(&AxisOrBtn::Btn(Button::Start))
+        );
+        assert_eq!(
+            Some(BUTTONS[0]),
+            mapping.map_rev(&AxisOrBtn::Btn(Button::South))
+        );
+        assert_eq!(3, mapping.button_count());
+        assert!(sdl_mapping.contains("a:b0,"));
+        assert!(sdl_mapping.contains("start:b0,"));
+
+        let parsed = Mapping::parse_sdl_mapping_strict(&sdl_mapping, &BUTTONS, &AXES).unwrap();
+        assert_eq!(mapping, parsed);
+    }
+
+    #[test]
+    fn unknown_button_alias() {
+        let mut data = MappingData::new();
+        data.insert_btn(ev::Code(BUTTONS[0]), Button::South);
+        data.insert_btn(ev::Code(BUTTONS[1]), Button::East);
+        let (_, sdl_mapping) =
+            Mapping::from_data(&data, &BUTTONS, &AXES, "Paddle", Uuid::nil()).unwrap();
+        // `Button::Unknown` both after and before known button.
+        assert!(sdl_mapping.contains("b:b1,"));
+        let sdl_mapping = format!(
+            "{}paddle1:b0,",
+            sdl_mapping.replace("b:b1,", "paddle2:b1,b:b1,")
+        );
+
+        let mapping = Mapping::parse_sdl_mapping_strict(&sdl_mapping, &BUTTONS, &AXES).unwrap();
+        assert_eq!(
+            Some(AxisOrBtn::Btn(Button::South)),
+            mapping.map(&BUTTONS[0])
+        );
+        assert!(mapping.aliases(&BUTTONS[0]).is_empty());
+        assert_eq!(Some(AxisOrBtn::Btn(Button::East)), mapping.map(&BUTTONS[1]));
+        assert!(mapping.aliases(&BUTTONS[1]).is_empty());
+        assert_eq!(2, mapping.button_count());
+    }
+
+    #[test]
+    fn swap_and_remap() {
+        let mut data = MappingData::new();
+        data.insert_btn(ev::Code(BUTTONS[0]), Button::South);
+        data.insert_btn(ev::Code(BUTTONS[0]), Button::Start);
+        data.insert_btn(ev::Code(BUTTONS[1]), Button::East);
+        let (mut mapping, _) =
+            Mapping::from_data(&data, &BUTTONS, &AXES, "Paddle", Uuid::nil()).unwrap();
+
+        mapping.swap(BUTTONS[0], BUTTONS[1]);
+        assert_eq!(Some(AxisOrBtn::Btn(Button::East)), mapping.map(&BUTTONS[0]));
+        assert_eq!(
+            Some(AxisOrBtn::Btn(Button::South)),
+            mapping.map(&BUTTONS[1])
+        );
+        assert!(mapping.aliases(&BUTTONS[0]).is_empty());
+        assert_eq!(&[Button::Start], mapping.aliases(&BUTTONS[1]));
+
+        // Swapping with unmapped element moves mapping.
+        mapping.swap(BUTTONS[1], BUTTONS[2]);
+        assert_eq!(None, mapping.map(&BUTTONS[1]));
+        assert_eq!(
+            Some(AxisOrBtn::Btn(Button::South)),
+            mapping.map(&BUTTONS[2])
+        );
+        assert_eq!(&[Button::Start], mapping.aliases(&BUTTONS[2]));
+
+        mapping.remap(BUTTONS[2], AxisOrBtn::Btn(Button::Mode));
+        assert_eq!(Some(AxisOrBtn::Btn(Button::Mode)), mapping.map(&BUTTONS[2]));
+        assert!(mapping.aliases(&BUTTONS[2]).is_empty());
+        assert_eq!(None, mapping.map_rev(&AxisOrBtn::Btn(Button::Start)));
+
+        let mut mapping = Mapping::default_all().with_sdl_line("line");
+        mapping.mark_custom("Paddle");
+        assert!(!mapping.is_default());
+        assert_eq!("Paddle", mapping.name());
+        assert_eq!(None, mapping.sdl_line());
+        mapping.mark_custom("Other");
+        assert_eq!("Paddle", mapping.name());
+    }
+
+    #[test]
+    fn sdl2_compatibility() {
+        let mut data = MappingData::new();
+        data.insert_btn(ev::Code(BUTTONS[0]), Button::South);
+        data.insert_btn(ev::Code(BUTTONS[1]), Button::Misc1);
+        data.insert_axis(ev::Code(AXES[0]), Axis::LeftStickX);
+        assert_eq!(data.check_sdl2_compatible(), Ok(()));
+
+        let mut with_c = data.clone();
+        with_c.insert_btn(ev::Code(BUTTONS[2]), Button::C);
+        assert_eq!(
+            with_c.check_sdl2_compatible(),
+            Err(MappingError::NotSdl2Compatible)
+        );
+
+        let mut with_z_axis = data.clone();
+        with_z_axis.insert_axis(ev::Code(AXES[2]), Axis::LeftZ);
+        assert_eq!(
+            with_z_axis.check_sdl2_compatible(),
+            Err(MappingError::NotSdl2Compatible)
+        );
+
+        // Axes without SDL name are rejected by the check, not later by `from_data()`.
+        let mut with_dpad_axis = data.clone();
+        with_dpad_axis.insert_axis(ev::Code(AXES[3]), Axis::DPadX);
+        assert_eq!(
+            with_dpad_axis.check_sdl2_compatible(),
+            Err(MappingError::NotSdl2Compatible)
+        );
+        assert_eq!(
+            Mapping::from_data(&with_dpad_axis, &BUTTONS, &AXES, "name", Uuid::nil()).unwrap_err(),
+            MappingError::UnknownElement
+        );
+
+        data.insert_btn(ev::Code(BUTTONS[0]), Button::Start);
+        assert_eq!(
+            data.check_sdl2_compatible(),
+            Err(MappingError::NotSdl2Compatible)
+        );
+    }
+
     #[test]
     fn with_mappings() {
         let mappings = format!(
@@ -728,4 +1660,363 @@ mod tests {
             db.get(Uuid::parse_str("03000000260900008888000000010001").unwrap())
         );
     }
+
+    // Highest number of buttons and axes that backend can report. SDL mappings reference elements
+    // by index, so codes don't matter here.
+    #[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd"))]
+    const MAX_ELEMENTS: (usize, usize) = (96, 64);
+    #[cfg(target_os = "macos")]
+    const MAX_ELEMENTS: (usize, usize) = (64, 16);
+    #[cfg(target_os = "windows")]
+    const MAX_ELEMENTS: (usize, usize) = (128, 8);
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "macos",
+        target_os = "windows"
+    )))]
+    const MAX_ELEMENTS: (usize, usize) = (0, 0);
+
+    // GUIDs of included mappings that are expected to fail in strict mode.
+    const KNOWN_INVALID_MAPPINGS: &[&str] = &[];
+
+    #[test]
+    fn included_mappings() {
+        let buttons: Vec<_> = BUTTONS
+            .iter()
+            .cloned()
+            .cycle()
+            .take(MAX_ELEMENTS.0)
+            .collect();
+        let axes: Vec<_> = AXES.iter().cloned().cycle().take(MAX_ELEMENTS.1).collect();
+        let included = include_str!(concat!(
+            env!("OUT_DIR"),
+            PATH_SEPARATOR!(),
+            "gamecontrollerdb.txt"
+        ));
+
+        let mut lines = 0;
+        let mut parsed = 0;
+        let mut failed = Vec::new();
+        let mut errors = Vec::new();
+        // Known invalid mappings are only listed for current platform.
+        let host = format!("platform:{},", SDL_PLATFORM_NAME);
+        for line in included.lines().filter(|line| line.ends_with(&host)) {
+            lines += 1;
+            match Mapping::parse_sdl_mapping_strict(line, &buttons, &axes) {
+                Ok(_) => parsed += 1,
+                Err(e) => {
+                    errors.push(format!("{}: {}", e, line));
+                    failed.push(line.split(',').next().unwrap_or_default());
+                }
+            }
+        }
+
+        failed.sort_unstable();
+        assert_eq!(
+            failed,
+            KNOWN_INVALID_MAPPINGS,
+            "invalid mappings:\n{}",
+            errors.join("\n")
+        );
+        assert_eq!(parsed + KNOWN_INVALID_MAPPINGS.len(), lines);
+    }
+
+    #[test]
+    fn bundled_platforms() {
+        let host = format!("platform:{},", SDL_PLATFORM_NAME);
+        let other = BUNDLED_MAPPINGS
+            .lines()
+            .find(|line| !line.ends_with(&host) && line.contains("platform:"));
+
+        if cfg!(not(feature = "all-platform-mappings")) {
+            assert_eq!(other, None);
+            return;
+        }
+
+        // Any line whose UUID isn't used by mapping for current platform.
+        let other = BUNDLED_MAPPINGS
+            .lines()
+            .filter(|line| !line.ends_with(&host))
+            .find(|line| {
+                let uuid = line.split(',').next().unwrap();
+                !BUNDLED_MAPPINGS
+                    .lines()
+                    .any(|l| l.starts_with(uuid) && l.ends_with(&host))
+            })
+            .expect("no bundled mapping that is only for other platform");
+        let uuid = Uuid::parse_str(other.split(',').next().unwrap()).unwrap();
+        let platform = other
+            .trim_end_matches(',')
+            .rsplit_once("platform:")
+            .unwrap()
+            .1;
+
+        let mut db = MappingDb::new();
+        db.add_included_mappings();
+        assert_eq!(db.get(uuid), None);
+        db.set_platforms(MappingPlatforms::With(platform.to_owned()));
+        assert_eq!(db.get(uuid), Some(other));
+    }
+
+    #[test]
+    fn bundled_info() {
+        let included = include_str!(concat!(
+            env!("OUT_DIR"),
+            PATH_SEPARATOR!(),
+            "gamecontrollerdb.txt"
+        ));
+        let entries = included
+            .lines()
+            .filter(|line| {
+                line.split(',')
+                    .next()
+                    .is_some_and(|uuid| Uuid::parse_str(uuid).is_ok())
+            })
+            .count();
+
+        let info = bundled_mappings_info();
+        assert_eq!(info.entries, entries);
+        assert_eq!(info.hash.len(), 16);
+        assert!(info.hash.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(info.build_date.len(), 10);
+        assert_eq!(info.build_date.matches('-').count(), 2);
+
+        let mut db = MappingDb::new();
+        db.add_included_mappings();
+        assert!(db.len() <= info.entries);
+    }
+
+    #[test]
+    fn name_crc() {
+        // Check value of CRC-16/ARC.
+        assert_eq!(crc16(b"123456789"), 0xbb3d);
+        assert_eq!(crc16(b""), 0);
+
+        let uuid = Uuid::parse_str("030000005e0400008e02000010010000").unwrap();
+        // GUID of wired Xbox 360 controller in SDL 2.26+ mappings for Linux.
+        let with_crc = with_name_crc(uuid, "Microsoft X-Box 360 pad").unwrap();
+        assert_eq!(
+            with_crc,
+            Uuid::parse_str("030081b85e0400008e02000010010000").unwrap()
+        );
+        // Check value of CRC-16/ARC is stored in little endian.
+        assert_eq!(
+            with_name_crc(uuid, "123456789").unwrap(),
+            Uuid::parse_str("03003dbb5e0400008e02000010010000").unwrap()
+        );
+        assert_eq!(with_name_crc(uuid, ""), None);
+        assert_eq!(with_name_crc(with_crc, "Microsoft X-Box 360 pad"), None);
+
+        let mut db = MappingDb::new();
+        let plain = format!("{},Xbox 360 Controller,a:b0,", uuid.simple());
+        db.insert(&plain);
+        // Without CRC line, mapping keyed on plain GUID is used.
+        assert_eq!(
+            db.get_for(uuid, "Microsoft X-Box 360 pad"),
+            Some(plain.as_str())
+        );
+
+        let crc_line = format!("{},Xbox 360 Controller,a:b1,", with_crc.simple());
+        db.insert(&crc_line);
+        assert_eq!(
+            db.get_for(uuid, "Microsoft X-Box 360 pad"),
+            Some(crc_line.as_str())
+        );
+        // Other devices with the same VID/PID use mapping without CRC.
+        assert_eq!(db.get_for(uuid, "Xbox 360 clone"), Some(plain.as_str()));
+        assert_eq!(db.get(uuid), Some(plain.as_str()));
+    }
+
+    #[test]
+    fn same_uuid_different_names() {
+        let uuid = Uuid::parse_str("03000000260900008888000000010003").unwrap();
+        let original = format!("{},Retro Pad,a:b0,", uuid.as_simple());
+        let clone = format!("{},Cheap Clone,a:b1,", uuid.as_simple());
+
+        let mut db = MappingDb::new();
+        db.insert(&original);
+        db.insert(&clone);
+
+        assert_eq!(db.get_for(uuid, "ACME retro pad"), Some(original.as_str()));
+        assert_eq!(
+            db.get_for(uuid, "CHEAP CLONE Controller"),
+            Some(clone.as_str())
+        );
+        // Newest line is used if name doesn't match.
+        assert_eq!(db.get_for(uuid, "Unknown"), Some(clone.as_str()));
+        assert_eq!(db.get(uuid), Some(clone.as_str()));
+
+        // Line with the same name replaces older one.
+        let fixed = format!("{},Retro Pad,a:b2,", uuid.as_simple());
+        db.insert(&fixed);
+        assert_eq!(db.get_for(uuid, "ACME retro pad"), Some(fixed.as_str()));
+        assert_eq!(db.get(uuid), Some(fixed.as_str()));
+        assert_eq!(db.len(), 1);
+    }
+
+    #[test]
+    fn user_lines_win_over_bundled() {
+        let uuid = Uuid::parse_str("03000000260900008888000000010003").unwrap();
+        let bundled = format!("{},Retro Pad,a:b0,", uuid.as_simple());
+        let user = format!("{},My Pad,a:b1,", uuid.as_simple());
+
+        let mut db = MappingDb::new();
+        db.insert_lines(&bundled, true);
+        assert_eq!(db.get_for(uuid, "ACME retro pad"), Some(bundled.as_str()));
+
+        // Newer user line is used even though only bundled line matches the name.
+        db.insert(&user);
+        assert_eq!(db.get_for(uuid, "ACME retro pad"), Some(user.as_str()));
+
+        // Bundled line doesn't replace user line with the same name.
+        let user = format!("{},Retro Pad,a:b2,", uuid.as_simple());
+        db.insert(&user);
+        db.insert_lines(&bundled, true);
+        assert_eq!(db.get_for(uuid, "ACME retro pad"), Some(user.as_str()));
+    }
+
+    #[test]
+    fn other_platforms() {
+        let uuid = Uuid::parse_str("03000000260900008888000000010002").unwrap();
+        let host = format!(
+            "{},Host,a:b0,platform:{},",
+            uuid.as_simple(),
+            SDL_PLATFORM_NAME
+        );
+        let ios = format!("{},iOS,a:b1,platform:iOS,", uuid.as_simple());
+        let tvos = format!("{},tvOS,a:b2,platform:tvOS,", uuid.as_simple());
+
+        let mut db = MappingDb::new();
+        db.insert(&ios);
+        db.insert(&tvos);
+        assert_eq!(None, db.get(uuid));
+
+        db.set_platforms(MappingPlatforms::With("iOS".to_owned()));
+        assert_eq!(Some(ios.as_str()), db.get(uuid));
+
+        db.set_platforms(MappingPlatforms::All);
+        assert_eq!(Some(tvos.as_str()), db.get(uuid));
+
+        db.insert(&host);
+        assert_eq!(Some(host.as_str()), db.get(uuid));
+
+        let mapping =
+            Mapping::parse_sdl_mapping_for(&ios, &BUTTONS, &AXES, db.platforms()).unwrap();
+        assert_eq!(
+            Some(AxisOrBtn::Btn(Button::South)),
+            mapping.map(&BUTTONS[1])
+        );
+    }
+
+    #[test]
+    fn export() {
+        let export = |db: &MappingDb, include_bundled| {
+            let mut out = Vec::new();
+            db.export(&mut out, include_bundled).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let first = "03000000260900008888000000010004";
+        let second = "03000000260900008888000000010005";
+        let old = format!("{},Old,a:b0,", second);
+        let new = format!("{},New,a:b1,", second);
+        let ios = format!("{},iOS,a:b2,platform:iOS,", second);
+        let single = format!("{},Single,a:b3,", first);
+
+        let mut db = MappingDb::new();
+        db.add_included_mappings();
+        db.insert(&[old.as_str(), &new, &ios, &single].join("\n"));
+        // Sorted by UUID, oldest line of each UUID first and bundled lines skipped.
+        let user = export(&db, false);
+        assert_eq!(user, format!("{}\n{}\n{}\n{}\n", single, old, new, ios));
+
+        // Export of imported mappings is identical and keeps priority of lines.
+        let mut imported = MappingDb::new();
+        imported.insert(&user);
+        assert_eq!(export(&imported, true), user);
+        assert_eq!(
+            imported.get(Uuid::parse_str(second).unwrap()),
+            Some(new.as_str())
+        );
+        // Importing the same mappings again doesn't change anything.
+        db.insert(&user);
+        assert_eq!(export(&db, false), user);
+
+        let all = export(&db, true);
+        assert!(all.lines().count() >= db.len());
+        let mut imported = MappingDb::new();
+        imported.insert(&all);
+        assert_eq!(export(&imported, true), all);
+        // Only bundled lines are skipped.
+        let bundled: HashSet<_> = BUNDLED_MAPPINGS.lines().collect();
+        assert!(all
+            .lines()
+            .filter(|line| !user.lines().any(|l| l == *line))
+            .all(|line| bundled.contains(line)));
+    }
+
+    #[test]
+    fn sdl_keys() {
+        use Axis::*;
+        use Button::*;
+
+        let buttons = [
+            South,
+            East,
+            North,
+            West,
+            C,
+            Z,
+            LeftTrigger,
+            LeftTrigger2,
+            RightTrigger,
+            RightTrigger2,
+            Select,
+            Start,
+            Mode,
+            LeftThumb,
+            RightThumb,
+            DPadUp,
+            DPadDown,
+            DPadLeft,
+            DPadRight,
+            Misc1,
+        ];
+        for btn in buttons {
+            let key = btn.to_sdl_key().unwrap();
+            assert_eq!(Button::from_sdl_key(key), Some(btn), "{}", key);
+            assert_eq!(Axis::from_sdl_key(key), None);
+        }
+        assert_eq!(Button::Unknown.to_sdl_key(), None);
+
+        let axes = [
+            LeftStickX,
+            LeftStickY,
+            LeftZ,
+            RightStickX,
+            RightStickY,
+            RightZ,
+        ];
+        for axis in axes {
+            let key = axis.to_sdl_key().unwrap();
+            assert_eq!(Axis::from_sdl_key(key), Some(axis), "{}", key);
+            assert_eq!(Button::from_sdl_key(key), None);
+        }
+        for axis in [DPadX, DPadY, Axis::Unknown] {
+            assert_eq!(axis.to_sdl_key(), None);
+        }
+
+        assert_eq!(Button::from_sdl_key("a"), Some(South));
+        assert_eq!(Button::from_sdl_key("leftshoulder"), Some(LeftTrigger));
+        assert_eq!(Button::from_sdl_key("lefttrigger"), Some(LeftTrigger2));
+        assert_eq!(Button::from_sdl_key("dpup"), Some(DPadUp));
+        assert_eq!(Axis::from_sdl_key("leftx"), Some(LeftStickX));
+        // Valid SDL elements without gilrs equivalent.
+        assert_eq!(Button::from_sdl_key("paddle1"), None);
+        assert_eq!(Button::from_sdl_key("touchpad"), None);
+        assert_eq!(Button::from_sdl_key("misc1"), Some(Misc1));
+        assert_eq!(Button::from_sdl_key("platform"), None);
+        assert_eq!(Axis::from_sdl_key(""), None);
+    }
 }
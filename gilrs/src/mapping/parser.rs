@@ -11,74 +11,98 @@ use uuid::Uuid;
 
 use crate::ev::{Axis, AxisOrBtn, Button};
 
-// Must be sorted!
-static AXES_SDL: [&str; 31] = [
-    "a",
-    "b",
-    "back",
-    "c",
-    "dpdown",
-    "dpleft",
-    "dpright",
-    "dpup",
-    "guide",
-    "leftshoulder",
-    "leftstick",
-    "lefttrigger",
-    "leftx",
-    "lefty",
-    "leftz",
-    "misc1",
-    "paddle1",
-    "paddle2",
-    "paddle3",
-    "paddle4",
-    "rightshoulder",
-    "rightstick",
-    "righttrigger",
-    "rightx",
-    "righty",
-    "rightz",
-    "start",
-    "touchpad",
-    "x",
-    "y",
-    "z",
-];
-static AXES: [AxisOrBtn; 31] = [
-    AxisOrBtn::Btn(Button::South),
-    AxisOrBtn::Btn(Button::East),
-    AxisOrBtn::Btn(Button::Select),
-    AxisOrBtn::Btn(Button::C),
-    AxisOrBtn::Btn(Button::DPadDown),
-    AxisOrBtn::Btn(Button::DPadLeft),
-    AxisOrBtn::Btn(Button::DPadRight),
-    AxisOrBtn::Btn(Button::DPadUp),
-    AxisOrBtn::Btn(Button::Mode),
-    AxisOrBtn::Btn(Button::LeftTrigger),
-    AxisOrBtn::Btn(Button::LeftThumb),
-    AxisOrBtn::Btn(Button::LeftTrigger2),
-    AxisOrBtn::Axis(Axis::LeftStickX),
-    AxisOrBtn::Axis(Axis::LeftStickY),
-    AxisOrBtn::Axis(Axis::LeftZ),
-    AxisOrBtn::Btn(Button::Unknown),
-    AxisOrBtn::Btn(Button::Unknown),
-    AxisOrBtn::Btn(Button::Unknown),
-    AxisOrBtn::Btn(Button::Unknown),
-    AxisOrBtn::Btn(Button::Unknown),
-    AxisOrBtn::Btn(Button::RightTrigger),
-    AxisOrBtn::Btn(Button::RightThumb),
-    AxisOrBtn::Btn(Button::RightTrigger2),
-    AxisOrBtn::Axis(Axis::RightStickX),
-    AxisOrBtn::Axis(Axis::RightStickY),
-    AxisOrBtn::Axis(Axis::RightZ),
-    AxisOrBtn::Btn(Button::Start),
-    AxisOrBtn::Btn(Button::Unknown),
-    AxisOrBtn::Btn(Button::West),
-    AxisOrBtn::Btn(Button::North),
-    AxisOrBtn::Btn(Button::Z),
+// SDL names of gamepad elements and what they are mapped to. SDL elements that have no gilrs
+// equivalent are mapped to `Button::Unknown`. Some names (`c`, `z`, `leftz`, `rightz`) are gilrs
+// extensions that SDL2 doesn't understand. This is the only place that defines SDL names and
+// their SDL2 compatibility, so parser, `Mapping::from_data()`, `Button::to_sdl_key()`,
+// `Button::is_sdl2_compatible()` etc. always agree.
+//
+// Must be sorted by name!
+static SDL_ELEMENTS: [SdlElement; 31] = [
+    el("a", AxisOrBtn::Btn(Button::South), true),
+    el("b", AxisOrBtn::Btn(Button::East), true),
+    el("back", AxisOrBtn::Btn(Button::Select), true),
+    el("c", AxisOrBtn::Btn(Button::C), false),
+    el("dpdown", AxisOrBtn::Btn(Button::DPadDown), true),
+    el("dpleft", AxisOrBtn::Btn(Button::DPadLeft), true),
+    el("dpright", AxisOrBtn::Btn(Button::DPadRight), true),
+    el("dpup", AxisOrBtn::Btn(Button::DPadUp), true),
+    el("guide", AxisOrBtn::Btn(Button::Mode), true),
+    el("leftshoulder", AxisOrBtn::Btn(Button::LeftTrigger), true),
+    el("leftstick", AxisOrBtn::Btn(Button::LeftThumb), true),
+    el("lefttrigger", AxisOrBtn::Btn(Button::LeftTrigger2), true),
+    el("leftx", AxisOrBtn::Axis(Axis::LeftStickX), true),
+    el("lefty", AxisOrBtn::Axis(Axis::LeftStickY), true),
+    el("leftz", AxisOrBtn::Axis(Axis::LeftZ), false),
+    el("misc1", AxisOrBtn::Btn(Button::Misc1), true),
+    el("paddle1", AxisOrBtn::Btn(Button::Unknown), true),
+    el("paddle2", AxisOrBtn::Btn(Button::Unknown), true),
+    el("paddle3", AxisOrBtn::Btn(Button::Unknown), true),
+    el("paddle4", AxisOrBtn::Btn(Button::Unknown), true),
+    el("rightshoulder", AxisOrBtn::Btn(Button::RightTrigger), true),
+    el("rightstick", AxisOrBtn::Btn(Button::RightThumb), true),
+    el("righttrigger", AxisOrBtn::Btn(Button::RightTrigger2), true),
+    el("rightx", AxisOrBtn::Axis(Axis::RightStickX), true),
+    el("righty", AxisOrBtn::Axis(Axis::RightStickY), true),
+    el("rightz", AxisOrBtn::Axis(Axis::RightZ), false),
+    el("start", AxisOrBtn::Btn(Button::Start), true),
+    el("touchpad", AxisOrBtn::Btn(Button::Unknown), true),
+    el("x", AxisOrBtn::Btn(Button::West), true),
+    el("y", AxisOrBtn::Btn(Button::North), true),
+    el("z", AxisOrBtn::Btn(Button::Z), false),
 ];
 
+/// Entry of SDL element table.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct SdlElement {
+    pub name: &'static str,
+    pub element: AxisOrBtn,
+    /// `false` for gilrs extensions.
+    pub sdl2: bool,
+}
+
+const fn el(name: &'static str, element: AxisOrBtn, sdl2: bool) -> SdlElement {
+    SdlElement {
+        name,
+        element,
+        sdl2,
+    }
+}
+
+/// Returns element with SDL name `key`.
+pub(crate) fn sdl_key_to_element(key: &str) -> Option<AxisOrBtn> {
+    SDL_ELEMENTS
+        .binary_search_by_key(&key, |el| el.name)
+        .ok()
+        .map(|idx| SDL_ELEMENTS[idx].element)
+}
+
+/// Returns SDL entry of `element` or `None` if it has no SDL name. `Button::Unknown` has none.
+pub(crate) fn sdl_element(element: AxisOrBtn) -> Option<&'static SdlElement> {
+    if element == AxisOrBtn::Btn(Button::Unknown) {
+        return None;
+    }
+
+    SDL_ELEMENTS.iter().find(|el| el.element == element)
+}
+
+/// Returns button with index `idx` (`Button as usize`) and its SDL entry.
+pub(crate) fn sdl_button(idx: usize) -> Option<(Button, &'static SdlElement)> {
+    SDL_ELEMENTS.iter().find_map(|el| match el.element {
+        AxisOrBtn::Btn(Button::Unknown) => None,
+        AxisOrBtn::Btn(btn) if btn as usize == idx => Some((btn, el)),
+        _ => None,
+    })
+}
+
+/// Returns axis with index `idx` (`Axis as usize`) and its SDL entry.
+pub(crate) fn sdl_axis(idx: usize) -> Option<(Axis, &'static SdlElement)> {
+    SDL_ELEMENTS.iter().find_map(|el| match el.element {
+        AxisOrBtn::Axis(axis) if axis as usize == idx => Some((axis, el)),
+        _ => None,
+    })
+}
+
 pub struct Parser<'a> {
     data: &'a str,
     pos: usize,
@@ -234,14 +258,13 @@ impl<'a> Parser<'a> {
                     .and_then(|s| s.parse().ok())
                     .ok_or_else(|| Error::new(ErrorKind::InvalidValue, pos + dot_idx + 1))?;
 
-                let idx = AXES_SDL
-                    .binary_search(&key)
-                    .map_err(|_| Error::new(ErrorKind::UnknownButton, pos))?;
+                let to = sdl_key_to_element(key)
+                    .ok_or_else(|| Error::new(ErrorKind::UnknownButton, pos))?;
 
                 return Ok(Token::HatMapping {
                     hat,
                     direction,
-                    to: AXES[idx],
+                    to,
                     output,
                 });
             }
@@ -251,27 +274,21 @@ impl<'a> Parser<'a> {
         .map_err(|_| Error::new(ErrorKind::InvalidValue, pos))?;
 
         if is_axis {
-            let idx = AXES_SDL
-                .binary_search(&key)
-                .map_err(|_| Error::new(ErrorKind::UnknownAxis, pos))?;
+            let to =
+                sdl_key_to_element(key).ok_or_else(|| Error::new(ErrorKind::UnknownAxis, pos))?;
 
             Ok(Token::AxisMapping {
                 from,
-                to: AXES[idx],
+                to,
                 input,
                 output,
                 inverted,
             })
         } else {
-            let idx = AXES_SDL
-                .binary_search(&key)
-                .map_err(|_| Error::new(ErrorKind::UnknownButton, pos))?;
+            let to =
+                sdl_key_to_element(key).ok_or_else(|| Error::new(ErrorKind::UnknownButton, pos))?;
 
-            Ok(Token::ButtonMapping {
-                from,
-                to: AXES[idx],
-                output,
-            })
+            Ok(Token::ButtonMapping { from, to, output })
         }
     }
 
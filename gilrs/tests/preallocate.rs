@@ -0,0 +1,132 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Checks that `next_event()` doesn't allocate with `GilrsBuilder::preallocate_events()` once
+//! every element sent its first event.
+
+use gilrs::{Axis, Button, Error, Event, EventType, GamepadId, Gilrs, GilrsBuilder};
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Counts allocations made by current thread, so tests running in parallel and background threads
+/// of backend don't interfere.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+fn count_allocation() {
+    // Thread local may be already destroyed when thread exits.
+    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const FRAME_EVENTS: usize = 64;
+
+fn setup(capacity: usize) -> (Gilrs, GamepadId) {
+    let mut gilrs = match GilrsBuilder::new().preallocate_events(capacity).build() {
+        Ok(gilrs) | Err(Error::NotImplemented(gilrs)) => gilrs,
+        Err(e) => panic!("{}", e),
+    };
+    let id = gilrs.register_test_gamepad("Synthetic gamepad", [7; 16]);
+    while gilrs.next_event().is_some() {}
+
+    (gilrs, id)
+}
+
+/// Queues frame of `len` button and stick events and returns number of events returned by
+/// `next_event()`.
+fn frame(gilrs: &mut Gilrs, id: GamepadId, n: usize, len: usize) -> usize {
+    let south = Button::South.to_standard_code().unwrap();
+    let stick = Axis::LeftStickX.to_standard_code().unwrap();
+    for i in 0..len {
+        let event = if i % 8 == 0 {
+            if (n + i) % 16 == 0 {
+                EventType::ButtonPressed(Button::South, south)
+            } else {
+                EventType::ButtonReleased(Button::South, south)
+            }
+        } else {
+            let val = ((n * len + i) % 200) as f32 / 100.0 - 1.0;
+            EventType::AxisChanged(Axis::LeftStickX, val, stick)
+        };
+        gilrs.insert_event(Event::new(id, event));
+    }
+
+    let mut count = 0;
+    while gilrs.next_event().is_some() {
+        count += 1;
+    }
+
+    count
+}
+
+#[test]
+fn steady_state_doesnt_allocate() {
+    let (mut gilrs, id) = setup(4 * FRAME_EVENTS);
+    // Warm up: first event of every element creates its state.
+    for n in 0..16 {
+        frame(&mut gilrs, id, n, FRAME_EVENTS);
+    }
+
+    let before = allocations();
+    for n in 0..1000 {
+        frame(&mut gilrs, id, n, FRAME_EVENTS);
+    }
+    assert_eq!(
+        allocations() - before,
+        0,
+        "next_event() allocated in steady state"
+    );
+}
+
+#[test]
+fn burst_larger_than_capacity() {
+    let (mut gilrs, id) = setup(FRAME_EVENTS);
+    let (mut large, large_id) = setup(16 * FRAME_EVENTS);
+    for n in 0..16 {
+        frame(&mut gilrs, id, n, FRAME_EVENTS);
+        frame(&mut large, large_id, n, FRAME_EVENTS);
+    }
+
+    // Queue grows instead of panicking and no events are lost.
+    assert_eq!(
+        frame(&mut gilrs, id, 0, 8 * FRAME_EVENTS),
+        frame(&mut large, large_id, 0, 8 * FRAME_EVENTS)
+    );
+
+    // After growing, queue has enough space for the same burst.
+    let before = allocations();
+    for n in 0..100 {
+        frame(&mut gilrs, id, n, 8 * FRAME_EVENTS);
+    }
+    assert_eq!(allocations() - before, 0);
+}
@@ -0,0 +1,31 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Format of recorded raw events, shared by `replay` test and `capture_fixture` example.
+
+use gilrs_core::{EventType as RawEventType, ReplayDevice};
+use serde::{Deserialize, Serialize};
+
+/// Raw events of one gamepad.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Fixture {
+    /// `gilrs_core::BACKEND_NAME` of backend that recorded events. Codes of elements are platform
+    /// specific, so fixture can only be replayed by the same backend.
+    pub backend: String,
+    /// SDL mapping used when replaying. Bundled and environment mappings are not used, so output
+    /// doesn't depend on version of mappings database.
+    pub mapping: Option<String>,
+    pub device: ReplayDevice,
+    pub events: Vec<RecordedEvent>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    /// Milliseconds since start of recording.
+    pub time: u64,
+    pub event: RawEventType,
+}
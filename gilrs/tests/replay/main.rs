@@ -0,0 +1,213 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Replays raw events from `fixtures/*.json` through `Gilrs` and compares returned events with
+//! `golden/*.txt`. After intended change in event processing, run with `GILRS_BLESS=1` to update
+//! golden files. New fixtures can be recorded with `capture_fixture` example.
+//!
+//! Fixtures with `synthetic_` prefix were written by hand from elements and event codes that
+//! evdev reports for these devices, not recorded from them. They check event processing, but not
+//! quirks of real hardware, like noise or order of events in one report. There are no recorded
+//! fixtures yet; they should be named after the device without the prefix.
+//!
+//! Only Linux backend can replay events, so on other platforms tests are ignored.
+
+mod fixture;
+
+use fixture::Fixture;
+use gilrs::{Axis, Button, Error, EventType, GilrsBuilder, RawEvent, RawEventType};
+use gilrs_core::ReplayDevice;
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+fn replay_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/replay")
+}
+
+fn describe(event: &EventType) -> String {
+    match *event {
+        EventType::ButtonPressed(btn, code) => format!("ButtonPressed({:?}) {}", btn, code),
+        EventType::ButtonRepeated(btn, code) => format!("ButtonRepeated({:?}) {}", btn, code),
+        EventType::ButtonReleased(btn, code) => format!("ButtonReleased({:?}) {}", btn, code),
+        EventType::ButtonChanged(btn, val, code) => {
+            format!("ButtonChanged({:?}, {:.4}) {}", btn, val, code)
+        }
+        EventType::AxisChanged(axis, val, code) => {
+            format!("AxisChanged({:?}, {:.4}) {}", axis, val, code)
+        }
+        ref event => format!("{:?}", event),
+    }
+}
+
+/// Returns events of replayed gamepad, one per line, or `None` if backend can't replay the
+/// fixture.
+fn replay(fixture: &Fixture) -> Option<String> {
+    if fixture.backend != gilrs_core::BACKEND_NAME {
+        return None;
+    }
+
+    let mut gilrs = match GilrsBuilder::new()
+        .add_included_mappings(false)
+        .add_env_mappings(false)
+        .add_mappings(fixture.mapping.as_deref().unwrap_or_default())
+        .build()
+    {
+        Ok(g) | Err(Error::NotImplemented(g)) => g,
+        Err(e) => panic!("{}", e),
+    };
+    // Connected events of real gamepads.
+    while gilrs.next_event().is_some() {}
+
+    let id = gilrs.add_replay_gamepad(&fixture.device)?;
+    for recorded in &fixture.events {
+        let mut event = RawEvent::new(id.into(), recorded.event);
+        event.time = UNIX_EPOCH + Duration::from_millis(recorded.time);
+        gilrs.insert_raw_event(event);
+    }
+
+    let mut output = String::new();
+    while let Some(ev) = gilrs.next_event() {
+        if ev.id != id {
+            continue;
+        }
+
+        let time = ev.time.duration_since(UNIX_EPOCH).unwrap().as_millis();
+        writeln!(output, "{:>6} {}", time, describe(&ev.event)).unwrap();
+    }
+
+    Some(output)
+}
+
+/// Regression test for mapping of button and axis with the same numeric code, like `KEY(0)` and
+/// `ABS(0)` (`ABS_X`) on Linux. They must stay separate elements.
+#[test]
+#[cfg_attr(
+    not(target_os = "linux"),
+    ignore = "only Linux backend can replay events"
+)]
+fn colliding_codes() {
+    let device: ReplayDevice = serde_json::from_str(
+        r#"{
+            "name": "Colliding codes",
+            "uuid": [3, 0, 0, 0, 1, 2, 0, 0, 3, 4, 0, 0, 0, 0, 0, 0],
+            "buttons": [{"kind": 1, "code": 0}],
+            "axes": [[{"kind": 3, "code": 0}, {"min": -32768, "max": 32767, "deadzone": null}]]
+        }"#,
+    )
+    .unwrap();
+    let key_0: RawEventType =
+        serde_json::from_str(r#"{"ButtonPressed": {"kind": 1, "code": 0}}"#).unwrap();
+    let abs_x: RawEventType =
+        serde_json::from_str(r#"{"AxisValueChanged": [32767, {"kind": 3, "code": 0}]}"#).unwrap();
+
+    let mut gilrs = match GilrsBuilder::new()
+        .with_default_filters(false)
+        .add_included_mappings(false)
+        .add_env_mappings(false)
+        .add_mappings("03000000010200000304000000000000,Colliding codes,a:b0,leftx:a0,")
+        .build()
+    {
+        Ok(g) | Err(Error::NotImplemented(g)) => g,
+        Err(e) => panic!("{}", e),
+    };
+    while gilrs.next_event().is_some() {}
+
+    let id = gilrs.add_replay_gamepad(&device).unwrap();
+    for event in [RawEventType::Connected, key_0, abs_x] {
+        gilrs.insert_raw_event(RawEvent::new(id.into(), event));
+    }
+    let mut events = Vec::new();
+    while let Some(ev) = gilrs.next_event() {
+        if ev.id == id {
+            events.push(describe(&ev.event));
+        }
+    }
+
+    assert_eq!(
+        events,
+        [
+            "Connected",
+            "ButtonPressed(South) KEY(0)",
+            "ButtonChanged(South, 1.0000) KEY(0)",
+            "AxisChanged(LeftStickX, 1.0000) ABS(0)",
+        ]
+    );
+    let gamepad = gilrs.gamepad(id);
+    assert_ne!(
+        gamepad.button_code(Button::South),
+        gamepad.axis_code(Axis::LeftStickX)
+    );
+    assert!(gamepad.is_pressed(Button::South));
+    assert_eq!(gamepad.value(Axis::LeftStickX), 1.0);
+}
+
+#[test]
+#[cfg_attr(
+    not(target_os = "linux"),
+    ignore = "only Linux backend can replay events"
+)]
+fn fixtures() {
+    let dir = replay_dir();
+    let bless = env::var_os("GILRS_BLESS").is_some();
+    let mut fixtures: Vec<_> = fs::read_dir(dir.join("fixtures"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    fixtures.sort();
+    assert!(!fixtures.is_empty());
+
+    let mut failed = Vec::new();
+    let mut replayed = 0;
+    for path in fixtures {
+        let name = path.file_stem().unwrap().to_str().unwrap().to_owned();
+        let fixture: Fixture = serde_json::from_str(&fs::read_to_string(&path).unwrap())
+            .unwrap_or_else(|e| panic!("Invalid fixture {}: {}", name, e));
+        let output = match replay(&fixture) {
+            Some(output) => output,
+            None => {
+                println!("Skipping {}, recorded by {} backend", name, fixture.backend);
+                continue;
+            }
+        };
+        replayed += 1;
+
+        let golden = dir.join("golden").join(format!("{}.txt", name));
+        if bless {
+            fs::write(&golden, output).unwrap();
+            continue;
+        }
+
+        let expected = fs::read_to_string(&golden).unwrap_or_default();
+        if output != expected {
+            let line = output
+                .lines()
+                .zip(expected.lines())
+                .position(|(actual, expected)| actual != expected)
+                .unwrap_or_else(|| output.lines().count().min(expected.lines().count()));
+            println!(
+                "{}: output differs from {} at line {}:\n{}",
+                name,
+                golden.display(),
+                line + 1,
+                output
+            );
+            failed.push(name);
+        }
+    }
+
+    assert!(replayed > 0, "No fixture could be replayed by this backend");
+    assert!(
+        failed.is_empty(),
+        "Events of {:?} differ from golden files, rerun with GILRS_BLESS=1 if it's expected",
+        failed
+    );
+}